@@ -1,6 +1,11 @@
 mod network_frame;
+pub use network_frame::ErrorFields;
 pub use network_frame::NetworkFrame;
 pub use network_frame::NetworkFrameError;
 
 mod pg_codec;
 pub use pg_codec::PgCodec;
+
+mod value_encoding;
+pub use value_encoding::ValueEncoding;
+pub use value_encoding::ValueEncodingError;
@@ -4,8 +4,12 @@ use std::convert::TryFrom;
 use std::num::TryFromIntError;
 use thiserror::Error;
 
-use crate::constants::{PgErrorCodes, PgErrorLevels};
-use crate::engine::objects::SqlTuple;
+use super::{ValueEncoding, ValueEncodingError};
+use crate::constants::{
+    PgErrorCodes, PgErrorLevels, PgFormatCode, PgTransactionStatus, TypeDefinitions,
+};
+use crate::engine::objects::types::{BaseSqlTypesMapper, SqlTypeDefinition};
+use crate::engine::objects::{ColumnOrigin, CommandType, SqlTuple};
 
 #[derive(Clone, Debug)]
 pub struct NetworkFrame {
@@ -24,7 +28,30 @@ impl NetworkFrame {
         NetworkFrame::new(b'R', Bytes::from_static(b"\0\0\0\0"))
     }
 
-    pub fn command_complete(command_tag: String) -> NetworkFrame {
+    pub fn bind_complete() -> NetworkFrame {
+        NetworkFrame::new(b'2', Bytes::new())
+    }
+
+    pub fn close_complete() -> NetworkFrame {
+        NetworkFrame::new(b'3', Bytes::new())
+    }
+
+    /// Tags are listed here: https://www.postgresql.org/docs/current/protocol-message-formats.html
+    pub fn command_complete(command_type: CommandType, row_count: usize) -> NetworkFrame {
+        let command_tag = match command_type {
+            CommandType::Select => format!("SELECT {}", row_count),
+            //The zero is where the oid of the inserted row used to go
+            CommandType::Insert => format!("INSERT 0 {}", row_count),
+            CommandType::Update => format!("UPDATE {}", row_count),
+            CommandType::Delete => format!("DELETE {}", row_count),
+            CommandType::CreateTable => "CREATE TABLE".to_string(),
+            CommandType::CreateType => "CREATE TYPE".to_string(),
+            CommandType::DropType => "DROP TYPE".to_string(),
+            CommandType::Begin => "BEGIN".to_string(),
+            CommandType::Commit => "COMMIT".to_string(),
+            CommandType::Rollback => "ROLLBACK".to_string(),
+        };
+
         let mut buffer = BytesMut::new();
 
         buffer.put(command_tag.as_bytes());
@@ -33,7 +60,11 @@ impl NetworkFrame {
         NetworkFrame::new(b'C', buffer.freeze())
     }
 
-    pub fn data_rows(rows: Vec<SqlTuple>) -> Result<Vec<NetworkFrame>, NetworkFrameError> {
+    /// Formats are the per column format codes from a Bind, empty for text everywhere
+    pub fn data_rows(
+        rows: Vec<SqlTuple>,
+        formats: &[PgFormatCode],
+    ) -> Result<Vec<NetworkFrame>, NetworkFrameError> {
         let mut frames = vec![];
 
         for row in rows {
@@ -42,11 +73,15 @@ impl NetworkFrame {
             let column_count = u16::try_from(row.0.len())?;
             buffer.put_u16(column_count);
 
-            for field in row.0.into_iter() {
+            for (i, field) in row.0.into_iter().enumerate() {
                 match field {
                     Some(f) => {
-                        let f_str = format!("{}", f);
-                        let f_bytes = f_str.as_bytes();
+                        let mut f_bytes = BytesMut::new();
+                        ValueEncoding::encode(
+                            &f,
+                            PgFormatCode::for_column(formats, i),
+                            &mut f_bytes,
+                        )?;
                         let f_len = i32::try_from(f_bytes.len())?;
                         buffer.put_i32(f_len);
                         buffer.put(f_bytes);
@@ -63,29 +98,67 @@ impl NetworkFrame {
         Ok(frames)
     }
 
+    pub fn empty_query_response() -> NetworkFrame {
+        NetworkFrame::new(b'I', Bytes::new())
+    }
+
+    pub fn no_data() -> NetworkFrame {
+        NetworkFrame::new(b'n', Bytes::new())
+    }
+
+    pub fn parameter_description(
+        parameters: &[BaseSqlTypesMapper],
+    ) -> Result<NetworkFrame, NetworkFrameError> {
+        let mut buffer = BytesMut::new();
+
+        buffer.put_u16(u16::try_from(parameters.len())?);
+        for p in parameters {
+            buffer.put_u32(ValueEncoding::type_oid(p));
+        }
+
+        Ok(NetworkFrame::new(b't', buffer.freeze()))
+    }
+
+    pub fn parse_complete() -> NetworkFrame {
+        NetworkFrame::new(b'1', Bytes::new())
+    }
+
+    pub fn portal_suspended() -> NetworkFrame {
+        NetworkFrame::new(b's', Bytes::new())
+    }
+
     //Note this claims that the server is ALWAYS ready, even if its not
-    pub fn ready_for_query() -> NetworkFrame {
-        NetworkFrame::new(b'Z', Bytes::from_static(b"I"))
+    pub fn ready_for_query(status: PgTransactionStatus) -> NetworkFrame {
+        NetworkFrame::new(b'Z', status.value())
     }
 
-    pub fn row_description(column_names: Vec<String>) -> Result<NetworkFrame, NetworkFrameError> {
+    /// Origins line up with the columns, missing ones are sent as computed columns
+    pub fn row_description(
+        columns: &SqlTypeDefinition,
+        origins: &[ColumnOrigin],
+        formats: &[PgFormatCode],
+    ) -> Result<NetworkFrame, NetworkFrameError> {
         let mut buffer = BytesMut::new();
 
-        let field_count = u16::try_from(column_names.len())?;
+        let field_count = u16::try_from(columns.len())?;
         buffer.put_u16(field_count);
 
-        for column in column_names {
-            buffer.put(column.as_bytes());
+        for (i, (name, sql_type)) in columns.iter().enumerate() {
+            let origin = origins.get(i).cloned().unwrap_or_default();
+
+            buffer.put(name.as_bytes());
             buffer.put_u8(b'\0');
 
-            //The next following fields are going to be dummied out unless testing shows I need them.
             //https://www.postgresql.org/docs/current/protocol-message-formats.html
-            buffer.put_u32(0); //Table OID
-            buffer.put_u16(0); //Table Column
-            buffer.put_u32(0); //Type OID
-            buffer.put_i16(0); //Type length
-            buffer.put_i32(0); //Type specifier
-            buffer.put_i16(0); //Format code, we're doing text for everything
+            buffer.put_u32(origin.table_oid); //Table OID
+            buffer.put_i16(origin.column_number); //Table Column
+            buffer.put_u32(ValueEncoding::column_type_oid(
+                sql_type,
+                origin.modifier.as_ref(),
+            )); //Type OID
+            buffer.put_i16(TypeDefinitions::type_len(sql_type)); //Type length
+            buffer.put_i32(origin.modifier.map(|m| m.to_typmod()).unwrap_or(-1)); //Type modifier
+            buffer.put_i16(PgFormatCode::for_column(formats, i).value()); //Format code
         }
 
         Ok(NetworkFrame::new(b'T', buffer.freeze()))
@@ -93,30 +166,86 @@ impl NetworkFrame {
 
     //Valid severities can be found here: https://www.postgresql.org/docs/current/protocol-error-fields.html
     //Valid error codes can be found here: https://www.postgresql.org/docs/current/errcodes-appendix.html
-    //TODO #26 need to figure out a better error method to show errors
     pub fn error_response(
         severity: PgErrorLevels,
         code: PgErrorCodes,
         message: String,
+        fields: &ErrorFields,
     ) -> NetworkFrame {
         let mut buffer = BytesMut::new();
         buffer.put_u8(b'S'); //Severity
         buffer.put(severity.value());
         buffer.put_u8(b'\0');
-        buffer.put_u8(b'M'); //Code
+        buffer.put_u8(b'M'); //Message
         buffer.put(message.as_bytes());
         buffer.put_u8(b'\0');
         buffer.put_u8(b'C'); //Code
         buffer.put(code.value());
         buffer.put_u8(b'\0');
+
+        let position = fields.position.map(|p| p.to_string());
+        let optional = [
+            (b'D', &fields.detail),
+            (b'H', &fields.hint),
+            (b'P', &position),
+            (b't', &fields.table),
+            (b'c', &fields.column),
+        ];
+        for (field_type, value) in optional {
+            if let Some(v) = value {
+                buffer.put_u8(field_type);
+                buffer.put(v.as_bytes());
+                buffer.put_u8(b'\0');
+            }
+        }
         buffer.put_u8(b'\0');
 
         NetworkFrame::new(b'E', buffer.freeze())
     }
 }
 
+/// The optional fields of an ErrorResponse, unset ones are left out of the message
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ErrorFields {
+    pub detail: Option<String>,
+    pub hint: Option<String>,
+    ///Where in the query the error is, counted in characters starting from one
+    pub position: Option<usize>,
+    pub table: Option<String>,
+    pub column: Option<String>,
+}
+
 #[derive(Error, Debug)]
 pub enum NetworkFrameError {
     #[error(transparent)]
     TooManyFields(#[from] TryFromIntError),
+    #[error(transparent)]
+    ValueEncodingError(#[from] ValueEncodingError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_complete() {
+        let tags = vec![
+            (CommandType::Select, 2, "SELECT 2"),
+            (CommandType::Insert, 1, "INSERT 0 1"),
+            (CommandType::Update, 3, "UPDATE 3"),
+            (CommandType::Delete, 0, "DELETE 0"),
+            (CommandType::CreateTable, 0, "CREATE TABLE"),
+            (CommandType::CreateType, 0, "CREATE TYPE"),
+            (CommandType::DropType, 0, "DROP TYPE"),
+            (CommandType::Begin, 0, "BEGIN"),
+            (CommandType::Commit, 0, "COMMIT"),
+            (CommandType::Rollback, 0, "ROLLBACK"),
+        ];
+
+        for (command_type, row_count, tag) in tags {
+            let frame = NetworkFrame::command_complete(command_type, row_count);
+            assert_eq!(frame.message_type, b'C');
+            assert_eq!(&frame.payload[..], format!("{}\0", tag).as_bytes());
+        }
+    }
 }
@@ -0,0 +1,382 @@
+//! Converts between our sql types and how postgres clients expect them on the wire.
+//!
+//! Type OIDs come from TypeDefinitions, the same ones postgres uses, enums carry the ones from their pg_type row.
+//! Binary formats are the send / recv functions for each type in postgres.
+
+use bytes::{Buf, BufMut, BytesMut};
+use std::convert::TryFrom;
+use std::str::Utf8Error;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::constants::{PgFormatCode, TypeDefinitions};
+use crate::engine::objects::types::{
+    bytea, date_time, BaseSqlTypes, BaseSqlTypesMapper, Interval, SqlFloat, TypeModifier,
+};
+use chrono::{Datelike, NaiveDate};
+
+/// The only version of the jsonb binary format, a version byte in front of the text form
+const JSONB_VERSION: u8 = 1;
+
+/// Postgres counts dates and timestamps from 2000-01-01 in binary
+const POSTGRES_EPOCH_DAYS: i32 = 730_120;
+const POSTGRES_EPOCH_MICROS: i64 = 946_684_800_000_000;
+
+pub struct ValueEncoding {}
+
+impl ValueEncoding {
+    /// Enums use the oids from their row in pg_type, the rest are built in
+    pub fn type_oid(sql_type: &BaseSqlTypesMapper) -> u32 {
+        match sql_type {
+            BaseSqlTypesMapper::Enum(e) => e.oid,
+            BaseSqlTypesMapper::Array(a) => match a.as_ref() {
+                BaseSqlTypesMapper::Enum(e) => e.array_oid,
+                _ => {
+                    TypeDefinitions::from_sql_type(a)
+                        .unwrap_or(TypeDefinitions::Text)
+                        .value()
+                        .array_oid
+                }
+            },
+            _ => {
+                TypeDefinitions::from_sql_type(sql_type)
+                    .unwrap_or(TypeDefinitions::Text)
+                    .value()
+                    .oid
+            }
+        }
+    }
+
+    /// varchar and char columns are stored as text, clients should still see the declared type
+    pub fn column_type_oid(sql_type: &BaseSqlTypesMapper, modifier: Option<&TypeModifier>) -> u32 {
+        let definition = match modifier {
+            Some(TypeModifier::Varchar(_)) => TypeDefinitions::Varchar.value(),
+            Some(TypeModifier::Char(_)) => TypeDefinitions::Bpchar.value(),
+            _ => return ValueEncoding::type_oid(sql_type),
+        };
+        match sql_type {
+            BaseSqlTypesMapper::Array(_) => definition.array_oid,
+            _ => definition.oid,
+        }
+    }
+
+    /// Zero means the client left the type for us to work out
+    pub fn type_from_oid(oid: u32) -> Result<Option<BaseSqlTypesMapper>, ValueEncodingError> {
+        match oid {
+            0 => Ok(None),
+            o => TypeDefinitions::sql_type_for_oid(o)
+                .map(Some)
+                .ok_or(ValueEncodingError::UnknownTypeOid(o)),
+        }
+    }
+
+    pub fn encode(
+        value: &BaseSqlTypes,
+        format: PgFormatCode,
+        buffer: &mut BytesMut,
+    ) -> Result<(), ValueEncodingError> {
+        if format == PgFormatCode::Text {
+            buffer.put(value.to_string().as_bytes());
+            return Ok(());
+        }
+
+        match value {
+            //TODO Numeric's binary form is base 10000 digit groups, not worth it yet
+            BaseSqlTypes::Array(_) | BaseSqlTypes::Numeric(_) => {
+                return Err(ValueEncodingError::BinaryNotSupported(value.clone()));
+            }
+            BaseSqlTypes::Bool(b) => buffer.put_u8(u8::from(*b)),
+            BaseSqlTypes::Smallint(i) => buffer.put_i16(*i),
+            BaseSqlTypes::Integer(i) => buffer.put_i32(*i),
+            BaseSqlTypes::Bigint(i) => buffer.put_i64(*i),
+            BaseSqlTypes::Real(f) => buffer.put_f32(f.0),
+            BaseSqlTypes::Double(f) => buffer.put_f64(f.0),
+            BaseSqlTypes::Date(d) => buffer.put_i32(d.num_days_from_ce() - POSTGRES_EPOCH_DAYS),
+            BaseSqlTypes::Time(t) => buffer.put_i64(date_time::time_to_micros(t)),
+            BaseSqlTypes::Timestamp(t) => {
+                buffer.put_i64(date_time::timestamp_to_micros(t) - POSTGRES_EPOCH_MICROS)
+            }
+            BaseSqlTypes::TimestampTz(t) => buffer
+                .put_i64(date_time::timestamp_to_micros(&t.naive_utc()) - POSTGRES_EPOCH_MICROS),
+            BaseSqlTypes::Interval(i) => {
+                buffer.put_i64(i.microseconds);
+                buffer.put_i32(i.days);
+                buffer.put_i32(i.months);
+            }
+            BaseSqlTypes::Bytea(b) => buffer.put(&b[..]),
+            BaseSqlTypes::Jsonb(j) => {
+                buffer.put_u8(JSONB_VERSION);
+                buffer.put(j.to_string().as_bytes());
+            }
+            BaseSqlTypes::Enum(e) => buffer.put(e.label.as_bytes()),
+            BaseSqlTypes::Text(t) => buffer.put(t.as_bytes()),
+            BaseSqlTypes::Uuid(u) => buffer.put(&u.as_bytes()[..]),
+        }
+        Ok(())
+    }
+
+    /// Turns a bound parameter into the text form the parser would have seen had it been written inline
+    pub fn decode_parameter(
+        value: &[u8],
+        format: PgFormatCode,
+        sql_type: &BaseSqlTypesMapper,
+    ) -> Result<String, ValueEncodingError> {
+        if format == PgFormatCode::Text {
+            return Ok(std::str::from_utf8(value)?.to_string());
+        }
+
+        match sql_type {
+            BaseSqlTypesMapper::Bool => match value {
+                [b] => Ok((*b != 0).to_string()),
+                _ => Err(ValueEncodingError::WrongLength(
+                    sql_type.clone(),
+                    value.len(),
+                )),
+            },
+            BaseSqlTypesMapper::Smallint => {
+                let bytes = <[u8; 2]>::try_from(value)
+                    .map_err(|_| ValueEncodingError::WrongLength(sql_type.clone(), value.len()))?;
+                Ok(i16::from_be_bytes(bytes).to_string())
+            }
+            BaseSqlTypesMapper::Integer => {
+                let bytes = <[u8; 4]>::try_from(value)
+                    .map_err(|_| ValueEncodingError::WrongLength(sql_type.clone(), value.len()))?;
+                Ok(i32::from_be_bytes(bytes).to_string())
+            }
+            BaseSqlTypesMapper::Bigint => {
+                let bytes = <[u8; 8]>::try_from(value)
+                    .map_err(|_| ValueEncodingError::WrongLength(sql_type.clone(), value.len()))?;
+                Ok(i64::from_be_bytes(bytes).to_string())
+            }
+            BaseSqlTypesMapper::Real => {
+                let bytes = <[u8; 4]>::try_from(value)
+                    .map_err(|_| ValueEncodingError::WrongLength(sql_type.clone(), value.len()))?;
+                Ok(SqlFloat(f32::from_be_bytes(bytes)).to_string())
+            }
+            BaseSqlTypesMapper::Double => {
+                let bytes = <[u8; 8]>::try_from(value)
+                    .map_err(|_| ValueEncodingError::WrongLength(sql_type.clone(), value.len()))?;
+                Ok(SqlFloat(f64::from_be_bytes(bytes)).to_string())
+            }
+            BaseSqlTypesMapper::Date => {
+                let bytes = <[u8; 4]>::try_from(value)
+                    .map_err(|_| ValueEncodingError::WrongLength(sql_type.clone(), value.len()))?;
+                i32::from_be_bytes(bytes)
+                    .checked_add(POSTGRES_EPOCH_DAYS)
+                    .and_then(NaiveDate::from_num_days_from_ce_opt)
+                    .map(|d| BaseSqlTypes::Date(d).to_string())
+                    .ok_or_else(|| ValueEncodingError::OutOfRange(sql_type.clone()))
+            }
+            BaseSqlTypesMapper::Time => {
+                let bytes = <[u8; 8]>::try_from(value)
+                    .map_err(|_| ValueEncodingError::WrongLength(sql_type.clone(), value.len()))?;
+                date_time::micros_to_time(i64::from_be_bytes(bytes))
+                    .map(|t| date_time::format_time(&t))
+                    .ok_or_else(|| ValueEncodingError::OutOfRange(sql_type.clone()))
+            }
+            BaseSqlTypesMapper::Timestamp | BaseSqlTypesMapper::TimestampTz => {
+                let bytes = <[u8; 8]>::try_from(value)
+                    .map_err(|_| ValueEncodingError::WrongLength(sql_type.clone(), value.len()))?;
+                let timestamp = i64::from_be_bytes(bytes)
+                    .checked_add(POSTGRES_EPOCH_MICROS)
+                    .and_then(date_time::micros_to_timestamp)
+                    .ok_or_else(|| ValueEncodingError::OutOfRange(sql_type.clone()))?;
+                match sql_type {
+                    BaseSqlTypesMapper::TimestampTz => {
+                        Ok(date_time::format_timestamptz(&timestamp.and_utc()))
+                    }
+                    _ => Ok(date_time::format_timestamp(&timestamp)),
+                }
+            }
+            BaseSqlTypesMapper::Interval => {
+                if value.len() != 16 {
+                    return Err(ValueEncodingError::WrongLength(
+                        sql_type.clone(),
+                        value.len(),
+                    ));
+                }
+                let mut buffer = value;
+                let microseconds = buffer.get_i64();
+                let days = buffer.get_i32();
+                let months = buffer.get_i32();
+                Ok(Interval::new(months, days, microseconds).to_string())
+            }
+            BaseSqlTypesMapper::Bytea => Ok(bytea::format_bytea(value)),
+            BaseSqlTypesMapper::Jsonb => match value.split_first() {
+                Some((&JSONB_VERSION, text)) => Ok(std::str::from_utf8(text)?.to_string()),
+                _ => Err(ValueEncodingError::UnknownJsonbVersion()),
+            },
+            BaseSqlTypesMapper::Enum(_) | BaseSqlTypesMapper::Text => {
+                Ok(std::str::from_utf8(value)?.to_string())
+            }
+            BaseSqlTypesMapper::Uuid => {
+                let bytes = <[u8; 16]>::try_from(value)
+                    .map_err(|_| ValueEncodingError::WrongLength(sql_type.clone(), value.len()))?;
+                Ok(Uuid::from_bytes(bytes).to_string())
+            }
+            BaseSqlTypesMapper::Array(_) | BaseSqlTypesMapper::Numeric => Err(
+                ValueEncodingError::BinaryParameterNotSupported(sql_type.clone()),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ValueEncodingError {
+    #[error("Binary format is not supported for {0}")]
+    BinaryNotSupported(BaseSqlTypes),
+    #[error("Binary format is not supported for parameters of type {0}")]
+    BinaryParameterNotSupported(BaseSqlTypesMapper),
+    #[error(transparent)]
+    NotUtf8(#[from] Utf8Error),
+    #[error("Unsupported binary jsonb version")]
+    UnknownJsonbVersion(),
+    #[error("Binary {0} is out of range")]
+    OutOfRange(BaseSqlTypesMapper),
+    #[error("Unknown type oid {0}")]
+    UnknownTypeOid(u32),
+    #[error("Binary {0} can't be {1} bytes long")]
+    WrongLength(BaseSqlTypesMapper, usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::objects::types::EnumType;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_binary_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let values = vec![
+            (BaseSqlTypes::Bool(true), BaseSqlTypesMapper::Bool),
+            (BaseSqlTypes::Smallint(-12), BaseSqlTypesMapper::Smallint),
+            (BaseSqlTypes::Integer(1234), BaseSqlTypesMapper::Integer),
+            (BaseSqlTypes::Bigint(i64::MIN), BaseSqlTypesMapper::Bigint),
+            (
+                BaseSqlTypes::Real(SqlFloat(-0.25)),
+                BaseSqlTypesMapper::Real,
+            ),
+            (
+                BaseSqlTypes::Double(SqlFloat(f64::INFINITY)),
+                BaseSqlTypesMapper::Double,
+            ),
+            (
+                BaseSqlTypes::Text("foo".to_string()),
+                BaseSqlTypesMapper::Text,
+            ),
+            (BaseSqlTypes::Uuid(Uuid::new_v4()), BaseSqlTypesMapper::Uuid),
+            (
+                BaseSqlTypes::Bytea(vec![0, 159, 146, 150, 255]),
+                BaseSqlTypesMapper::Bytea,
+            ),
+            (
+                BaseSqlTypes::parse(BaseSqlTypesMapper::Jsonb, r#"{"a": [1, "b", null]}"#)?,
+                BaseSqlTypesMapper::Jsonb,
+            ),
+            (
+                BaseSqlTypes::parse(BaseSqlTypesMapper::Date, "1999-12-31")?,
+                BaseSqlTypesMapper::Date,
+            ),
+            (
+                BaseSqlTypes::parse(BaseSqlTypesMapper::Time, "23:59:59.000001")?,
+                BaseSqlTypesMapper::Time,
+            ),
+            (
+                BaseSqlTypes::parse(BaseSqlTypesMapper::Timestamp, "2021-06-01 12:30:00.25")?,
+                BaseSqlTypesMapper::Timestamp,
+            ),
+            (
+                BaseSqlTypes::TimestampTz(date_time::now()),
+                BaseSqlTypesMapper::TimestampTz,
+            ),
+            (
+                BaseSqlTypes::Interval(Interval::new(-14, 3, -14_400_000_000)),
+                BaseSqlTypesMapper::Interval,
+            ),
+        ];
+
+        for (value, sql_type) in values {
+            let mut buffer = BytesMut::new();
+            ValueEncoding::encode(&value, PgFormatCode::Binary, &mut buffer)?;
+            if let Ok(size) = usize::try_from(TypeDefinitions::type_len(&sql_type)) {
+                assert_eq!(buffer.len(), size);
+            }
+            let text = ValueEncoding::decode_parameter(&buffer, PgFormatCode::Binary, &sql_type)?;
+            assert_eq!(BaseSqlTypes::parse(sql_type.clone(), &text)?, value);
+
+            assert_eq!(
+                ValueEncoding::type_from_oid(ValueEncoding::type_oid(&sql_type))?,
+                Some(sql_type)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_column_type_oid() {
+        let text_array = BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Text));
+        assert_eq!(
+            ValueEncoding::column_type_oid(&BaseSqlTypesMapper::Text, None),
+            TypeDefinitions::Text.value().oid
+        );
+        assert_eq!(
+            ValueEncoding::column_type_oid(&BaseSqlTypesMapper::Text, Some(&TypeModifier::Char(3))),
+            TypeDefinitions::Bpchar.value().oid
+        );
+        assert_eq!(
+            ValueEncoding::column_type_oid(&text_array, Some(&TypeModifier::Varchar(3))),
+            TypeDefinitions::Varchar.value().array_oid
+        );
+        assert_eq!(
+            ValueEncoding::column_type_oid(
+                &BaseSqlTypesMapper::Numeric,
+                Some(&TypeModifier::Numeric(5, 2))
+            ),
+            TypeDefinitions::Numeric.value().oid
+        );
+
+        let mood = Arc::new(BaseSqlTypesMapper::Enum(Arc::new(EnumType::new(
+            Uuid::new_v4(),
+            16384,
+            16385,
+            "mood".to_string(),
+            vec!["sad".to_string()],
+        ))));
+        assert_eq!(ValueEncoding::column_type_oid(&mood, None), 16384);
+        assert_eq!(
+            ValueEncoding::column_type_oid(&BaseSqlTypesMapper::Array(mood), None),
+            16385
+        );
+    }
+
+    #[test]
+    fn test_bad_binary() {
+        assert!(ValueEncoding::decode_parameter(
+            &[0, 1],
+            PgFormatCode::Binary,
+            &BaseSqlTypesMapper::Integer
+        )
+        .is_err());
+        assert!(ValueEncoding::decode_parameter(
+            &[0, 0, 0, 1],
+            PgFormatCode::Binary,
+            &BaseSqlTypesMapper::Bigint
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_postgres_epoch() -> Result<(), Box<dyn std::error::Error>> {
+        let mut buffer = BytesMut::new();
+        let value = BaseSqlTypes::parse(BaseSqlTypesMapper::Timestamp, "2000-01-02 00:00:01")?;
+        ValueEncoding::encode(&value, PgFormatCode::Binary, &mut buffer)?;
+        assert_eq!(&buffer[..], &86_401_000_000i64.to_be_bytes()[..]);
+
+        let mut buffer = BytesMut::new();
+        let value = BaseSqlTypes::parse(BaseSqlTypesMapper::Date, "2000-01-01")?;
+        ValueEncoding::encode(&value, PgFormatCode::Binary, &mut buffer)?;
+        assert_eq!(&buffer[..], &0i32.to_be_bytes()[..]);
+        Ok(())
+    }
+}
@@ -2,10 +2,12 @@ mod nullable;
 pub use nullable::Nullable;
 
 mod page_settings;
+pub use page_settings::DEFAULT_SORT_MEMORY;
 pub use page_settings::MAX_FILE_HANDLE_COUNT;
 pub use page_settings::MAX_PAGE_CACHE;
 pub use page_settings::PAGES_PER_FILE;
 pub use page_settings::PAGE_SIZE;
+pub use page_settings::WAL_CHECKPOINT_SIZE;
 
 mod pg_error_codes;
 pub use pg_error_codes::PgErrorCodes;
@@ -13,5 +15,17 @@ pub use pg_error_codes::PgErrorCodes;
 mod pg_error_levels;
 pub use pg_error_levels::PgErrorLevels;
 
+mod pg_format_codes;
+pub use pg_format_codes::PgFormatCode;
+pub use pg_format_codes::PgFormatCodeError;
+
+mod pg_transaction_status;
+pub use pg_transaction_status::PgTransactionStatus;
+
 pub mod system_tables;
 pub use system_tables::SystemTables;
+
+mod type_definitions;
+pub use type_definitions::TypeDefinition;
+pub use type_definitions::TypeDefinitions;
+pub use type_definitions::FIRST_USER_OID;
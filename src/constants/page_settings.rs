@@ -12,3 +12,9 @@ pub const MAX_PAGE_CACHE: usize = 128;
 /// Linux seems to limit to 1024, macos 256, windows 512 but I'm staying low until
 /// a benchmark proves I need to change it.
 pub const MAX_FILE_HANDLE_COUNT: usize = 128;
+
+/// Once the write ahead log grows past this many bytes the next commit triggers a checkpoint
+pub const WAL_CHECKPOINT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Bytes of rows a sort may hold in memory before it starts writing runs to disk, like postgres' work_mem
+pub const DEFAULT_SORT_MEMORY: usize = 4 * 1024 * 1024;
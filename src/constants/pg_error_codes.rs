@@ -3,15 +3,94 @@
 use bytes::Bytes;
 
 //https://stackoverflow.com/a/62759252/160208
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum PgErrorCodes {
+    AmbiguousColumn,
+    ArraySubscriptError,
+    CannotCoerce,
+    CharacterNotInRepertoire,
+    DatatypeMismatch,
+    DatetimeFieldOverflow,
+    DependentObjectsStillExist,
+    DivisionByZero,
+    DuplicateAlias,
+    DuplicateColumn,
+    DuplicateObject,
+    DuplicatePreparedStatement,
+    FeatureNotSupported,
+    GroupingError,
+    InFailedSqlTransaction,
+    IntervalFieldOverflow,
+    InvalidBinaryRepresentation,
+    InvalidColumnReference,
+    InvalidCursorName,
+    InvalidDatetimeFormat,
+    InvalidParameterValue,
+    InvalidRowCountInLimitClause,
+    InvalidSqlStatementName,
+    InvalidTextRepresentation,
+    NotNullViolation,
+    NullValueNotAllowed,
+    NumericValueOutOfRange,
+    ObjectNotInPrerequisiteState,
+    ProgramLimitExceeded,
+    ProtocolViolation,
+    StringDataRightTruncation,
+    SyntaxError,
     SystemError,
+    UndefinedColumn,
+    UndefinedFunction,
+    UndefinedObject,
+    UndefinedParameter,
+    UndefinedTable,
+    UniqueViolation,
+    WrongObjectType,
 }
 
 impl PgErrorCodes {
     pub const fn value(self) -> Bytes {
         use PgErrorCodes::*;
         match self {
+            AmbiguousColumn => Bytes::from_static(b"42702"),
+            ArraySubscriptError => Bytes::from_static(b"2202E"),
+            CannotCoerce => Bytes::from_static(b"42846"),
+            CharacterNotInRepertoire => Bytes::from_static(b"22021"),
+            DatatypeMismatch => Bytes::from_static(b"42804"),
+            DatetimeFieldOverflow => Bytes::from_static(b"22008"),
+            DependentObjectsStillExist => Bytes::from_static(b"2BP01"),
+            DivisionByZero => Bytes::from_static(b"22012"),
+            DuplicateAlias => Bytes::from_static(b"42712"),
+            DuplicateColumn => Bytes::from_static(b"42701"),
+            DuplicateObject => Bytes::from_static(b"42710"),
+            DuplicatePreparedStatement => Bytes::from_static(b"42P05"),
+            FeatureNotSupported => Bytes::from_static(b"0A000"),
+            GroupingError => Bytes::from_static(b"42803"),
+            InFailedSqlTransaction => Bytes::from_static(b"25P02"),
+            IntervalFieldOverflow => Bytes::from_static(b"22015"),
+            InvalidBinaryRepresentation => Bytes::from_static(b"22P03"),
+            InvalidColumnReference => Bytes::from_static(b"42P10"),
+            InvalidCursorName => Bytes::from_static(b"34000"),
+            InvalidDatetimeFormat => Bytes::from_static(b"22007"),
+            InvalidParameterValue => Bytes::from_static(b"22023"),
+            InvalidRowCountInLimitClause => Bytes::from_static(b"2201W"),
+            InvalidSqlStatementName => Bytes::from_static(b"26000"),
+            InvalidTextRepresentation => Bytes::from_static(b"22P02"),
+            NotNullViolation => Bytes::from_static(b"23502"),
+            NullValueNotAllowed => Bytes::from_static(b"22004"),
+            NumericValueOutOfRange => Bytes::from_static(b"22003"),
+            ObjectNotInPrerequisiteState => Bytes::from_static(b"55000"),
+            ProgramLimitExceeded => Bytes::from_static(b"54000"),
+            ProtocolViolation => Bytes::from_static(b"08P01"),
+            StringDataRightTruncation => Bytes::from_static(b"22001"),
+            SyntaxError => Bytes::from_static(b"42601"),
             SystemError => Bytes::from_static(b"58000"),
+            UndefinedColumn => Bytes::from_static(b"42703"),
+            UndefinedFunction => Bytes::from_static(b"42883"),
+            UndefinedObject => Bytes::from_static(b"42704"),
+            UndefinedParameter => Bytes::from_static(b"42P02"),
+            UndefinedTable => Bytes::from_static(b"42P01"),
+            UniqueViolation => Bytes::from_static(b"23505"),
+            WrongObjectType => Bytes::from_static(b"42809"),
         }
     }
 }
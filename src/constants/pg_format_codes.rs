@@ -0,0 +1,61 @@
+//! Format codes for parameters and result columns, found here: https://www.postgresql.org/docs/current/protocol-overview.html#PROTOCOL-FORMAT-CODES
+
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PgFormatCode {
+    Text,
+    Binary,
+}
+
+impl PgFormatCode {
+    pub fn from_code(code: i16) -> Result<PgFormatCode, PgFormatCodeError> {
+        match code {
+            0 => Ok(PgFormatCode::Text),
+            1 => Ok(PgFormatCode::Binary),
+            c => Err(PgFormatCodeError::Unknown(c)),
+        }
+    }
+
+    pub const fn value(self) -> i16 {
+        match self {
+            PgFormatCode::Text => 0,
+            PgFormatCode::Binary => 1,
+        }
+    }
+
+    /// No codes means everything is text and a single code applies to every column
+    pub fn for_column(codes: &[PgFormatCode], column: usize) -> PgFormatCode {
+        match codes.len() {
+            0 => PgFormatCode::Text,
+            1 => codes[0],
+            _ => codes.get(column).copied().unwrap_or(PgFormatCode::Text),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PgFormatCodeError {
+    #[error("Unknown format code {0}")]
+    Unknown(i16),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_column() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(PgFormatCode::for_column(&[], 3), PgFormatCode::Text);
+        assert_eq!(
+            PgFormatCode::for_column(&[PgFormatCode::Binary], 3),
+            PgFormatCode::Binary
+        );
+        assert_eq!(
+            PgFormatCode::for_column(&[PgFormatCode::Text, PgFormatCode::Binary], 1),
+            PgFormatCode::Binary
+        );
+        assert!(PgFormatCode::from_code(2).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+//! Transaction status indicators sent with ReadyForQuery, found here: https://www.postgresql.org/docs/current/protocol-message-formats.html
+
+use bytes::Bytes;
+
+pub enum PgTransactionStatus {
+    Idle,
+    InTransaction,
+    Failed,
+}
+
+impl PgTransactionStatus {
+    pub const fn value(self) -> Bytes {
+        use PgTransactionStatus::*;
+        match self {
+            Idle => Bytes::from_static(b"I"),
+            InTransaction => Bytes::from_static(b"T"),
+            Failed => Bytes::from_static(b"E"),
+        }
+    }
+}
@@ -6,30 +6,38 @@ use std::sync::Arc;
 pub mod pg_attribute;
 pub mod pg_class;
 pub mod pg_constraint;
+pub mod pg_enum;
 pub mod pg_index;
+pub mod pg_type;
 
 #[derive(Copy, Clone)]
 pub enum SystemTables {
     PgAttribute, //Columns
     PgClass,     //Tables
     PgConstraint,
+    PgEnum, //User defined enum types
     PgIndex,
+    PgType, //User defined types
 }
 
 impl SystemTables {
     //TODO Should this be removed?
-    pub const VALUES: [SystemTables; 4] = [
+    pub const VALUES: [SystemTables; 6] = [
         SystemTables::PgAttribute,
         SystemTables::PgClass,
         SystemTables::PgConstraint,
+        SystemTables::PgEnum,
         SystemTables::PgIndex,
+        SystemTables::PgType,
     ];
     pub fn value(self) -> Arc<Table> {
         match self {
             SystemTables::PgClass => pg_class::get_table(),
             SystemTables::PgAttribute => pg_attribute::get_table(),
             SystemTables::PgConstraint => pg_constraint::get_table(),
+            SystemTables::PgEnum => pg_enum::get_table(),
             SystemTables::PgIndex => pg_index::get_table(),
+            SystemTables::PgType => pg_type::get_table(),
         }
     }
 }
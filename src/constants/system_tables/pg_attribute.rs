@@ -9,12 +9,14 @@ use uuid::Uuid;
 
 pub const ID: Uuid = Uuid::from_bytes(hex!("EE89957F3E9F482C836DDA6C349AC632"));
 pub const NAME: &str = "pg_attribute";
+pub const OID: u32 = 1249;
 
 pub const COLUMN_CLASS_ID: &str = "class_id";
 pub const COLUMN_NAME: &str = "name";
-pub const COLUMN_SQL_TYPE: &str = "type_name";
+pub const COLUMN_TYPE_OID: &str = "type_oid";
 pub const COLUMN_COLUMN_NUM: &str = "column_num";
 pub const COLUMN_NULLABLE: &str = "nullable";
+pub const COLUMN_TYPE_MODIFIER: &str = "type_modifier";
 
 pub fn get_columns() -> Vec<Attribute> {
     vec![
@@ -31,8 +33,8 @@ pub fn get_columns() -> Vec<Attribute> {
             None,
         ),
         Attribute::new(
-            COLUMN_SQL_TYPE.to_string(),
-            BaseSqlTypesMapper::Text, //TODO join to pg_type instead, for now its a string
+            COLUMN_TYPE_OID.to_string(),
+            BaseSqlTypesMapper::Bigint, //The oid in pg_type, arrays use the array_oid
             Nullable::NotNull,
             None,
         ),
@@ -48,6 +50,12 @@ pub fn get_columns() -> Vec<Attribute> {
             Nullable::NotNull,
             None,
         ),
+        Attribute::new(
+            COLUMN_TYPE_MODIFIER.to_string(),
+            BaseSqlTypesMapper::Integer, //Packed like postgres' atttypmod, see TypeModifier
+            Nullable::Null,
+            None,
+        ),
     ]
 }
 
@@ -65,6 +73,7 @@ pub fn get_table() -> Arc<Table> {
     let index = get_index(&columns);
     Arc::new(Table::new(
         ID,
+        OID,
         NAME.to_string(),
         get_columns(),
         vec![Constraint::PrimaryKey(PrimaryKeyConstraint {
@@ -9,9 +9,11 @@ use uuid::Uuid;
 
 pub const ID: Uuid = Uuid::from_bytes(hex!("EE919E33D9054F4889537EBB6CC911EB"));
 pub const NAME: &str = "pg_class";
+pub const OID: u32 = 1259;
 
 pub const COLUMN_ID: &str = "id";
 pub const COLUMN_NAME: &str = "name";
+pub const COLUMN_OID: &str = "oid";
 
 pub fn get_columns() -> Vec<Attribute> {
     vec![
@@ -27,6 +29,12 @@ pub fn get_columns() -> Vec<Attribute> {
             Nullable::NotNull,
             None,
         ),
+        Attribute::new(
+            COLUMN_OID.to_string(),
+            BaseSqlTypesMapper::Bigint,
+            Nullable::NotNull,
+            None,
+        ),
     ]
 }
 
@@ -44,6 +52,7 @@ pub fn get_table() -> Arc<Table> {
     let index = get_index(&columns);
     Arc::new(Table::new(
         ID,
+        OID,
         NAME.to_string(),
         columns,
         vec![Constraint::PrimaryKey(PrimaryKeyConstraint {
@@ -0,0 +1,58 @@
+//! The labels of enums in pg_type, unlike postgres each type is a single row with its labels in declaration order
+use crate::constants::Nullable;
+use crate::engine::objects::{
+    types::{BaseSqlTypesMapper, SqlTypeDefinition},
+    Attribute, Constraint, Index, PrimaryKeyConstraint, Table,
+};
+use hex_literal::hex;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const ID: Uuid = Uuid::from_bytes(hex!("8C5D1E7A4B2F4E61A09D3F6B2C71E845"));
+pub const NAME: &str = "pg_enum";
+pub const OID: u32 = 3501;
+
+pub const COLUMN_TYPE_ID: &str = "type_id";
+pub const COLUMN_LABELS: &str = "labels";
+
+pub fn get_columns() -> Vec<Attribute> {
+    vec![
+        Attribute::new(
+            COLUMN_TYPE_ID.to_string(),
+            BaseSqlTypesMapper::Uuid,
+            Nullable::NotNull,
+            None,
+        ),
+        Attribute::new(
+            COLUMN_LABELS.to_string(),
+            BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Text)),
+            Nullable::NotNull,
+            None,
+        ),
+    ]
+}
+
+pub fn get_index(attrs: &[Attribute]) -> Arc<Index> {
+    Arc::new(Index {
+        id: Uuid::from_bytes(hex!("2E7F90C1D3A84B5C9E61F04A8B3D27C6")),
+        name: NAME.to_string() + "_type_id_index",
+        columns: Arc::new(SqlTypeDefinition::new(&[attrs[0].clone()])),
+        unique: true,
+    })
+}
+
+pub fn get_table() -> Arc<Table> {
+    let columns = get_columns();
+    let index = get_index(&columns);
+    Arc::new(Table::new(
+        ID,
+        OID,
+        NAME.to_string(),
+        columns,
+        vec![Constraint::PrimaryKey(PrimaryKeyConstraint {
+            name: NAME.to_string() + "_primary_key",
+            index: index.clone(),
+        })],
+        vec![index],
+    ))
+}
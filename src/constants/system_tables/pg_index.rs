@@ -9,6 +9,7 @@ use uuid::Uuid;
 
 pub const ID: Uuid = Uuid::from_bytes(hex!("3AB3B076A0EA46E186130F088D06FA02"));
 pub const NAME: &str = "pg_index";
+pub const OID: u32 = 2610;
 
 pub const COLUMN_ID: &str = "id";
 pub const COLUMN_CLASS_ID: &str = "class_id";
@@ -65,6 +66,7 @@ pub fn get_table() -> Arc<Table> {
     let index = get_index(&columns);
     Arc::new(Table::new(
         ID,
+        OID,
         NAME.to_string(),
         columns,
         vec![Constraint::PrimaryKey(PrimaryKeyConstraint {
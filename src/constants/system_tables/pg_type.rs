@@ -0,0 +1,91 @@
+//! Every type a column can have, the built in ones are bootstrapped from TypeDefinitions
+use crate::constants::Nullable;
+use crate::engine::objects::{
+    types::{BaseSqlTypesMapper, SqlTypeDefinition},
+    Attribute, Constraint, Index, PrimaryKeyConstraint, Table,
+};
+use hex_literal::hex;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const ID: Uuid = Uuid::from_bytes(hex!("6D1F3B2A9C0E4F7B8A51D2E4C6B89F03"));
+pub const NAME: &str = "pg_type";
+pub const OID: u32 = 1247;
+
+//Same names as postgres except for id, which pg_enum rows point to
+pub const COLUMN_ID: &str = "id";
+pub const COLUMN_OID: &str = "oid";
+pub const COLUMN_TYPNAME: &str = "typname";
+pub const COLUMN_TYPLEN: &str = "typlen";
+pub const COLUMN_TYPTYPE: &str = "typtype";
+pub const COLUMN_TYPARRAY: &str = "typarray";
+
+/// The typtype of the built in types
+pub const TYPTYPE_BASE: &str = "b";
+pub const TYPTYPE_ENUM: &str = "e";
+
+pub fn get_columns() -> Vec<Attribute> {
+    vec![
+        Attribute::new(
+            COLUMN_ID.to_string(),
+            BaseSqlTypesMapper::Uuid,
+            Nullable::NotNull,
+            None,
+        ),
+        Attribute::new(
+            COLUMN_OID.to_string(),
+            BaseSqlTypesMapper::Bigint, //Postgres oids are unsigned 32 bit
+            Nullable::NotNull,
+            None,
+        ),
+        Attribute::new(
+            COLUMN_TYPNAME.to_string(),
+            BaseSqlTypesMapper::Text,
+            Nullable::NotNull,
+            None,
+        ),
+        Attribute::new(
+            COLUMN_TYPLEN.to_string(),
+            BaseSqlTypesMapper::Smallint, //Variable length types are -1
+            Nullable::NotNull,
+            None,
+        ),
+        Attribute::new(
+            COLUMN_TYPTYPE.to_string(),
+            BaseSqlTypesMapper::Text,
+            Nullable::NotNull,
+            None,
+        ),
+        Attribute::new(
+            COLUMN_TYPARRAY.to_string(),
+            BaseSqlTypesMapper::Bigint,
+            Nullable::NotNull,
+            None,
+        ),
+    ]
+}
+
+pub fn get_index(attrs: &[Attribute]) -> Arc<Index> {
+    Arc::new(Index {
+        id: Uuid::from_bytes(hex!("C4A7E2915B3D4E08B6F19A2D7E3C5B41")),
+        name: NAME.to_string() + "_name_index",
+        columns: Arc::new(SqlTypeDefinition::new(&[attrs[2].clone()])),
+        unique: true,
+    })
+}
+
+pub fn get_table() -> Arc<Table> {
+    let columns = get_columns();
+    let index = get_index(&columns);
+    Arc::new(Table::new(
+        ID,
+        OID,
+        NAME.to_string(),
+        columns,
+        vec![Constraint::PrimaryKey(PrimaryKeyConstraint {
+            name: NAME.to_string() + "_primary_key",
+            index: index.clone(),
+        })],
+        vec![index],
+    ))
+}
@@ -1,23 +1,270 @@
-//! This defines all the system types so we can bootstrap the system
+//! This defines all the built in types so we can bootstrap pg_type.
+//!
+//! Their rows are written to pg_type the first time the database starts, see Executor::bootstrap.
+//! OIDs match postgres so clients can decode them: https://github.com/postgres/postgres/blob/master/src/include/catalog/pg_type.dat
 
+use crate::engine::objects::types::BaseSqlTypesMapper;
 use hex_literal::hex;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
 
-use super::super::engine::objects::SqlType;
+/// Same as postgres' FirstNormalObjectId, everything below is reserved for built in objects
+pub const FIRST_USER_OID: u32 = 16384;
 
-#[derive(Copy,Clone)]
+pub struct TypeDefinition {
+    pub id: Uuid,
+    pub name: &'static str,
+    pub oid: u32,
+    pub array_oid: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum TypeDefinitions {
-    UuidType,
-    TextType
+    Bool,
+    Bytea,
+    Int8,
+    Int2,
+    Int4,
+    Text,
+    Float4,
+    Float8,
+    Bpchar,
+    Varchar,
+    Date,
+    Time,
+    Timestamp,
+    TimestampTz,
+    Interval,
+    Numeric,
+    Uuid,
+    Jsonb,
 }
 
 impl TypeDefinitions {
-    pub const values: [TypeDefinitions; 2] = [TypeDefinitions::UuidType, TypeDefinitions::TextType];
-    pub fn value(self) -> Box<dyn SqlType> {
-        use TypeDefinitions::*;
+    pub const VALUES: [TypeDefinitions; 18] = [
+        TypeDefinitions::Bool,
+        TypeDefinitions::Bytea,
+        TypeDefinitions::Int8,
+        TypeDefinitions::Int2,
+        TypeDefinitions::Int4,
+        TypeDefinitions::Text,
+        TypeDefinitions::Float4,
+        TypeDefinitions::Float8,
+        TypeDefinitions::Bpchar,
+        TypeDefinitions::Varchar,
+        TypeDefinitions::Date,
+        TypeDefinitions::Time,
+        TypeDefinitions::Timestamp,
+        TypeDefinitions::TimestampTz,
+        TypeDefinitions::Interval,
+        TypeDefinitions::Numeric,
+        TypeDefinitions::Uuid,
+        TypeDefinitions::Jsonb,
+    ];
+
+    pub fn value(self) -> TypeDefinition {
+        let (id, name, oid, array_oid) = match self {
+            TypeDefinitions::Bool => (hex!("A71398D0D56F42B1A87696310480581E"), "bool", 16, 1000),
+            TypeDefinitions::Bytea => (hex!("FF6CF0281C1A45BF866BBBFA25531E17"), "bytea", 17, 1001),
+            TypeDefinitions::Int8 => (hex!("E46E3E7B80364DBFB35474C4753A3C62"), "int8", 20, 1016),
+            TypeDefinitions::Int2 => (hex!("F7C9BE6C78374F84909536E1BBAC8DD2"), "int2", 21, 1005),
+            TypeDefinitions::Int4 => (hex!("0E4A812B8FFC4141AA2230914853F483"), "int4", 23, 1007),
+            TypeDefinitions::Text => (hex!("EB53D9A6969441C7AFD0D3AB5DB0362C"), "text", 25, 1009),
+            TypeDefinitions::Float4 => (
+                hex!("A514A6D861864B0FA14B9F7E7B5DB9FF"),
+                "float4",
+                700,
+                1021,
+            ),
+            TypeDefinitions::Float8 => (
+                hex!("4A757985D7804B79AFFA434FD0F23F31"),
+                "float8",
+                701,
+                1022,
+            ),
+            TypeDefinitions::Bpchar => (
+                hex!("00EAF604EF4D437399F80BF13257FDA2"),
+                "bpchar",
+                1042,
+                1014,
+            ),
+            TypeDefinitions::Varchar => (
+                hex!("EA04004CE69B4367B3DFB4E25836529D"),
+                "varchar",
+                1043,
+                1015,
+            ),
+            TypeDefinitions::Date => (hex!("42F715719AB9419F9FCC4056B3678F50"), "date", 1082, 1182),
+            TypeDefinitions::Time => (hex!("B07A60856499441EB44B57B9FAC871D5"), "time", 1083, 1183),
+            TypeDefinitions::Timestamp => (
+                hex!("251E1385D6BF4DCB8743F342E04BA881"),
+                "timestamp",
+                1114,
+                1115,
+            ),
+            TypeDefinitions::TimestampTz => (
+                hex!("D5844921F55B4EE1B471E97FB3865927"),
+                "timestamptz",
+                1184,
+                1185,
+            ),
+            TypeDefinitions::Interval => (
+                hex!("377018ADFDB246D88DB7EA7C587DBE34"),
+                "interval",
+                1186,
+                1187,
+            ),
+            TypeDefinitions::Numeric => (
+                hex!("DD8883C81B9E40AF9A946A031CA5EB12"),
+                "numeric",
+                1700,
+                1231,
+            ),
+            TypeDefinitions::Uuid => (hex!("E316B264542842D5BA63880BB422E8A9"), "uuid", 2950, 2951),
+            TypeDefinitions::Jsonb => (
+                hex!("B8E8A9CE50B645E2A9DD998C26D7B92B"),
+                "jsonb",
+                3802,
+                3807,
+            ),
+        };
+        TypeDefinition {
+            id: Uuid::from_bytes(id),
+            name,
+            oid,
+            array_oid,
+        }
+    }
+
+    /// varchar and bpchar are stored as text, their lengths are a TypeModifier on the column
+    pub fn sql_type(self) -> BaseSqlTypesMapper {
         match self {
-            UuidType => {
-                UuidType::new_existing(uuid::Uuid::from_bytes(hex!("C0CE4AFB34D949BFA02E0759C91D605A")), "uuid".to_string(), Vec::new())
+            TypeDefinitions::Bool => BaseSqlTypesMapper::Bool,
+            TypeDefinitions::Bytea => BaseSqlTypesMapper::Bytea,
+            TypeDefinitions::Int8 => BaseSqlTypesMapper::Bigint,
+            TypeDefinitions::Int2 => BaseSqlTypesMapper::Smallint,
+            TypeDefinitions::Int4 => BaseSqlTypesMapper::Integer,
+            TypeDefinitions::Text | TypeDefinitions::Bpchar | TypeDefinitions::Varchar => {
+                BaseSqlTypesMapper::Text
             }
+            TypeDefinitions::Float4 => BaseSqlTypesMapper::Real,
+            TypeDefinitions::Float8 => BaseSqlTypesMapper::Double,
+            TypeDefinitions::Date => BaseSqlTypesMapper::Date,
+            TypeDefinitions::Time => BaseSqlTypesMapper::Time,
+            TypeDefinitions::Timestamp => BaseSqlTypesMapper::Timestamp,
+            TypeDefinitions::TimestampTz => BaseSqlTypesMapper::TimestampTz,
+            TypeDefinitions::Interval => BaseSqlTypesMapper::Interval,
+            TypeDefinitions::Numeric => BaseSqlTypesMapper::Numeric,
+            TypeDefinitions::Uuid => BaseSqlTypesMapper::Uuid,
+            TypeDefinitions::Jsonb => BaseSqlTypesMapper::Jsonb,
         }
     }
-}
\ No newline at end of file
+
+    /// Same as pg_type.typlen, variable length types are -1
+    pub fn type_len(sql_type: &BaseSqlTypesMapper) -> i16 {
+        match sql_type {
+            BaseSqlTypesMapper::Bool => 1,
+            BaseSqlTypesMapper::Smallint => 2,
+            BaseSqlTypesMapper::Integer | BaseSqlTypesMapper::Real | BaseSqlTypesMapper::Date => 4,
+            BaseSqlTypesMapper::Bigint
+            | BaseSqlTypesMapper::Double
+            | BaseSqlTypesMapper::Time
+            | BaseSqlTypesMapper::Timestamp
+            | BaseSqlTypesMapper::TimestampTz => 8,
+            BaseSqlTypesMapper::Interval | BaseSqlTypesMapper::Uuid => 16,
+            _ => -1,
+        }
+    }
+
+    /// Accepts every spelling parse_type does, arrays and user defined types are not built in
+    pub fn from_name(name: &str) -> Option<TypeDefinitions> {
+        match name.to_lowercase().as_str() {
+            "varchar" | "character varying" => Some(TypeDefinitions::Varchar),
+            "char" | "character" | "bpchar" => Some(TypeDefinitions::Bpchar),
+            _ => TypeDefinitions::from_sql_type(&BaseSqlTypesMapper::from_str(name).ok()?),
+        }
+    }
+
+    pub fn from_id(id: Uuid) -> Option<TypeDefinitions> {
+        TypeDefinitions::VALUES
+            .iter()
+            .find(|t| t.value().id == id)
+            .copied()
+    }
+
+    pub fn from_sql_type(sql_type: &BaseSqlTypesMapper) -> Option<TypeDefinitions> {
+        TypeDefinitions::VALUES
+            .iter()
+            .find(|t| t.sql_type() == *sql_type)
+            .copied()
+    }
+
+    /// Also finds array types, the bool is set if the oid was for an array of the type
+    pub fn from_oid(oid: u32) -> Option<(TypeDefinitions, bool)> {
+        TypeDefinitions::VALUES.iter().find_map(|t| {
+            let definition = t.value();
+            match oid {
+                o if o == definition.oid => Some((*t, false)),
+                o if o == definition.array_oid => Some((*t, true)),
+                _ => None,
+            }
+        })
+    }
+
+    /// The column type for an oid, arrays included
+    pub fn sql_type_for_oid(oid: u32) -> Option<BaseSqlTypesMapper> {
+        TypeDefinitions::from_oid(oid).map(|(t, array)| match array {
+            true => BaseSqlTypesMapper::Array(Arc::new(t.sql_type())),
+            false => t.sql_type(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_type_definitions() {
+        let mut ids = HashSet::new();
+        let mut oids = HashSet::new();
+        for t in TypeDefinitions::VALUES {
+            let definition = t.value();
+            assert!(ids.insert(definition.id));
+            assert!(oids.insert(definition.oid));
+            assert!(oids.insert(definition.array_oid));
+            assert!(definition.array_oid < FIRST_USER_OID);
+            assert_eq!(TypeDefinitions::from_name(definition.name), Some(t));
+            assert_eq!(TypeDefinitions::from_id(definition.id), Some(t));
+        }
+
+        assert_eq!(
+            TypeDefinitions::from_name("integer"),
+            Some(TypeDefinitions::Int4)
+        );
+        assert_eq!(
+            TypeDefinitions::from_name("CHARACTER"),
+            Some(TypeDefinitions::Bpchar)
+        );
+        assert_eq!(TypeDefinitions::from_name("int[]"), None);
+        assert_eq!(TypeDefinitions::from_name("mood"), None);
+        assert_eq!(
+            TypeDefinitions::from_sql_type(&BaseSqlTypesMapper::Text),
+            Some(TypeDefinitions::Text)
+        );
+
+        assert_eq!(
+            TypeDefinitions::sql_type_for_oid(1007),
+            Some(BaseSqlTypesMapper::Array(Arc::new(
+                BaseSqlTypesMapper::Integer
+            )))
+        );
+        assert_eq!(
+            TypeDefinitions::sql_type_for_oid(1043),
+            Some(BaseSqlTypesMapper::Text)
+        );
+        assert_eq!(TypeDefinitions::sql_type_for_oid(FIRST_USER_OID), None);
+    }
+}
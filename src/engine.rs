@@ -10,7 +10,8 @@ pub mod io;
 use futures::pin_mut;
 use io::{RowManager, VisibleRowManager};
 pub mod objects;
-use objects::ParseTree;
+use objects::types::{BaseSqlTypesMapper, SqlTypeDefinition, TypeModifier};
+use objects::{ColumnOrigin, CommandType, ParseExpressionError, ParseTree, SqlTuple};
 
 pub mod planner;
 pub use planner::Planner;
@@ -37,8 +38,7 @@ use self::io::block_layer::file_manager2::FileManager2;
 use self::io::block_layer::free_space_manager::FreeSpaceManager;
 use self::io::ConstraintManager;
 use self::io::IndexManager;
-use self::objects::QueryResult;
-use std::ops::Deref;
+use self::objects::{QueryResult, QueryTree};
 use std::sync::Arc;
 use thiserror::Error;
 use tokio_stream::StreamExt;
@@ -52,29 +52,55 @@ pub struct Engine {
 impl Engine {
     pub fn new(file_manager: Arc<FileManager2>, tran_manager: TransactionManager) -> Engine {
         let fsm = FreeSpaceManager::new(file_manager.clone());
-        let vis_row_man =
-            VisibleRowManager::new(RowManager::new(file_manager.clone(), fsm), tran_manager);
+        let vis_row_man = VisibleRowManager::new(
+            RowManager::new(file_manager.clone(), fsm),
+            tran_manager.clone(),
+        );
+        //Same name as postgres uses for its temp files
+        let temp_dir = file_manager.data_dir().join("pgsql_tmp");
         let index_manager = IndexManager::new(file_manager);
         let con_man = ConstraintManager::new(index_manager, vis_row_man.clone());
         Engine {
-            analyzer: Analyzer::new(vis_row_man),
-            executor: Executor::new(con_man),
+            analyzer: Analyzer::new(vis_row_man, tran_manager),
+            executor: Executor::new(con_man, temp_dir),
         }
     }
 
+    /// Sets how many bytes of rows each sort can hold before spilling to disk
+    pub fn with_sort_memory(mut self, sort_memory: usize) -> Engine {
+        self.executor = self.executor.with_sort_memory(sort_memory);
+        self
+    }
+
+    /// Fills in the catalog rows a new database starts with, does nothing if they are already there
+    pub async fn bootstrap(&mut self, tran_id: TransactionId) -> Result<(), EngineError> {
+        Ok(self.executor.bootstrap(tran_id).await?)
+    }
+
     pub async fn process_query(
         &mut self,
         tran_id: TransactionId,
         query: String,
     ) -> Result<QueryResult, EngineError> {
-        //Parse it - I need to figure out if I should do statement splitting here
+        //Parse it, simple queries with several statements are split up by the ClientProcessor
         let parse_tree = SqlParser::parse(&query)?;
 
-        if Engine::should_bypass_planning(&parse_tree) {
+        self.process_parse_tree(tran_id, parse_tree).await
+    }
+
+    pub async fn process_parse_tree(
+        &mut self,
+        tran_id: TransactionId,
+        parse_tree: ParseTree,
+    ) -> Result<QueryResult, EngineError> {
+        if let Some(command_type) = Engine::utility_command(&parse_tree) {
             let output_rows = self.executor.execute_utility(tran_id, parse_tree).await?;
             return Ok(QueryResult {
-                columns: vec![],
+                command_type,
+                columns: SqlTypeDefinition(vec![]),
+                origins: vec![],
                 rows: output_rows,
+                row_count: 0,
             });
         }
 
@@ -96,16 +122,90 @@ impl Engine {
             result.push(value?);
         }
 
-        let output_columns = query_tree.targets.iter().map(|t| t.0.clone()).collect();
-
+        //Inserts, updates and deletes hand back the rows they changed
+        let (columns, origins) = Engine::output_columns(&query_tree);
+        let result = Engine::pad_char_columns(&origins, result);
         Ok(QueryResult {
-            columns: output_columns,
+            command_type: query_tree.command_type,
+            columns,
+            origins,
+            row_count: result.len(),
             rows: result,
         })
     }
 
-    fn should_bypass_planning(parse_tree: &ParseTree) -> bool {
-        matches!(parse_tree.deref(), ParseTree::CreateTable(_))
+    /// Works out the parameter types and output columns of a prepared statement without running it
+    pub async fn describe(
+        &mut self,
+        tran_id: TransactionId,
+        parse_tree: ParseTree,
+        declared_parameters: Vec<Option<BaseSqlTypesMapper>>,
+    ) -> Result<
+        (
+            Vec<BaseSqlTypesMapper>,
+            SqlTypeDefinition,
+            Vec<ColumnOrigin>,
+        ),
+        EngineError,
+    > {
+        if Engine::utility_command(&parse_tree).is_some()
+            || matches!(parse_tree, ParseTree::Transaction(_))
+        {
+            return Ok((vec![], SqlTypeDefinition(vec![]), vec![]));
+        }
+
+        let (parameters, query_tree) = self
+            .analyzer
+            .describe(tran_id, parse_tree, declared_parameters)
+            .await?;
+
+        let (columns, origins) = Engine::output_columns(&query_tree);
+        Ok((parameters, columns, origins))
+    }
+
+    //Only selects hand rows back to the client
+    fn output_columns(query_tree: &QueryTree) -> (SqlTypeDefinition, Vec<ColumnOrigin>) {
+        match query_tree.command_type {
+            CommandType::Select => (
+                query_tree.targets.as_ref().clone(),
+                query_tree.target_origins.clone(),
+            ),
+            _ => (SqlTypeDefinition(vec![]), vec![]),
+        }
+    }
+
+    /// char(n) values are stored without their trailing spaces so comparisons ignore them, the client still sees n characters
+    fn pad_char_columns(origins: &[ColumnOrigin], rows: Vec<SqlTuple>) -> Vec<SqlTuple> {
+        if !origins
+            .iter()
+            .any(|o| matches!(o.modifier, Some(TypeModifier::Char(_))))
+        {
+            return rows;
+        }
+        rows.into_iter()
+            .map(|row| {
+                SqlTuple(
+                    row.0
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, value)| match origins.get(i).and_then(|o| o.modifier) {
+                            Some(modifier) => value.map(|v| modifier.pad(v)),
+                            None => value,
+                        })
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    /// Utility statements skip planning and go straight to the executor
+    fn utility_command(parse_tree: &ParseTree) -> Option<CommandType> {
+        match parse_tree {
+            ParseTree::CreateTable(_) => Some(CommandType::CreateTable),
+            ParseTree::CreateType(_) => Some(CommandType::CreateType),
+            ParseTree::DropType(_) => Some(CommandType::DropType),
+            _ => None,
+        }
     }
 }
 
@@ -116,6 +216,8 @@ pub enum EngineError {
     #[error(transparent)]
     ExecutorError(#[from] ExecutorError),
     #[error(transparent)]
+    ParseExpressionError(#[from] ParseExpressionError),
+    #[error(transparent)]
     QueryNotUtf8(#[from] std::string::FromUtf8Error),
     #[error(transparent)]
     RewriterError(#[from] RewriterError),
@@ -141,18 +243,22 @@ mod tests {
         let insert_test = "insert into foo values('test text')".to_string();
         let select_test = "select bar from foo".to_string();
 
-        let mut transaction_manager = TransactionManager::new();
-        let mut engine = Engine::new(
-            Arc::new(FileManager2::new(tmp_dir)?),
-            transaction_manager.clone(),
-        );
+        let fm = Arc::new(FileManager2::new(tmp_dir)?);
+        let mut transaction_manager = TransactionManager::new(fm.clone()).await?;
+        let mut engine = Engine::new(fm, transaction_manager.clone());
 
         let tran = transaction_manager.start_trans().await?;
-        engine.process_query(tran, create_test).await?;
+        engine.bootstrap(tran).await?;
+        let created = engine.process_query(tran, create_test).await?;
+        assert_eq!(created.command_type, CommandType::CreateTable);
         transaction_manager.commit_trans(tran).await?;
 
-        engine.process_query(tran, insert_test).await?;
-        engine.process_query(tran, select_test).await?;
+        let inserted = engine.process_query(tran, insert_test).await?;
+        assert_eq!(inserted.command_type, CommandType::Insert);
+        assert_eq!(inserted.row_count, 1);
+        let selected = engine.process_query(tran, select_test).await?;
+        assert_eq!(selected.command_type, CommandType::Select);
+        assert_eq!(selected.row_count, 1);
 
         Ok(())
     }
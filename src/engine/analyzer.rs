@@ -1,31 +1,48 @@
 //! The analyzer should check that tables and columns exist before allowing a query to proceed.
 //! More features will come I'm sure
 mod definition_lookup;
-use definition_lookup::{DefinitionLookup, DefinitionLookupError};
+use definition_lookup::DefinitionLookup;
+pub use definition_lookup::DefinitionLookupError;
 
-use crate::constants::Nullable;
-use crate::engine::objects::{JoinType, SqlTuple};
+use crate::constants::{Nullable, TypeDefinitions};
+use crate::engine::objects::SqlTuple;
 
 use super::io::VisibleRowManager;
-use super::objects::types::{BaseSqlTypes, BaseSqlTypesError, SqlTypeDefinition};
+use super::objects::types::{
+    BaseSqlTypes, BaseSqlTypesError, BaseSqlTypesMapper, Jsonb, SqlTypeDefinition, TypeModifier,
+};
 use super::objects::{
-    Attribute, CommandType, ParseExpression, ParseTree, QueryTree, RangeRelation,
-    RangeRelationTable, RawInsertCommand, RawSelectCommand, Table,
+    Aggregate, AggregateFunction, Attribute, BinaryOperator, ColumnOrigin, CommandType, Expression,
+    ExpressionError, ParseExpression, ParseTree, QueryTree, RangeRelation, RangeRelationTable,
+    RawDeleteCommand, RawInsertCommand, RawSelectCommand, RawTableReference, RawUpdateCommand,
+    ScalarFunction, SortKey, SortType, Table,
 };
-use super::transactions::TransactionId;
+use super::transactions::{TransactionId, TransactionManager};
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::sync::Arc;
 use thiserror::Error;
 
 #[derive(Clone)]
 pub struct Analyzer {
     dl: DefinitionLookup,
+    tran_manager: TransactionManager,
+}
+
+/// What a single statement's analysis needs beyond the parse tree
+struct StatementContext {
+    parameters: Vec<Option<BaseSqlTypesMapper>>,
+    ///now() and current_timestamp are the start of the transaction, same as postgres.
+    ///Only known while the transaction is in progress.
+    transaction_start: Option<DateTime<Utc>>,
 }
 
 impl Analyzer {
-    pub fn new(vis_row_man: VisibleRowManager) -> Analyzer {
+    pub fn new(vis_row_man: VisibleRowManager, tran_manager: TransactionManager) -> Analyzer {
         Analyzer {
             dl: DefinitionLookup::new(vis_row_man),
+            tran_manager,
         }
     }
 
@@ -33,10 +50,56 @@ impl Analyzer {
         &self,
         tran_id: TransactionId,
         parse_tree: ParseTree,
+    ) -> Result<QueryTree, AnalyzerError> {
+        //Parameters must have been bound by now, see ParseTree::bind_parameters
+        let mut context = StatementContext {
+            parameters: vec![],
+            transaction_start: self.tran_manager.start_time(tran_id).await.ok(),
+        };
+        let query_tree = self
+            .analyze_with_parameters(tran_id, parse_tree, &mut context)
+            .await?;
+        if !context.parameters.is_empty() {
+            return Err(AnalyzerError::UnboundParameter(context.parameters.len()));
+        }
+        Ok(query_tree)
+    }
+
+    /// Analyzes a prepared statement before its parameters are known, returning the type of each parameter.
+    ///
+    /// Parameters without a declared type take the type of whatever they are used with, same as literals.
+    pub async fn describe(
+        &self,
+        tran_id: TransactionId,
+        parse_tree: ParseTree,
+        declared_parameters: Vec<Option<BaseSqlTypesMapper>>,
+    ) -> Result<(Vec<BaseSqlTypesMapper>, QueryTree), AnalyzerError> {
+        let mut context = StatementContext {
+            parameters: declared_parameters,
+            transaction_start: self.tran_manager.start_time(tran_id).await.ok(),
+        };
+        let query_tree = self
+            .analyze_with_parameters(tran_id, parse_tree, &mut context)
+            .await?;
+        let parameters = context
+            .parameters
+            .into_iter()
+            .map(|p| p.unwrap_or(BaseSqlTypesMapper::Text))
+            .collect();
+        Ok((parameters, query_tree))
+    }
+
+    async fn analyze_with_parameters(
+        &self,
+        tran_id: TransactionId,
+        parse_tree: ParseTree,
+        context: &mut StatementContext,
     ) -> Result<QueryTree, AnalyzerError> {
         match parse_tree {
-            ParseTree::Insert(i) => self.insert_processing(tran_id, i).await,
-            ParseTree::Select(i) => self.select_processing(tran_id, i).await,
+            ParseTree::Delete(d) => self.delete_processing(tran_id, d, context).await,
+            ParseTree::Insert(i) => self.insert_processing(tran_id, i, context).await,
+            ParseTree::Select(i) => self.select_processing(tran_id, *i, context).await,
+            ParseTree::Update(u) => self.update_processing(tran_id, u, context).await,
             _ => Err(AnalyzerError::NotImplemented()),
         }
     }
@@ -45,6 +108,7 @@ impl Analyzer {
         &self,
         tran_id: TransactionId,
         raw_insert: RawInsertCommand,
+        context: &mut StatementContext,
     ) -> Result<QueryTree, AnalyzerError> {
         let definition = self
             .dl
@@ -55,6 +119,7 @@ impl Analyzer {
             definition.clone(),
             raw_insert.provided_columns,
             raw_insert.provided_values,
+            context,
         )?;
 
         let anon_tbl = RangeRelation::AnonymousTable(Arc::new(vec![val_cols]));
@@ -68,8 +133,18 @@ impl Analyzer {
             command_type: CommandType::Insert,
             //Insert columns will be the target
             targets: Arc::new(output_type),
-            range_tables: vec![target_tbl.clone(), anon_tbl.clone()],
-            joins: vec![(JoinType::Inner, target_tbl, anon_tbl)],
+            target_entries: vec![],
+            target_origins: vec![],
+            range_tables: vec![target_tbl, anon_tbl],
+            qualification: None,
+            assignments: vec![],
+            joins: vec![],
+            group_by: vec![],
+            aggregates: vec![],
+            having: None,
+            sorts: vec![],
+            limit: None,
+            offset: 0,
         })
     }
 
@@ -77,38 +152,961 @@ impl Analyzer {
         &self,
         tran_id: TransactionId,
         raw_select: RawSelectCommand,
+        context: &mut StatementContext,
     ) -> Result<QueryTree, AnalyzerError> {
-        let definition = self.dl.get_definition(tran_id, raw_select.table).await?;
+        let mut scope = vec![self.table_reference(tran_id, raw_select.table).await?];
+
+        //Each join condition can only see the tables joined so far
+        let mut joins = vec![];
+        for raw_join in raw_select.joins {
+            let rrt = self.table_reference(tran_id, raw_join.table).await?;
+            let rrt_name = rrt.alias.as_ref().unwrap_or(&rrt.table.name);
+            if scope
+                .iter()
+                .any(|s| s.alias.as_ref().unwrap_or(&s.table.name) == rrt_name)
+            {
+                return Err(AnalyzerError::DuplicateTableName(rrt_name.clone()));
+            }
+            scope.push(rrt);
+
+            let condition = match raw_join.condition {
+                Some(c) => Some(Arc::new(Analyzer::without_aggregates(
+                    Analyzer::analyze_qualification(&scope, &c, context)?,
+                    "JOIN conditions",
+                )?)),
+                None => None,
+            };
+            joins.push((raw_join.join_type, condition));
+        }
 
         //Need to valid the columns asked for exist
         let mut targets = vec![];
-        'outer: for rcol in raw_select.columns {
-            for c in definition.attributes.as_slice() {
-                if rcol == c.name {
-                    targets.push((c.name.clone(), c.sql_type.clone()));
-                    continue 'outer;
+        let mut target_entries = vec![];
+        let mut target_origins = vec![];
+        for rcol in raw_select.columns {
+            let entry = match &rcol {
+                ParseExpression::FunctionCall(name, args)
+                    if name.eq_ignore_ascii_case("unnest") =>
+                {
+                    Analyzer::analyze_unnest(&scope, name, args, context)?
                 }
+                _ => Analyzer::analyze_expression(&scope, &rcol, None, context)?,
+            };
+            targets.push((Analyzer::output_name(&rcol), entry.sql_type()));
+            target_origins.push(Analyzer::column_origin(&scope, &entry));
+            target_entries.push(Arc::new(entry));
+        }
+
+        let qualification = match raw_select.where_clause {
+            Some(w) => Some(Arc::new(Analyzer::without_aggregates(
+                Analyzer::analyze_qualification(&scope, &w, context)?,
+                "WHERE",
+            )?)),
+            None => None,
+        };
+
+        let mut group_by = vec![];
+        for g in raw_select.group_by {
+            let key = Analyzer::analyze_expression(&scope, &g, None, context)?;
+            group_by.push(Arc::new(Analyzer::without_aggregates(key, "GROUP BY")?));
+        }
+
+        let mut having = match raw_select.having {
+            Some(h) => Some(Arc::new(Analyzer::analyze_qualification(
+                &scope, &h, context,
+            )?)),
+            None => None,
+        };
+
+        let mut sorts = vec![];
+        for order_by in raw_select.order_by {
+            //ORDER BY 2 sorts on the second output column
+            let expression = match order_by.position {
+                Some(p) => p
+                    .checked_sub(1)
+                    .and_then(|p| target_entries.get(p))
+                    .cloned()
+                    .ok_or(AnalyzerError::InvalidSortPosition(p))?,
+                None => Arc::new(Analyzer::analyze_expression(
+                    &scope,
+                    &order_by.expression,
+                    None,
+                    context,
+                )?),
+            };
+            sorts.push(SortKey {
+                expression,
+                sort_type: order_by.sort_type,
+                nulls_first: order_by
+                    .nulls_first
+                    .unwrap_or(order_by.sort_type == SortType::Descending),
+            });
+        }
+
+        //Once grouped everything after the WHERE sees a row per group instead, made of the
+        //group keys followed by the aggregate results
+        let mut aggregates = vec![];
+        let grouped = !group_by.is_empty()
+            || having.is_some()
+            || target_entries.iter().any(|t| t.contains_aggregate())
+            || sorts.iter().any(|s| s.expression.contains_aggregate());
+        if grouped {
+            for entry in target_entries.iter_mut() {
+                *entry = Arc::new(Analyzer::group_expression(
+                    &scope,
+                    entry,
+                    &group_by,
+                    &mut aggregates,
+                )?);
+            }
+            if let Some(h) = having.as_mut() {
+                *h = Arc::new(Analyzer::group_expression(
+                    &scope,
+                    h,
+                    &group_by,
+                    &mut aggregates,
+                )?);
+            }
+            for sort in sorts.iter_mut() {
+                sort.expression = Arc::new(Analyzer::group_expression(
+                    &scope,
+                    &sort.expression,
+                    &group_by,
+                    &mut aggregates,
+                )?);
             }
-            return Err(AnalyzerError::UnknownColumn(rcol));
         }
 
+        let limit = match raw_select.limit {
+            Some(l) => Analyzer::analyze_row_count(&scope, &l, context)?,
+            None => None,
+        };
+        let offset = match raw_select.offset {
+            Some(o) => Analyzer::analyze_row_count(&scope, &o, context)?.unwrap_or(0),
+            None => 0,
+        };
+
         //We should be good to build the query tree if we got here
         Ok(QueryTree {
             command_type: CommandType::Select,
             targets: Arc::new(SqlTypeDefinition(targets)),
-            range_tables: vec![RangeRelation::Table(RangeRelationTable {
-                table: definition,
-                alias: None,
-            })],
+            target_entries,
+            target_origins,
+            range_tables: scope.into_iter().map(RangeRelation::Table).collect(),
+            qualification,
+            assignments: vec![],
+            joins,
+            group_by,
+            aggregates,
+            having,
+            sorts,
+            limit,
+            offset,
+        })
+    }
+
+    async fn table_reference(
+        &self,
+        tran_id: TransactionId,
+        reference: RawTableReference,
+    ) -> Result<RangeRelationTable, AnalyzerError> {
+        Ok(RangeRelationTable {
+            table: self.dl.get_definition(tran_id, reference.name).await?,
+            alias: reference.alias,
+        })
+    }
+
+    async fn update_processing(
+        &self,
+        tran_id: TransactionId,
+        raw_update: RawUpdateCommand,
+        context: &mut StatementContext,
+    ) -> Result<QueryTree, AnalyzerError> {
+        let definition = self
+            .dl
+            .get_definition(tran_id, raw_update.table_name)
+            .await?;
+        let target = RangeRelationTable {
+            table: definition.clone(),
+            alias: None,
+        };
+        let scope = std::slice::from_ref(&target);
+
+        let mut assignments: Vec<(usize, Arc<Expression>)> = vec![];
+        for (column, value) in raw_update.assignments {
+            let offset = definition
+                .get_column_index(&column)
+                .map_err(|_| AnalyzerError::UnknownColumn(column.clone(), column.clone()))?;
+            if assignments.iter().any(|(o, _)| *o == offset) {
+                return Err(AnalyzerError::MultipleAssignments(column));
+            }
+
+            let col_type = &definition.attributes[offset].sql_type;
+            let expr = Analyzer::without_aggregates(
+                Analyzer::analyze_expression(scope, &value, Some(col_type), context)?,
+                "UPDATE",
+            )?;
+            let expr = Analyzer::assignment_cast(expr, col_type)?;
+            assignments.push((offset, Arc::new(expr)));
+        }
+
+        let qualification = match raw_update.where_clause {
+            Some(w) => Some(Arc::new(Analyzer::without_aggregates(
+                Analyzer::analyze_qualification(scope, &w, context)?,
+                "WHERE",
+            )?)),
+            None => None,
+        };
+
+        Ok(QueryTree {
+            command_type: CommandType::Update,
+            targets: definition.sql_type.clone(),
+            target_entries: vec![],
+            target_origins: vec![],
+            range_tables: vec![RangeRelation::Table(target)],
+            qualification,
+            assignments,
+            joins: vec![],
+            group_by: vec![],
+            aggregates: vec![],
+            having: None,
+            sorts: vec![],
+            limit: None,
+            offset: 0,
+        })
+    }
+
+    async fn delete_processing(
+        &self,
+        tran_id: TransactionId,
+        raw_delete: RawDeleteCommand,
+        context: &mut StatementContext,
+    ) -> Result<QueryTree, AnalyzerError> {
+        let definition = self
+            .dl
+            .get_definition(tran_id, raw_delete.table_name)
+            .await?;
+        let target = RangeRelationTable {
+            table: definition.clone(),
+            alias: None,
+        };
+
+        let qualification = match raw_delete.where_clause {
+            Some(w) => Some(Arc::new(Analyzer::without_aggregates(
+                Analyzer::analyze_qualification(std::slice::from_ref(&target), &w, context)?,
+                "WHERE",
+            )?)),
+            None => None,
+        };
+
+        Ok(QueryTree {
+            command_type: CommandType::Delete,
+            targets: definition.sql_type.clone(),
+            target_entries: vec![],
+            target_origins: vec![],
+            range_tables: vec![RangeRelation::Table(target)],
+            qualification,
+            assignments: vec![],
             joins: vec![],
+            group_by: vec![],
+            aggregates: vec![],
+            having: None,
+            sorts: vec![],
+            limit: None,
+            offset: 0,
         })
     }
 
+    /// A qualification (aka WHERE clause) must always produce a boolean
+    fn analyze_qualification(
+        scope: &[RangeRelationTable],
+        expr: &ParseExpression,
+        context: &mut StatementContext,
+    ) -> Result<Expression, AnalyzerError> {
+        let qual =
+            Analyzer::analyze_expression(scope, expr, Some(&BaseSqlTypesMapper::Bool), context)?;
+        Analyzer::expect_type(&qual, &BaseSqlTypesMapper::Bool)?;
+        Ok(qual)
+    }
+
+    /// Resolves the columns and types of a ParseExpression against the tables it will be evaluated on.
+    /// Column offsets assume the rows of the tables in scope are merged together in order.
+    ///
+    /// Literals are untyped until we know what they are being used with, the hint provides that type.
+    /// Parameters are typed the same way, their types are collected into the context and they
+    /// analyze as a NULL of that type.
+    fn analyze_expression(
+        scope: &[RangeRelationTable],
+        expr: &ParseExpression,
+        hint: Option<&BaseSqlTypesMapper>,
+        context: &mut StatementContext,
+    ) -> Result<Expression, AnalyzerError> {
+        match expr {
+            ParseExpression::String(s) => {
+                let target = hint.cloned().unwrap_or(BaseSqlTypesMapper::Text);
+                let value = BaseSqlTypes::parse(target.clone(), s)?;
+                Ok(Expression::Constant(Some(value), target))
+            }
+            ParseExpression::TypedString(sql_type, s) => {
+                let value = BaseSqlTypes::parse(sql_type.clone(), s)?;
+                Ok(Expression::Constant(Some(value), sql_type.clone()))
+            }
+            ParseExpression::Null() => {
+                let target = hint.cloned().unwrap_or(BaseSqlTypesMapper::Text);
+                Ok(Expression::Constant(None, target))
+            }
+            ParseExpression::Parameter(i) => {
+                if context.parameters.len() < *i {
+                    context.parameters.resize(*i, None);
+                }
+                let target = context.parameters[i - 1]
+                    .get_or_insert_with(|| hint.cloned().unwrap_or(BaseSqlTypesMapper::Text))
+                    .clone();
+                Ok(Expression::Constant(None, target))
+            }
+            ParseExpression::Identifier(parts) => {
+                let name = parts.join(".");
+                let (qualifier, column_name) = match parts.as_slice() {
+                    [c] => (None, c.as_str()),
+                    [q, c] => (Some(q.as_str()), c.as_str()),
+                    _ => {
+                        let column_name = parts.last().cloned().unwrap_or_default();
+                        return Err(AnalyzerError::UnknownColumn(name, column_name));
+                    }
+                };
+
+                let mut found = None;
+                let mut offset = 0;
+                for rrt in scope {
+                    let rrt_name = rrt.alias.as_ref().unwrap_or(&rrt.table.name);
+                    if qualifier.is_none() || qualifier == Some(rrt_name.as_str()) {
+                        for (i, attr) in rrt.table.attributes.iter().enumerate() {
+                            if attr.name == column_name {
+                                if found.is_some() {
+                                    return Err(AnalyzerError::AmbiguousColumn(
+                                        name.clone(),
+                                        column_name.to_string(),
+                                    ));
+                                }
+                                found = Some(Expression::Column(offset + i, attr.sql_type.clone()));
+                            }
+                        }
+                    }
+                    offset += rrt.table.attributes.len();
+                }
+
+                found.ok_or_else(|| AnalyzerError::UnknownColumn(name, column_name.to_string()))
+            }
+            ParseExpression::BinaryOperation(left, op, right) => match op {
+                BinaryOperator::And | BinaryOperator::Or => {
+                    let left = Analyzer::analyze_qualification(scope, left, context)?;
+                    let right = Analyzer::analyze_qualification(scope, right, context)?;
+                    Ok(Expression::BinaryOperation(
+                        Box::new(left),
+                        *op,
+                        Box::new(right),
+                    ))
+                }
+                _ => {
+                    //Arithmetic passes the hint through so a lone literal knows its width,
+                    //comparisons always produce a boolean so there is nothing to pass on
+                    let first_hint = match op.is_arithmetic() {
+                        true => Some(
+                            hint.filter(|h| h.is_numeric())
+                                .unwrap_or(&BaseSqlTypesMapper::Integer),
+                        ),
+                        false => None,
+                    };
+
+                    //Untyped literals take on the type of the other side
+                    let (left, right) = if Analyzer::is_literal(left) {
+                        let right =
+                            Analyzer::analyze_expression(scope, right, first_hint, context)?;
+                        let left_hint = Analyzer::literal_hint(left, *op, &right.sql_type(), true);
+                        let left =
+                            Analyzer::analyze_expression(scope, left, Some(&left_hint), context)?;
+                        (left, right)
+                    } else {
+                        let left = Analyzer::analyze_expression(scope, left, first_hint, context)?;
+                        let right_hint =
+                            Analyzer::literal_hint(right, *op, &left.sql_type(), false);
+                        let right =
+                            Analyzer::analyze_expression(scope, right, Some(&right_hint), context)?;
+                        (left, right)
+                    };
+                    let (left, right) = Analyzer::widen_integers(left, right);
+                    let left = Analyzer::trim_char_literal(scope, left, &right);
+                    let right = Analyzer::trim_char_literal(scope, right, &left);
+
+                    let valid = match op.has_result_type() {
+                        true => op
+                            .result_type(&left.sql_type(), &right.sql_type())
+                            .is_some(),
+                        false => left.sql_type() == right.sql_type(),
+                    };
+                    if !valid {
+                        return Err(AnalyzerError::OperatorTypeMismatch(
+                            *op,
+                            left.sql_type(),
+                            right.sql_type(),
+                        ));
+                    }
+
+                    Ok(Expression::BinaryOperation(
+                        Box::new(left),
+                        *op,
+                        Box::new(right),
+                    ))
+                }
+            },
+            ParseExpression::Not(e) => Ok(Expression::Not(Box::new(
+                Analyzer::analyze_qualification(scope, e, context)?,
+            ))),
+            ParseExpression::IsNull(e) => Ok(Expression::IsNull(Box::new(
+                Analyzer::analyze_expression(scope, e, None, context)?,
+            ))),
+            ParseExpression::IsNotNull(e) => Ok(Expression::IsNotNull(Box::new(
+                Analyzer::analyze_expression(scope, e, None, context)?,
+            ))),
+            ParseExpression::FunctionCall(name, args)
+                if ["now", "current_timestamp"].contains(&name.to_lowercase().as_str()) =>
+            {
+                if !args.is_empty() {
+                    return Err(AnalyzerError::WrongArgumentCount(name.clone(), args.len()));
+                }
+                let start = context
+                    .transaction_start
+                    .ok_or_else(|| AnalyzerError::NoTransactionStart(name.clone()))?;
+                Ok(Expression::Constant(
+                    Some(BaseSqlTypes::TimestampTz(start)),
+                    BaseSqlTypesMapper::TimestampTz,
+                ))
+            }
+            ParseExpression::FunctionCall(name, _) if name.eq_ignore_ascii_case("unnest") => {
+                Err(AnalyzerError::UnexpectedSetFunction(name.clone()))
+            }
+            ParseExpression::FunctionCall(name, args)
+                if ScalarFunction::from_name(name).is_some() =>
+            {
+                let function = ScalarFunction::from_name(name)
+                    .ok_or_else(|| AnalyzerError::UnknownFunction(name.clone()))?;
+                Analyzer::analyze_scalar_function(scope, function, name, args, context)
+            }
+            ParseExpression::FunctionCall(name, args) => {
+                let function = AggregateFunction::from_name(name)
+                    .ok_or_else(|| AnalyzerError::UnknownFunction(name.clone()))?;
+
+                let (argument, sql_type) = match (function, args.as_slice()) {
+                    (AggregateFunction::Count, [ParseExpression::Wildcard()]) => {
+                        (None, BaseSqlTypesMapper::Bigint)
+                    }
+                    (_, [arg]) => {
+                        let arg = Analyzer::analyze_expression(scope, arg, None, context)?;
+                        if arg.contains_aggregate() {
+                            return Err(AnalyzerError::NestedAggregate(function));
+                        }
+
+                        let arg_type = arg.sql_type();
+                        let sql_type = match function {
+                            //Same as postgres, counts and sums are 64 bit so they rarely overflow
+                            AggregateFunction::Count => BaseSqlTypesMapper::Bigint,
+                            AggregateFunction::Sum if arg_type.is_integer() => {
+                                BaseSqlTypesMapper::Bigint
+                            }
+                            AggregateFunction::Sum if arg_type.is_numeric() => arg_type,
+                            //Averages of integers are exact, postgres doesn't truncate them either
+                            AggregateFunction::Avg if arg_type.is_integer() => {
+                                BaseSqlTypesMapper::Numeric
+                            }
+                            AggregateFunction::Avg => match arg_type {
+                                BaseSqlTypesMapper::Real | BaseSqlTypesMapper::Double => {
+                                    BaseSqlTypesMapper::Double
+                                }
+                                BaseSqlTypesMapper::Numeric => BaseSqlTypesMapper::Numeric,
+                                _ => {
+                                    return Err(AnalyzerError::AggregateTypeMismatch(
+                                        function, arg_type,
+                                    ))
+                                }
+                            },
+                            AggregateFunction::JsonbAgg => BaseSqlTypesMapper::Jsonb,
+                            AggregateFunction::Max | AggregateFunction::Min
+                                if !matches!(arg_type, BaseSqlTypesMapper::Array(_)) =>
+                            {
+                                arg_type
+                            }
+                            _ => {
+                                return Err(AnalyzerError::AggregateTypeMismatch(
+                                    function, arg_type,
+                                ))
+                            }
+                        };
+                        (Some(Box::new(arg)), sql_type)
+                    }
+                    (_, _) => {
+                        return Err(AnalyzerError::WrongArgumentCount(name.clone(), args.len()))
+                    }
+                };
+
+                Ok(Expression::Aggregate(Box::new(Aggregate {
+                    function,
+                    argument,
+                    sql_type,
+                })))
+            }
+            ParseExpression::Wildcard() => Err(AnalyzerError::UnexpectedWildcard()),
+            ParseExpression::Array(items) => {
+                let element = match hint {
+                    Some(BaseSqlTypesMapper::Array(e)) => e.as_ref().clone(),
+                    _ => Analyzer::array_element_type(scope, items, context)?,
+                };
+                let array_type = BaseSqlTypesMapper::Array(Arc::new(element.clone()));
+                let items = items
+                    .iter()
+                    .map(|i| match i {
+                        ParseExpression::Array(_) => {
+                            Analyzer::analyze_expression(scope, i, Some(&array_type), context)
+                        }
+                        _ => {
+                            let item =
+                                Analyzer::analyze_expression(scope, i, Some(&element), context)?;
+                            Analyzer::assignment_cast(item, &element)
+                        }
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(Expression::Array(items, element))
+            }
+            ParseExpression::Subscript(e, subscripts) => {
+                let e = Analyzer::analyze_expression(scope, e, None, context)?;
+                Analyzer::expect_array(&e)?;
+                let subscripts = subscripts
+                    .iter()
+                    .map(|s| {
+                        let s = Analyzer::analyze_expression(
+                            scope,
+                            s,
+                            Some(&BaseSqlTypesMapper::Integer),
+                            context,
+                        )?;
+                        Analyzer::expect_type(&s, &BaseSqlTypesMapper::Integer)?;
+                        Ok(s)
+                    })
+                    .collect::<Result<_, AnalyzerError>>()?;
+                Ok(Expression::Subscript(Box::new(e), subscripts))
+            }
+            ParseExpression::ArrayComparison(left, op, quantifier, right) => {
+                //The literal takes its type from the other side, the same as a comparison
+                let (left, right) = if Analyzer::is_literal(left) {
+                    let right = Analyzer::analyze_expression(scope, right, None, context)?;
+                    let element = Analyzer::expect_array(&right)?;
+                    let left = Analyzer::analyze_expression(scope, left, Some(&element), context)?;
+                    (left, right)
+                } else {
+                    let left = Analyzer::analyze_expression(scope, left, None, context)?;
+                    let array_type = BaseSqlTypesMapper::Array(Arc::new(left.sql_type()));
+                    let right =
+                        Analyzer::analyze_expression(scope, right, Some(&array_type), context)?;
+                    (left, right)
+                };
+                if Analyzer::expect_array(&right)? != left.sql_type() {
+                    return Err(AnalyzerError::OperatorTypeMismatch(
+                        *op,
+                        left.sql_type(),
+                        right.sql_type(),
+                    ));
+                }
+                Ok(Expression::ArrayComparison(
+                    Box::new(left),
+                    *op,
+                    *quantifier,
+                    Box::new(right),
+                ))
+            }
+        }
+    }
+
+    /// Without a hint ARRAY[...] is typed by its first element that isn't a literal.
+    /// If they are all literals they are integers or numerics if they all parse as one, same as
+    /// postgres' numeric constants, otherwise text.
+    fn array_element_type(
+        scope: &[RangeRelationTable],
+        items: &[ParseExpression],
+        context: &mut StatementContext,
+    ) -> Result<BaseSqlTypesMapper, AnalyzerError> {
+        fn leaves<'a>(items: &'a [ParseExpression], output: &mut Vec<&'a ParseExpression>) {
+            for i in items {
+                match i {
+                    ParseExpression::Array(sub) => leaves(sub, output),
+                    _ => output.push(i),
+                }
+            }
+        }
+        let mut items_found = vec![];
+        leaves(items, &mut items_found);
+
+        if let Some(i) = items_found.iter().find(|i| !Analyzer::is_literal(i)) {
+            return Ok(Analyzer::analyze_expression(scope, i, None, context)?.sql_type());
+        }
+        let literals: Vec<&String> = items_found
+            .iter()
+            .filter_map(|i| match i {
+                ParseExpression::String(s) => Some(s),
+                _ => None,
+            })
+            .collect();
+        let candidates = [BaseSqlTypesMapper::Integer, BaseSqlTypesMapper::Numeric];
+        Ok(candidates
+            .iter()
+            .find(|c| {
+                !literals.is_empty()
+                    && literals
+                        .iter()
+                        .all(|s| BaseSqlTypes::parse((*c).clone(), s).is_ok())
+            })
+            .cloned()
+            .unwrap_or(BaseSqlTypesMapper::Text))
+    }
+
+    /// unnest(array) turns each element into a row, multi-dimensional arrays are flattened
+    fn analyze_unnest(
+        scope: &[RangeRelationTable],
+        name: &str,
+        args: &[ParseExpression],
+        context: &mut StatementContext,
+    ) -> Result<Expression, AnalyzerError> {
+        let arg = match args {
+            [arg] => Analyzer::analyze_expression(scope, arg, None, context)?,
+            _ => {
+                return Err(AnalyzerError::WrongArgumentCount(
+                    name.to_string(),
+                    args.len(),
+                ))
+            }
+        };
+        Analyzer::expect_array(&arg)?;
+        Ok(Expression::Unnest(Box::new(arg)))
+    }
+
+    /// Returns the element type
+    fn expect_array(expr: &Expression) -> Result<BaseSqlTypesMapper, AnalyzerError> {
+        match expr.sql_type() {
+            BaseSqlTypesMapper::Array(e) => Ok(e.as_ref().clone()),
+            t => Err(AnalyzerError::NotAnArray(t)),
+        }
+    }
+
+    /// jsonb_build_object takes pairs of any type, a literal key is text and a literal value is
+    /// a json number or boolean if it looks like one otherwise a json string.
+    /// array_length takes an array and the dimension to measure.
+    fn analyze_scalar_function(
+        scope: &[RangeRelationTable],
+        function: ScalarFunction,
+        name: &str,
+        args: &[ParseExpression],
+        context: &mut StatementContext,
+    ) -> Result<Expression, AnalyzerError> {
+        match function {
+            ScalarFunction::JsonbBuildObject => {
+                if args.len() % 2 == 1 {
+                    return Err(AnalyzerError::WrongArgumentCount(
+                        name.to_string(),
+                        args.len(),
+                    ));
+                }
+                let arguments = args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| {
+                        let hint = match arg {
+                            ParseExpression::String(s) if i % 2 == 1 => match s.parse::<Jsonb>() {
+                                Ok(Jsonb::Number(_)) | Ok(Jsonb::Bool(_)) => {
+                                    BaseSqlTypesMapper::Jsonb
+                                }
+                                _ => BaseSqlTypesMapper::Text,
+                            },
+                            _ => BaseSqlTypesMapper::Text,
+                        };
+                        Analyzer::analyze_expression(scope, arg, Some(&hint), context)
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(Expression::Function(function, arguments))
+            }
+            ScalarFunction::ArrayLength => match args {
+                [array, dimension] => {
+                    let array = Analyzer::analyze_expression(scope, array, None, context)?;
+                    Analyzer::expect_array(&array)?;
+                    let dimension = Analyzer::analyze_expression(
+                        scope,
+                        dimension,
+                        Some(&BaseSqlTypesMapper::Integer),
+                        context,
+                    )?;
+                    Analyzer::expect_type(&dimension, &BaseSqlTypesMapper::Integer)?;
+                    Ok(Expression::Function(function, vec![array, dimension]))
+                }
+                _ => Err(AnalyzerError::WrongArgumentCount(
+                    name.to_string(),
+                    args.len(),
+                )),
+            },
+        }
+    }
+
+    /// Columns are named after what they select, postgres' ?column? covers anything else
+    fn output_name(expr: &ParseExpression) -> String {
+        match expr {
+            ParseExpression::Identifier(parts) => parts.last().cloned().unwrap_or_default(),
+            ParseExpression::FunctionCall(name, _) => name.to_lowercase(),
+            //Same as postgres, a typed literal is named after its type
+            ParseExpression::TypedString(sql_type, _) => TypeDefinitions::from_sql_type(sql_type)
+                .map(|t| t.value().name.to_string())
+                .unwrap_or_else(|| "?column?".to_string()),
+            _ => "?column?".to_string(),
+        }
+    }
+
+    fn without_aggregates(
+        expr: Expression,
+        clause: &'static str,
+    ) -> Result<Expression, AnalyzerError> {
+        match expr.contains_aggregate() {
+            true => Err(AnalyzerError::AggregateNotAllowed(clause)),
+            false => Ok(expr),
+        }
+    }
+
+    /// Rewrites an expression over the merged row into one over the grouped row, the group keys
+    /// become their key column and aggregates are collected to be computed per group.
+    /// Any other column isn't the same across the group so it can't be used.
+    fn group_expression(
+        scope: &[RangeRelationTable],
+        expr: &Expression,
+        group_by: &[Arc<Expression>],
+        aggregates: &mut Vec<Arc<Aggregate>>,
+    ) -> Result<Expression, AnalyzerError> {
+        if let Some(i) = group_by.iter().position(|g| **g == *expr) {
+            return Ok(Expression::Column(i, expr.sql_type()));
+        }
+
+        let regroup = |e: &Expression, aggregates: &mut Vec<Arc<Aggregate>>| {
+            Analyzer::group_expression(scope, e, group_by, aggregates).map(Box::new)
+        };
+        match expr {
+            Expression::Aggregate(a) => {
+                let i = match aggregates.iter().position(|agg| **agg == **a) {
+                    Some(i) => i,
+                    None => {
+                        aggregates.push(Arc::new(a.as_ref().clone()));
+                        aggregates.len() - 1
+                    }
+                };
+                Ok(Expression::Column(group_by.len() + i, a.sql_type.clone()))
+            }
+            Expression::Column(offset, _) => {
+                let (name, column_name) = Analyzer::column_name(scope, *offset);
+                Err(AnalyzerError::UngroupedColumn(name, column_name))
+            }
+            Expression::Constant(_, _) => Ok(expr.clone()),
+            Expression::BinaryOperation(left, op, right) => Ok(Expression::BinaryOperation(
+                regroup(left, aggregates)?,
+                *op,
+                regroup(right, aggregates)?,
+            )),
+            Expression::Not(e) => Ok(Expression::Not(regroup(e, aggregates)?)),
+            Expression::IsNull(e) => Ok(Expression::IsNull(regroup(e, aggregates)?)),
+            Expression::IsNotNull(e) => Ok(Expression::IsNotNull(regroup(e, aggregates)?)),
+            Expression::Cast(e, t) => Ok(Expression::Cast(regroup(e, aggregates)?, t.clone())),
+            Expression::Function(f, args) => Ok(Expression::Function(
+                *f,
+                args.iter()
+                    .map(|a| regroup(a, aggregates).map(|a| *a))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Expression::Array(items, t) => Ok(Expression::Array(
+                items
+                    .iter()
+                    .map(|i| regroup(i, aggregates).map(|i| *i))
+                    .collect::<Result<_, _>>()?,
+                t.clone(),
+            )),
+            Expression::Subscript(e, subscripts) => Ok(Expression::Subscript(
+                regroup(e, aggregates)?,
+                subscripts
+                    .iter()
+                    .map(|s| regroup(s, aggregates).map(|s| *s))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Expression::ArrayComparison(left, op, quantifier, right) => {
+                Ok(Expression::ArrayComparison(
+                    regroup(left, aggregates)?,
+                    *op,
+                    *quantifier,
+                    regroup(right, aggregates)?,
+                ))
+            }
+            Expression::Unnest(e) => Ok(Expression::Unnest(regroup(e, aggregates)?)),
+        }
+    }
+
+    /// Finds the qualified name of a column offset in the merged row, for error messages
+    /// Gives the qualified name for messages along with the bare column name
+    fn column_name(scope: &[RangeRelationTable], offset: usize) -> (String, String) {
+        let mut remaining = offset;
+        for rrt in scope {
+            match rrt.table.attributes.get(remaining) {
+                Some(attr) => {
+                    let rrt_name = rrt.alias.as_ref().unwrap_or(&rrt.table.name);
+                    return (format!("{}.{}", rrt_name, attr.name), attr.name.clone());
+                }
+                None => remaining -= rrt.table.attributes.len(),
+            }
+        }
+        (offset.to_string(), offset.to_string())
+    }
+
+    /// Only plain column references come from a table, this has to be worked out before grouping changes the offsets
+    fn column_origin(scope: &[RangeRelationTable], expr: &Expression) -> ColumnOrigin {
+        let mut remaining = match expr {
+            Expression::Column(offset, _) => *offset,
+            _ => return ColumnOrigin::default(),
+        };
+        for rrt in scope {
+            if remaining < rrt.table.attributes.len() {
+                return ColumnOrigin::new(&rrt.table, remaining);
+            }
+            remaining -= rrt.table.attributes.len();
+        }
+        ColumnOrigin::default()
+    }
+
+    /// char(n) columns are stored without trailing spaces, so a literal compared against one drops them too
+    fn trim_char_literal(
+        scope: &[RangeRelationTable],
+        literal: Expression,
+        other: &Expression,
+    ) -> Expression {
+        match (literal, Analyzer::column_origin(scope, other).modifier) {
+            (
+                Expression::Constant(Some(BaseSqlTypes::Text(t)), sql_type),
+                Some(TypeModifier::Char(_)),
+            ) => Expression::Constant(
+                Some(BaseSqlTypes::Text(t.trim_end_matches(' ').to_string())),
+                sql_type,
+            ),
+            (literal, _) => literal,
+        }
+    }
+
+    /// LIMIT and OFFSET take a constant row count, NULL means no limit
+    fn analyze_row_count(
+        scope: &[RangeRelationTable],
+        expr: &ParseExpression,
+        context: &mut StatementContext,
+    ) -> Result<Option<usize>, AnalyzerError> {
+        let count =
+            Analyzer::analyze_expression(scope, expr, Some(&BaseSqlTypesMapper::Integer), context)?;
+        Analyzer::expect_type(&count, &BaseSqlTypesMapper::Integer)?;
+        match count {
+            Expression::Constant(Some(BaseSqlTypes::Integer(i)), _) => usize::try_from(i)
+                .map(Some)
+                .map_err(|_| AnalyzerError::NegativeRowCount(i)),
+            Expression::Constant(_, _) => Ok(None),
+            _ => Err(AnalyzerError::NonConstantRowCount()),
+        }
+    }
+
+    /// Arithmetic doesn't need both sides to match, date + 1 and timestamp - '1 day' are fine.
+    /// So a literal tries the other side's type first then the types date arithmetic takes,
+    /// keeping the first one it parses as that the operator accepts. The jsonb and containment
+    /// operators work the same way, doc -> 0 is an array element and doc -> 'a' is a field.
+    /// Next to an integer a literal is at least an integer and a bigint if it needs to be, same as
+    /// postgres' integer constants, so smallint * 1000 doesn't overflow.
+    fn literal_hint(
+        literal: &ParseExpression,
+        op: BinaryOperator,
+        other: &BaseSqlTypesMapper,
+        literal_on_left: bool,
+    ) -> BaseSqlTypesMapper {
+        if !op.has_result_type() {
+            return other.clone();
+        }
+        let candidates = [
+            other
+                .wider_integer(&BaseSqlTypesMapper::Integer)
+                .unwrap_or_else(|| other.clone()),
+            BaseSqlTypesMapper::Bigint,
+            BaseSqlTypesMapper::Integer,
+            BaseSqlTypesMapper::Interval,
+            BaseSqlTypesMapper::Jsonb,
+            BaseSqlTypesMapper::Text,
+        ];
+        candidates
+            .iter()
+            .find(|c| {
+                let parses = match literal {
+                    ParseExpression::String(s) => BaseSqlTypes::parse((*c).clone(), s).is_ok(),
+                    _ => true,
+                };
+                let result = match literal_on_left {
+                    true => op.result_type(c, other),
+                    false => op.result_type(other, c),
+                };
+                parses && result.is_some()
+            })
+            .cloned()
+            .unwrap_or_else(|| other.clone())
+    }
+
+    /// Integers of different widths meet at the wider one, the narrower side is cast up to it
+    fn widen_integers(left: Expression, right: Expression) -> (Expression, Expression) {
+        let (left_type, right_type) = (left.sql_type(), right.sql_type());
+        match left_type.wider_integer(&right_type) {
+            Some(wider) if wider != left_type => (Expression::Cast(Box::new(left), wider), right),
+            Some(wider) if wider != right_type => (left, Expression::Cast(Box::new(right), wider)),
+            _ => (left, right),
+        }
+    }
+
+    fn is_literal(expr: &ParseExpression) -> bool {
+        matches!(
+            expr,
+            ParseExpression::String(_) | ParseExpression::Null() | ParseExpression::Parameter(_)
+        )
+    }
+
+    fn expect_type(expr: &Expression, expected: &BaseSqlTypesMapper) -> Result<(), AnalyzerError> {
+        let found = expr.sql_type();
+        if found != *expected {
+            return Err(AnalyzerError::ExpressionTypeMismatch(
+                expected.clone(),
+                found,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Storing a value converts it to the column's type if that can't lose its meaning,
+    /// such as now() into a timestamp column or an integer into a numeric one
+    fn assignment_cast(
+        expr: Expression,
+        target: &BaseSqlTypesMapper,
+    ) -> Result<Expression, AnalyzerError> {
+        let found = expr.sql_type();
+        if found == *target {
+            return Ok(expr);
+        }
+        if !found.converts_to(target) {
+            return Err(AnalyzerError::ExpressionTypeMismatch(target.clone(), found));
+        }
+        Ok(Expression::Cast(Box::new(expr), target.clone()))
+    }
+
     /// This function will sort the columns and values and convert them
     fn validate_columns(
         table: Arc<Table>,
         provided_columns: Option<Vec<String>>,
         provided_values: Vec<ParseExpression>,
+        context: &mut StatementContext,
     ) -> Result<(SqlTypeDefinition, SqlTuple), AnalyzerError> {
         let columns = match provided_columns {
             Some(pc) => {
@@ -149,11 +1147,12 @@ impl Analyzer {
             }
         };
 
-        Analyzer::convert_into_types(columns)
+        Analyzer::convert_into_types(columns, context)
     }
 
     fn convert_into_types(
         provided: Vec<(Attribute, Option<ParseExpression>)>,
+        context: &mut StatementContext,
     ) -> Result<(SqlTypeDefinition, SqlTuple), AnalyzerError> {
         let mut tbl_cols = vec![];
         let mut val_cols = vec![];
@@ -168,6 +1167,14 @@ impl Analyzer {
                         tbl_cols.push((a.name, a.sql_type));
                         val_cols.push(None);
                     }
+                    _ => {
+                        //Anything fancier has to be computed up front, there is no row to reference
+                        let expr =
+                            Analyzer::analyze_expression(&[], &s2, Some(&a.sql_type), context)?;
+                        let expr = Analyzer::assignment_cast(expr, &a.sql_type)?;
+                        tbl_cols.push((a.name, a.sql_type));
+                        val_cols.push(expr.evaluate(&SqlTuple(vec![]))?);
+                    }
                 },
                 None => {
                     tbl_cols.push((a.name, a.sql_type));
@@ -189,12 +1196,52 @@ pub enum AnalyzerError {
     ColumnVsColumnMismatch(Vec<String>, Vec<String>),
     #[error("Provided value count {0} does not match the underlying table column count {1}")]
     ValueVsColumnMismatch(usize, usize),
+    #[error("Column {0} assigned more than once")]
+    MultipleAssignments(String),
     #[error("Missing required column {0}")]
     MissingColumn(Attribute),
+    #[error("Column reference {0} is ambiguous")]
+    AmbiguousColumn(String, String),
+    #[error("Table name {0} specified more than once")]
+    DuplicateTableName(String),
     #[error("Unknown column received {0}")]
-    UnknownColumn(String),
+    UnknownColumn(String, String),
     #[error("Unknown columns received {0:?}")]
     UnknownColumns(Vec<String>),
+    #[error(transparent)]
+    ExpressionError(#[from] ExpressionError),
+    #[error("Expected an expression of type {0}, got {1}")]
+    ExpressionTypeMismatch(BaseSqlTypesMapper, BaseSqlTypesMapper),
+    #[error("Operator {0} does not support {1} and {2}")]
+    OperatorTypeMismatch(BinaryOperator, BaseSqlTypesMapper, BaseSqlTypesMapper),
+    #[error("Aggregate functions are not allowed in {0}")]
+    AggregateNotAllowed(&'static str),
+    #[error("Aggregate {0} does not support {1}")]
+    AggregateTypeMismatch(AggregateFunction, BaseSqlTypesMapper),
+    #[error("Expected an array, got {0}")]
+    NotAnArray(BaseSqlTypesMapper),
+    #[error("Aggregate {0} calls cannot be nested")]
+    NestedAggregate(AggregateFunction),
+    #[error("Column {0} must appear in the GROUP BY clause or be used in an aggregate function")]
+    UngroupedColumn(String, String),
+    #[error("* is only allowed in count(*)")]
+    UnexpectedWildcard(),
+    #[error("Set returning function {0} is only allowed as a select column")]
+    UnexpectedSetFunction(String),
+    #[error("Function {0} does not exist")]
+    UnknownFunction(String),
+    #[error("Function {0} does not take {1} arguments")]
+    WrongArgumentCount(String, usize),
+    #[error("Function {0} is only available while a transaction is in progress")]
+    NoTransactionStart(String),
+    #[error("ORDER BY position {0} is not in select list")]
+    InvalidSortPosition(usize),
+    #[error("LIMIT and OFFSET must be a constant")]
+    NonConstantRowCount(),
+    #[error("LIMIT and OFFSET must not be negative, got {0}")]
+    NegativeRowCount(i32),
     #[error("Not implemented")]
     NotImplemented(),
+    #[error("Query has parameters up to ${0} that were never bound")]
+    UnboundParameter(usize),
 }
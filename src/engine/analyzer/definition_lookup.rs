@@ -8,13 +8,16 @@ use super::super::objects::{
     Attribute, Table, TableError,
 };
 use super::super::transactions::TransactionId;
-use crate::constants::system_tables::{pg_attribute, pg_class, pg_constraint, pg_index};
-use crate::constants::{Nullable, SystemTables};
-use crate::engine::objects::types::{BaseSqlTypesError, SqlTypeDefinition};
+use crate::constants::system_tables::{
+    pg_attribute, pg_class, pg_constraint, pg_enum, pg_index, pg_type,
+};
+use crate::constants::{Nullable, SystemTables, TypeDefinitions};
+use crate::engine::objects::types::{
+    BaseSqlTypesError, EnumType, SqlTypeDefinition, TypeModifier, TypeModifierError,
+};
 use crate::engine::objects::{Constraint, Index, PrimaryKeyConstraint};
 use std::convert::TryFrom;
 use std::num::TryFromIntError;
-use std::str::FromStr;
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::pin;
@@ -49,6 +52,10 @@ impl DefinitionLookup {
             BaseSqlTypes::Uuid(u) => u,
             _ => return Err(DefinitionLookupError::ColumnWrongType()),
         };
+        let table_oid = match pg_class_entry.get_column_not_null(pg_class::COLUMN_OID)? {
+            BaseSqlTypes::Bigint(o) => u32::try_from(o)?,
+            _ => return Err(DefinitionLookupError::ColumnWrongType()),
+        };
 
         let tbl_columns = self.get_table_columns(tran_id, table_id).await?;
         let mut tbl_attrs = vec![];
@@ -57,22 +64,24 @@ impl DefinitionLookup {
                 BaseSqlTypes::Text(t) => t,
                 _ => return Err(DefinitionLookupError::ColumnWrongType()),
             };
-            let c_type = match c.get_column_not_null(pg_attribute::COLUMN_SQL_TYPE)? {
-                BaseSqlTypes::Text(t) => t,
-                _ => return Err(DefinitionLookupError::ColumnWrongType()),
-            };
+            let (c_type, c_type_name) =
+                match c.get_column_not_null(pg_attribute::COLUMN_TYPE_OID)? {
+                    BaseSqlTypes::Bigint(o) => self.get_type(tran_id, u32::try_from(o)?).await?,
+                    _ => return Err(DefinitionLookupError::ColumnWrongType()),
+                };
 
             let c_null = match c.get_column_not_null(pg_attribute::COLUMN_NULLABLE)? {
                 BaseSqlTypes::Bool(b) => Nullable::from(b),
                 _ => return Err(DefinitionLookupError::ColumnWrongType()),
             };
 
-            tbl_attrs.push(Attribute::new(
-                c_name,
-                BaseSqlTypesMapper::from_str(&c_type)?,
-                c_null,
-                None, //Todo encode the column length
-            ));
+            let c_modifier = match c.get_column(pg_attribute::COLUMN_TYPE_MODIFIER)? {
+                Some(BaseSqlTypes::Integer(i)) => Some(TypeModifier::from_typmod(&c_type_name, i)?),
+                Some(_) => return Err(DefinitionLookupError::ColumnWrongType()),
+                None => None,
+            };
+
+            tbl_attrs.push(Attribute::new(c_name, c_type, c_null, c_modifier));
         }
 
         let indexes = self
@@ -84,6 +93,7 @@ impl DefinitionLookup {
 
         Ok(Arc::new(Table::new(
             table_id,
+            table_oid,
             name,
             tbl_attrs,
             constraints,
@@ -91,6 +101,96 @@ impl DefinitionLookup {
         )))
     }
 
+    /// Resolves a column's type oid through pg_type, anything that isn't built in is an enum.
+    /// The name is the element type's, which is what TypeModifier expects.
+    pub async fn get_type(
+        &self,
+        tran_id: TransactionId,
+        oid: u32,
+    ) -> Result<(BaseSqlTypesMapper, String), DefinitionLookupError> {
+        let row_stream = self
+            .vis_row_man
+            .clone()
+            .get_stream(tran_id, &SystemTables::PgType.value());
+        pin!(row_stream);
+        while let Some(row_res) = row_stream.next().await {
+            let row = row_res?;
+            let is_array = Self::get_oid(&row, pg_type::COLUMN_TYPARRAY)? == oid;
+            if !is_array && Self::get_oid(&row, pg_type::COLUMN_OID)? != oid {
+                continue;
+            }
+
+            let id = match row.get_column_not_null(pg_type::COLUMN_ID)? {
+                BaseSqlTypes::Uuid(u) => u,
+                _ => return Err(DefinitionLookupError::ColumnWrongType()),
+            };
+            let name = match row.get_column_not_null(pg_type::COLUMN_TYPNAME)? {
+                BaseSqlTypes::Text(t) => t,
+                _ => return Err(DefinitionLookupError::ColumnWrongType()),
+            };
+            let element = match TypeDefinitions::from_id(id) {
+                Some(builtin) => builtin.sql_type(),
+                None => {
+                    let labels = self.get_enum_labels(tran_id, id).await?;
+                    BaseSqlTypesMapper::Enum(Arc::new(EnumType::new(
+                        id,
+                        Self::get_oid(&row, pg_type::COLUMN_OID)?,
+                        Self::get_oid(&row, pg_type::COLUMN_TYPARRAY)?,
+                        name.clone(),
+                        labels,
+                    )))
+                }
+            };
+            return match is_array {
+                true => Ok((BaseSqlTypesMapper::Array(Arc::new(element)), name)),
+                false => Ok((element, name)),
+            };
+        }
+
+        Err(DefinitionLookupError::TypeDoesNotExist(oid))
+    }
+
+    fn get_oid(row: &RowData, column: &str) -> Result<u32, DefinitionLookupError> {
+        match row.get_column_not_null(column)? {
+            BaseSqlTypes::Bigint(o) => Ok(u32::try_from(o)?),
+            _ => Err(DefinitionLookupError::ColumnWrongType()),
+        }
+    }
+
+    async fn get_enum_labels(
+        &self,
+        tran_id: TransactionId,
+        type_id: Uuid,
+    ) -> Result<Vec<String>, DefinitionLookupError> {
+        let row_stream = self
+            .vis_row_man
+            .clone()
+            .get_stream(tran_id, &SystemTables::PgEnum.value());
+        pin!(row_stream);
+        while let Some(row_res) = row_stream.next().await {
+            let row = row_res?;
+            if row.get_column_not_null(pg_enum::COLUMN_TYPE_ID)? != BaseSqlTypes::Uuid(type_id) {
+                continue;
+            }
+
+            let mut labels = vec![];
+            match row.get_column_not_null(pg_enum::COLUMN_LABELS)? {
+                BaseSqlTypes::Array(a) => {
+                    for label in a {
+                        match label {
+                            Some(BaseSqlTypes::Text(t)) => labels.push(t),
+                            _ => return Err(DefinitionLookupError::ColumnWrongType()),
+                        }
+                    }
+                }
+                _ => return Err(DefinitionLookupError::ColumnWrongType()),
+            }
+            return Ok(labels);
+        }
+
+        Err(DefinitionLookupError::EnumLabelsMissing(type_id))
+    }
+
     async fn get_table_row(
         &self,
         tran_id: TransactionId,
@@ -156,8 +256,8 @@ impl DefinitionLookup {
         //Now the columns are good but we need to check for gaps
         column_tuples.sort_by(|a, b| a.0.cmp(b.0));
         for (i, tup) in column_tuples.iter().enumerate() {
-            let i_u32 = u32::try_from(i)?;
-            if tup.0 != &i_u32 {
+            let i_i32 = i32::try_from(i)?;
+            if tup.0 != &i_i32 {
                 return Err(DefinitionLookupError::ColumnGap(i));
             }
         }
@@ -206,7 +306,7 @@ impl DefinitionLookup {
                     let mut cols = vec![];
                     for col in a {
                         match col {
-                            BaseSqlTypes::Integer(i) => {
+                            Some(BaseSqlTypes::Integer(i)) => {
                                 let i_usize = usize::try_from(i)?;
                                 cols.push(
                                     attributes
@@ -291,6 +391,10 @@ impl DefinitionLookup {
 pub enum DefinitionLookupError {
     #[error("{0} is not a valid table")]
     TableDoesNotExist(String),
+    #[error("Type oid {0} does not exist")]
+    TypeDoesNotExist(u32),
+    #[error("Enum type {0} has no labels")]
+    EnumLabelsMissing(Uuid),
     #[error("No columns found")]
     NoColumnsFound(),
     #[error("Column index does not exist {0}")]
@@ -313,6 +417,8 @@ pub enum DefinitionLookupError {
     TableError(#[from] TableError),
     #[error(transparent)]
     TryFromIntError(#[from] TryFromIntError),
+    #[error(transparent)]
+    TypeModifierError(#[from] TypeModifierError),
 }
 
 #[cfg(test)]
@@ -335,8 +441,8 @@ mod tests {
 
         let fm = Arc::new(FileManager2::new(tmp_dir.clone())?);
         let fsm = FreeSpaceManager::new(fm.clone());
-        let rm = RowManager::new(fm, fsm);
-        let tm = TransactionManager::new();
+        let rm = RowManager::new(fm.clone(), fsm);
+        let tm = TransactionManager::new(fm).await?;
         let vm = VisibleRowManager::new(rm, tm);
         let dl = DefinitionLookup::new(vm);
 
@@ -355,8 +461,8 @@ mod tests {
 
         let fm = Arc::new(FileManager2::new(tmp_dir.clone())?);
         let fsm = FreeSpaceManager::new(fm.clone());
-        let rm = RowManager::new(fm, fsm);
-        let tm = TransactionManager::new();
+        let rm = RowManager::new(fm.clone(), fsm);
+        let tm = TransactionManager::new(fm).await?;
         let vm = VisibleRowManager::new(rm, tm);
         let dl = DefinitionLookup::new(vm);
 
@@ -380,12 +486,13 @@ mod tests {
         let fm = Arc::new(FileManager2::new(tmp_dir.clone())?);
         let fsm = FreeSpaceManager::new(fm.clone());
         let rm = RowManager::new(fm.clone(), fsm);
-        let mut tm = TransactionManager::new();
+        let mut tm = TransactionManager::new(fm.clone()).await?;
         let vm = VisibleRowManager::new(rm, tm.clone());
         let dl = DefinitionLookup::new(vm);
         let mut engine = Engine::new(fm, tm.clone());
 
         let tran = tm.start_trans().await?;
+        engine.bootstrap(tran).await?;
         engine
             .process_query(tran, "create table foo (bar text)".to_string())
             .await?;
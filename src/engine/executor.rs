@@ -1,18 +1,34 @@
-use crate::constants::SystemTables;
+mod external_sort;
+use external_sort::ExternalSort;
+pub use external_sort::ExternalSortError;
+
+use crate::constants::system_tables::{pg_attribute, pg_class, pg_enum, pg_type};
+use crate::constants::{SystemTables, TypeDefinitions, DEFAULT_SORT_MEMORY, FIRST_USER_OID};
 use crate::engine::objects::types::BaseSqlTypes;
 use crate::engine::objects::{ConstraintMapper, SqlTuple};
 
-use super::io::{ConstraintManager, ConstraintManagerError};
-use super::objects::types::SqlTypeDefinition;
-use super::objects::{ParseTree, Plan, PlannedStatement, SqlTupleError, Table};
+use super::io::row_formats::{ItemPointer, RowData, RowDataError};
+use super::io::{ConstraintManager, ConstraintManagerError, VisibleRowManagerError};
+use super::objects::types::{SqlTypeDefinition, TypeModifier, TypeModifierError};
+use super::objects::{
+    AggregateError, Expression, ExpressionError, HashAggregatePlan, HashJoinPlan, IndexScan,
+    JoinType, LimitPlan, NestedLoopJoinPlan, ParseTree, Plan, PlannedStatement,
+    RawCreateTableCommand, RawCreateTypeCommand, RawDropTypeCommand, SortPlan, SqlTupleError,
+    Table,
+};
 use super::transactions::TransactionId;
 use async_stream::try_stream;
+use futures::pin_mut;
 use futures::stream::Stream;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::num::TryFromIntError;
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::Arc;
 use thiserror::Error;
+use tokio::sync::Mutex;
+use tokio_stream::StreamExt;
 use uuid::Uuid;
 
 //TODO way too many clones / Arc flipping. Unsure if I could make use of references better
@@ -20,11 +36,27 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct Executor {
     cons_man: ConstraintManager,
+    //Where sorts too big for sort_memory write their runs
+    temp_dir: Arc<PathBuf>,
+    sort_memory: usize,
+    //Next oid to hand out, shared by every clone so concurrent transactions never get the same one.
+    //Loaded from the catalogs on first use.
+    next_oid: Arc<Mutex<Option<i64>>>,
 }
 
 impl Executor {
-    pub fn new(cons_man: ConstraintManager) -> Executor {
-        Executor { cons_man }
+    pub fn new(cons_man: ConstraintManager, temp_dir: PathBuf) -> Executor {
+        Executor {
+            cons_man,
+            temp_dir: Arc::new(temp_dir),
+            sort_memory: DEFAULT_SORT_MEMORY,
+            next_oid: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn with_sort_memory(mut self, sort_memory: usize) -> Executor {
+        self.sort_memory = sort_memory;
+        self
     }
 
     pub fn execute(
@@ -44,11 +76,28 @@ impl Executor {
             Plan::CartesianJoin(cp) => {
                 self.cartesian_join(tran_id, cp.left.clone(), cp.right.clone())
             }
+            Plan::Delete(d) => self.delete(tran_id, d.table.clone(), d.qualification.clone()),
+            Plan::Filter(f) => self.filter(tran_id, f.source.clone(), f.qualification.clone()),
             Plan::FullTableScan(fts) => {
                 self.full_table_scan(tran_id, fts.src_table.clone(), fts.target_type.clone())
             }
+            Plan::HashAggregate(ha) => self.hash_aggregate(tran_id, ha),
+            Plan::HashJoin(hj) => self.hash_join(tran_id, hj),
+            Plan::IndexScan(is) => self.index_scan(tran_id, is),
+            Plan::Limit(l) => self.limit(tran_id, l),
             Plan::ModifyTable(mt) => self.modify_table(tran_id, &mt.table, mt.source.clone()),
+            Plan::NestedLoopJoin(nl) => self.nested_loop_join(tran_id, nl),
+            Plan::Projection(p) => {
+                self.projection(tran_id, p.source.clone(), p.target_entries.clone())
+            }
+            Plan::Sort(sp) => self.sort(tran_id, sp),
             Plan::StaticData(sd) => self.static_data(sd.clone()),
+            Plan::Update(u) => self.update(
+                tran_id,
+                u.table.clone(),
+                u.qualification.clone(),
+                u.assignments.clone(),
+            ),
         }
     }
 
@@ -72,6 +121,43 @@ impl Executor {
         Box::pin(s)
     }
 
+    fn delete(
+        self,
+        tran_id: TransactionId,
+        table: Arc<Table>,
+        qualification: Option<Arc<Expression>>,
+    ) -> Pin<Box<impl Stream<Item = Result<SqlTuple, ExecutorError>>>> {
+        let s = try_stream! {
+            let mut cm = self.cons_man.clone();
+            let rows = self.matching_rows(tran_id, table.clone(), qualification).await?;
+
+            for (pointer, row) in rows {
+                cm.delete_row(tran_id, &table, pointer).await?;
+                yield row.user_data;
+            }
+        };
+        Box::pin(s)
+    }
+
+    fn filter(
+        self,
+        tran_id: TransactionId,
+        source: Arc<Plan>,
+        qualification: Arc<Expression>,
+    ) -> Pin<Box<impl Stream<Item = Result<SqlTuple, ExecutorError>>>> {
+        let s = try_stream! {
+            for await row in self.execute_plans(tran_id, source) {
+                let row = row?;
+
+                //Only true passes, false and null are both filtered out
+                if qualification.evaluate(&row)? == Some(BaseSqlTypes::Bool(true)) {
+                    yield row;
+                }
+            }
+        };
+        Box::pin(s)
+    }
+
     fn full_table_scan(
         self,
         tran_id: TransactionId,
@@ -93,6 +179,66 @@ impl Executor {
         Box::pin(s)
     }
 
+    fn index_scan(
+        self,
+        tran_id: TransactionId,
+        index_scan: &IndexScan,
+    ) -> Pin<Box<impl Stream<Item = Result<SqlTuple, ExecutorError>>>> {
+        let src_table = index_scan.src_table.clone();
+        let index = index_scan.index.clone();
+        let lower = index_scan.lower.clone();
+        let upper = index_scan.upper.clone();
+        let target_type = index_scan.target_type.clone();
+
+        let s = try_stream! {
+            let mut cm = self.cons_man;
+            let ptrs = cm.search_index(&index, &lower, &upper).await?;
+
+            //The index keeps pointers to every version of a row, only the visible one is returned
+            for ptr in ptrs {
+                let row = match cm.get(tran_id, &src_table, ptr).await {
+                    Ok(r) => r,
+                    Err(ConstraintManagerError::VisibleRowManagerError(
+                        VisibleRowManagerError::NotVisibleRow(_),
+                    )) => continue,
+                    Err(e) => Err(e)?,
+                };
+
+                yield row.user_data.filter_map(&src_table.sql_type, &target_type)?;
+            }
+        };
+        Box::pin(s)
+    }
+
+    fn limit(
+        self,
+        tran_id: TransactionId,
+        limit: &LimitPlan,
+    ) -> Pin<Box<impl Stream<Item = Result<SqlTuple, ExecutorError>>>> {
+        let source = limit.source.clone();
+        let offset = limit.offset;
+        let count = limit.count;
+
+        let s = try_stream! {
+            if count != Some(0) {
+                let mut seen = 0;
+                //Leaving the loop drops the source so nothing more gets read
+                for await row in self.execute_plans(tran_id, source) {
+                    let row = row?;
+                    seen += 1;
+                    if seen <= offset {
+                        continue;
+                    }
+                    yield row;
+                    if Some(seen - offset) == count {
+                        break;
+                    }
+                }
+            }
+        };
+        Box::pin(s)
+    }
+
     fn modify_table(
         self,
         tran_id: TransactionId,
@@ -114,6 +260,321 @@ impl Executor {
         Box::pin(s)
     }
 
+    fn hash_aggregate(
+        self,
+        tran_id: TransactionId,
+        aggregate: &HashAggregatePlan,
+    ) -> Pin<Box<impl Stream<Item = Result<SqlTuple, ExecutorError>>>> {
+        let source = aggregate.source.clone();
+        let group_by = aggregate.group_by.clone();
+        let aggregates = aggregate.aggregates.clone();
+
+        let s = try_stream! {
+            //Groups come out in the order they were first seen
+            let mut group_index: HashMap<SqlTuple, usize> = HashMap::new();
+            let mut groups = vec![];
+            if group_by.is_empty() {
+                group_index.insert(SqlTuple(vec![]), 0);
+                groups.push((SqlTuple(vec![]), aggregates.iter().map(|a| a.start()).collect()));
+            }
+
+            for await row in self.execute_plans(tran_id, source) {
+                let row = row?;
+
+                //Unlike a join NULL keys are equal to each other here
+                let mut key = Vec::with_capacity(group_by.len());
+                for g in group_by.iter() {
+                    key.push(g.evaluate(&row)?);
+                }
+                let key = SqlTuple(key);
+
+                let i = match group_index.get(&key) {
+                    Some(i) => *i,
+                    None => {
+                        group_index.insert(key.clone(), groups.len());
+                        groups.push((key, aggregates.iter().map(|a| a.start()).collect()));
+                        groups.len() - 1
+                    }
+                };
+
+                let states: &mut Vec<_> = &mut groups[i].1;
+                for (a, state) in aggregates.iter().zip(states.iter_mut()) {
+                    a.update(state, &row)?;
+                }
+            }
+
+            for (key, states) in groups {
+                let mut output = key.0;
+                for (a, state) in aggregates.iter().zip(states) {
+                    output.push(a.finish(state)?);
+                }
+                yield SqlTuple(output);
+            }
+        };
+        Box::pin(s)
+    }
+
+    fn hash_join(
+        self,
+        tran_id: TransactionId,
+        join: &HashJoinPlan,
+    ) -> Pin<Box<impl Stream<Item = Result<SqlTuple, ExecutorError>>>> {
+        let join_type = join.join_type;
+        let left = join.left.clone();
+        let right = join.right.clone();
+        let left_keys = join.left_keys.clone();
+        let right_keys = join.right_keys.clone();
+        let residual = join.residual.clone();
+        let (left_width, right_width) = (join.left_width, join.right_width);
+
+        let s = try_stream! {
+            let right_rows = self.clone().collect_rows(tran_id, right).await?;
+            let mut right_matched = vec![false; right_rows.len()];
+
+            let mut table: HashMap<SqlTuple, Vec<usize>> = HashMap::new();
+            for (i, row) in right_rows.iter().enumerate() {
+                if let Some(key) = Executor::join_key(&right_keys, row)? {
+                    table.entry(key).or_default().push(i);
+                }
+            }
+
+            for await left_row in self.clone().execute_plans(tran_id, left) {
+                let left_row = left_row?;
+                let candidates = match Executor::join_key(&left_keys, &left_row)? {
+                    Some(key) => table.get(&key).cloned().unwrap_or_default(),
+                    None => vec![],
+                };
+
+                let mut matched = false;
+                for i in candidates {
+                    let merged = SqlTuple::merge(&left_row, &right_rows[i]);
+                    if Executor::join_matches(&residual, &merged)? {
+                        matched = true;
+                        right_matched[i] = true;
+                        yield merged;
+                    }
+                }
+
+                if !matched && matches!(join_type, JoinType::OuterLeft | JoinType::OuterFull) {
+                    yield SqlTuple::merge(&left_row, &Executor::null_row(right_width));
+                }
+            }
+
+            if matches!(join_type, JoinType::OuterRight | JoinType::OuterFull) {
+                for (row, matched) in right_rows.iter().zip(right_matched) {
+                    if !matched {
+                        yield SqlTuple::merge(&Executor::null_row(left_width), row);
+                    }
+                }
+            }
+        };
+        Box::pin(s)
+    }
+
+    fn nested_loop_join(
+        self,
+        tran_id: TransactionId,
+        join: &NestedLoopJoinPlan,
+    ) -> Pin<Box<impl Stream<Item = Result<SqlTuple, ExecutorError>>>> {
+        let join_type = join.join_type;
+        let left = join.left.clone();
+        let right = join.right.clone();
+        let condition = join.condition.clone();
+        let (left_width, right_width) = (join.left_width, join.right_width);
+
+        let s = try_stream! {
+            //The inner side is read once instead of for every outer row
+            let right_rows = self.clone().collect_rows(tran_id, right).await?;
+            let mut right_matched = vec![false; right_rows.len()];
+
+            for await left_row in self.clone().execute_plans(tran_id, left) {
+                let left_row = left_row?;
+
+                let mut matched = false;
+                for (i, right_row) in right_rows.iter().enumerate() {
+                    let merged = SqlTuple::merge(&left_row, right_row);
+                    if Executor::join_matches(&condition, &merged)? {
+                        matched = true;
+                        right_matched[i] = true;
+                        yield merged;
+                    }
+                }
+
+                if !matched && matches!(join_type, JoinType::OuterLeft | JoinType::OuterFull) {
+                    yield SqlTuple::merge(&left_row, &Executor::null_row(right_width));
+                }
+            }
+
+            if matches!(join_type, JoinType::OuterRight | JoinType::OuterFull) {
+                for (row, matched) in right_rows.iter().zip(right_matched) {
+                    if !matched {
+                        yield SqlTuple::merge(&Executor::null_row(left_width), row);
+                    }
+                }
+            }
+        };
+        Box::pin(s)
+    }
+
+    async fn collect_rows(
+        self,
+        tran_id: TransactionId,
+        plan: Arc<Plan>,
+    ) -> Result<Vec<SqlTuple>, ExecutorError> {
+        let mut rows = vec![];
+        let row_stream = self.execute_plans(tran_id, plan);
+        pin_mut!(row_stream);
+        while let Some(row) = row_stream.next().await {
+            rows.push(row?);
+        }
+        Ok(rows)
+    }
+
+    fn join_matches(
+        condition: &Option<Arc<Expression>>,
+        row: &SqlTuple,
+    ) -> Result<bool, ExecutorError> {
+        Ok(match condition {
+            Some(c) => c.evaluate(row)? == Some(BaseSqlTypes::Bool(true)),
+            None => true,
+        })
+    }
+
+    /// None if any part of the key is NULL since it can't equal anything
+    fn join_key(
+        keys: &[Arc<Expression>],
+        row: &SqlTuple,
+    ) -> Result<Option<SqlTuple>, ExecutorError> {
+        let mut key = Vec::with_capacity(keys.len());
+        for k in keys {
+            match k.evaluate(row)? {
+                Some(v) => key.push(Some(v)),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(SqlTuple(key)))
+    }
+
+    fn null_row(width: usize) -> SqlTuple {
+        SqlTuple(vec![None; width])
+    }
+
+    fn projection(
+        self,
+        tran_id: TransactionId,
+        source: Arc<Plan>,
+        target_entries: Vec<Arc<Expression>>,
+    ) -> Pin<Box<impl Stream<Item = Result<SqlTuple, ExecutorError>>>> {
+        let s = try_stream! {
+            for await row in self.execute_plans(tran_id, source) {
+                let row = row?;
+                let mut output = Vec::with_capacity(target_entries.len());
+                let mut sets = vec![];
+                for (i, entry) in target_entries.iter().enumerate() {
+                    match entry.evaluate_set(&row)? {
+                        Some(values) => {
+                            sets.push((i, values));
+                            output.push(None);
+                        }
+                        None => output.push(entry.evaluate(&row)?),
+                    }
+                }
+                if sets.is_empty() {
+                    yield SqlTuple(output);
+                    continue;
+                }
+
+                //Same as postgres the shorter sets are padded with NULLs
+                let rows = sets.iter().map(|(_, values)| values.len()).max().unwrap_or(0);
+                for r in 0..rows {
+                    let mut expanded = output.clone();
+                    for (i, values) in sets.iter() {
+                        expanded[*i] = values.get(r).cloned().flatten();
+                    }
+                    yield SqlTuple(expanded);
+                }
+            }
+        };
+        Box::pin(s)
+    }
+
+    fn sort(
+        self,
+        tran_id: TransactionId,
+        sort: &SortPlan,
+    ) -> Pin<Box<impl Stream<Item = Result<SqlTuple, ExecutorError>>>> {
+        let source = sort.source.clone();
+        let mut sorter = ExternalSort::new(
+            sort.keys.clone(),
+            sort.columns.clone(),
+            self.sort_memory,
+            self.temp_dir.as_ref().clone(),
+        );
+
+        let s = try_stream! {
+            for await row in self.execute_plans(tran_id, source) {
+                sorter.push(row?).await?;
+            }
+
+            let mut sorted = sorter.finish().await?;
+            while let Some(row) = sorted.next().await? {
+                yield row;
+            }
+        };
+        Box::pin(s)
+    }
+
+    fn update(
+        self,
+        tran_id: TransactionId,
+        table: Arc<Table>,
+        qualification: Option<Arc<Expression>>,
+        assignments: Vec<(usize, Arc<Expression>)>,
+    ) -> Pin<Box<impl Stream<Item = Result<SqlTuple, ExecutorError>>>> {
+        let s = try_stream! {
+            let mut cm = self.cons_man.clone();
+            let rows = self.matching_rows(tran_id, table.clone(), qualification).await?;
+
+            for (pointer, row) in rows {
+                //Every assignment is computed from the old version of the row
+                let mut new_data = row.user_data.clone();
+                for (offset, expr) in assignments.iter() {
+                    new_data.0[*offset] = expr.evaluate(&row.user_data)?;
+                }
+
+                cm.update_row(tran_id, &table, pointer, new_data.clone())
+                    .await?;
+                yield new_data;
+            }
+        };
+        Box::pin(s)
+    }
+
+    /// Finds the visible rows to modify up front so an update never sees the versions it wrote
+    async fn matching_rows(
+        self,
+        tran_id: TransactionId,
+        table: Arc<Table>,
+        qualification: Option<Arc<Expression>>,
+    ) -> Result<Vec<(ItemPointer, RowData)>, ExecutorError> {
+        let mut rows = vec![];
+
+        let row_stream = self.cons_man.get_stream_with_pointers(tran_id, table);
+        pin_mut!(row_stream);
+        while let Some(row) = row_stream.next().await {
+            let (pointer, row) = row?;
+            let matches = match &qualification {
+                Some(q) => q.evaluate(&row.user_data)? == Some(BaseSqlTypes::Bool(true)),
+                None => true,
+            };
+            if matches {
+                rows.push((pointer, row));
+            }
+        }
+
+        Ok(rows)
+    }
+
     fn static_data(
         self,
         rows: Arc<Vec<SqlTuple>>,
@@ -131,19 +592,71 @@ impl Executor {
         &mut self,
         tran_id: TransactionId,
         parse_tree: ParseTree,
+    ) -> Result<Vec<SqlTuple>, ExecutorError> {
+        match parse_tree {
+            ParseTree::CreateTable(t) => self.create_table(tran_id, t).await,
+            ParseTree::CreateType(t) => self.create_type(tran_id, t).await,
+            ParseTree::DropType(t) => self.drop_type(tran_id, t).await,
+            _ => Err(ExecutorError::NotUtility()),
+        }
+    }
+
+    /// Seeds pg_type with the built in types the first time the database starts
+    pub async fn bootstrap(&mut self, tran_id: TransactionId) -> Result<(), ExecutorError> {
+        let first = TypeDefinitions::VALUES[0].value();
+        if self
+            .find_row(
+                tran_id,
+                SystemTables::PgType,
+                pg_type::COLUMN_TYPNAME,
+                BaseSqlTypes::Text(first.name.to_string()),
+            )
+            .await?
+            .is_some()
+        {
+            return Ok(());
+        }
+
+        for builtin in TypeDefinitions::VALUES {
+            let definition = builtin.value();
+            let type_row = SqlTuple(vec![
+                Some(BaseSqlTypes::Uuid(definition.id)),
+                Some(BaseSqlTypes::Bigint(i64::from(definition.oid))),
+                Some(BaseSqlTypes::Text(definition.name.to_string())),
+                Some(BaseSqlTypes::Smallint(TypeDefinitions::type_len(
+                    &builtin.sql_type(),
+                ))),
+                Some(BaseSqlTypes::Text(pg_type::TYPTYPE_BASE.to_string())),
+                Some(BaseSqlTypes::Bigint(i64::from(definition.array_oid))),
+            ]);
+            self.cons_man
+                .clone()
+                .insert_row(tran_id, &SystemTables::PgType.value(), type_row)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn create_table(
+        &mut self,
+        tran_id: TransactionId,
+        create_table: RawCreateTableCommand,
     ) -> Result<Vec<SqlTuple>, ExecutorError> {
         let mut cm = self.cons_man.clone();
 
-        let create_table = match parse_tree {
-            ParseTree::CreateTable(t) => t,
-            _ => return Err(ExecutorError::NotUtility()),
-        };
+        let mut type_oids = Vec::with_capacity(create_table.provided_columns.len());
+        for column in &create_table.provided_columns {
+            type_oids.push(self.type_oid(tran_id, &column.sql_type).await?);
+        }
+
+        let table_oid = self.allocate_oids(tran_id, 1).await?;
 
         let table_id = Uuid::new_v4();
         let pg_class = SystemTables::PgClass.value();
         let table_row = SqlTuple(vec![
             Some(BaseSqlTypes::Uuid(table_id)),
             Some(BaseSqlTypes::Text(create_table.table_name.clone())),
+            Some(BaseSqlTypes::Bigint(table_oid)),
         ]);
 
         cm.insert_row(tran_id, &pg_class, table_row).await?;
@@ -151,27 +664,29 @@ impl Executor {
         let mut primary_key_cols = vec![];
 
         let pg_attribute = SystemTables::PgAttribute.value();
-        for i in 0..create_table.provided_columns.len() {
+        for (i, (column, type_oid)) in create_table
+            .provided_columns
+            .iter()
+            .zip(type_oids)
+            .enumerate()
+        {
             let cm = self.cons_man.clone();
-            let i_u32 = u32::try_from(i).map_err(ExecutorError::ConversionError)?;
+            let i_i32 = i32::try_from(i).map_err(ExecutorError::ConversionError)?;
+            let modifier = TypeModifier::new(&column.sql_type, &column.type_modifiers)?;
             let table_row = SqlTuple(vec![
                 Some(BaseSqlTypes::Uuid(table_id)),
-                Some(BaseSqlTypes::Text(
-                    create_table.provided_columns[i].name.clone(),
-                )),
-                Some(BaseSqlTypes::Text(
-                    //TODO we did not validate that it is a real type
-                    create_table.provided_columns[i].sql_type.clone(),
-                )),
-                Some(BaseSqlTypes::Integer(i_u32)),
-                Some(BaseSqlTypes::Bool(create_table.provided_columns[i].null)),
+                Some(BaseSqlTypes::Text(column.name.clone())),
+                Some(type_oid),
+                Some(BaseSqlTypes::Integer(i_i32)),
+                Some(BaseSqlTypes::Bool(column.null)),
+                modifier.map(|m| BaseSqlTypes::Integer(m.to_typmod())),
             ]);
             cm.clone()
                 .insert_row(tran_id, &pg_attribute, table_row)
                 .await?;
 
-            if create_table.provided_columns[i].primary_key {
-                primary_key_cols.push(BaseSqlTypes::Integer(i_u32));
+            if column.primary_key {
+                primary_key_cols.push(Some(BaseSqlTypes::Integer(i_i32)));
             }
         }
 
@@ -214,16 +729,248 @@ impl Executor {
 
         Ok(vec![])
     }
+
+    /// Enums get the next two free oids, one for the type and one for arrays of it
+    async fn create_type(
+        &mut self,
+        tran_id: TransactionId,
+        create_type: RawCreateTypeCommand,
+    ) -> Result<Vec<SqlTuple>, ExecutorError> {
+        if self
+            .find_type(tran_id, &create_type.type_name)
+            .await?
+            .is_some()
+        {
+            return Err(ExecutorError::TypeAlreadyExists(create_type.type_name));
+        }
+
+        for (i, label) in create_type.labels.iter().enumerate() {
+            if create_type.labels[..i].contains(label) {
+                return Err(ExecutorError::DuplicateEnumLabel(label.clone()));
+            }
+        }
+
+        let type_oid = self.allocate_oids(tran_id, 2).await?;
+
+        let type_id = Uuid::new_v4();
+        let type_row = SqlTuple(vec![
+            Some(BaseSqlTypes::Uuid(type_id)),
+            Some(BaseSqlTypes::Bigint(type_oid)),
+            Some(BaseSqlTypes::Text(create_type.type_name)),
+            Some(BaseSqlTypes::Smallint(-1)), //Labels are stored as text
+            Some(BaseSqlTypes::Text(pg_type::TYPTYPE_ENUM.to_string())),
+            Some(BaseSqlTypes::Bigint(type_oid + 1)),
+        ]);
+        self.cons_man
+            .clone()
+            .insert_row(tran_id, &SystemTables::PgType.value(), type_row)
+            .await?;
+
+        let enum_row = SqlTuple(vec![
+            Some(BaseSqlTypes::Uuid(type_id)),
+            Some(BaseSqlTypes::Array(
+                create_type
+                    .labels
+                    .into_iter()
+                    .map(|l| Some(BaseSqlTypes::Text(l)))
+                    .collect(),
+            )),
+        ]);
+        self.cons_man
+            .clone()
+            .insert_row(tran_id, &SystemTables::PgEnum.value(), enum_row)
+            .await?;
+
+        Ok(vec![])
+    }
+
+    /// Any column that still uses the type, or an array of it, blocks the drop
+    async fn drop_type(
+        &mut self,
+        tran_id: TransactionId,
+        drop_type: RawDropTypeCommand,
+    ) -> Result<Vec<SqlTuple>, ExecutorError> {
+        let (type_pointer, type_row) = match self.find_type(tran_id, &drop_type.type_name).await? {
+            Some(found) => found,
+            None if drop_type.if_exists => return Ok(vec![]),
+            None => return Err(ExecutorError::TypeDoesNotExist(drop_type.type_name)),
+        };
+        if matches!(
+            type_row.get_column_not_null(pg_type::COLUMN_OID)?,
+            BaseSqlTypes::Bigint(o) if o < i64::from(FIRST_USER_OID)
+        ) {
+            return Err(ExecutorError::SystemType(drop_type.type_name));
+        }
+
+        let oids = [
+            type_row.get_column_not_null(pg_type::COLUMN_OID)?,
+            type_row.get_column_not_null(pg_type::COLUMN_TYPARRAY)?,
+        ];
+        let row_stream = self
+            .cons_man
+            .clone()
+            .get_stream(tran_id, SystemTables::PgAttribute.value());
+        pin_mut!(row_stream);
+        while let Some(row) = row_stream.next().await {
+            if oids.contains(&row?.get_column_not_null(pg_attribute::COLUMN_TYPE_OID)?) {
+                return Err(ExecutorError::TypeInUse(drop_type.type_name));
+            }
+        }
+
+        let type_id = type_row.get_column_not_null(pg_type::COLUMN_ID)?;
+        if let Some((enum_pointer, _)) = self
+            .find_row(
+                tran_id,
+                SystemTables::PgEnum,
+                pg_enum::COLUMN_TYPE_ID,
+                type_id,
+            )
+            .await?
+        {
+            self.cons_man
+                .clone()
+                .delete_row(tran_id, &SystemTables::PgEnum.value(), enum_pointer)
+                .await?;
+        }
+        self.cons_man
+            .clone()
+            .delete_row(tran_id, &SystemTables::PgType.value(), type_pointer)
+            .await?;
+
+        Ok(vec![])
+    }
+
+    /// What pg_attribute stores for a column type, the oid of its row in pg_type
+    async fn type_oid(
+        &self,
+        tran_id: TransactionId,
+        type_name: &str,
+    ) -> Result<BaseSqlTypes, ExecutorError> {
+        let element_name = type_name.trim_end_matches("[]");
+        let is_array = element_name.len() < type_name.len();
+
+        let (_, type_row) = self
+            .find_type(tran_id, element_name)
+            .await?
+            .ok_or_else(|| ExecutorError::TypeDoesNotExist(element_name.to_string()))?;
+        Ok(type_row.get_column_not_null(match is_array {
+            true => pg_type::COLUMN_TYPARRAY,
+            false => pg_type::COLUMN_OID,
+        })?)
+    }
+
+    /// Built in types can be written several ways, such as integer for int4, pg_type only has the postgres name
+    async fn find_type(
+        &self,
+        tran_id: TransactionId,
+        type_name: &str,
+    ) -> Result<Option<(ItemPointer, RowData)>, ExecutorError> {
+        let name = TypeDefinitions::from_name(type_name)
+            .map(|builtin| builtin.value().name)
+            .unwrap_or(type_name);
+        self.find_row(
+            tran_id,
+            SystemTables::PgType,
+            pg_type::COLUMN_TYPNAME,
+            BaseSqlTypes::Text(name.to_string()),
+        )
+        .await
+    }
+
+    async fn find_row(
+        &self,
+        tran_id: TransactionId,
+        table: SystemTables,
+        column: &str,
+        value: BaseSqlTypes,
+    ) -> Result<Option<(ItemPointer, RowData)>, ExecutorError> {
+        let row_stream = self
+            .cons_man
+            .clone()
+            .get_stream_with_pointers(tran_id, table.value());
+        pin_mut!(row_stream);
+        while let Some(row) = row_stream.next().await {
+            let (pointer, row) = row?;
+            if row.get_column_not_null(column)? == value {
+                return Ok(Some((pointer, row)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reserves count consecutive oids and returns the first.
+    ///
+    /// Like postgres the counter isn't transactional, an aborted create just leaves a gap.
+    async fn allocate_oids(
+        &self,
+        tran_id: TransactionId,
+        count: i64,
+    ) -> Result<i64, ExecutorError> {
+        let mut next_oid = self.next_oid.lock().await;
+        let oid = match *next_oid {
+            Some(o) => o,
+            None => {
+                let mut last_oid = self
+                    .last_oid(tran_id, SystemTables::PgClass, pg_class::COLUMN_OID)
+                    .await?;
+                for column in [pg_type::COLUMN_OID, pg_type::COLUMN_TYPARRAY] {
+                    last_oid =
+                        last_oid.max(self.last_oid(tran_id, SystemTables::PgType, column).await?);
+                }
+                last_oid + 1
+            }
+        };
+        *next_oid = Some(oid + count);
+        Ok(oid)
+    }
+
+    /// The highest oid in a catalog column, user objects start at FIRST_USER_OID
+    async fn last_oid(
+        &self,
+        tran_id: TransactionId,
+        table: SystemTables,
+        column: &str,
+    ) -> Result<i64, ExecutorError> {
+        let mut last_oid = i64::from(FIRST_USER_OID) - 1;
+        let row_stream = self.cons_man.clone().get_stream(tran_id, table.value());
+        pin_mut!(row_stream);
+        while let Some(row) = row_stream.next().await {
+            if let BaseSqlTypes::Bigint(o) = row?.get_column_not_null(column)? {
+                last_oid = last_oid.max(o);
+            }
+        }
+        Ok(last_oid)
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum ExecutorError {
     #[error("Not a utility statement")]
     NotUtility(),
+    #[error("Enum label \"{0}\" used more than once")]
+    DuplicateEnumLabel(String),
+    #[error("Type \"{0}\" already exists")]
+    TypeAlreadyExists(String),
+    #[error("Type \"{0}\" does not exist")]
+    TypeDoesNotExist(String),
+    #[error("Type \"{0}\" is still used by a column")]
+    TypeInUse(String),
+    #[error("Type \"{0}\" is built in and can't be dropped")]
+    SystemType(String),
+    #[error(transparent)]
+    AggregateError(#[from] AggregateError),
+    #[error(transparent)]
+    ExpressionError(#[from] ExpressionError),
+    #[error(transparent)]
+    ExternalSortError(#[from] ExternalSortError),
+    #[error(transparent)]
+    RowDataError(#[from] RowDataError),
     #[error(transparent)]
     SqlTupleError(#[from] SqlTupleError),
     #[error(transparent)]
     ConstraintManagerError(#[from] ConstraintManagerError),
+    #[error(transparent)]
+    TypeModifierError(#[from] TypeModifierError),
     #[error("Unable to convert usize to u32")]
     ConversionError(#[from] TryFromIntError),
     #[error("Recursive Plans Not Allowed")]
@@ -0,0 +1,465 @@
+//! Sorts rows that may not fit in memory.
+//!
+//! Rows are buffered until they pass the memory budget, then the buffer is sorted and written out
+//! to a temp file as a run. Once the input is done the runs and whatever is left in memory are merged.
+//! Each run's file is removed when it is dropped so an abandoned sort doesn't leave files behind.
+use crate::engine::io::SelfEncodedSize;
+use crate::engine::objects::types::{BaseSqlTypes, BaseSqlTypesError, BaseSqlTypesMapper};
+use crate::engine::objects::{ExpressionError, SortKey, SortType, SqlTuple};
+use bytes::{Buf, BufMut, BytesMut};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::mem::size_of;
+use std::num::TryFromIntError;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tokio::fs::{self, File};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
+use uuid::Uuid;
+
+/// Past this many runs on disk they are merged into one so the final merge doesn't need too many files open
+const MAX_MERGE_RUNS: usize = 16;
+
+pub struct ExternalSort {
+    keys: Vec<SortKey>,
+    columns: Vec<BaseSqlTypesMapper>,
+    memory: usize,
+    temp_dir: PathBuf,
+    //Each row is kept with its evaluated sort keys
+    buffer: Vec<(SqlTuple, SqlTuple)>,
+    buffer_size: usize,
+    runs: Vec<Run>,
+}
+
+impl ExternalSort {
+    pub fn new(
+        keys: Vec<SortKey>,
+        columns: Vec<BaseSqlTypesMapper>,
+        memory: usize,
+        temp_dir: PathBuf,
+    ) -> ExternalSort {
+        ExternalSort {
+            keys,
+            columns,
+            memory,
+            temp_dir,
+            buffer: vec![],
+            buffer_size: 0,
+            runs: vec![],
+        }
+    }
+
+    pub async fn push(&mut self, row: SqlTuple) -> Result<(), ExternalSortError> {
+        let sort_values = sort_values(&self.keys, &row)?;
+        self.buffer_size += row_size(&sort_values) + row_size(&row);
+        self.buffer.push((sort_values, row));
+
+        if self.buffer_size > self.memory {
+            self.spill().await?;
+        }
+        Ok(())
+    }
+
+    /// Hands back the rows in order, the in memory rows are merged with the runs as they are read
+    pub async fn finish(mut self) -> Result<SortedRows, ExternalSortError> {
+        let keys = &self.keys;
+        self.buffer.sort_by(|a, b| compare(keys, &a.0, &b.0));
+
+        //Memory goes last so rows that sort equal stay in the order they came in
+        let mut sources = Vec::with_capacity(self.runs.len() + 1);
+        for run in self.runs {
+            sources.push(RunSource::open(run).await?);
+        }
+        sources.push(RunSource::Memory(self.buffer.into_iter()));
+
+        SortedRows::new(self.keys, self.columns, sources).await
+    }
+
+    async fn spill(&mut self) -> Result<(), ExternalSortError> {
+        let keys = &self.keys;
+        self.buffer.sort_by(|a, b| compare(keys, &a.0, &b.0));
+
+        let mut writer = RunWriter::create(&self.temp_dir).await?;
+        for (_, row) in self.buffer.drain(..) {
+            writer.write(&row).await?;
+        }
+        self.runs.push(writer.finish().await?);
+        self.buffer_size = 0;
+
+        if self.runs.len() >= MAX_MERGE_RUNS {
+            let mut sources = Vec::with_capacity(self.runs.len());
+            for run in self.runs.drain(..) {
+                sources.push(RunSource::open(run).await?);
+            }
+            let mut merged =
+                SortedRows::new(self.keys.clone(), self.columns.clone(), sources).await?;
+
+            let mut writer = RunWriter::create(&self.temp_dir).await?;
+            while let Some(row) = merged.next().await? {
+                writer.write(&row).await?;
+            }
+            self.runs.push(writer.finish().await?);
+        }
+
+        Ok(())
+    }
+}
+
+pub struct SortedRows {
+    keys: Vec<SortKey>,
+    columns: Vec<BaseSqlTypesMapper>,
+    sources: Vec<RunSource>,
+    //The next row of each source, None once it runs out
+    heads: Vec<Option<(SqlTuple, SqlTuple)>>,
+}
+
+impl SortedRows {
+    async fn new(
+        keys: Vec<SortKey>,
+        columns: Vec<BaseSqlTypesMapper>,
+        mut sources: Vec<RunSource>,
+    ) -> Result<SortedRows, ExternalSortError> {
+        let mut heads = Vec::with_capacity(sources.len());
+        for source in sources.iter_mut() {
+            heads.push(source.next(&keys, &columns).await?);
+        }
+        Ok(SortedRows {
+            keys,
+            columns,
+            sources,
+            heads,
+        })
+    }
+
+    pub async fn next(&mut self) -> Result<Option<SqlTuple>, ExternalSortError> {
+        //Ties go to the earlier source, that keeps the sort stable
+        let mut smallest: Option<usize> = None;
+        for (i, head) in self.heads.iter().enumerate() {
+            if let Some((values, _)) = head {
+                smallest = match smallest {
+                    Some(s) => match &self.heads[s] {
+                        Some((s_values, _))
+                            if compare(&self.keys, values, s_values) == Ordering::Less =>
+                        {
+                            Some(i)
+                        }
+                        _ => Some(s),
+                    },
+                    None => Some(i),
+                };
+            }
+        }
+
+        let i = match smallest {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+        let next = self.sources[i].next(&self.keys, &self.columns).await?;
+        Ok(std::mem::replace(&mut self.heads[i], next).map(|(_, row)| row))
+    }
+}
+
+enum RunSource {
+    Memory(std::vec::IntoIter<(SqlTuple, SqlTuple)>),
+    File {
+        reader: BufReader<File>,
+        remaining: usize,
+        //Held so the file is only removed once we are done reading it
+        _run: Run,
+    },
+}
+
+impl RunSource {
+    async fn open(run: Run) -> Result<RunSource, ExternalSortError> {
+        Ok(RunSource::File {
+            reader: BufReader::new(File::open(&run.path).await?),
+            remaining: run.rows,
+            _run: run,
+        })
+    }
+
+    async fn next(
+        &mut self,
+        keys: &[SortKey],
+        columns: &[BaseSqlTypesMapper],
+    ) -> Result<Option<(SqlTuple, SqlTuple)>, ExternalSortError> {
+        match self {
+            RunSource::Memory(rows) => Ok(rows.next()),
+            RunSource::File {
+                reader, remaining, ..
+            } => {
+                if *remaining == 0 {
+                    return Ok(None);
+                }
+                *remaining -= 1;
+
+                let length = usize::try_from(reader.read_u32_le().await?)?;
+                let mut buffer = vec![0; length];
+                reader.read_exact(&mut buffer).await?;
+                let row = decode_row(columns, &mut buffer.as_slice())?;
+
+                //Cheaper to evaluate the keys again than to write them out
+                Ok(Some((sort_values(keys, &row)?, row)))
+            }
+        }
+    }
+}
+
+/// A sorted file of rows, removed once dropped
+struct Run {
+    path: PathBuf,
+    rows: usize,
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            warn!("Unable to remove sort run {:?} due to {}", self.path, e);
+        }
+    }
+}
+
+struct RunWriter {
+    writer: BufWriter<File>,
+    run: Run,
+}
+
+impl RunWriter {
+    async fn create(temp_dir: &Path) -> Result<RunWriter, ExternalSortError> {
+        fs::create_dir_all(temp_dir).await?;
+        let path = temp_dir.join(format!("sort_{}", Uuid::new_v4()));
+        let file = File::create(&path).await?;
+        Ok(RunWriter {
+            writer: BufWriter::new(file),
+            run: Run { path, rows: 0 },
+        })
+    }
+
+    async fn write(&mut self, row: &SqlTuple) -> Result<(), ExternalSortError> {
+        let mut buffer = BytesMut::new();
+        encode_row(row, &mut buffer);
+
+        self.writer
+            .write_u32_le(u32::try_from(buffer.len())?)
+            .await?;
+        self.writer.write_all(&buffer).await?;
+        self.run.rows += 1;
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<Run, ExternalSortError> {
+        self.writer.flush().await?;
+        Ok(self.run)
+    }
+}
+
+fn sort_values(keys: &[SortKey], row: &SqlTuple) -> Result<SqlTuple, ExternalSortError> {
+    let mut values = Vec::with_capacity(keys.len());
+    for k in keys {
+        values.push(k.expression.evaluate(row)?);
+    }
+    Ok(SqlTuple(values))
+}
+
+/// Rough memory use of a row, the encoded size misses the space the columns take themselves
+fn row_size(row: &SqlTuple) -> usize {
+    size_of::<Option<BaseSqlTypes>>() * row.len() + row.encoded_size()
+}
+
+/// Compares the sort key values of two rows, NULLs go wherever the key says no matter the direction
+pub fn compare(keys: &[SortKey], left: &SqlTuple, right: &SqlTuple) -> Ordering {
+    for (key, (l, r)) in keys.iter().zip(left.iter().zip(right.iter())) {
+        let order = match (l, r) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) if key.nulls_first => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) if key.nulls_first => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(l), Some(r)) => match key.sort_type {
+                SortType::Ascending => l.cmp(r),
+                SortType::Descending => r.cmp(l),
+            },
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Each column is a flag byte saying if it's there followed by its value
+fn encode_row(row: &SqlTuple, buffer: &mut impl BufMut) {
+    for column in row.iter() {
+        match column {
+            Some(c) => {
+                buffer.put_u8(1);
+                c.serialize(buffer);
+            }
+            None => buffer.put_u8(0),
+        }
+    }
+}
+
+fn decode_row(
+    columns: &[BaseSqlTypesMapper],
+    buffer: &mut impl Buf,
+) -> Result<SqlTuple, ExternalSortError> {
+    let mut row = Vec::with_capacity(columns.len());
+    for column in columns {
+        if !buffer.has_remaining() {
+            return Err(ExternalSortError::RowTooShort(columns.len()));
+        }
+        match buffer.get_u8() {
+            0 => row.push(None),
+            _ => row.push(Some(BaseSqlTypes::deserialize(column, buffer)?)),
+        }
+    }
+    Ok(SqlTuple(row))
+}
+
+#[derive(Debug, Error)]
+pub enum ExternalSortError {
+    #[error(transparent)]
+    BaseSqlTypesError(#[from] BaseSqlTypesError),
+    #[error(transparent)]
+    ExpressionError(#[from] ExpressionError),
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
+    #[error("Spilled row is missing columns, expected {0}")]
+    RowTooShort(usize),
+    #[error(transparent)]
+    TryFromIntError(#[from] TryFromIntError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::objects::Expression;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    fn row(id: Option<i32>, name: &str) -> SqlTuple {
+        SqlTuple(vec![
+            id.map(BaseSqlTypes::Integer),
+            Some(BaseSqlTypes::Text(name.to_string())),
+        ])
+    }
+
+    fn key(offset: usize, sort_type: SortType, nulls_first: bool) -> SortKey {
+        SortKey {
+            expression: Arc::new(Expression::Column(offset, BaseSqlTypesMapper::Integer)),
+            sort_type,
+            nulls_first,
+        }
+    }
+
+    async fn sort_all(
+        keys: Vec<SortKey>,
+        rows: Vec<SqlTuple>,
+        memory: usize,
+        temp_dir: PathBuf,
+    ) -> Result<Vec<SqlTuple>, Box<dyn std::error::Error>> {
+        let mut sort = ExternalSort::new(
+            keys,
+            vec![BaseSqlTypesMapper::Integer, BaseSqlTypesMapper::Text],
+            memory,
+            temp_dir,
+        );
+        for r in rows {
+            sort.push(r).await?;
+        }
+
+        let mut sorted = sort.finish().await?;
+        let mut output = vec![];
+        while let Some(r) = sorted.next().await? {
+            output.push(r);
+        }
+        Ok(output)
+    }
+
+    #[tokio::test]
+    async fn test_sort_in_memory() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = TempDir::new()?;
+        let rows = vec![
+            row(Some(2), "b"),
+            row(None, "null"),
+            row(Some(1), "a"),
+            row(Some(2), "c"),
+        ];
+
+        let ascending = sort_all(
+            vec![key(0, SortType::Ascending, false)],
+            rows.clone(),
+            usize::MAX,
+            tmp.path().to_path_buf(),
+        )
+        .await?;
+        assert_eq!(
+            ascending,
+            vec![
+                row(Some(1), "a"),
+                row(Some(2), "b"),
+                row(Some(2), "c"),
+                row(None, "null"),
+            ]
+        );
+
+        //Ties keep their input order when descending too
+        let descending = sort_all(
+            vec![key(0, SortType::Descending, false)],
+            rows,
+            usize::MAX,
+            tmp.path().to_path_buf(),
+        )
+        .await?;
+        assert_eq!(
+            descending,
+            vec![
+                row(Some(2), "b"),
+                row(Some(2), "c"),
+                row(Some(1), "a"),
+                row(None, "null"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_spills() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = TempDir::new()?;
+        let temp_dir = tmp.path().join("sort");
+
+        //Enough rows at a tiny budget to need several merges of the runs
+        let mut rows = vec![];
+        for i in 0..1000i32 {
+            let id = match i % 100 {
+                0 => None,
+                _ => Some((i * 7919) % 1000),
+            };
+            rows.push(row(id, &format!("row {}", i)));
+        }
+
+        let mut expected = rows.clone();
+        expected.sort_by(|a, b| match (&a.0[0], &b.0[0]) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(a), Some(b)) => b.cmp(a),
+        });
+
+        let sorted = sort_all(
+            vec![key(0, SortType::Descending, true)],
+            rows,
+            200,
+            temp_dir.clone(),
+        )
+        .await?;
+        assert_eq!(sorted, expected);
+
+        //All the runs are cleaned up
+        assert_eq!(std::fs::read_dir(&temp_dir)?.count(), 0);
+
+        Ok(())
+    }
+}
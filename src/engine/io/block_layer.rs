@@ -36,5 +36,7 @@ pub mod free_space_manager;
 
 pub mod lock_manager;
 
+pub mod write_ahead_log;
+
 mod resource_formatter;
 pub use resource_formatter::ResourceFormatter;
@@ -3,8 +3,9 @@ use moka::future::Cache;
 use std::convert::TryFrom;
 use std::io::SeekFrom;
 use std::num::TryFromIntError;
-use std::ops::DerefMut;
+use std::ops::{DerefMut, Range};
 use std::{
+    collections::HashSet,
     ffi::{OsStr, OsString},
     path::{Path, PathBuf},
     sync::{atomic::AtomicUsize, Arc},
@@ -12,17 +13,24 @@ use std::{
 use thiserror::Error;
 use tokio::fs::{read_dir, File};
 use tokio::io::{AsyncReadExt, AsyncSeekExt};
-use tokio::sync::{Mutex, OwnedRwLockReadGuard, OwnedRwLockWriteGuard};
+use tokio::sync::{Mutex, OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
 
-use crate::constants::{MAX_FILE_HANDLE_COUNT, MAX_PAGE_CACHE};
+use crate::constants::{MAX_FILE_HANDLE_COUNT, MAX_PAGE_CACHE, WAL_CHECKPOINT_SIZE};
 use crate::engine::io::block_layer::ResourceFormatter;
 use crate::{
     constants::PAGE_SIZE,
-    engine::io::page_formats::{PageId, PageOffset},
+    engine::{
+        io::page_formats::{PageId, PageOffset},
+        transactions::TransactionId,
+    },
 };
 
 use super::file_operations::{FileOperations, FileOperationsError};
 use super::lock_manager::LockManager;
+use super::write_ahead_log::{WalRecord, WriteAheadLog, WriteAheadLogError};
+
+/// A page write waiting to be made, the guard keeps it locked until then
+pub type PageWrite = (OwnedRwLockWriteGuard<(PageId, PageOffset)>, Bytes);
 
 /// Empty page buffer
 const EMPTY_BUFFER: [u8; 16] = [0u8; 16];
@@ -30,12 +38,22 @@ const EMPTY_BUFFER: [u8; 16] = [0u8; 16];
 /// Attempt to move away from channels for the FileManager Service.
 ///
 /// This code has ended up tremendously simpler than the prior version!
+///
+/// All page writes go through the write ahead log first, call recover before using
+/// the file manager on an existing data directory.
 pub struct FileManager2 {
     data_dir: PathBuf,
     file_handles: Cache<(PageId, usize), Arc<Mutex<File>>>,
     file_offsets: Cache<PageId, Arc<AtomicUsize>>,
     lock_manager: LockManager,
     page_cache: Cache<(PageId, PageOffset), Bytes>,
+    wal: WriteAheadLog,
+    //Writers hold this shared from logging until the page is in place, checkpoints hold it exclusively
+    checkpoint_lock: RwLock<()>,
+    //Files written since the last checkpoint, they need an fsync before the log can be truncated
+    dirty_files: Mutex<HashSet<(PageId, usize)>>,
+    //Pages with a full image in the log since the last checkpoint, later changes can log just a range
+    logged_images: Mutex<HashSet<(PageId, PageOffset)>>,
 }
 
 impl FileManager2 {
@@ -48,15 +66,97 @@ impl FileManager2 {
             ));
         }
 
+        let wal = WriteAheadLog::open(&data_dir)?;
+
         Ok(FileManager2 {
             data_dir,
             file_handles: Cache::new(MAX_FILE_HANDLE_COUNT),
             file_offsets: Cache::new(10000),
             lock_manager: LockManager::new(),
             page_cache: Cache::new(MAX_PAGE_CACHE),
+            wal,
+            checkpoint_lock: RwLock::new(()),
+            dirty_files: Mutex::new(HashSet::new()),
+            logged_images: Mutex::new(HashSet::new()),
         })
     }
 
+    /// Replays the write ahead log left by a crash and then checkpoints.
+    ///
+    /// Must be called before any other use of the file manager. Returns the number of records found.
+    pub async fn recover(&self) -> Result<usize, FileManager2Error> {
+        let records = self.wal.read_all().await?;
+
+        for record in records.iter() {
+            match record {
+                WalRecord::PageWrites(pages) => {
+                    for (page_id, offset, page) in pages {
+                        self.write_in_place(*page_id, *offset, page.clone()).await?;
+                    }
+                }
+                WalRecord::PageRange(page_id, offset, start, bytes) => {
+                    let mut buffer = match self.get_page_for_update(page_id, offset).await {
+                        Ok((page, _guard)) => BytesMut::from(&page[..]),
+                        Err(FileManager2Error::PageDoesNotExist(_)) => {
+                            BytesMut::from(&[0u8; PAGE_SIZE as usize][..])
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    buffer[*start..*start + bytes.len()].copy_from_slice(bytes);
+                    self.write_in_place(*page_id, *offset, buffer.freeze())
+                        .await?;
+                }
+                WalRecord::Commit(_) | WalRecord::Abort(_) => {}
+            }
+        }
+
+        if !records.is_empty() {
+            info!("Replayed {} write ahead log records", records.len());
+        }
+
+        self.checkpoint().await?;
+        Ok(records.len())
+    }
+
+    /// Makes a commit durable, along with every page write logged before it
+    pub async fn log_commit(&self, tran_id: TransactionId) -> Result<(), FileManager2Error> {
+        self.wal
+            .append_and_sync(&WalRecord::Commit(tran_id))
+            .await?;
+
+        if self.wal.size().await? > WAL_CHECKPOINT_SIZE {
+            self.checkpoint().await?;
+        }
+        Ok(())
+    }
+
+    /// Aborts don't need to be durable, a transaction that never committed is treated as aborted
+    pub async fn log_abort(&self, tran_id: TransactionId) -> Result<(), FileManager2Error> {
+        self.wal.append(&WalRecord::Abort(tran_id)).await?;
+        Ok(())
+    }
+
+    /// Flushes every data file written since the last checkpoint and then empties the log
+    pub async fn checkpoint(&self) -> Result<(), FileManager2Error> {
+        let _checkpoint = self.checkpoint_lock.write().await;
+
+        let mut dirty_files = self.dirty_files.lock().await;
+        for (page_id, file_number) in dirty_files.iter() {
+            let file_handle = self.get_file_handle(*page_id, *file_number).await?;
+            let file = file_handle.lock().await;
+            file.sync_all().await?;
+        }
+        dirty_files.clear();
+
+        self.wal.truncate().await?;
+        self.logged_images.lock().await.clear();
+        Ok(())
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
     pub async fn get_next_offset(
         &self,
         page_id: &PageId,
@@ -83,23 +183,7 @@ impl FileManager2 {
         guard: OwnedRwLockWriteGuard<(PageId, PageOffset)>,
         page: Bytes,
     ) -> Result<(), FileManager2Error> {
-        let data_dir = self.data_dir.clone();
-        let page_id = guard.0;
-        let file_number = guard.1.get_file_number();
-        let file_handle = self
-            .file_handles
-            .get_or_try_insert_with((page_id, file_number), async move {
-                let handle = FileOperations::open_path(&data_dir, &page_id, file_number).await?;
-                Ok::<Arc<Mutex<File>>, FileManager2Error>(Arc::new(Mutex::const_new(handle)))
-            })
-            .await?;
-        let mut file = file_handle.lock().await;
-
-        self.page_cache
-            .insert((page_id, guard.1), page.clone())
-            .await;
-        let _ = FileOperations::add_chunk(file.deref_mut(), &guard.1, page).await?;
-        Ok(())
+        self.update_pages(vec![(guard, page)]).await
     }
 
     pub async fn get_page(
@@ -192,30 +276,118 @@ impl FileManager2 {
         Ok((chunk, write_lock))
     }
 
+    /// Locks a page for writing without reading it, for callers that address their pages directly
+    /// and may be writing one that doesn't exist yet.
+    pub async fn get_page_for_write(
+        &self,
+        page_id: &PageId,
+        offset: &PageOffset,
+    ) -> OwnedRwLockWriteGuard<(PageId, PageOffset)> {
+        self.lock_manager.write(*page_id, *offset).await
+    }
+
     pub async fn update_page(
         &self,
         guard: OwnedRwLockWriteGuard<(PageId, PageOffset)>,
         page: Bytes,
     ) -> Result<(), FileManager2Error> {
-        let data_dir = self.data_dir.clone();
-        let page_id = guard.0;
-        let file_number = guard.1.get_file_number();
-        let file_handle = self
-            .file_handles
-            .get_or_try_insert_with((page_id, file_number), async move {
-                let handle = FileOperations::open_path(&data_dir, &page_id, file_number).await?;
-                Ok::<Arc<Mutex<File>>, FileManager2Error>(Arc::new(Mutex::const_new(handle)))
-            })
+        self.update_pages(vec![(guard, page)]).await
+    }
+
+    /// Writes several pages as one unit, after a crash either all of them are there or none are.
+    pub async fn update_pages(&self, pages: Vec<PageWrite>) -> Result<(), FileManager2Error> {
+        if pages.is_empty() {
+            return Ok(());
+        }
+
+        let _checkpoint = self.checkpoint_lock.read().await;
+
+        let images: Vec<_> = pages
+            .iter()
+            .map(|(guard, page)| (guard.0, guard.1, page.clone()))
+            .collect();
+        //The images must be on disk before the pages are, otherwise the OS could write a data
+        //file back first and a crash part way through would leave a torn page nothing can repair.
+        self.wal
+            .append_and_sync(&WalRecord::PageWrites(images.clone()))
             .await?;
+        self.logged_images.lock().await.extend(
+            images
+                .into_iter()
+                .map(|(page_id, offset, _)| (page_id, offset)),
+        );
+
+        for (guard, page) in pages {
+            self.write_in_place(guard.0, guard.1, page).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes a page where only the given range changed, for callers that make lots of small
+    /// changes to the same pages. The first change after a checkpoint still logs the whole page.
+    pub async fn update_page_range(
+        &self,
+        guard: OwnedRwLockWriteGuard<(PageId, PageOffset)>,
+        page: Bytes,
+        range: Range<usize>,
+    ) -> Result<(), FileManager2Error> {
+        let _checkpoint = self.checkpoint_lock.read().await;
+
+        //The guard keeps other writers of this page out until it is in place
+        let key = (guard.0, guard.1);
+        if self.logged_images.lock().await.contains(&key) {
+            self.wal
+                .append_and_sync(&WalRecord::PageRange(
+                    key.0,
+                    key.1,
+                    range.start,
+                    page.slice(range),
+                ))
+                .await?;
+        } else {
+            self.wal
+                .append_and_sync(&WalRecord::PageWrites(vec![(key.0, key.1, page.clone())]))
+                .await?;
+            self.logged_images.lock().await.insert(key);
+        }
+
+        self.write_in_place(key.0, key.1, page).await
+    }
+
+    async fn write_in_place(
+        &self,
+        page_id: PageId,
+        offset: PageOffset,
+        page: Bytes,
+    ) -> Result<(), FileManager2Error> {
+        let file_number = offset.get_file_number();
+        let file_handle = self.get_file_handle(page_id, file_number).await?;
         let mut file = file_handle.lock().await;
 
         self.page_cache
-            .insert((page_id, guard.1), page.clone())
+            .insert((page_id, offset), page.clone())
             .await;
-        let _ = FileOperations::update_chunk(file.deref_mut(), &guard.1, page).await?;
+        FileOperations::add_chunk(file.deref_mut(), &offset, page).await?;
+
+        self.dirty_files.lock().await.insert((page_id, file_number));
         Ok(())
     }
 
+    async fn get_file_handle(
+        &self,
+        page_id: PageId,
+        file_number: usize,
+    ) -> Result<Arc<Mutex<File>>, FileManager2Error> {
+        let data_dir = self.data_dir.clone();
+        Ok(self
+            .file_handles
+            .get_or_try_insert_with((page_id, file_number), async move {
+                let handle = FileOperations::open_path(&data_dir, &page_id, file_number).await?;
+                Ok::<Arc<Mutex<File>>, FileManager2Error>(Arc::new(Mutex::const_new(handle)))
+            })
+            .await?)
+    }
+
     async fn find_next_offset(
         data_dir: &Path,
         page_id: &PageId,
@@ -341,6 +513,8 @@ pub enum FileManager2Error {
     NeedDirectory(String),
     #[error(transparent)]
     TryFromIntError(#[from] TryFromIntError),
+    #[error(transparent)]
+    WriteAheadLogError(#[from] WriteAheadLogError),
 }
 
 #[cfg(test)]
@@ -396,6 +570,88 @@ mod tests {
         let (test_page_get2, _test_guard2) = fm2.get_page(&page_id, &test_po).await?;
         assert_eq!(test_page2, test_page_get2);
 
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_recovery() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = TempDir::new()?;
+        let tmp_dir = tmp.path();
+
+        let page_id = PageId {
+            resource_key: Uuid::new_v4(),
+            page_type: PageType::Data,
+        };
+
+        let fm = FileManager2::new(tmp_dir.as_os_str().to_os_string())?;
+        let (test_po, test_guard) = fm.get_next_offset(&page_id).await?;
+        fm.add_page(test_guard, get_test_page(1)).await?;
+
+        //Simulate crashing after the log write but before the pages were written in place
+        fm.wal
+            .append(&WalRecord::PageWrites(vec![
+                (page_id, test_po, get_test_page(2)),
+                (page_id, PageOffset(1), get_test_page(3)),
+            ]))
+            .await?;
+        drop(fm);
+
+        let fm2 = FileManager2::new(tmp_dir.as_os_str().to_os_string())?;
+        assert_eq!(fm2.recover().await?, 2);
+        assert_eq!(fm2.wal.size().await?, 0);
+
+        let (page, _guard) = fm2.get_page(&page_id, &test_po).await?;
+        assert_eq!(page, get_test_page(2));
+        let (page, _guard) = fm2.get_page(&page_id, &PageOffset(1)).await?;
+        assert_eq!(page, get_test_page(3));
+
+        //New pages must not land on top of the recovered ones
+        let (next_po, _guard) = fm2.get_next_offset(&page_id).await?;
+        assert!(next_po > PageOffset(1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_range_recovery() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = TempDir::new()?;
+        let tmp_dir = tmp.path();
+
+        let page_id = PageId {
+            resource_key: Uuid::new_v4(),
+            page_type: PageType::CommitLog,
+        };
+
+        let fm = FileManager2::new(tmp_dir.as_os_str().to_os_string())?;
+        let (test_po, test_guard) = fm.get_next_offset(&page_id).await?;
+        fm.add_page(test_guard, get_test_page(1)).await?;
+        fm.checkpoint().await?;
+
+        let mut expected = BytesMut::from(&get_test_page(1)[..]);
+        for (i, fill) in [(5, 7u8), (9, 8u8)] {
+            expected[i] = fill;
+            let (_, guard) = fm.get_page_for_update(&page_id, &test_po).await?;
+            fm.update_page_range(guard, expected.clone().freeze(), i..i + 1)
+                .await?;
+        }
+
+        //Only the first change after the checkpoint carries the whole page
+        let records = fm.wal.read_all().await?;
+        assert!(matches!(records[0], WalRecord::PageWrites(_)));
+        assert_eq!(
+            records[1],
+            WalRecord::PageRange(page_id, test_po, 9, Bytes::from_static(&[8]))
+        );
+
+        //Simulate the page being torn by a crash while it was written in place
+        fm.write_in_place(page_id, test_po, get_test_page(0))
+            .await?;
+        drop(fm);
+
+        let fm2 = FileManager2::new(tmp_dir.as_os_str().to_os_string())?;
+        assert_eq!(fm2.recover().await?, 2);
+        let (page, _guard) = fm2.get_page(&page_id, &test_po).await?;
+        assert_eq!(page, expected.freeze());
+
         Ok(())
     }
 }
@@ -0,0 +1,385 @@
+//! The write ahead log is what makes the block layer crash safe.
+//!
+//! Every page change is appended to the log and fsync'd before it is written in place. Pages that
+//! must change together (such as a btree split) are logged as a single record, so they are either
+//! all replayed or none are. Commit records are fsync'd before a commit is acknowledged.
+//!
+//! Small changes can be logged as just the bytes that changed, as long as a full image of the page
+//! was logged since the last checkpoint. Replaying the image first repairs a torn page, then the
+//! ranges are applied on top of it.
+//!
+//! On startup the log is replayed (redo only, full page images make it idempotent) and then
+//! truncated at a checkpoint once the data files are fsync'd.
+//!
+//! Record format:
+//!     length (u32) | crc32 of the body (u32) | body
+//! A record that is short or fails its crc is a torn write from a crash, it and everything
+//! after it is discarded.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::convert::TryFrom;
+use std::num::TryFromIntError;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::constants::PAGE_SIZE;
+use crate::engine::io::format_traits::{Parseable, Serializable};
+use crate::engine::io::page_formats::{PageId, PageOffset, PageOffsetError, PageType};
+use crate::engine::transactions::TransactionId;
+
+const WAL_FILE_NAME: &str = "write_ahead.log";
+const RECORD_HEADER_SIZE: usize = 8;
+
+const RECORD_PAGE_WRITES: u8 = 1;
+const RECORD_COMMIT: u8 = 2;
+const RECORD_ABORT: u8 = 3;
+const RECORD_PAGE_RANGE: u8 = 4;
+
+pub struct WriteAheadLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl WriteAheadLog {
+    pub fn open(data_dir: &Path) -> Result<WriteAheadLog, WriteAheadLogError> {
+        let mut path = data_dir.to_path_buf();
+        path.push(WAL_FILE_NAME);
+
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+
+        Ok(WriteAheadLog {
+            path,
+            file: Mutex::new(File::from_std(file)),
+        })
+    }
+
+    /// Appends a record without waiting for it to reach the disk
+    pub async fn append(&self, record: &WalRecord) -> Result<(), WriteAheadLogError> {
+        let buffer = Self::frame(record)?;
+
+        let mut file = self.file.lock().await;
+        file.write_all(&buffer).await?;
+        Ok(())
+    }
+
+    /// Appends a record and fsyncs the log, everything logged before it is durable once this returns
+    pub async fn append_and_sync(&self, record: &WalRecord) -> Result<(), WriteAheadLogError> {
+        let buffer = Self::frame(record)?;
+
+        let mut file = self.file.lock().await;
+        file.write_all(&buffer).await?;
+        file.sync_data().await?;
+        Ok(())
+    }
+
+    /// Reads every intact record, a torn tail is cut off so new records follow the good ones
+    pub async fn read_all(&self) -> Result<Vec<WalRecord>, WriteAheadLogError> {
+        let file = self.file.lock().await;
+
+        let contents = tokio::fs::read(&self.path).await?;
+        let total_len = contents.len();
+        let mut buffer = Bytes::from(contents);
+
+        let mut records = vec![];
+        while let Some(record) = Self::unframe(&mut buffer)? {
+            records.push(record);
+        }
+
+        if buffer.has_remaining() {
+            let valid_len = u64::try_from(total_len - buffer.remaining())?;
+            warn!(
+                "Discarding {} bytes of torn write ahead log",
+                buffer.remaining()
+            );
+            file.set_len(valid_len).await?;
+            file.sync_all().await?;
+        }
+
+        Ok(records)
+    }
+
+    /// Empties the log, only safe once everything it covers is durable in the data files
+    pub async fn truncate(&self) -> Result<(), WriteAheadLogError> {
+        let file = self.file.lock().await;
+        file.set_len(0).await?;
+        file.sync_all().await?;
+        Ok(())
+    }
+
+    pub async fn size(&self) -> Result<u64, WriteAheadLogError> {
+        let file = self.file.lock().await;
+        Ok(file.metadata().await?.len())
+    }
+
+    fn frame(record: &WalRecord) -> Result<Bytes, WriteAheadLogError> {
+        let mut body = BytesMut::new();
+        record.serialize(&mut body);
+
+        let mut buffer = BytesMut::with_capacity(RECORD_HEADER_SIZE + body.len());
+        buffer.put_u32_le(u32::try_from(body.len())?);
+        buffer.put_u32_le(crc32(&body));
+        buffer.put(body);
+        Ok(buffer.freeze())
+    }
+
+    /// Returns None when there isn't a complete and valid record left in the buffer
+    fn unframe(buffer: &mut Bytes) -> Result<Option<WalRecord>, WriteAheadLogError> {
+        if buffer.remaining() < RECORD_HEADER_SIZE {
+            return Ok(None);
+        }
+
+        let mut header = buffer.slice(0..RECORD_HEADER_SIZE);
+        let body_len = usize::try_from(header.get_u32_le())?;
+        let checksum = header.get_u32_le();
+
+        if buffer.remaining() < RECORD_HEADER_SIZE + body_len {
+            return Ok(None);
+        }
+
+        let body = buffer.slice(RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + body_len);
+        if crc32(&body) != checksum {
+            return Ok(None);
+        }
+
+        let record = WalRecord::parse(&mut body.clone())?;
+        buffer.advance(RECORD_HEADER_SIZE + body_len);
+        Ok(Some(record))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WalRecord {
+    /// Full page images that must be applied together
+    PageWrites(Vec<(PageId, PageOffset, Bytes)>),
+    /// Bytes to overwrite in a page, starting at the given position within it
+    PageRange(PageId, PageOffset, usize, Bytes),
+    Commit(TransactionId),
+    Abort(TransactionId),
+}
+
+impl Serializable for WalRecord {
+    fn serialize(&self, buffer: &mut impl BufMut) {
+        match self {
+            WalRecord::PageWrites(pages) => {
+                buffer.put_u8(RECORD_PAGE_WRITES);
+                buffer.put_u32_le(pages.len() as u32);
+                for (page_id, offset, page) in pages {
+                    serialize_page_id(page_id, buffer);
+                    offset.serialize(buffer);
+                    buffer.put_slice(page);
+                }
+            }
+            WalRecord::PageRange(page_id, offset, start, bytes) => {
+                buffer.put_u8(RECORD_PAGE_RANGE);
+                serialize_page_id(page_id, buffer);
+                offset.serialize(buffer);
+                buffer.put_u32_le(*start as u32);
+                buffer.put_u32_le(bytes.len() as u32);
+                buffer.put_slice(bytes);
+            }
+            WalRecord::Commit(t) => {
+                buffer.put_u8(RECORD_COMMIT);
+                buffer.put_u64_le(t.get_u64());
+            }
+            WalRecord::Abort(t) => {
+                buffer.put_u8(RECORD_ABORT);
+                buffer.put_u64_le(t.get_u64());
+            }
+        }
+    }
+}
+
+impl Parseable<WriteAheadLogError> for WalRecord {
+    type Output = Self;
+    fn parse(buffer: &mut impl Buf) -> Result<Self, WriteAheadLogError> {
+        if !buffer.has_remaining() {
+            return Err(WriteAheadLogError::BufferTooShort());
+        }
+
+        match buffer.get_u8() {
+            RECORD_PAGE_WRITES => {
+                if buffer.remaining() < 4 {
+                    return Err(WriteAheadLogError::BufferTooShort());
+                }
+                let count = buffer.get_u32_le();
+
+                let mut pages = vec![];
+                for _ in 0..count {
+                    let page_id = parse_page_id(buffer)?;
+                    let offset = PageOffset::parse(buffer)?;
+
+                    if buffer.remaining() < PAGE_SIZE as usize {
+                        return Err(WriteAheadLogError::BufferTooShort());
+                    }
+                    let page = buffer.copy_to_bytes(PAGE_SIZE as usize);
+
+                    pages.push((page_id, offset, page));
+                }
+                Ok(WalRecord::PageWrites(pages))
+            }
+            RECORD_PAGE_RANGE => {
+                let page_id = parse_page_id(buffer)?;
+                let offset = PageOffset::parse(buffer)?;
+
+                if buffer.remaining() < 8 {
+                    return Err(WriteAheadLogError::BufferTooShort());
+                }
+                let start = usize::try_from(buffer.get_u32_le())?;
+                let len = usize::try_from(buffer.get_u32_le())?;
+                if start + len > PAGE_SIZE as usize {
+                    return Err(WriteAheadLogError::RangeOutsidePage(start, len));
+                }
+
+                if buffer.remaining() < len {
+                    return Err(WriteAheadLogError::BufferTooShort());
+                }
+                let bytes = buffer.copy_to_bytes(len);
+
+                Ok(WalRecord::PageRange(page_id, offset, start, bytes))
+            }
+            RECORD_COMMIT => {
+                if buffer.remaining() < 8 {
+                    return Err(WriteAheadLogError::BufferTooShort());
+                }
+                Ok(WalRecord::Commit(TransactionId::new(buffer.get_u64_le())))
+            }
+            RECORD_ABORT => {
+                if buffer.remaining() < 8 {
+                    return Err(WriteAheadLogError::BufferTooShort());
+                }
+                Ok(WalRecord::Abort(TransactionId::new(buffer.get_u64_le())))
+            }
+            u => Err(WriteAheadLogError::UnknownRecordType(u)),
+        }
+    }
+}
+
+fn serialize_page_id(page_id: &PageId, buffer: &mut impl BufMut) {
+    buffer.put_slice(page_id.resource_key.as_bytes());
+    buffer.put_u8(match page_id.page_type {
+        PageType::Data => 0,
+        PageType::FreeSpaceMap => 1,
+        PageType::CommitLog => 2,
+    });
+}
+
+fn parse_page_id(buffer: &mut impl Buf) -> Result<PageId, WriteAheadLogError> {
+    if buffer.remaining() < 17 {
+        return Err(WriteAheadLogError::BufferTooShort());
+    }
+    let mut uuid = [0u8; 16];
+    buffer.copy_to_slice(&mut uuid);
+    let page_type = match buffer.get_u8() {
+        0 => PageType::Data,
+        1 => PageType::FreeSpaceMap,
+        2 => PageType::CommitLog,
+        u => return Err(WriteAheadLogError::UnknownPageType(u)),
+    };
+
+    Ok(PageId {
+        resource_key: Uuid::from_bytes(uuid),
+        page_type,
+    })
+}
+
+/// Standard crc32 (IEEE), bitwise since the log isn't hot enough to need a table
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (!(crc & 1)).wrapping_add(1);
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[derive(Debug, Error)]
+pub enum WriteAheadLogError {
+    #[error("Buffer too short to parse a write ahead log record")]
+    BufferTooShort(),
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
+    #[error(transparent)]
+    PageOffsetError(#[from] PageOffsetError),
+    #[error("Page range of {1} bytes at {0} goes past the end of the page")]
+    RangeOutsidePage(usize, usize),
+    #[error(transparent)]
+    TryFromIntError(#[from] TryFromIntError),
+    #[error("Unknown page type {0} in the write ahead log")]
+    UnknownPageType(u8),
+    #[error("Unknown record type {0} in the write ahead log")]
+    UnknownRecordType(u8),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn get_test_page(fill: u8) -> Bytes {
+        Bytes::from(vec![fill; PAGE_SIZE as usize])
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[tokio::test]
+    async fn test_roundtrip_and_torn_tail() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = TempDir::new()?;
+
+        let page_id = PageId {
+            resource_key: Uuid::new_v4(),
+            page_type: PageType::FreeSpaceMap,
+        };
+        let records = vec![
+            WalRecord::PageWrites(vec![
+                (page_id, PageOffset(0), get_test_page(1)),
+                (page_id, PageOffset(7), get_test_page(2)),
+            ]),
+            WalRecord::Commit(TransactionId::new(5)),
+            WalRecord::PageRange(page_id, PageOffset(3), 10, Bytes::from_static(&[1, 2, 3])),
+            WalRecord::Abort(TransactionId::new(6)),
+        ];
+
+        let wal = WriteAheadLog::open(tmp.path())?;
+        for r in &records {
+            wal.append(r).await?;
+        }
+        assert_eq!(wal.read_all().await?, records);
+
+        //Simulate a crash part way through a record
+        let good_len = wal.size().await?;
+        let mut raw = std::fs::OpenOptions::new()
+            .append(true)
+            .open(tmp.path().join(WAL_FILE_NAME))?;
+        raw.write_all(&[200, 0, 0, 0, 1, 2, 3])?;
+        drop(raw);
+
+        let wal = WriteAheadLog::open(tmp.path())?;
+        assert_eq!(wal.read_all().await?, records);
+        assert_eq!(wal.size().await?, good_len);
+
+        wal.append_and_sync(&WalRecord::Commit(TransactionId::new(7)))
+            .await?;
+        assert_eq!(wal.read_all().await?.len(), 5);
+
+        wal.truncate().await?;
+        assert!(wal.read_all().await?.is_empty());
+
+        Ok(())
+    }
+}
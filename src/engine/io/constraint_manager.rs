@@ -1,5 +1,6 @@
 use async_stream::try_stream;
 use futures::Stream;
+use std::ops::Bound;
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -7,8 +8,8 @@ use crate::{
     constants::Nullable,
     engine::{
         objects::{
-            types::{BaseSqlTypes, BaseSqlTypesMapper},
-            SqlTuple, SqlTupleError, Table,
+            types::{BaseSqlTypes, BaseSqlTypesMapper, TypeModifierError},
+            Index, SqlTuple, SqlTupleError, Table,
         },
         transactions::TransactionId,
     },
@@ -43,6 +44,131 @@ impl ConstraintManager {
         table: &Arc<Table>,
         user_data: SqlTuple,
     ) -> Result<ItemPointer, ConstraintManagerError> {
+        let user_data = Self::apply_modifiers(table, user_data)?;
+        self.check_row(current_tran_id, table, &user_data, None)
+            .await?;
+
+        //Insert the row
+        let row_item_ptr = self
+            .vis_row_man
+            .insert_row(current_tran_id, table, user_data.clone())
+            .await?;
+
+        self.add_to_indexes(table, &user_data, row_item_ptr).await?;
+
+        Ok(row_item_ptr)
+    }
+
+    /// Writes a new version of an existing row, the new version must pass the same checks as an insert
+    pub async fn update_row(
+        &mut self,
+        current_tran_id: TransactionId,
+        table: &Arc<Table>,
+        row_pointer: ItemPointer,
+        new_user_data: SqlTuple,
+    ) -> Result<ItemPointer, ConstraintManagerError> {
+        let new_user_data = Self::apply_modifiers(table, new_user_data)?;
+        self.check_row(current_tran_id, table, &new_user_data, Some(row_pointer))
+            .await?;
+
+        let new_row_ptr = self
+            .vis_row_man
+            .update_row(current_tran_id, table, row_pointer, new_user_data.clone())
+            .await?;
+
+        self.add_to_indexes(table, &new_user_data, new_row_ptr)
+            .await?;
+
+        Ok(new_row_ptr)
+    }
+
+    /// Logically deletes a row, the index entries are left for vacuum since they fail visibility
+    pub async fn delete_row(
+        &mut self,
+        current_tran_id: TransactionId,
+        table: &Arc<Table>,
+        row_pointer: ItemPointer,
+    ) -> Result<(), ConstraintManagerError> {
+        Ok(self
+            .vis_row_man
+            .delete_row(current_tran_id, table, row_pointer)
+            .await?)
+    }
+
+    /// Gets a specific tuple from below, at the moment just a passthrough
+    pub async fn get(
+        &mut self,
+        tran_id: TransactionId,
+        table: &Arc<Table>,
+        row_pointer: ItemPointer,
+    ) -> Result<RowData, ConstraintManagerError> {
+        Ok(self.vis_row_man.get(tran_id, table, row_pointer).await?)
+    }
+
+    /// Finds the rows an index points to for a range of keys, the rows still need a visibility check
+    pub async fn search_index(
+        &self,
+        index: &Index,
+        lower: &Bound<SqlTuple>,
+        upper: &Bound<SqlTuple>,
+    ) -> Result<Vec<ItemPointer>, ConstraintManagerError> {
+        Ok(self.index_manager.search_range(index, lower, upper).await?)
+    }
+
+    /// Provides a filtered view that respects transaction visibility
+    /// At the moment this is practically just a passthrough
+    pub fn get_stream(
+        self,
+        tran_id: TransactionId,
+        table: Arc<Table>,
+    ) -> impl Stream<Item = Result<RowData, ConstraintManagerError>> {
+        try_stream! {
+            for await row in self.get_stream_with_pointers(tran_id, table) {
+                let (_, unwrap_row) = row?;
+                yield unwrap_row;
+            }
+        }
+    }
+
+    /// Same as get_stream, with where each row is so it can be updated or deleted
+    pub fn get_stream_with_pointers(
+        self,
+        tran_id: TransactionId,
+        table: Arc<Table>,
+    ) -> impl Stream<Item = Result<(ItemPointer, RowData), ConstraintManagerError>> {
+        try_stream! {
+            for await row in self.vis_row_man.get_stream_with_pointers(tran_id, &table) {
+                let unwrap_row = row?;
+                yield unwrap_row;
+            }
+        }
+    }
+
+    /// Fits values to their column's type modifier, such as padding a char(n) column.
+    /// Values of the wrong type are left alone for check_row to report.
+    fn apply_modifiers(
+        table: &Arc<Table>,
+        mut user_data: SqlTuple,
+    ) -> Result<SqlTuple, ConstraintManagerError> {
+        for (data, column) in user_data.0.iter_mut().zip(&table.attributes) {
+            if let (Some(d), Some(modifier)) = (data, column.length) {
+                if d.type_matches(&column.sql_type) {
+                    *d = modifier.apply(d.clone())?;
+                }
+            }
+        }
+        Ok(user_data)
+    }
+
+    /// Verifies the column count, types, nulls and constraints of a row about to be written.
+    /// When updating, the row being replaced is excluded from the primary key check.
+    async fn check_row(
+        &mut self,
+        current_tran_id: TransactionId,
+        table: &Arc<Table>,
+        user_data: &SqlTuple,
+        replacing: Option<ItemPointer>,
+    ) -> Result<(), ConstraintManagerError> {
         //column count check
         if table.attributes.len() != user_data.0.len() {
             return Err(ConstraintManagerError::TableRowSizeMismatch(
@@ -64,7 +190,10 @@ impl ConstraintManager {
                 }
                 None => {
                     if column.nullable != Nullable::Null {
-                        return Err(ConstraintManagerError::UnexpectedNull(column.name));
+                        return Err(ConstraintManagerError::UnexpectedNull(
+                            table.name.clone(),
+                            column.name,
+                        ));
                     }
                 }
             }
@@ -75,24 +204,25 @@ impl ConstraintManager {
             match c {
                 crate::engine::objects::Constraint::PrimaryKey(p) => {
                     debug!("searching for {:?}", user_data);
-                    match self
-                        .index_manager
-                        .search_for_key(
-                            &p.index,
-                            &user_data
-                                .clone()
-                                .filter_map(&table.sql_type, &p.index.columns)?,
-                        )
-                        .await?
-                    {
-                        Some(rows) => {
+                    let key = user_data
+                        .clone()
+                        .filter_map(&table.sql_type, &p.index.columns)?;
+                    match self.index_manager.search_for_key(&p.index, &key).await? {
+                        Some(mut rows) => {
+                            if let Some(r) = replacing {
+                                rows.retain(|p| *p != r);
+                            }
+
                             //We need to check if each of these rows are alive
                             if self
                                 .vis_row_man
                                 .any_visible(table, current_tran_id, &rows)
                                 .await?
                             {
-                                return Err(ConstraintManagerError::PrimaryKeyViolation());
+                                return Err(ConstraintManagerError::PrimaryKeyViolation(
+                                    table.name.clone(),
+                                    ConstraintManager::describe_key(&p.index, &key),
+                                ));
                             }
                         }
                         None => {
@@ -103,13 +233,29 @@ impl ConstraintManager {
             }
         }
 
-        //Insert the row
-        let row_item_ptr = self
-            .vis_row_man
-            .insert_row(current_tran_id, table, user_data.clone())
-            .await?;
+        Ok(())
+    }
+
+    /// Formats a key the same way postgres does in its error details, (col1, col2)=(value1, value2)
+    fn describe_key(index: &Index, key: &SqlTuple) -> String {
+        let columns: Vec<&str> = index.columns.iter().map(|(n, _)| n.as_str()).collect();
+        let values: Vec<String> = key
+            .0
+            .iter()
+            .map(|v| match v {
+                Some(v) => v.to_string(),
+                None => "null".to_string(),
+            })
+            .collect();
+        format!("({})=({})", columns.join(", "), values.join(", "))
+    }
 
-        //Update the indexes
+    async fn add_to_indexes(
+        &mut self,
+        table: &Arc<Table>,
+        user_data: &SqlTuple,
+        row_item_ptr: ItemPointer,
+    ) -> Result<(), ConstraintManagerError> {
         //TODO figure out if that makes sense in this layer
         for i in &table.indexes {
             let tuple_for_index = match user_data.clone().filter_map(&table.sql_type, &i.columns) {
@@ -124,32 +270,7 @@ impl ConstraintManager {
                 .await?;
         }
 
-        Ok(row_item_ptr)
-    }
-
-    /// Gets a specific tuple from below, at the moment just a passthrough
-    pub async fn get(
-        &mut self,
-        tran_id: TransactionId,
-        table: &Arc<Table>,
-        row_pointer: ItemPointer,
-    ) -> Result<RowData, ConstraintManagerError> {
-        Ok(self.vis_row_man.get(tran_id, table, row_pointer).await?)
-    }
-
-    /// Provides a filtered view that respects transaction visibility
-    /// At the moment this is practically just a passthrough
-    pub fn get_stream(
-        self,
-        tran_id: TransactionId,
-        table: Arc<Table>,
-    ) -> impl Stream<Item = Result<RowData, ConstraintManagerError>> {
-        try_stream! {
-            for await row in self.vis_row_man.get_stream(tran_id, &table) {
-                let unwrap_row = row?;
-                yield unwrap_row;
-            }
-        }
+        Ok(())
     }
 }
 
@@ -157,8 +278,8 @@ impl ConstraintManager {
 pub enum ConstraintManagerError {
     #[error(transparent)]
     IndexManagerError(#[from] IndexManagerError),
-    #[error("Primary Key violation")]
-    PrimaryKeyViolation(),
+    #[error("Primary Key violation on table {0}, key {1} already exists")]
+    PrimaryKeyViolation(String, String),
     #[error(transparent)]
     SqlTupleError(#[from] SqlTupleError),
     #[error("Table definition length {0} does not match columns passed {1}")]
@@ -166,7 +287,9 @@ pub enum ConstraintManagerError {
     #[error("Table definition type {0} does not match column passed {1}")]
     TableRowTypeMismatch(BaseSqlTypes, BaseSqlTypesMapper),
     #[error(transparent)]
+    TypeModifierError(#[from] TypeModifierError),
+    #[error(transparent)]
     VisibleRowManagerError(#[from] VisibleRowManagerError),
-    #[error("Column null when ask not to be {0}")]
-    UnexpectedNull(String),
+    #[error("Column {1} of table {0} can not be null")]
+    UnexpectedNull(String, String),
 }
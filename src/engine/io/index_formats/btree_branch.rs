@@ -60,8 +60,8 @@ impl BTreeBranch {
         //Find where the new key fits
         let mut new_key_loc = self.keys.len();
         for i in 0..self.keys.len() {
-            if key > self.keys[i] {
-                new_key_loc = i + 1;
+            if key < self.keys[i] {
+                new_key_loc = i;
                 break;
             }
         }
@@ -87,8 +87,8 @@ impl BTreeBranch {
         //Unchecked add
         let mut new_key_loc = self.keys.len();
         for i in 0..self.keys.len() {
-            if key > self.keys[i] {
-                new_key_loc = i + 1;
+            if key < self.keys[i] {
+                new_key_loc = i;
                 break;
             }
         }
@@ -258,7 +258,7 @@ mod tests {
     //Super unsafe function to get test data, just don't count too high
     fn get_key(index: usize) -> (SqlTuple, ItemPointer) {
         (
-            SqlTuple(vec![Some(BaseSqlTypes::Integer(index as u32))]),
+            SqlTuple(vec![Some(BaseSqlTypes::Integer(index as i32))]),
             ItemPointer::new(PageOffset(index), UInt12::new(index as u16).unwrap()),
         )
     }
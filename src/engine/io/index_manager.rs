@@ -26,7 +26,9 @@ use super::row_formats::ItemPointer;
 use crate::engine::io::format_traits::Serializable;
 use crate::engine::io::index_formats::BTreeBranch;
 use crate::engine::objects::{Index, SqlTuple};
+use std::cmp::Ordering;
 use std::num::TryFromIntError;
+use std::ops::Bound;
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -63,7 +65,8 @@ impl IndexManager {
         };
 
         //Find the target leaf
-        let (page_guard, mut leaf) = find_leaf(&self.file_manager, index_def, &new_key).await?;
+        let (page_guard, mut leaf, mut path) =
+            find_leaf(&self.file_manager, index_def, &new_key).await?;
 
         //If the key fits in the leaf, we add it and are done
         if leaf.can_fit(&new_key) {
@@ -75,6 +78,9 @@ impl IndexManager {
             return Ok(());
         }
 
+        //Every page touched by the split is written as one unit so a crash can't tear the tree
+        let mut writes = vec![];
+
         //Lock the leafs left and right if they exist
         let left_neighbor = leaf.left_node;
         let left_page = match left_neighbor {
@@ -89,16 +95,20 @@ impl IndexManager {
         };
 
         //Doesn't fit so we have to split and work back up to the loop
-        let (mut split_key, mut parent_node_offset, new_left_offset, new_right_offset) =
-            split_leaf(&self.file_manager, index_def, leaf, new_key, item_ptr).await?;
+        let (mut split_key, _, mut new_left_offset, mut new_right_offset) = split_leaf(
+            &self.file_manager,
+            index_def,
+            leaf,
+            new_key,
+            item_ptr,
+            &mut writes,
+        )
+        .await?;
 
         if let Some((mut left_buffer, left_guard)) = left_page {
             if let BTreeNode::Leaf(mut l) = BTreeNode::parse(&mut left_buffer, index_def)? {
                 l.right_node = Some(new_left_offset);
-
-                self.file_manager
-                    .update_page(left_guard, l.serialize_and_pad())
-                    .await?;
+                writes.push((left_guard, l.serialize_and_pad()));
             } else {
                 return Err(IndexManagerError::UnexpectedBranch(left_neighbor.unwrap()));
             }
@@ -107,15 +117,14 @@ impl IndexManager {
         if let Some((mut right_buffer, right_guard)) = right_page {
             if let BTreeNode::Leaf(mut l) = BTreeNode::parse(&mut right_buffer, index_def)? {
                 l.left_node = Some(new_right_offset);
-                self.file_manager
-                    .update_page(right_guard, l.serialize_and_pad())
-                    .await?;
+                writes.push((right_guard, l.serialize_and_pad()));
             } else {
                 return Err(IndexManagerError::UnexpectedBranch(right_neighbor.unwrap()));
             }
         }
 
-        //Now its time to fix the tree
+        //Now its time to fix the tree, an empty path means we split the root
+        let mut parent_node_offset = path.pop().unwrap_or(PageOffset(0));
         loop {
             let (mut parent_page, parent_guard) = self
                 .file_manager
@@ -128,47 +137,38 @@ impl IndexManager {
 
                 let new_root =
                     BTreeBranch::new(PageOffset(0), new_left_offset, split_key, new_right_offset);
-
-                self.file_manager
-                    .update_page(new_root_guard, new_root.serialize_and_pad())
-                    .await?;
+                writes.push((new_root_guard, new_root.serialize_and_pad()));
 
                 let first_page = BTreeFirstPage {
                     root_offset: new_root_offset,
                 };
-                self.file_manager
-                    .update_page(parent_guard, first_page.serialize_and_pad())
-                    .await?;
+                writes.push((parent_guard, first_page.serialize_and_pad()));
 
+                self.file_manager.update_pages(writes).await?;
                 return Ok(());
             }
             if let BTreeNode::Branch(mut b) = BTreeNode::parse(&mut parent_page, index_def)? {
                 if b.can_fit(&split_key) {
                     b.add(new_left_offset, split_key, new_right_offset)?;
+                    writes.push((parent_guard, b.serialize_and_pad()));
 
-                    self.file_manager
-                        .update_page(parent_guard, b.serialize_and_pad())
-                        .await?;
-
+                    self.file_manager.update_pages(writes).await?;
                     return Ok(());
                 } else {
                     //Need to split the branch and move up a level
-                    let (new_right_offset, new_right_guard) =
+                    let (new_branch_offset, new_branch_guard) =
                         self.file_manager.get_next_offset(&page_id).await?;
 
                     let (middle_key, new_right) =
                         b.add_and_split(new_left_offset, split_key, new_right_offset)?;
+                    writes.push((new_branch_guard, new_right.serialize_and_pad()));
 
-                    self.file_manager
-                        .update_page(new_right_guard, new_right.serialize_and_pad())
-                        .await?;
-
-                    self.file_manager
-                        .update_page(parent_guard, b.serialize_and_pad())
-                        .await?;
-
-                    parent_node_offset = b.parent_node;
+                    //The left half stays where the branch was
+                    new_left_offset = parent_node_offset;
+                    new_right_offset = new_branch_offset;
+                    parent_node_offset = path.pop().unwrap_or(PageOffset(0));
                     split_key = middle_key;
+                    writes.push((parent_guard, b.serialize_and_pad()));
 
                     continue;
                 }
@@ -224,6 +224,91 @@ impl IndexManager {
             }
         }
     }
+
+    /// Finds every pointer whose key falls between the bounds. The bounds may be shorter than the index key,
+    /// then only the leading columns of each key are compared so a partial key can select a range.
+    pub async fn search_range(
+        &self,
+        index_def: &Index,
+        lower: &Bound<SqlTuple>,
+        upper: &Bound<SqlTuple>,
+    ) -> Result<Vec<ItemPointer>, IndexManagerError> {
+        let page_id = PageId {
+            resource_key: index_def.id,
+            page_type: PageType::Data,
+        };
+
+        debug!("index searching from {:?} to {:?}", lower, upper);
+
+        let (mut first_page, _first_guard) =
+            match self.file_manager.get_page(&page_id, &PageOffset(0)).await {
+                Ok(s) => s,
+                Err(FileManager2Error::PageDoesNotExist(_)) => {
+                    return Ok(vec![]);
+                }
+                Err(e) => {
+                    return Err(IndexManagerError::FileManager2Error(e));
+                }
+            };
+        let first_node = BTreeFirstPage::parse(&mut first_page)?;
+
+        //Walk down to the leaf holding the lower bound, a shorter key sorts before everything it prefixes
+        let mut current_offset = first_node.root_offset;
+        let mut leaf = loop {
+            let (mut current_page, _current_guard) = self
+                .file_manager
+                .get_page(&page_id, &current_offset)
+                .await?;
+
+            match BTreeNode::parse(&mut current_page, index_def)? {
+                BTreeNode::Branch(b) => {
+                    current_offset = *b.search((lower.clone(), Bound::Unbounded))?;
+                }
+                BTreeNode::Leaf(l) => break l,
+            }
+        };
+
+        //Then follow the leaves to the right until we pass the upper bound
+        let mut found = vec![];
+        loop {
+            for (key, ptrs) in leaf.nodes.iter() {
+                let above_lower = match lower {
+                    Bound::Included(b) => Self::compare_prefix(key, b) != Ordering::Less,
+                    Bound::Excluded(b) => Self::compare_prefix(key, b) == Ordering::Greater,
+                    Bound::Unbounded => true,
+                };
+                if !above_lower {
+                    continue;
+                }
+
+                let below_upper = match upper {
+                    Bound::Included(b) => Self::compare_prefix(key, b) != Ordering::Greater,
+                    Bound::Excluded(b) => Self::compare_prefix(key, b) == Ordering::Less,
+                    Bound::Unbounded => true,
+                };
+                if !below_upper {
+                    return Ok(found);
+                }
+
+                found.extend_from_slice(ptrs);
+            }
+
+            let right = match leaf.right_node {
+                Some(r) => r,
+                None => return Ok(found),
+            };
+            let (mut right_page, _right_guard) =
+                self.file_manager.get_page(&page_id, &right).await?;
+            leaf = match BTreeNode::parse(&mut right_page, index_def)? {
+                BTreeNode::Leaf(l) => l,
+                BTreeNode::Branch(_) => return Err(IndexManagerError::UnexpectedBranch(right)),
+            };
+        }
+    }
+
+    fn compare_prefix(key: &SqlTuple, bound: &SqlTuple) -> Ordering {
+        key.0.iter().take(bound.0.len()).cmp(bound.0.iter())
+    }
 }
 
 #[derive(Debug, Error)]
@@ -294,7 +379,7 @@ mod tests {
         (
             SqlTuple(vec![
                 Some(BaseSqlTypes::Text("test".to_string())),
-                Some(BaseSqlTypes::Integer(num as u32)),
+                Some(BaseSqlTypes::Integer(num as i32)),
             ]),
             ItemPointer::new(PageOffset(num), UInt12::new(0).unwrap()),
         )
@@ -340,8 +425,35 @@ mod tests {
             im.add(&index, key, ptr).await?;
         }
 
-        let (key, ptr) = get_key_and_ptr(999);
-        assert_eq!(Some(vec![ptr]), im.search_for_key(&index, &key).await?);
+        for i in 0..1000 {
+            let (key, ptr) = get_key_and_ptr(i);
+            assert_eq!(Some(vec![ptr]), im.search_for_key(&index, &key).await?);
+        }
+
+        //Ranges that cross leaves
+        let (low_key, _) = get_key_and_ptr(100);
+        let (high_key, _) = get_key_and_ptr(700);
+        let found = im
+            .search_range(
+                &index,
+                &Bound::Excluded(low_key),
+                &Bound::Included(high_key),
+            )
+            .await?;
+        assert_eq!(found.len(), 600);
+        assert!(found.contains(&get_key_and_ptr(101).1));
+        assert!(!found.contains(&get_key_and_ptr(100).1));
+
+        //Only the leading column
+        let prefix = SqlTuple(vec![Some(BaseSqlTypes::Text("test".to_string()))]);
+        let found = im
+            .search_range(
+                &index,
+                &Bound::Included(prefix.clone()),
+                &Bound::Included(prefix),
+            )
+            .await?;
+        assert_eq!(found.len(), 1000);
 
         Ok(())
     }
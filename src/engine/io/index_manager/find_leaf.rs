@@ -13,17 +13,27 @@ use crate::engine::{
 use thiserror::Error;
 use tokio::sync::OwnedRwLockWriteGuard;
 
+/// Finds the leaf a key belongs in, also returning the branches passed through on the way down (root first).
+/// The path is what splits use to work back up the tree, the parent pointers stored in the nodes are not
+/// updated when their parent splits so they can't be trusted.
 pub async fn find_leaf(
     fm: &FileManager2,
     index_def: &Index,
     new_key: &SqlTuple,
-) -> Result<(OwnedRwLockWriteGuard<(PageId, PageOffset)>, BTreeLeaf), FindLeafError> {
+) -> Result<
+    (
+        OwnedRwLockWriteGuard<(PageId, PageOffset)>,
+        BTreeLeaf,
+        Vec<PageOffset>,
+    ),
+    FindLeafError,
+> {
     let page_id = PageId {
         resource_key: index_def.id,
         page_type: PageType::Data,
     };
 
-    let mut prior_offset = PageOffset(0);
+    let mut path = vec![];
     let mut offset = PageOffset(0);
 
     loop {
@@ -36,12 +46,12 @@ pub async fn find_leaf(
 
                         let (root_offset, root_guard) = fm.get_next_offset(&page_id).await?;
                         let root_node = BTreeLeaf::new(offset);
-                        fm.add_page(root_guard, root_node.serialize_and_pad())
-                            .await?;
-
                         let first_node = BTreeFirstPage { root_offset };
-                        fm.add_page(page_guard, first_node.serialize_and_pad())
-                            .await?;
+                        fm.update_pages(vec![
+                            (root_guard, root_node.serialize_and_pad()),
+                            (page_guard, first_node.serialize_and_pad()),
+                        ])
+                        .await?;
 
                         offset = root_offset;
                         continue;
@@ -50,14 +60,14 @@ pub async fn find_leaf(
                 } else {
                     let node = BTreeNode::parse(&mut buffer, index_def)?;
 
-                    prior_offset = offset;
                     match node {
                         BTreeNode::Branch(b) => {
+                            path.push(offset);
                             offset = *b.search(new_key..new_key)?;
                             continue;
                         }
                         BTreeNode::Leaf(l) => {
-                            return Ok((page_guard, l));
+                            return Ok((page_guard, l, path));
                         }
                     }
                 }
@@ -73,12 +83,12 @@ pub async fn find_leaf(
 
                     let (root_offset, root_guard) = fm.get_next_offset(&page_id).await?;
                     let root_node = BTreeLeaf::new(new_offset);
-                    fm.add_page(root_guard, root_node.serialize_and_pad())
-                        .await?;
-
                     let first_node = BTreeFirstPage { root_offset };
-                    fm.add_page(new_guard, first_node.serialize_and_pad())
-                        .await?;
+                    fm.update_pages(vec![
+                        (root_guard, root_node.serialize_and_pad()),
+                        (new_guard, first_node.serialize_and_pad()),
+                    ])
+                    .await?;
 
                     offset = root_offset;
                     continue;
@@ -118,7 +128,7 @@ mod tests {
     //Super unsafe function to get test data, just don't count too high
     fn get_key(index: usize) -> (SqlTuple, ItemPointer) {
         (
-            SqlTuple(vec![Some(BaseSqlTypes::Integer(index as u32))]),
+            SqlTuple(vec![Some(BaseSqlTypes::Integer(index as i32))]),
             ItemPointer::new(PageOffset(index), UInt12::new(index as u16).unwrap()),
         )
     }
@@ -161,14 +171,15 @@ mod tests {
         fm.update_page(root_guard, root.serialize_and_pad()).await?;
 
         // Okay now its time to actually test
-        let (guard, leaf) = find_leaf(&fm, &index, &key).await?;
+        let (guard, leaf, path) = find_leaf(&fm, &index, &key).await?;
         assert_eq!(leaf, root);
+        assert!(path.is_empty());
         assert_ne!(guard.1, PageOffset(0));
 
         let po1 = guard.1;
         drop(guard);
 
-        let (guard2, leaf2) = find_leaf(&fm, &index, &key).await?;
+        let (guard2, leaf2, _) = find_leaf(&fm, &index, &key).await?;
         assert_eq!(leaf2, root);
         assert_eq!(po1, guard2.1);
         Ok(())
@@ -1,6 +1,6 @@
 use crate::engine::{
     io::{
-        block_layer::file_manager2::{FileManager2, FileManager2Error},
+        block_layer::file_manager2::{FileManager2, FileManager2Error, PageWrite},
         format_traits::Serializable,
         index_formats::{BTreeLeaf, BTreeLeafError},
         page_formats::{PageId, PageOffset, PageType},
@@ -10,13 +10,14 @@ use crate::engine::{
 };
 use thiserror::Error;
 
-/// Takes a leaf node and produces a new right node
+/// Takes a leaf node and produces a new right node, the new pages are added to writes
 pub async fn split_leaf(
     fm: &FileManager2,
     index_def: &Index,
     mut leaf: BTreeLeaf,
     new_key: SqlTuple,
     item_ptr: ItemPointer,
+    writes: &mut Vec<PageWrite>,
 ) -> Result<(SqlTuple, PageOffset, PageOffset, PageOffset), SplitLeafError> {
     let page_id = PageId {
         resource_key: index_def.id,
@@ -31,10 +32,8 @@ pub async fn split_leaf(
 
     let parent_node_offset = leaf.parent_node;
 
-    fm.update_page(left_node_guard, leaf.serialize_and_pad())
-        .await?;
-    fm.update_page(right_node_guard, new_right_node.serialize_and_pad())
-        .await?;
+    writes.push((left_node_guard, leaf.serialize_and_pad()));
+    writes.push((right_node_guard, new_right_node.serialize_and_pad()));
 
     Ok((
         new_split_key,
@@ -72,7 +71,7 @@ mod tests {
     //Super unsafe function to get test data, just don't count too high
     fn get_key(index: usize) -> (SqlTuple, ItemPointer) {
         (
-            SqlTuple(vec![Some(BaseSqlTypes::Integer(index as u32))]),
+            SqlTuple(vec![Some(BaseSqlTypes::Integer(index as i32))]),
             ItemPointer::new(PageOffset(index), UInt12::new(index as u16).unwrap()),
         )
     }
@@ -111,8 +110,10 @@ mod tests {
 
         let (key, ptr) = get_key(11);
 
+        let mut writes = vec![];
         let (split_key, parent_node, left_offset, right_offset) =
-            split_leaf(&fm, &index, leaf, key, ptr).await?;
+            split_leaf(&fm, &index, leaf, key, ptr, &mut writes).await?;
+        fm.update_pages(writes).await?;
 
         let (mut left_page, _left_guard) = fm.get_page(&page_id, &left_offset).await?;
         let left_node = match BTreeNode::parse(&mut left_page, &index)? {
@@ -8,7 +8,7 @@ use super::super::row_formats::{ItemPointer, RowData, RowDataError};
 use super::{
     ItemIdData, ItemIdDataError, PageHeader, PageHeaderError, PageOffset, UInt12, UInt12Error,
 };
-use async_stream::stream;
+use async_stream::{stream, try_stream};
 use bytes::{BufMut, Bytes};
 use futures::stream::Stream;
 use std::convert::TryFrom;
@@ -96,6 +96,20 @@ impl PageData {
         }
     }
 
+    /// Pairs each row with where it is stored, once a row is updated its own item pointer
+    /// points forward to the new version instead
+    pub fn get_stream_with_pointers(
+        &self,
+    ) -> impl Stream<Item = Result<(ItemPointer, RowData), PageDataError>> {
+        let page = self.page;
+        let rows_clone = self.rows.clone();
+        try_stream! {
+            for (count, row) in rows_clone.into_iter().enumerate() {
+                yield (ItemPointer::new(page, UInt12::try_from(count)?), row);
+            }
+        }
+    }
+
     pub fn parse(
         table: &Arc<Table>,
         page: PageOffset,
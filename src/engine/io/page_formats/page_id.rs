@@ -29,6 +29,7 @@ impl fmt::Display for PageId {
 
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub enum PageType {
+    CommitLog,
     Data,
     FreeSpaceMap,
     //VisibilityMap
@@ -53,6 +54,7 @@ impl PageType {
 impl Display for PageType {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
+            PageType::CommitLog => write!(f, "clog"),
             PageType::Data => write!(f, "data"),
             PageType::FreeSpaceMap => write!(f, "fs"),
         }
@@ -222,7 +222,7 @@ pub enum RowDataError {
 mod tests {
     use bytes::BytesMut;
 
-    use crate::constants::Nullable;
+    use crate::constants::{Nullable, FIRST_USER_OID};
     use crate::engine::io::page_formats::PageOffset;
     use crate::engine::objects::types::BaseSqlTypesMapper;
 
@@ -238,6 +238,7 @@ mod tests {
     fn test_row_data_single_text() -> Result<(), Box<dyn std::error::Error>> {
         let table = Arc::new(Table::new(
             uuid::Uuid::new_v4(),
+            FIRST_USER_OID,
             "test_table".to_string(),
             vec![Attribute::new(
                 "header".to_string(),
@@ -271,6 +272,7 @@ mod tests {
     fn test_row_data_double_text() -> Result<(), Box<dyn std::error::Error>> {
         let table = Arc::new(Table::new(
             uuid::Uuid::new_v4(),
+            FIRST_USER_OID,
             "test_table".to_string(),
             vec![
                 Attribute::new(
@@ -315,6 +317,7 @@ mod tests {
     fn test_row_uuid_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
         let table = Arc::new(Table::new(
             uuid::Uuid::new_v4(),
+            FIRST_USER_OID,
             "test_table".to_string(),
             vec![Attribute::new(
                 "header".to_string(),
@@ -348,6 +351,7 @@ mod tests {
     fn test_row_uuid_double_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
         let table = Arc::new(Table::new(
             uuid::Uuid::new_v4(),
+            FIRST_USER_OID,
             "test_table".to_string(),
             vec![
                 Attribute::new(
@@ -392,6 +396,7 @@ mod tests {
     fn test_row_uuid_double_opt_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
         let table = Arc::new(Table::new(
             uuid::Uuid::new_v4(),
+            FIRST_USER_OID,
             "test_table".to_string(),
             vec![
                 Attribute::new(
@@ -434,6 +439,7 @@ mod tests {
     fn test_row_complex_data_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
         let table = Arc::new(Table::new(
             uuid::Uuid::new_v4(),
+            FIRST_USER_OID,
             "test_table".to_string(),
             vec![
                 Attribute::new(
@@ -468,8 +474,8 @@ mod tests {
                 Some(BaseSqlTypes::Text("this is a test".to_string())),
                 None,
                 Some(BaseSqlTypes::Array(vec![
-                    BaseSqlTypes::Integer(1),
-                    BaseSqlTypes::Integer(2),
+                    Some(BaseSqlTypes::Integer(1)),
+                    Some(BaseSqlTypes::Integer(2)),
                 ])),
             ]),
         );
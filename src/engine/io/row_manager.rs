@@ -44,11 +44,13 @@ impl RowManager {
 
     //Note this is a logical delete
     //TODO debating if this should respect the visibility map, probably yes just trying to limit the pain
+    //aborted_max is a max the caller found to be aborted in the commit log, so it can be overwritten
     pub async fn delete_row(
         &self,
         current_tran_id: TransactionId,
         table: &Arc<Table>,
         row_pointer: ItemPointer,
+        aborted_max: Option<TransactionId>,
     ) -> Result<(), RowManagerError> {
         let page_id = PageId {
             resource_key: table.id,
@@ -69,11 +71,8 @@ impl RowManager {
             ))?
             .clone();
 
-        if row.max.is_some() {
-            return Err(RowManagerError::AlreadyDeleted(
-                row_pointer.count,
-                row.max.unwrap(),
-            ));
+        if let Some(max) = row.max.filter(|m| Some(*m) != aborted_max) {
+            return Err(RowManagerError::AlreadyDeleted(row_pointer.count, max));
         }
 
         row.max = Some(current_tran_id);
@@ -87,12 +86,14 @@ impl RowManager {
     }
 
     //Note this is an insert new row, delete old row operation
+    //aborted_max works the same as for delete_row
     pub async fn update_row(
         &mut self,
         current_tran_id: TransactionId,
         table: &Arc<Table>,
         row_pointer: ItemPointer,
         new_user_data: SqlTuple,
+        aborted_max: Option<TransactionId>,
     ) -> Result<ItemPointer, RowManagerError> {
         //First get the current row so we have it for the update
         let page_id = PageId {
@@ -115,11 +116,8 @@ impl RowManager {
             ))?
             .clone();
 
-        if old_row.max.is_some() {
-            return Err(RowManagerError::AlreadyDeleted(
-                row_pointer.count,
-                old_row.max.unwrap(),
-            ));
+        if let Some(max) = old_row.max.filter(|m| Some(*m) != aborted_max) {
+            return Err(RowManagerError::AlreadyDeleted(row_pointer.count, max));
         }
 
         let new_row_len = RowData::encoded_size(&new_user_data);
@@ -182,6 +180,21 @@ impl RowManager {
         &self,
         table: &Arc<Table>,
     ) -> impl Stream<Item = Result<RowData, RowManagerError>> {
+        let rows = self.get_stream_with_pointers(table);
+
+        try_stream! {
+            for await row in rows {
+                let (_, row) = row?;
+                yield row;
+            }
+        }
+    }
+
+    /// Same as get_stream, with where each row is so it can be updated or deleted
+    pub fn get_stream_with_pointers(
+        &self,
+        table: &Arc<Table>,
+    ) -> impl Stream<Item = Result<(ItemPointer, RowData), RowManagerError>> {
         let page_id = PageId {
             resource_key: table.id,
             page_type: PageType::Data,
@@ -197,8 +210,8 @@ impl RowManager {
                 match file_manager.get_page(&page_id, &page_num).await {
                     Ok((buffer, _guard)) => {
                         let page = PageData::parse(&table, page_num, &buffer)?;
-                        for await row in page.get_stream() {
-                            yield row;
+                        for await row in page.get_stream_with_pointers() {
+                            yield row?;
                         }
                     },
                     Err(_) => {
@@ -365,6 +378,7 @@ mod tests {
                 &table,
                 insert_pointer,
                 get_row("test2".to_string()),
+                None,
             )
             .await?;
 
@@ -375,7 +389,59 @@ mod tests {
 
         let tran_id_3 = TransactionId::new(3);
 
-        rm.delete_row(tran_id_3, &table, update_pointer).await?;
+        rm.delete_row(tran_id_3, &table, update_pointer, None)
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_row_manager_pointers_after_update() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = TempDir::new()?;
+        let tmp_dir = tmp.path().as_os_str().to_os_string();
+
+        let table = get_table();
+        let fm = Arc::new(FileManager2::new(tmp_dir)?);
+        let fsm = FreeSpaceManager::new(fm.clone());
+        let mut rm = RowManager::new(fm, fsm);
+
+        let tran_id = TransactionId::new(1);
+        let insert_pointer = rm
+            .insert_row(tran_id, &table, get_row("test".to_string()))
+            .await?;
+
+        //The old version links forward but the stream still reports where it is
+        let tran_id_2 = TransactionId::new(2);
+        let update_pointer = rm
+            .update_row(
+                tran_id_2,
+                &table,
+                insert_pointer,
+                get_row("test2".to_string()),
+                None,
+            )
+            .await?;
+        assert_eq!(
+            rm.get(&table, insert_pointer).await?.item_pointer,
+            update_pointer
+        );
+
+        let pointers: Vec<ItemPointer> = rm
+            .get_stream_with_pointers(&table)
+            .map(|r| r.unwrap().0)
+            .collect()
+            .await;
+        assert_eq!(pointers, vec![insert_pointer, update_pointer]);
+
+        //Only a max the caller knows aborted can be overwritten
+        let tran_id_3 = TransactionId::new(3);
+        assert!(rm
+            .delete_row(tran_id_3, &table, insert_pointer, None)
+            .await
+            .is_err());
+        rm.delete_row(tran_id_3, &table, insert_pointer, Some(tran_id_2))
+            .await?;
+        assert_eq!(rm.get(&table, insert_pointer).await?.max, Some(tran_id_3));
 
         Ok(())
     }
@@ -5,12 +5,17 @@ use thiserror::Error;
 /// Will provide the length in bytes the supplied usize will encode to without encoding
 pub fn expected_encoded_size(size: usize) -> usize {
     //Discussion here: https://github.com/rust-lang/rfcs/issues/2844
-    (size + 127 - 1) / 127
+    //Zero still takes a byte so the parser has something to read
+    std::cmp::max((size + 127 - 1) / 127, 1)
 }
 
 /// Writes a length out to a byte stream as a series of 7 bit numbers, with the high
 /// bit used to indicate we have hit the end of the length
 pub fn encode_size(buffer: &mut impl BufMut, mut size: usize) {
+    if size == 0 {
+        buffer.put_u8(0);
+        return;
+    }
     while size > 0 {
         let last_count = size as u8;
         let mut digit: u8 = last_count & 0x7f;
@@ -72,6 +77,16 @@ mod tests {
         let parsed = parse_size(&mut serialized)?;
         assert_eq!(test, parsed);
 
+        let test = 0;
+
+        let mut buffer = BytesMut::with_capacity(expected_encoded_size(test));
+        encode_size(&mut buffer, test);
+        let mut serialized = buffer.freeze();
+
+        assert_eq!(serialized.len(), expected_encoded_size(test));
+        let parsed = parse_size(&mut serialized)?;
+        assert_eq!(test, parsed);
+
         let test = 128;
 
         let mut buffer = BytesMut::with_capacity(expected_encoded_size(test));
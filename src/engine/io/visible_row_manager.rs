@@ -43,6 +43,67 @@ impl VisibleRowManager {
             .map_err(VisibleRowManagerError::RowManagerError)
     }
 
+    pub async fn update_row(
+        &mut self,
+        current_tran_id: TransactionId,
+        table: &Arc<Table>,
+        row_pointer: ItemPointer,
+        new_user_data: SqlTuple,
+    ) -> Result<ItemPointer, VisibleRowManagerError> {
+        match self
+            .row_manager
+            .update_row(
+                current_tran_id,
+                table,
+                row_pointer,
+                new_user_data.clone(),
+                None,
+            )
+            .await
+        {
+            Err(RowManagerError::AlreadyDeleted(count, max)) => {
+                if !self.is_aborted(max).await? {
+                    return Err(RowManagerError::AlreadyDeleted(count, max).into());
+                }
+                Ok(self
+                    .row_manager
+                    .update_row(
+                        current_tran_id,
+                        table,
+                        row_pointer,
+                        new_user_data,
+                        Some(max),
+                    )
+                    .await?)
+            }
+            result => Ok(result?),
+        }
+    }
+
+    pub async fn delete_row(
+        &self,
+        current_tran_id: TransactionId,
+        table: &Arc<Table>,
+        row_pointer: ItemPointer,
+    ) -> Result<(), VisibleRowManagerError> {
+        match self
+            .row_manager
+            .delete_row(current_tran_id, table, row_pointer, None)
+            .await
+        {
+            Err(RowManagerError::AlreadyDeleted(count, max)) => {
+                if !self.is_aborted(max).await? {
+                    return Err(RowManagerError::AlreadyDeleted(count, max).into());
+                }
+                Ok(self
+                    .row_manager
+                    .delete_row(current_tran_id, table, row_pointer, Some(max))
+                    .await?)
+            }
+            result => Ok(result?),
+        }
+    }
+
     pub async fn get(
         &mut self,
         tran_id: TransactionId,
@@ -64,15 +125,31 @@ impl VisibleRowManager {
         tran_id: TransactionId,
         table: &Arc<Table>,
     ) -> impl Stream<Item = Result<RowData, VisibleRowManagerError>> {
+        let rows = self.get_stream_with_pointers(tran_id, table);
+
+        try_stream! {
+            for await row in rows {
+                let (_, unwrap_row) = row?;
+                yield unwrap_row;
+            }
+        }
+    }
+
+    /// Same as get_stream, with where each row is so it can be updated or deleted
+    pub fn get_stream_with_pointers(
+        &self,
+        tran_id: TransactionId,
+        table: &Arc<Table>,
+    ) -> impl Stream<Item = Result<(ItemPointer, RowData), VisibleRowManagerError>> {
         let rm = self.row_manager.clone();
         let mut tm = self.tran_manager.clone();
         let table = table.clone();
 
         try_stream! {
-            for await row in rm.get_stream(&table) {
-                let unwrap_row = row?;
+            for await row in rm.get_stream_with_pointers(&table) {
+                let (pointer, unwrap_row) = row?;
                 if VisibleRowManager::is_visible(&mut tm, tran_id, &unwrap_row).await? {
-                    yield unwrap_row;
+                    yield (pointer, unwrap_row);
                 }
             }
         }
@@ -96,6 +173,12 @@ impl VisibleRowManager {
         Ok(false)
     }
 
+    /// A row deleted or updated by a transaction that rolled back is still live
+    async fn is_aborted(&self, tran_id: TransactionId) -> Result<bool, VisibleRowManagerError> {
+        let mut tm = self.tran_manager.clone();
+        Ok(tm.get_status(tran_id).await? == TransactionStatus::Aborted)
+    }
+
     //TODO I want to find a way to NOT depend on tm
     async fn is_visible(
         tm: &mut TransactionManager,
@@ -128,6 +211,11 @@ impl VisibleRowManager {
 
         match row_data.max {
             Some(m) => {
+                //Deleted by us, even though we haven't committed yet
+                if m == tran_id {
+                    return Ok(false);
+                }
+
                 if m > tran_id || tm.get_status(m).await? != TransactionStatus::Commited {
                     Ok(true)
                 } else {
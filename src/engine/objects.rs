@@ -1,31 +1,68 @@
+mod aggregate;
+pub use aggregate::Aggregate;
+pub use aggregate::AggregateError;
+pub use aggregate::AggregateFunction;
+pub use aggregate::AggregateState;
+pub use aggregate::RunningTotal;
+
 mod attribute;
 pub use attribute::Attribute;
 
+mod binary_operator;
+pub use binary_operator::BinaryOperator;
+pub use binary_operator::Quantifier;
+
+mod column_origin;
+pub use column_origin::ColumnOrigin;
+
 mod constraints;
 pub use constraints::Constraint;
 pub use constraints::ConstraintMapper;
 pub use constraints::PrimaryKeyConstraint;
 
+mod expression;
+pub use expression::Expression;
+pub use expression::ExpressionError;
+
 mod index;
 pub use index::Index;
 
 mod parse_expression;
 pub use parse_expression::ParseExpression;
+pub use parse_expression::ParseExpressionError;
 
 mod parse_tree;
 pub use parse_tree::ParseTree;
 pub use parse_tree::RawColumn;
 pub use parse_tree::RawCreateTableCommand;
+pub use parse_tree::RawCreateTypeCommand;
+pub use parse_tree::RawDeleteCommand;
+pub use parse_tree::RawDropTypeCommand;
 pub use parse_tree::RawInsertCommand;
+pub use parse_tree::RawJoin;
+pub use parse_tree::RawOrderBy;
 pub use parse_tree::RawSelectCommand;
+pub use parse_tree::RawTableReference;
+pub use parse_tree::RawUpdateCommand;
+pub use parse_tree::TransactionCommand;
 
 mod planned_statement;
 pub use planned_statement::CartesianJoin;
+pub use planned_statement::DeletePlan;
+pub use planned_statement::FilterPlan;
 pub use planned_statement::FullTableScan;
+pub use planned_statement::HashAggregatePlan;
+pub use planned_statement::HashJoinPlan;
+pub use planned_statement::IndexScan;
+pub use planned_statement::LimitPlan;
 pub use planned_statement::ModifyTablePlan;
+pub use planned_statement::NestedLoopJoinPlan;
 pub use planned_statement::Plan;
 pub use planned_statement::PlannedCommon;
 pub use planned_statement::PlannedStatement;
+pub use planned_statement::ProjectionPlan;
+pub use planned_statement::SortPlan;
+pub use planned_statement::UpdatePlan;
 
 mod query_result;
 pub use query_result::QueryResult;
@@ -36,8 +73,14 @@ pub use query_tree::JoinType;
 pub use query_tree::QueryTree;
 pub use query_tree::RangeRelation;
 pub use query_tree::RangeRelationTable;
+pub use query_tree::SortKey;
+pub use query_tree::SortType;
 //pub use query_tree::TargetEntry;
 
+mod scalar_function;
+pub use scalar_function::ScalarFunction;
+pub use scalar_function::ScalarFunctionError;
+
 mod sql_tuple;
 pub use sql_tuple::SqlTuple;
 pub use sql_tuple::SqlTupleError;
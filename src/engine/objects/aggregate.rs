@@ -0,0 +1,369 @@
+//! Aggregate functions and the running state used to compute them over a group of rows.
+//! See here: https://www.postgresql.org/docs/current/functions-aggregate.html
+use super::types::{
+    BaseSqlTypes, BaseSqlTypesError, BaseSqlTypesMapper, Jsonb, JsonbError, Numeric, NumericError,
+};
+use super::{Expression, ExpressionError, SqlTuple};
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AggregateFunction {
+    Avg,
+    Count,
+    JsonbAgg,
+    Max,
+    Min,
+    Sum,
+}
+
+impl AggregateFunction {
+    pub fn from_name(name: &str) -> Option<AggregateFunction> {
+        match name.to_lowercase().as_str() {
+            "avg" => Some(AggregateFunction::Avg),
+            "count" => Some(AggregateFunction::Count),
+            "jsonb_agg" => Some(AggregateFunction::JsonbAgg),
+            "max" => Some(AggregateFunction::Max),
+            "min" => Some(AggregateFunction::Min),
+            "sum" => Some(AggregateFunction::Sum),
+            _ => None,
+        }
+    }
+}
+
+impl Display for AggregateFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregateFunction::Avg => write!(f, "avg"),
+            AggregateFunction::Count => write!(f, "count"),
+            AggregateFunction::JsonbAgg => write!(f, "jsonb_agg"),
+            AggregateFunction::Max => write!(f, "max"),
+            AggregateFunction::Min => write!(f, "min"),
+            AggregateFunction::Sum => write!(f, "sum"),
+        }
+    }
+}
+
+/// A call to an aggregate, the argument is evaluated against each input row. count(*) has no argument.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aggregate {
+    pub function: AggregateFunction,
+    pub argument: Option<Box<Expression>>,
+    pub sql_type: BaseSqlTypesMapper,
+}
+
+impl Aggregate {
+    pub fn start(&self) -> AggregateState {
+        match self.function {
+            AggregateFunction::Avg => AggregateState::Avg(None, 0),
+            AggregateFunction::Count => AggregateState::Count(0),
+            AggregateFunction::JsonbAgg => AggregateState::JsonbAgg(vec![]),
+            AggregateFunction::Max => AggregateState::Max(None),
+            AggregateFunction::Min => AggregateState::Min(None),
+            AggregateFunction::Sum => AggregateState::Sum(None),
+        }
+    }
+
+    /// Adds a row to the state, NULL arguments are skipped by every aggregate except jsonb_agg
+    pub fn update(&self, state: &mut AggregateState, row: &SqlTuple) -> Result<(), AggregateError> {
+        let value = match &self.argument {
+            Some(a) => a.evaluate(row)?,
+            None => {
+                if let AggregateState::Count(c) = state {
+                    *c += 1;
+                }
+                return Ok(());
+            }
+        };
+
+        if let AggregateState::JsonbAgg(items) = state {
+            items.push(Jsonb::from_value(value.as_ref())?);
+            return Ok(());
+        }
+        let value = match value {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+
+        match state {
+            AggregateState::Count(c) => *c += 1,
+            AggregateState::Avg(total, count) => {
+                self.add(total, value)?;
+                *count += 1;
+            }
+            AggregateState::Sum(total) => self.add(total, value)?,
+            AggregateState::Max(max) => {
+                if max.as_ref().map(|m| value > *m).unwrap_or(true) {
+                    *max = Some(value);
+                }
+            }
+            AggregateState::Min(min) => {
+                if min.as_ref().map(|m| value < *m).unwrap_or(true) {
+                    *min = Some(value);
+                }
+            }
+            AggregateState::JsonbAgg(_) => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn add(
+        &self,
+        total: &mut Option<RunningTotal>,
+        value: BaseSqlTypes,
+    ) -> Result<(), AggregateError> {
+        let next = match (total.take(), Self::as_integer(&value)) {
+            (None, Some(i)) => RunningTotal::Integer(i),
+            (Some(RunningTotal::Integer(t)), Some(i)) => RunningTotal::Integer(t + i),
+            (None, None) => RunningTotal::Value(value.convert(&self.sql_type)?),
+            (Some(RunningTotal::Value(t)), None) => {
+                RunningTotal::Value(t.checked_add(&value.convert(&self.sql_type)?)?)
+            }
+            (_, _) => return Err(AggregateError::UnsupportedValue(self.function, value)),
+        };
+        *total = Some(next);
+        Ok(())
+    }
+
+    /// The result for the group, an aggregate that saw no values is NULL except for count
+    pub fn finish(&self, state: AggregateState) -> Result<Option<BaseSqlTypes>, AggregateError> {
+        Ok(match state {
+            AggregateState::Avg(None, _) => None,
+            AggregateState::Avg(Some(RunningTotal::Integer(sum)), count) => {
+                Some(BaseSqlTypes::Numeric(
+                    Numeric::from(sum).checked_div(&Numeric::from(i128::from(count)))?,
+                ))
+            }
+            AggregateState::Avg(Some(RunningTotal::Value(sum)), count) => {
+                let count = BaseSqlTypes::Bigint(i64::try_from(count).map_err(|_| {
+                    AggregateError::OutOfRange(self.function, BaseSqlTypesMapper::Bigint)
+                })?);
+                Some(sum.checked_div(&count.convert(&self.sql_type)?)?)
+            }
+            AggregateState::Count(c) => Some(self.to_integer(i128::from(c))?),
+            AggregateState::JsonbAgg(items) if items.is_empty() => None,
+            AggregateState::JsonbAgg(items) => Some(BaseSqlTypes::Jsonb(Jsonb::Array(items))),
+            AggregateState::Max(v) | AggregateState::Min(v) => v,
+            AggregateState::Sum(None) => None,
+            AggregateState::Sum(Some(RunningTotal::Integer(sum))) => Some(self.to_integer(sum)?),
+            AggregateState::Sum(Some(RunningTotal::Value(sum))) => Some(sum),
+        })
+    }
+
+    /// Sums are kept wider than any column so only the final result can be out of range
+    fn as_integer(value: &BaseSqlTypes) -> Option<i128> {
+        match value {
+            BaseSqlTypes::Smallint(i) => Some(i128::from(*i)),
+            BaseSqlTypes::Integer(i) => Some(i128::from(*i)),
+            BaseSqlTypes::Bigint(i) => Some(i128::from(*i)),
+            _ => None,
+        }
+    }
+
+    fn to_integer(&self, value: i128) -> Result<BaseSqlTypes, AggregateError> {
+        let out_of_range = |_| AggregateError::OutOfRange(self.function, self.sql_type.clone());
+        match self.sql_type {
+            BaseSqlTypesMapper::Smallint => Ok(BaseSqlTypes::Smallint(
+                i16::try_from(value).map_err(out_of_range)?,
+            )),
+            BaseSqlTypesMapper::Integer => Ok(BaseSqlTypes::Integer(
+                i32::try_from(value).map_err(out_of_range)?,
+            )),
+            BaseSqlTypesMapper::Bigint => Ok(BaseSqlTypes::Bigint(
+                i64::try_from(value).map_err(out_of_range)?,
+            )),
+            _ => Err(AggregateError::UnsupportedType(
+                self.function,
+                self.sql_type.clone(),
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AggregateState {
+    ///Sum and count of the values seen
+    Avg(Option<RunningTotal>, u64),
+    Count(u64),
+    JsonbAgg(Vec<Jsonb>),
+    Max(Option<BaseSqlTypes>),
+    Min(Option<BaseSqlTypes>),
+    Sum(Option<RunningTotal>),
+}
+
+/// Integers are totalled as an i128 so only the final result can be out of range,
+/// everything else is totalled in the result type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RunningTotal {
+    Integer(i128),
+    Value(BaseSqlTypes),
+}
+
+#[derive(Debug, Error)]
+pub enum AggregateError {
+    #[error(transparent)]
+    BaseSqlTypesError(#[from] BaseSqlTypesError),
+    #[error(transparent)]
+    ExpressionError(#[from] ExpressionError),
+    #[error(transparent)]
+    JsonbError(#[from] JsonbError),
+    #[error(transparent)]
+    NumericError(#[from] NumericError),
+    #[error("Result of {0} is out of range for {1}")]
+    OutOfRange(AggregateFunction, BaseSqlTypesMapper),
+    #[error("Aggregate {0} can not produce a {1}")]
+    UnsupportedType(AggregateFunction, BaseSqlTypesMapper),
+    #[error("Aggregate {0} does not support the value {1}")]
+    UnsupportedValue(AggregateFunction, BaseSqlTypes),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::objects::types::SqlFloat;
+
+    fn aggregate(function: AggregateFunction) -> Aggregate {
+        aggregate_as(function, BaseSqlTypesMapper::Integer)
+    }
+
+    fn aggregate_as(function: AggregateFunction, sql_type: BaseSqlTypesMapper) -> Aggregate {
+        Aggregate {
+            function,
+            argument: Some(Box::new(Expression::Column(0, BaseSqlTypesMapper::Integer))),
+            sql_type,
+        }
+    }
+
+    fn run(
+        agg: &Aggregate,
+        values: &[Option<i32>],
+    ) -> Result<Option<BaseSqlTypes>, AggregateError> {
+        let mut state = agg.start();
+        for v in values {
+            agg.update(&mut state, &SqlTuple(vec![v.map(BaseSqlTypes::Integer)]))?;
+        }
+        agg.finish(state)
+    }
+
+    #[test]
+    fn test_aggregates() -> Result<(), Box<dyn std::error::Error>> {
+        let values = [Some(4), None, Some(1), Some(7)];
+        let integer = |i| Some(BaseSqlTypes::Integer(i));
+
+        let numeric = |s: &str| -> Result<Option<BaseSqlTypes>, NumericError> {
+            Ok(Some(BaseSqlTypes::Numeric(s.parse()?)))
+        };
+
+        assert_eq!(
+            run(
+                &aggregate_as(AggregateFunction::Avg, BaseSqlTypesMapper::Numeric),
+                &values
+            )?
+            .map(|v| v.to_string()),
+            Some("4.000000000000000".to_string())
+        );
+        assert_eq!(
+            run(&aggregate(AggregateFunction::Count), &values)?,
+            integer(3)
+        );
+        assert_eq!(
+            run(&aggregate(AggregateFunction::Max), &values)?,
+            integer(7)
+        );
+        assert_eq!(
+            run(&aggregate(AggregateFunction::Min), &values)?,
+            integer(1)
+        );
+        assert_eq!(
+            run(&aggregate(AggregateFunction::Sum), &values)?,
+            integer(12)
+        );
+
+        let count_star = Aggregate {
+            function: AggregateFunction::Count,
+            argument: None,
+            sql_type: BaseSqlTypesMapper::Integer,
+        };
+        assert_eq!(run(&count_star, &values)?, integer(4));
+
+        //Nothing to aggregate is NULL, except for counting
+        assert_eq!(run(&aggregate(AggregateFunction::Sum), &[None])?, None);
+        assert_eq!(
+            run(
+                &aggregate_as(AggregateFunction::Avg, BaseSqlTypesMapper::Numeric),
+                &[]
+            )?,
+            None
+        );
+        assert_eq!(run(&count_star, &[])?, integer(0));
+
+        //Only jsonb_agg keeps the NULLs
+        let jsonb_agg = Aggregate {
+            function: AggregateFunction::JsonbAgg,
+            argument: Some(Box::new(Expression::Column(0, BaseSqlTypesMapper::Integer))),
+            sql_type: BaseSqlTypesMapper::Jsonb,
+        };
+        assert_eq!(
+            run(&jsonb_agg, &values)?,
+            Some(BaseSqlTypes::Jsonb("[4, null, 1, 7]".parse()?))
+        );
+        assert_eq!(run(&jsonb_agg, &[])?, None);
+
+        assert_eq!(
+            run(
+                &aggregate_as(AggregateFunction::Avg, BaseSqlTypesMapper::Numeric),
+                &[Some(-7), Some(2)]
+            )?,
+            numeric("-2.5")?
+        );
+        assert!(run(
+            &aggregate(AggregateFunction::Sum),
+            &[Some(i32::MAX), Some(1)]
+        )
+        .is_err());
+
+        //A wider result can hold what the column can't
+        let bigint_sum = Aggregate {
+            function: AggregateFunction::Sum,
+            argument: Some(Box::new(Expression::Column(0, BaseSqlTypesMapper::Integer))),
+            sql_type: BaseSqlTypesMapper::Bigint,
+        };
+        assert_eq!(
+            run(&bigint_sum, &[Some(i32::MAX), Some(1)])?,
+            Some(BaseSqlTypes::Bigint(i64::from(i32::MAX) + 1))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_floating_aggregates() -> Result<(), Box<dyn std::error::Error>> {
+        let agg = |function, sql_type| Aggregate {
+            function,
+            argument: Some(Box::new(Expression::Column(0, BaseSqlTypesMapper::Real))),
+            sql_type,
+        };
+        let run = |agg: &Aggregate| -> Result<Option<BaseSqlTypes>, AggregateError> {
+            let mut state = agg.start();
+            for v in [1.5f32, 2.0, 3.0] {
+                agg.update(
+                    &mut state,
+                    &SqlTuple(vec![Some(BaseSqlTypes::Real(SqlFloat(v)))]),
+                )?;
+            }
+            agg.finish(state)
+        };
+
+        assert_eq!(
+            run(&agg(AggregateFunction::Sum, BaseSqlTypesMapper::Real))?,
+            Some(BaseSqlTypes::Real(SqlFloat(6.5)))
+        );
+        assert_eq!(
+            run(&agg(AggregateFunction::Avg, BaseSqlTypesMapper::Double))?,
+            Some(BaseSqlTypes::Double(SqlFloat(6.5 / 3.0)))
+        );
+
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 //!Postgres Doc: https://www.postgresql.org/docs/current/catalog-pg-attribute.html
 
-use super::types::BaseSqlTypesMapper;
+use super::types::{BaseSqlTypesMapper, TypeModifier};
 use crate::constants::Nullable;
 use std::fmt;
 
@@ -9,7 +9,7 @@ pub struct Attribute {
     pub name: String,                 //Column Name
     pub sql_type: BaseSqlTypesMapper, //Underlying type
     pub nullable: Nullable,           //Null constraint
-    pub length: Option<usize>,        //Length of variable length columns - constraint
+    pub length: Option<TypeModifier>, //Length or precision limit - constraint
 }
 
 impl Attribute {
@@ -17,7 +17,7 @@ impl Attribute {
         name: String,
         sql_type: BaseSqlTypesMapper,
         nullable: Nullable,
-        length: Option<usize>, //TODO make use of the length for certain types
+        length: Option<TypeModifier>,
     ) -> Attribute {
         Attribute {
             name,
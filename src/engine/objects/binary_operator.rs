@@ -0,0 +1,188 @@
+//! Operators that sit between two expressions, see here: https://www.postgresql.org/docs/current/functions-comparison.html
+//! and here: https://www.postgresql.org/docs/current/functions-math.html
+//! and here: https://www.postgresql.org/docs/current/functions-json.html
+//! and here: https://www.postgresql.org/docs/current/functions-array.html
+use super::types::BaseSqlTypesMapper;
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOperator {
+    And,
+    Or,
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    ///-> an object field or array element
+    JsonField,
+    ///->> same as JsonField but as text
+    JsonFieldText,
+    ///#> the value at a path of fields and elements
+    JsonPath,
+    ///? the string is a top level key or array element
+    JsonHasKey,
+    ///@> for both jsonb and arrays
+    Contains,
+    ///&& the arrays have an element in common
+    Overlap,
+}
+
+impl BinaryOperator {
+    pub fn is_comparison(&self) -> bool {
+        !matches!(self, BinaryOperator::And | BinaryOperator::Or) && !self.has_result_type()
+    }
+
+    /// For these result_type decides what the sides can be and what they produce, any other
+    /// operator needs both sides to be the same type
+    pub fn has_result_type(&self) -> bool {
+        self.is_arithmetic() || self.is_json() || self.is_containment()
+    }
+
+    /// Arithmetic produces a value instead of a boolean, see result_type for which
+    pub fn is_arithmetic(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::Add
+                | BinaryOperator::Subtract
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide
+        )
+    }
+
+    /// The jsonb operators, like arithmetic their sides don't have to be the same type
+    pub fn is_json(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::JsonField
+                | BinaryOperator::JsonFieldText
+                | BinaryOperator::JsonPath
+                | BinaryOperator::JsonHasKey
+        )
+    }
+
+    pub fn is_containment(&self) -> bool {
+        matches!(self, BinaryOperator::Contains | BinaryOperator::Overlap)
+    }
+
+    /// The type arithmetic, a jsonb or a containment operator on these operands produces, None if
+    /// the operator isn't defined for them.
+    ///
+    /// Numbers stay the same type, except integers of different widths which produce the wider one.
+    /// Date and time follow postgres: https://www.postgresql.org/docs/current/functions-datetime.html
+    pub fn result_type(
+        &self,
+        left: &BaseSqlTypesMapper,
+        right: &BaseSqlTypesMapper,
+    ) -> Option<BaseSqlTypesMapper> {
+        use BaseSqlTypesMapper::*;
+        if self.is_json() {
+            return self.json_result_type(left, right);
+        }
+        if self.is_containment() {
+            return match (self, left, right) {
+                (BinaryOperator::Contains, Jsonb, Jsonb) => Some(Bool),
+                (_, Array(l), Array(r)) if l == r => Some(Bool),
+                (_, _, _) => None,
+            };
+        }
+        if !self.is_arithmetic() {
+            return None;
+        }
+        if left == right && left.is_numeric() {
+            return Some(left.clone());
+        }
+        if let Some(wider) = left.wider_integer(right) {
+            return Some(wider);
+        }
+        let result = match (self, left, right) {
+            (BinaryOperator::Add, Date, Integer) | (BinaryOperator::Add, Integer, Date) => Date,
+            (BinaryOperator::Add, Date, Time)
+            | (BinaryOperator::Add, Time, Date)
+            | (BinaryOperator::Add, Date, Interval)
+            | (BinaryOperator::Add, Interval, Date) => Timestamp,
+            (BinaryOperator::Add, Time, Interval) | (BinaryOperator::Add, Interval, Time) => Time,
+            (BinaryOperator::Add, Timestamp, Interval)
+            | (BinaryOperator::Add, Interval, Timestamp) => Timestamp,
+            (BinaryOperator::Add, TimestampTz, Interval)
+            | (BinaryOperator::Add, Interval, TimestampTz) => TimestampTz,
+            (BinaryOperator::Subtract, Date, Integer) => Date,
+            (BinaryOperator::Subtract, Date, Date) => Integer,
+            (BinaryOperator::Subtract, Date, Interval) => Timestamp,
+            (BinaryOperator::Subtract, Time, Interval) => Time,
+            (BinaryOperator::Subtract, Timestamp, Interval) => Timestamp,
+            (BinaryOperator::Subtract, TimestampTz, Interval) => TimestampTz,
+            (BinaryOperator::Subtract, Time, Time)
+            | (BinaryOperator::Subtract, Timestamp, Timestamp)
+            | (BinaryOperator::Subtract, TimestampTz, TimestampTz) => Interval,
+            (BinaryOperator::Add, Interval, Interval)
+            | (BinaryOperator::Subtract, Interval, Interval) => Interval,
+            (_, _, _) => return None,
+        };
+        Some(result)
+    }
+
+    fn json_result_type(
+        &self,
+        left: &BaseSqlTypesMapper,
+        right: &BaseSqlTypesMapper,
+    ) -> Option<BaseSqlTypesMapper> {
+        use BaseSqlTypesMapper::*;
+        let result = match (self, left, right) {
+            (BinaryOperator::JsonField, Jsonb, Text)
+            | (BinaryOperator::JsonField, Jsonb, Integer) => Jsonb,
+            (BinaryOperator::JsonFieldText, Jsonb, Text)
+            | (BinaryOperator::JsonFieldText, Jsonb, Integer) => Text,
+            (BinaryOperator::JsonPath, Jsonb, Text) => Jsonb,
+            (BinaryOperator::JsonHasKey, Jsonb, Text) => Bool,
+            (_, _, _) => return None,
+        };
+        Some(result)
+    }
+}
+
+impl Display for BinaryOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryOperator::And => write!(f, "AND"),
+            BinaryOperator::Or => write!(f, "OR"),
+            BinaryOperator::Equal => write!(f, "="),
+            BinaryOperator::NotEqual => write!(f, "<>"),
+            BinaryOperator::LessThan => write!(f, "<"),
+            BinaryOperator::LessThanOrEqual => write!(f, "<="),
+            BinaryOperator::GreaterThan => write!(f, ">"),
+            BinaryOperator::GreaterThanOrEqual => write!(f, ">="),
+            BinaryOperator::Add => write!(f, "+"),
+            BinaryOperator::Subtract => write!(f, "-"),
+            BinaryOperator::Multiply => write!(f, "*"),
+            BinaryOperator::Divide => write!(f, "/"),
+            BinaryOperator::JsonField => write!(f, "->"),
+            BinaryOperator::JsonFieldText => write!(f, "->>"),
+            BinaryOperator::JsonPath => write!(f, "#>"),
+            BinaryOperator::JsonHasKey => write!(f, "?"),
+            BinaryOperator::Contains => write!(f, "@>"),
+            BinaryOperator::Overlap => write!(f, "&&"),
+        }
+    }
+}
+
+/// Compares a value against every element of an array, such as x = ANY(array), SOME is the same as ANY
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Quantifier {
+    Any,
+    All,
+}
+
+impl Display for Quantifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Quantifier::Any => write!(f, "ANY"),
+            Quantifier::All => write!(f, "ALL"),
+        }
+    }
+}
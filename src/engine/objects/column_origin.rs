@@ -0,0 +1,23 @@
+//! Where an output column came from, sent in RowDescription so clients know a column's table and declared type.
+//! See here: https://www.postgresql.org/docs/current/protocol-message-formats.html
+use super::types::TypeModifier;
+use super::Table;
+use std::convert::TryFrom;
+
+/// Columns that are computed instead of read from a table use the default, same as postgres sending zeros
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColumnOrigin {
+    pub table_oid: u32,
+    pub column_number: i16, //Starts at one, zero means no column
+    pub modifier: Option<TypeModifier>,
+}
+
+impl ColumnOrigin {
+    pub fn new(table: &Table, column: usize) -> ColumnOrigin {
+        ColumnOrigin {
+            table_oid: table.oid,
+            column_number: i16::try_from(column + 1).unwrap_or(0),
+            modifier: table.attributes.get(column).and_then(|a| a.length),
+        }
+    }
+}
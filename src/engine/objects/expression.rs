@@ -0,0 +1,595 @@
+//! Typed expression tree produced by the analyzer from a ParseExpression.
+//!
+//! Columns have already been resolved into offsets of the tuple the expression will be evaluated against.
+use super::types::{array, BaseSqlTypes, BaseSqlTypesError, BaseSqlTypesMapper, Jsonb, JsonbError};
+use super::{
+    Aggregate, AggregateFunction, BinaryOperator, Quantifier, ScalarFunction, ScalarFunctionError,
+    SqlTuple,
+};
+use std::cmp::Ordering;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    ///Offset into the evaluated tuple plus the type found there
+    Column(usize, BaseSqlTypesMapper),
+    Constant(Option<BaseSqlTypes>, BaseSqlTypesMapper),
+    BinaryOperation(Box<Expression>, BinaryOperator, Box<Expression>),
+    Not(Box<Expression>),
+    IsNull(Box<Expression>),
+    IsNotNull(Box<Expression>),
+    ///Converts the value to another type, only added where the analyzer allows an implicit cast
+    Cast(Box<Expression>, BaseSqlTypesMapper),
+    Function(ScalarFunction, Vec<Expression>),
+    ///ARRAY[...] plus the element type, the items of a multi-dimensional array are Arrays too
+    Array(Vec<Expression>, BaseSqlTypesMapper),
+    Subscript(Box<Expression>, Vec<Expression>),
+    ArrayComparison(Box<Expression>, BinaryOperator, Quantifier, Box<Expression>),
+    ///Produces a row per element instead of a value so only a select column can be one, see evaluate_set
+    Unnest(Box<Expression>),
+    ///Only valid until the planner moves it into an aggregate node, see Analyzer
+    Aggregate(Box<Aggregate>),
+}
+
+impl Expression {
+    /// The type this expression will produce when evaluated
+    pub fn sql_type(&self) -> BaseSqlTypesMapper {
+        match self {
+            Expression::Column(_, t) => t.clone(),
+            Expression::Constant(_, t) => t.clone(),
+            Expression::Aggregate(a) => a.sql_type.clone(),
+            Expression::Cast(_, t) => t.clone(),
+            Expression::Function(f, _) => f.sql_type(),
+            Expression::Array(_, t) => BaseSqlTypesMapper::Array(Arc::new(t.clone())),
+            Expression::Subscript(e, _) | Expression::Unnest(e) => match e.sql_type() {
+                BaseSqlTypesMapper::Array(t) => t.as_ref().clone(),
+                t => t,
+            },
+            Expression::BinaryOperation(left, op, right) if op.has_result_type() => op
+                .result_type(&left.sql_type(), &right.sql_type())
+                .unwrap_or_else(|| left.sql_type()),
+            Expression::BinaryOperation(_, _, _)
+            | Expression::ArrayComparison(_, _, _, _)
+            | Expression::Not(_)
+            | Expression::IsNull(_)
+            | Expression::IsNotNull(_) => BaseSqlTypesMapper::Bool,
+        }
+    }
+
+    /// Evaluates the expression against a row, None is the sql NULL.
+    ///
+    /// Boolean logic follows sql's three valued rules: https://www.postgresql.org/docs/current/functions-logical.html
+    pub fn evaluate(&self, row: &SqlTuple) -> Result<Option<BaseSqlTypes>, ExpressionError> {
+        match self {
+            Expression::Column(offset, _) => {
+                let value = row
+                    .get(*offset)
+                    .ok_or(ExpressionError::ColumnOutOfRange(*offset, row.len()))?;
+                Ok(value.clone())
+            }
+            Expression::Constant(c, _) => Ok(c.clone()),
+            Expression::BinaryOperation(left, op, right) => {
+                let left_val = left.evaluate(row)?;
+                let right_val = right.evaluate(row)?;
+                match op {
+                    BinaryOperator::And => {
+                        let l = Self::as_bool(left_val)?;
+                        let r = Self::as_bool(right_val)?;
+                        Ok(match (l, r) {
+                            (Some(false), _) | (_, Some(false)) => Some(BaseSqlTypes::Bool(false)),
+                            (Some(true), Some(true)) => Some(BaseSqlTypes::Bool(true)),
+                            (_, _) => None,
+                        })
+                    }
+                    BinaryOperator::Or => {
+                        let l = Self::as_bool(left_val)?;
+                        let r = Self::as_bool(right_val)?;
+                        Ok(match (l, r) {
+                            (Some(true), _) | (_, Some(true)) => Some(BaseSqlTypes::Bool(true)),
+                            (Some(false), Some(false)) => Some(BaseSqlTypes::Bool(false)),
+                            (_, _) => None,
+                        })
+                    }
+                    _ => {
+                        let (l, r) = match (left_val, right_val) {
+                            (Some(l), Some(r)) => (l, r),
+                            (_, _) => return Ok(None),
+                        };
+                        if op.is_arithmetic() {
+                            return Ok(Some(Self::arithmetic(&l, *op, &r)?));
+                        }
+                        if op.is_json() {
+                            return Self::json_operation(&l, *op, &r);
+                        }
+                        if op.is_containment() {
+                            return Ok(Some(Self::containment(&l, *op, &r)?));
+                        }
+                        let ordering = Self::compare(&l, &r)?;
+                        let result = match op {
+                            BinaryOperator::Equal => ordering == Ordering::Equal,
+                            BinaryOperator::NotEqual => ordering != Ordering::Equal,
+                            BinaryOperator::LessThan => ordering == Ordering::Less,
+                            BinaryOperator::LessThanOrEqual => ordering != Ordering::Greater,
+                            BinaryOperator::GreaterThan => ordering == Ordering::Greater,
+                            BinaryOperator::GreaterThanOrEqual => ordering != Ordering::Less,
+                            _ => unreachable!(),
+                        };
+                        Ok(Some(BaseSqlTypes::Bool(result)))
+                    }
+                }
+            }
+            Expression::Not(e) => {
+                Ok(Self::as_bool(e.evaluate(row)?)?.map(|b| BaseSqlTypes::Bool(!b)))
+            }
+            Expression::IsNull(e) => Ok(Some(BaseSqlTypes::Bool(e.evaluate(row)?.is_none()))),
+            Expression::IsNotNull(e) => Ok(Some(BaseSqlTypes::Bool(e.evaluate(row)?.is_some()))),
+            Expression::Cast(e, t) => match e.evaluate(row)? {
+                Some(v) => Ok(Some(v.convert(t)?)),
+                None => Ok(None),
+            },
+            Expression::Function(f, args) => {
+                let values = args
+                    .iter()
+                    .map(|a| a.evaluate(row))
+                    .collect::<Result<_, _>>()?;
+                Ok(f.evaluate(values)?)
+            }
+            Expression::Array(items, _) => {
+                let values = items
+                    .iter()
+                    .map(|i| i.evaluate(row))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if array::dimensions(&values).is_none() {
+                    return Err(ExpressionError::ArrayDimensionMismatch());
+                }
+                Ok(Some(BaseSqlTypes::Array(values)))
+            }
+            Expression::Subscript(e, subscripts) => {
+                let mut indexes = Vec::with_capacity(subscripts.len());
+                for s in subscripts {
+                    match s.evaluate(row)? {
+                        Some(BaseSqlTypes::Integer(i)) => indexes.push(i64::from(i)),
+                        Some(v) => return Err(ExpressionError::InvalidSubscript(v)),
+                        None => return Ok(None),
+                    }
+                }
+                match e.evaluate(row)? {
+                    Some(BaseSqlTypes::Array(items)) => {
+                        Ok(array::subscript(&items, &indexes).cloned())
+                    }
+                    Some(v) => Err(ExpressionError::NotArray(v)),
+                    None => Ok(None),
+                }
+            }
+            Expression::ArrayComparison(left, op, quantifier, right) => {
+                let items = match right.evaluate(row)? {
+                    Some(BaseSqlTypes::Array(items)) => items,
+                    Some(v) => return Err(ExpressionError::NotArray(v)),
+                    None => return Ok(None),
+                };
+                let left = left.evaluate(row)?;
+                Self::array_comparison(left, *op, *quantifier, &items)
+            }
+            Expression::Unnest(_) => Err(ExpressionError::UnexpectedSetFunction()),
+            Expression::Aggregate(a) => Err(ExpressionError::UnplannedAggregate(a.function)),
+        }
+    }
+
+    /// The values of a set returning expression, a NULL array is no values at all.
+    /// None if the expression produces a single value, use evaluate for those.
+    pub fn evaluate_set(
+        &self,
+        row: &SqlTuple,
+    ) -> Result<Option<Vec<Option<BaseSqlTypes>>>, ExpressionError> {
+        match self {
+            Expression::Unnest(e) => match e.evaluate(row)? {
+                Some(BaseSqlTypes::Array(items)) => Ok(Some(
+                    array::elements(&items)
+                        .into_iter()
+                        .map(|v| v.cloned())
+                        .collect(),
+                )),
+                Some(v) => Err(ExpressionError::NotArray(v)),
+                None => Ok(Some(vec![])),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// True if there is an aggregate anywhere in the expression
+    pub fn contains_aggregate(&self) -> bool {
+        match self {
+            Expression::Aggregate(_) => true,
+            Expression::BinaryOperation(left, _, right) => {
+                left.contains_aggregate() || right.contains_aggregate()
+            }
+            Expression::Not(e)
+            | Expression::IsNull(e)
+            | Expression::IsNotNull(e)
+            | Expression::Cast(e, _)
+            | Expression::Unnest(e) => e.contains_aggregate(),
+            Expression::Function(_, args) | Expression::Array(args, _) => {
+                args.iter().any(|a| a.contains_aggregate())
+            }
+            Expression::Subscript(e, subscripts) => {
+                e.contains_aggregate() || subscripts.iter().any(|s| s.contains_aggregate())
+            }
+            Expression::ArrayComparison(left, _, _, right) => {
+                left.contains_aggregate() || right.contains_aggregate()
+            }
+            Expression::Column(_, _) | Expression::Constant(_, _) => false,
+        }
+    }
+
+    fn as_bool(value: Option<BaseSqlTypes>) -> Result<Option<bool>, ExpressionError> {
+        match value {
+            Some(BaseSqlTypes::Bool(b)) => Ok(Some(b)),
+            Some(v) => Err(ExpressionError::NotBoolean(v)),
+            None => Ok(None),
+        }
+    }
+
+    fn arithmetic(
+        left: &BaseSqlTypes,
+        op: BinaryOperator,
+        right: &BaseSqlTypes,
+    ) -> Result<BaseSqlTypes, ExpressionError> {
+        Ok(match op {
+            BinaryOperator::Add => left.checked_add(right)?,
+            BinaryOperator::Subtract => left.checked_sub(right)?,
+            BinaryOperator::Multiply => left.checked_mul(right)?,
+            BinaryOperator::Divide => left.checked_div(right)?,
+            _ => unreachable!(),
+        })
+    }
+
+    /// A missing field, element or path is NULL, same as postgres
+    fn json_operation(
+        left: &BaseSqlTypes,
+        op: BinaryOperator,
+        right: &BaseSqlTypes,
+    ) -> Result<Option<BaseSqlTypes>, ExpressionError> {
+        let json = match left {
+            BaseSqlTypes::Jsonb(j) => j,
+            _ => return Err(ExpressionError::NotJsonb(left.clone())),
+        };
+        let found = match (op, right) {
+            (BinaryOperator::JsonField, BaseSqlTypes::Text(key))
+            | (BinaryOperator::JsonFieldText, BaseSqlTypes::Text(key)) => json.field(key),
+            (BinaryOperator::JsonField, BaseSqlTypes::Integer(index))
+            | (BinaryOperator::JsonFieldText, BaseSqlTypes::Integer(index)) => {
+                json.element(i64::from(*index))
+            }
+            (BinaryOperator::JsonPath, BaseSqlTypes::Text(path)) => {
+                json.path(&Jsonb::parse_path(path)?)
+            }
+            (BinaryOperator::JsonHasKey, BaseSqlTypes::Text(key)) => {
+                return Ok(Some(BaseSqlTypes::Bool(json.has_key(key))));
+            }
+            (_, _) => {
+                return Err(ExpressionError::InvalidJsonOperand(op, right.clone()));
+            }
+        };
+        Ok(match op {
+            BinaryOperator::JsonFieldText => {
+                found.and_then(|f| f.as_text()).map(BaseSqlTypes::Text)
+            }
+            _ => found.cloned().map(BaseSqlTypes::Jsonb),
+        })
+    }
+
+    fn containment(
+        left: &BaseSqlTypes,
+        op: BinaryOperator,
+        right: &BaseSqlTypes,
+    ) -> Result<BaseSqlTypes, ExpressionError> {
+        let result = match (op, left, right) {
+            (BinaryOperator::Contains, BaseSqlTypes::Jsonb(l), BaseSqlTypes::Jsonb(r)) => {
+                l.contains(r)
+            }
+            (BinaryOperator::Contains, BaseSqlTypes::Array(l), BaseSqlTypes::Array(r)) => {
+                array::contains(l, r)
+            }
+            (BinaryOperator::Overlap, BaseSqlTypes::Array(l), BaseSqlTypes::Array(r)) => {
+                array::overlaps(l, r)
+            }
+            (_, _, _) => {
+                return Err(ExpressionError::IncomparableTypes(
+                    left.clone(),
+                    right.clone(),
+                ))
+            }
+        };
+        Ok(BaseSqlTypes::Bool(result))
+    }
+
+    /// Same as postgres, a NULL only matters if no element decides the result on its own.
+    /// So ANY is NULL if nothing matched but something was NULL and ALL is the reverse.
+    fn array_comparison(
+        left: Option<BaseSqlTypes>,
+        op: BinaryOperator,
+        quantifier: Quantifier,
+        items: &[Option<BaseSqlTypes>],
+    ) -> Result<Option<BaseSqlTypes>, ExpressionError> {
+        let decisive = quantifier == Quantifier::Any;
+        let mut saw_null = false;
+        for item in array::elements(items) {
+            let (l, r) = match (&left, item) {
+                (Some(l), Some(r)) => (l, r),
+                (_, _) => {
+                    saw_null = true;
+                    continue;
+                }
+            };
+            let ordering = Self::compare(l, r)?;
+            let matched = match op {
+                BinaryOperator::Equal => ordering == Ordering::Equal,
+                BinaryOperator::NotEqual => ordering != Ordering::Equal,
+                BinaryOperator::LessThan => ordering == Ordering::Less,
+                BinaryOperator::LessThanOrEqual => ordering != Ordering::Greater,
+                BinaryOperator::GreaterThan => ordering == Ordering::Greater,
+                BinaryOperator::GreaterThanOrEqual => ordering != Ordering::Less,
+                _ => return Err(ExpressionError::InvalidQuantifiedOperator(op)),
+            };
+            if matched == decisive {
+                return Ok(Some(BaseSqlTypes::Bool(decisive)));
+            }
+        }
+        match saw_null {
+            true => Ok(None),
+            false => Ok(Some(BaseSqlTypes::Bool(!decisive))),
+        }
+    }
+
+    fn compare(left: &BaseSqlTypes, right: &BaseSqlTypes) -> Result<Ordering, ExpressionError> {
+        //The analyzer should have already made sure both sides are the same type
+        if std::mem::discriminant(left) != std::mem::discriminant(right) {
+            return Err(ExpressionError::IncomparableTypes(
+                left.clone(),
+                right.clone(),
+            ));
+        }
+        Ok(left.cmp(right))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ExpressionError {
+    #[error("Multidimensional arrays must have sub-arrays with matching dimensions")]
+    ArrayDimensionMismatch(),
+    #[error(transparent)]
+    BaseSqlTypesError(#[from] BaseSqlTypesError),
+    #[error("Column offset {0} is outside of row length {1}")]
+    ColumnOutOfRange(usize, usize),
+    #[error("Unable to compare {0} and {1}")]
+    IncomparableTypes(BaseSqlTypes, BaseSqlTypes),
+    #[error("Operator {0} does not take {1}")]
+    InvalidJsonOperand(BinaryOperator, BaseSqlTypes),
+    #[error("Operator {0} can not be used with ANY or ALL")]
+    InvalidQuantifiedOperator(BinaryOperator),
+    #[error("Array subscript must be an integer, got {0}")]
+    InvalidSubscript(BaseSqlTypes),
+    #[error(transparent)]
+    JsonbError(#[from] JsonbError),
+    #[error("Expected an array, got {0}")]
+    NotArray(BaseSqlTypes),
+    #[error("Expected a boolean, got {0}")]
+    NotBoolean(BaseSqlTypes),
+    #[error("Expected a jsonb, got {0}")]
+    NotJsonb(BaseSqlTypes),
+    #[error(transparent)]
+    ScalarFunctionError(#[from] ScalarFunctionError),
+    #[error("Set returning functions are only allowed as a select column")]
+    UnexpectedSetFunction(),
+    #[error("Aggregate {0} can only be evaluated by an aggregate plan")]
+    UnplannedAggregate(AggregateFunction),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_row() -> SqlTuple {
+        SqlTuple(vec![
+            Some(BaseSqlTypes::Text("foo".to_string())),
+            None,
+            Some(BaseSqlTypes::Integer(5)),
+        ])
+    }
+
+    fn constant_int(i: i32) -> Box<Expression> {
+        Box::new(Expression::Constant(
+            Some(BaseSqlTypes::Integer(i)),
+            BaseSqlTypesMapper::Integer,
+        ))
+    }
+
+    #[test]
+    fn test_comparison() -> Result<(), Box<dyn std::error::Error>> {
+        let row = get_row();
+        let column = Box::new(Expression::Column(2, BaseSqlTypesMapper::Integer));
+
+        let test =
+            Expression::BinaryOperation(column.clone(), BinaryOperator::Equal, constant_int(5));
+        assert_eq!(test.evaluate(&row)?, Some(BaseSqlTypes::Bool(true)));
+
+        let test =
+            Expression::BinaryOperation(column.clone(), BinaryOperator::LessThan, constant_int(5));
+        assert_eq!(test.evaluate(&row)?, Some(BaseSqlTypes::Bool(false)));
+
+        let test = Expression::BinaryOperation(
+            column,
+            BinaryOperator::GreaterThanOrEqual,
+            constant_int(2),
+        );
+        assert_eq!(test.evaluate(&row)?, Some(BaseSqlTypes::Bool(true)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_arithmetic() -> Result<(), Box<dyn std::error::Error>> {
+        let row = get_row();
+        let column = Box::new(Expression::Column(2, BaseSqlTypesMapper::Integer));
+
+        let test = Expression::BinaryOperation(
+            Box::new(Expression::BinaryOperation(
+                column.clone(),
+                BinaryOperator::Multiply,
+                constant_int(-3),
+            )),
+            BinaryOperator::Add,
+            constant_int(1),
+        );
+        assert_eq!(test.sql_type(), BaseSqlTypesMapper::Integer);
+        assert_eq!(test.evaluate(&row)?, Some(BaseSqlTypes::Integer(-14)));
+
+        let null_col = Box::new(Expression::Column(1, BaseSqlTypesMapper::Integer));
+        let test = Expression::BinaryOperation(null_col, BinaryOperator::Subtract, constant_int(1));
+        assert_eq!(test.evaluate(&row)?, None);
+
+        let test =
+            Expression::BinaryOperation(column.clone(), BinaryOperator::Divide, constant_int(0));
+        assert!(matches!(
+            test.evaluate(&row),
+            Err(ExpressionError::BaseSqlTypesError(
+                BaseSqlTypesError::DivisionByZero()
+            ))
+        ));
+
+        let test = Expression::BinaryOperation(
+            column.clone(),
+            BinaryOperator::Add,
+            constant_int(i32::MAX),
+        );
+        assert!(matches!(
+            test.evaluate(&row),
+            Err(ExpressionError::BaseSqlTypesError(
+                BaseSqlTypesError::NumericOutOfRange(_)
+            ))
+        ));
+
+        let test = Expression::Cast(column, BaseSqlTypesMapper::Bigint);
+        assert_eq!(test.sql_type(), BaseSqlTypesMapper::Bigint);
+        assert_eq!(test.evaluate(&row)?, Some(BaseSqlTypes::Bigint(5)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_null_logic() -> Result<(), Box<dyn std::error::Error>> {
+        let row = get_row();
+        let null_col = Box::new(Expression::Column(1, BaseSqlTypesMapper::Integer));
+
+        let null_cmp = Box::new(Expression::BinaryOperation(
+            null_col.clone(),
+            BinaryOperator::Equal,
+            constant_int(5),
+        ));
+        assert_eq!(null_cmp.evaluate(&row)?, None);
+
+        let false_cmp = Box::new(Expression::BinaryOperation(
+            constant_int(1),
+            BinaryOperator::Equal,
+            constant_int(5),
+        ));
+        let true_cmp = Box::new(Expression::Not(false_cmp.clone()));
+
+        let test = Expression::BinaryOperation(null_cmp.clone(), BinaryOperator::And, false_cmp);
+        assert_eq!(test.evaluate(&row)?, Some(BaseSqlTypes::Bool(false)));
+
+        let test = Expression::BinaryOperation(null_cmp.clone(), BinaryOperator::Or, true_cmp);
+        assert_eq!(test.evaluate(&row)?, Some(BaseSqlTypes::Bool(true)));
+
+        let test = Expression::Not(null_cmp);
+        assert_eq!(test.evaluate(&row)?, None);
+
+        let test = Expression::IsNull(null_col.clone());
+        assert_eq!(test.evaluate(&row)?, Some(BaseSqlTypes::Bool(true)));
+
+        let test = Expression::IsNotNull(null_col);
+        assert_eq!(test.evaluate(&row)?, Some(BaseSqlTypes::Bool(false)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrays() -> Result<(), Box<dyn std::error::Error>> {
+        let row = get_row();
+        let ints = |values: &[Option<i32>]| {
+            Box::new(Expression::Constant(
+                Some(BaseSqlTypes::Array(
+                    values
+                        .iter()
+                        .map(|v| v.map(BaseSqlTypes::Integer))
+                        .collect(),
+                )),
+                BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Integer)),
+            ))
+        };
+        let column = Box::new(Expression::Column(2, BaseSqlTypesMapper::Integer));
+
+        let cases = vec![
+            (Quantifier::Any, vec![Some(1), Some(5)], Some(true)),
+            (Quantifier::Any, vec![Some(1), None], None),
+            (Quantifier::Any, vec![], Some(false)),
+            (Quantifier::All, vec![Some(5), Some(5)], Some(true)),
+            (Quantifier::All, vec![Some(1), None], Some(false)),
+            (Quantifier::All, vec![Some(5), None], None),
+            (Quantifier::All, vec![], Some(true)),
+        ];
+        for (quantifier, values, expected) in cases {
+            let test = Expression::ArrayComparison(
+                column.clone(),
+                BinaryOperator::Equal,
+                quantifier,
+                ints(&values),
+            );
+            assert_eq!(
+                test.evaluate(&row)?,
+                expected.map(BaseSqlTypes::Bool),
+                "{} {:?}",
+                quantifier,
+                values
+            );
+        }
+
+        let nested = Expression::Array(
+            vec![
+                Expression::Array(
+                    vec![*constant_int(1), *column.clone()],
+                    BaseSqlTypesMapper::Integer,
+                ),
+                Expression::Array(
+                    vec![*constant_int(3), *constant_int(4)],
+                    BaseSqlTypesMapper::Integer,
+                ),
+            ],
+            BaseSqlTypesMapper::Integer,
+        );
+        let test = Expression::Subscript(
+            Box::new(nested.clone()),
+            vec![*constant_int(1), *constant_int(2)],
+        );
+        assert_eq!(test.sql_type(), BaseSqlTypesMapper::Integer);
+        assert_eq!(test.evaluate(&row)?, Some(BaseSqlTypes::Integer(5)));
+
+        let test = Expression::Unnest(Box::new(nested));
+        assert!(test.evaluate(&row).is_err());
+        assert_eq!(test.evaluate_set(&row)?.map(|v| v.len()), Some(4));
+
+        let ragged = Expression::Array(
+            vec![
+                Expression::Array(vec![*constant_int(1)], BaseSqlTypesMapper::Integer),
+                *constant_int(2),
+            ],
+            BaseSqlTypesMapper::Integer,
+        );
+        assert!(matches!(
+            ragged.evaluate(&row),
+            Err(ExpressionError::ArrayDimensionMismatch())
+        ));
+        Ok(())
+    }
+}
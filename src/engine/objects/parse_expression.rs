@@ -1,5 +1,137 @@
+use super::types::BaseSqlTypesMapper;
+use super::{BinaryOperator, Quantifier};
+use thiserror::Error;
+
+/// Untyped expression tree as produced by the sql parser, the analyzer is responsible
+/// for resolving columns and types.
 #[derive(Clone, Debug, PartialEq)]
 pub enum ParseExpression {
     String(String),
+    ///A literal with its type in front, such as interval '1 hour'
+    TypedString(BaseSqlTypesMapper, String),
     Null(),
+    ///A column reference, qualified ones such as foo.bar have more than one part
+    Identifier(Vec<String>),
+    ///Placeholder for a prepared statement parameter, numbered from 1
+    Parameter(usize),
+    BinaryOperation(Box<ParseExpression>, BinaryOperator, Box<ParseExpression>),
+    Not(Box<ParseExpression>),
+    IsNull(Box<ParseExpression>),
+    IsNotNull(Box<ParseExpression>),
+    ///Name and arguments, functions aren't resolved until the analyzer
+    FunctionCall(String, Vec<ParseExpression>),
+    ///The * in count(*)
+    Wildcard(),
+    ///ARRAY[...], the inner brackets of a multi-dimensional array are nested Arrays
+    Array(Vec<ParseExpression>),
+    ///One or more subscripts after a value, such as a[1][2]
+    Subscript(Box<ParseExpression>, Vec<ParseExpression>),
+    ///x = ANY(array) or x = ALL(array)
+    ArrayComparison(
+        Box<ParseExpression>,
+        BinaryOperator,
+        Quantifier,
+        Box<ParseExpression>,
+    ),
+}
+
+impl ParseExpression {
+    /// Replaces parameter placeholders with the text of the values bound to them, None is a NULL.
+    ///
+    /// The values become untyped literals so the analyzer treats them the same as ones written inline.
+    pub fn bind_parameters(
+        self,
+        values: &[Option<String>],
+    ) -> Result<ParseExpression, ParseExpressionError> {
+        Ok(match self {
+            ParseExpression::Parameter(i) => match values.get(i - 1) {
+                Some(Some(v)) => ParseExpression::String(v.clone()),
+                Some(None) => ParseExpression::Null(),
+                None => return Err(ParseExpressionError::MissingParameter(i, values.len())),
+            },
+            ParseExpression::BinaryOperation(left, op, right) => ParseExpression::BinaryOperation(
+                Box::new(left.bind_parameters(values)?),
+                op,
+                Box::new(right.bind_parameters(values)?),
+            ),
+            ParseExpression::Not(e) => ParseExpression::Not(Box::new(e.bind_parameters(values)?)),
+            ParseExpression::IsNull(e) => {
+                ParseExpression::IsNull(Box::new(e.bind_parameters(values)?))
+            }
+            ParseExpression::IsNotNull(e) => {
+                ParseExpression::IsNotNull(Box::new(e.bind_parameters(values)?))
+            }
+            ParseExpression::FunctionCall(name, args) => ParseExpression::FunctionCall(
+                name,
+                args.into_iter()
+                    .map(|a| a.bind_parameters(values))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ParseExpression::Array(items) => ParseExpression::Array(
+                items
+                    .into_iter()
+                    .map(|i| i.bind_parameters(values))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ParseExpression::Subscript(e, subscripts) => ParseExpression::Subscript(
+                Box::new(e.bind_parameters(values)?),
+                subscripts
+                    .into_iter()
+                    .map(|s| s.bind_parameters(values))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ParseExpression::ArrayComparison(left, op, quantifier, right) => {
+                ParseExpression::ArrayComparison(
+                    Box::new(left.bind_parameters(values)?),
+                    op,
+                    quantifier,
+                    Box::new(right.bind_parameters(values)?),
+                )
+            }
+            ParseExpression::String(_)
+            | ParseExpression::TypedString(_, _)
+            | ParseExpression::Null()
+            | ParseExpression::Identifier(_)
+            | ParseExpression::Wildcard() => self,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ParseExpressionError {
+    #[error("Parameter ${0} was used but only {1} were bound")]
+    MissingParameter(usize, usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind_parameters() -> Result<(), Box<dyn std::error::Error>> {
+        let expr = ParseExpression::BinaryOperation(
+            Box::new(ParseExpression::Parameter(2)),
+            BinaryOperator::Or,
+            Box::new(ParseExpression::Not(Box::new(ParseExpression::Parameter(
+                1,
+            )))),
+        );
+
+        let bound = expr
+            .clone()
+            .bind_parameters(&[Some("foo".to_string()), None])?;
+        assert_eq!(
+            bound,
+            ParseExpression::BinaryOperation(
+                Box::new(ParseExpression::Null()),
+                BinaryOperator::Or,
+                Box::new(ParseExpression::Not(Box::new(ParseExpression::String(
+                    "foo".to_string()
+                )))),
+            )
+        );
+
+        assert!(expr.bind_parameters(&[None]).is_err());
+        Ok(())
+    }
 }
@@ -1,10 +1,95 @@
-use super::ParseExpression;
+use super::{JoinType, ParseExpression, ParseExpressionError, SortType};
 
 #[derive(Clone, Debug)]
 pub enum ParseTree {
     CreateTable(RawCreateTableCommand),
+    CreateType(RawCreateTypeCommand),
+    Delete(RawDeleteCommand),
+    DropType(RawDropTypeCommand),
     Insert(RawInsertCommand),
-    Select(RawSelectCommand),
+    Select(Box<RawSelectCommand>),
+    Transaction(TransactionCommand),
+    Update(RawUpdateCommand),
+}
+
+impl ParseTree {
+    /// Fills in the parameters of a prepared statement, see ParseExpression::bind_parameters
+    pub fn bind_parameters(
+        self,
+        values: &[Option<String>],
+    ) -> Result<ParseTree, ParseExpressionError> {
+        let bind_where =
+            |w: Option<ParseExpression>| w.map(|w| w.bind_parameters(values)).transpose();
+
+        Ok(match self {
+            ParseTree::Delete(d) => ParseTree::Delete(RawDeleteCommand {
+                table_name: d.table_name,
+                where_clause: bind_where(d.where_clause)?,
+            }),
+            ParseTree::Insert(i) => ParseTree::Insert(RawInsertCommand {
+                table_name: i.table_name,
+                provided_columns: i.provided_columns,
+                provided_values: i
+                    .provided_values
+                    .into_iter()
+                    .map(|v| v.bind_parameters(values))
+                    .collect::<Result<_, _>>()?,
+            }),
+            ParseTree::Select(s) => ParseTree::Select(Box::new(RawSelectCommand {
+                columns: s
+                    .columns
+                    .into_iter()
+                    .map(|c| c.bind_parameters(values))
+                    .collect::<Result<_, _>>()?,
+                table: s.table,
+                joins: s
+                    .joins
+                    .into_iter()
+                    .map(|j| {
+                        Ok(RawJoin {
+                            join_type: j.join_type,
+                            table: j.table,
+                            condition: bind_where(j.condition)?,
+                        })
+                    })
+                    .collect::<Result<_, ParseExpressionError>>()?,
+                where_clause: bind_where(s.where_clause)?,
+                group_by: s
+                    .group_by
+                    .into_iter()
+                    .map(|g| g.bind_parameters(values))
+                    .collect::<Result<_, _>>()?,
+                having: bind_where(s.having)?,
+                order_by: s
+                    .order_by
+                    .into_iter()
+                    .map(|o| {
+                        Ok(RawOrderBy {
+                            expression: o.expression.bind_parameters(values)?,
+                            position: o.position,
+                            sort_type: o.sort_type,
+                            nulls_first: o.nulls_first,
+                        })
+                    })
+                    .collect::<Result<_, ParseExpressionError>>()?,
+                limit: bind_where(s.limit)?,
+                offset: bind_where(s.offset)?,
+            })),
+            ParseTree::Update(u) => ParseTree::Update(RawUpdateCommand {
+                table_name: u.table_name,
+                assignments: u
+                    .assignments
+                    .into_iter()
+                    .map(|(c, v)| Ok((c, v.bind_parameters(values)?)))
+                    .collect::<Result<_, ParseExpressionError>>()?,
+                where_clause: bind_where(u.where_clause)?,
+            }),
+            ParseTree::CreateTable(_)
+            | ParseTree::CreateType(_)
+            | ParseTree::DropType(_)
+            | ParseTree::Transaction(_) => self,
+        })
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -17,10 +102,30 @@ pub struct RawCreateTableCommand {
 pub struct RawColumn {
     pub name: String,
     pub sql_type: String,
+    pub type_modifiers: Vec<i32>,
     pub null: bool,
     pub primary_key: bool,
 }
 
+///Only enums for now, the labels are in declaration order
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawCreateTypeCommand {
+    pub type_name: String,
+    pub labels: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawDeleteCommand {
+    pub table_name: String,
+    pub where_clause: Option<ParseExpression>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawDropTypeCommand {
+    pub type_name: String,
+    pub if_exists: bool,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct RawInsertCommand {
     pub table_name: String,
@@ -31,6 +136,53 @@ pub struct RawInsertCommand {
 //TODO This is VERY bare bones, will be radically changed once more is implemented
 #[derive(Clone, Debug, PartialEq)]
 pub struct RawSelectCommand {
-    pub columns: Vec<String>,
-    pub table: String,
+    pub columns: Vec<ParseExpression>,
+    pub table: RawTableReference,
+    pub joins: Vec<RawJoin>,
+    pub where_clause: Option<ParseExpression>,
+    pub group_by: Vec<ParseExpression>,
+    pub having: Option<ParseExpression>,
+    pub order_by: Vec<RawOrderBy>,
+    ///LIMIT ALL is the same as a NULL limit
+    pub limit: Option<ParseExpression>,
+    pub offset: Option<ParseExpression>,
+}
+
+///Nulls first is only set if the query said which
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawOrderBy {
+    pub expression: ParseExpression,
+    ///Set when the key is written as a bare integer, ORDER BY 2 means the second output column
+    pub position: Option<usize>,
+    pub sort_type: SortType,
+    pub nulls_first: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawTableReference {
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+///A comma or CROSS JOIN is an inner join without a condition
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawJoin {
+    pub join_type: JoinType,
+    pub table: RawTableReference,
+    pub condition: Option<ParseExpression>,
+}
+
+///Transaction control is handled by the connection, not the engine
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransactionCommand {
+    Begin,
+    Commit,
+    Rollback,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawUpdateCommand {
+    pub table_name: String,
+    pub assignments: Vec<(String, ParseExpression)>,
+    pub where_clause: Option<ParseExpression>,
 }
@@ -1,6 +1,10 @@
+use std::ops::Bound;
 use std::sync::Arc;
 
-use super::{types::SqlTypeDefinition, SqlTuple, Table};
+use super::{
+    types::{BaseSqlTypesMapper, SqlTypeDefinition},
+    Aggregate, Expression, Index, JoinType, SortKey, SqlTuple, Table,
+};
 
 pub struct PlannedStatement {
     pub common: PlannedCommon,
@@ -11,9 +15,19 @@ pub struct PlannedCommon {}
 
 pub enum Plan {
     CartesianJoin(CartesianJoin),
+    Delete(DeletePlan),
+    Filter(FilterPlan),
     FullTableScan(FullTableScan),
+    HashAggregate(HashAggregatePlan),
+    HashJoin(HashJoinPlan),
+    IndexScan(IndexScan),
+    Limit(LimitPlan),
     ModifyTable(ModifyTablePlan),
+    NestedLoopJoin(NestedLoopJoinPlan),
+    Projection(ProjectionPlan),
+    Sort(SortPlan),
     StaticData(Arc<Vec<SqlTuple>>),
+    Update(UpdatePlan),
 }
 
 pub struct CartesianJoin {
@@ -29,12 +43,98 @@ pub struct CartesianJoin {
     pub right: Arc<Plan>,
 }
 
+///Logically deletes the visible rows of the table that match the qualification
+pub struct DeletePlan {
+    pub table: Arc<Table>,
+    pub qualification: Option<Arc<Expression>>,
+}
+
+///Only passes on the rows from the source where the qualification is true
+pub struct FilterPlan {
+    pub source: Arc<Plan>,
+    pub qualification: Arc<Expression>,
+}
+
 pub struct FullTableScan {
     pub src_table: Arc<Table>,
     pub target_type: Arc<SqlTypeDefinition>,
 }
 
+///Groups the source rows on the keys using a hash table then computes the aggregates for each group.
+///Output rows are the key values followed by the aggregate results.
+///Without keys everything is one group, so a row comes out even if there was no input.
+pub struct HashAggregatePlan {
+    pub source: Arc<Plan>,
+    pub group_by: Vec<Arc<Expression>>,
+    pub aggregates: Vec<Arc<Aggregate>>,
+}
+
+///Joins on equal keys using a hash table built from the right side.
+///Rows with a NULL key never match, same as the = operator.
+pub struct HashJoinPlan {
+    pub join_type: JoinType,
+    pub left: Arc<Plan>,
+    pub right: Arc<Plan>,
+    ///Evaluated against the left row alone
+    pub left_keys: Vec<Arc<Expression>>,
+    ///Evaluated against the right row alone
+    pub right_keys: Vec<Arc<Expression>>,
+    ///The rest of the join condition, evaluated against the merged row
+    pub residual: Option<Arc<Expression>>,
+    pub left_width: usize,
+    pub right_width: usize,
+}
+
+///Reads only the rows whose index keys fall between the bounds, the bounds may cover just the leading columns
+pub struct IndexScan {
+    pub src_table: Arc<Table>,
+    pub index: Arc<Index>,
+    pub lower: Bound<SqlTuple>,
+    pub upper: Bound<SqlTuple>,
+    pub target_type: Arc<SqlTypeDefinition>,
+}
+
+///Skips the first offset rows then stops reading the source once count rows have been passed on
+pub struct LimitPlan {
+    pub source: Arc<Plan>,
+    pub offset: usize,
+    pub count: Option<usize>,
+}
+
 pub struct ModifyTablePlan {
     pub table: Arc<Table>,
     pub source: Arc<Plan>,
 }
+
+///Checks the condition against every pair of rows, works for any condition.
+///Outer joins pad the missing side with NULLs, the widths say how many.
+pub struct NestedLoopJoinPlan {
+    pub join_type: JoinType,
+    pub left: Arc<Plan>,
+    pub right: Arc<Plan>,
+    ///Evaluated against the merged row, None matches every pair
+    pub condition: Option<Arc<Expression>>,
+    pub left_width: usize,
+    pub right_width: usize,
+}
+
+///Computes each output column from the source row
+pub struct ProjectionPlan {
+    pub source: Arc<Plan>,
+    pub target_entries: Vec<Arc<Expression>>,
+}
+
+///Orders the source rows by the keys, spilling to disk if they don't fit in the sort memory.
+///The column types are needed to read spilled rows back.
+pub struct SortPlan {
+    pub source: Arc<Plan>,
+    pub keys: Vec<SortKey>,
+    pub columns: Vec<BaseSqlTypesMapper>,
+}
+
+///Writes a new version of every visible row matching the qualification with the assignments applied
+pub struct UpdatePlan {
+    pub table: Arc<Table>,
+    pub qualification: Option<Arc<Expression>>,
+    pub assignments: Vec<(usize, Arc<Expression>)>,
+}
@@ -1,7 +1,12 @@
-use super::SqlTuple;
+use super::types::SqlTypeDefinition;
+use super::{ColumnOrigin, CommandType, SqlTuple};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct QueryResult {
-    pub columns: Vec<String>,
+    pub command_type: CommandType,
+    pub columns: SqlTypeDefinition,
+    pub origins: Vec<ColumnOrigin>,
     pub rows: Vec<SqlTuple>,
+    //Rows returned or affected, what the command tag reports
+    pub row_count: usize,
 }
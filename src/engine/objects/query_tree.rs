@@ -1,6 +1,9 @@
 //! Is the result of the parse tree post validation
 //! See here: https://www.postgresql.org/docs/current/querytree.html
 use super::types::SqlTypeDefinition;
+use super::Aggregate;
+use super::ColumnOrigin;
+use super::Expression;
 use super::SqlTuple;
 use super::Table;
 use std::sync::Arc;
@@ -18,27 +21,55 @@ pub struct QueryTree {
     //the target list of columns to be affected
     pub targets: Arc<SqlTypeDefinition>,
 
+    //for selects, an expression per target computing it from the merged row of the range tables
+    pub target_entries: Vec<Arc<Expression>>,
+
+    //for selects, the table column each target reads if it is a plain column reference
+    pub target_origins: Vec<ColumnOrigin>,
+
     //These are tables being used as inputs for the query.
     //They could be a table, view, static data, or even a sub query.
     //How to represent some of this is TBD
     pub range_tables: Vec<RangeRelation>,
 
-    //the qualification - Don't really understand this yet
-    //pub qualification: Vec<WhereEntry>,
+    //the qualification is the WHERE clause, rows are only returned if it evaluates to true
+    pub qualification: Option<Arc<Expression>>,
+
+    //for updates, the column offsets to set and the expressions computing them from the old row
+    pub assignments: Vec<(usize, Arc<Expression>)>,
+
+    //the join tree is to relate entries in the range tables to each other. Joins are left deep,
+    //entry i joins range table i + 1 onto everything before it, the condition sees the merged row
+    pub joins: Vec<(JoinType, Option<Arc<Expression>>)>,
+
+    //GROUP BY keys over the merged row and the aggregates computed for each group. Once grouped,
+    //the target entries, having and sorts are evaluated against a row per group, which is the
+    //key values followed by the aggregate results
+    pub group_by: Vec<Arc<Expression>>,
+    pub aggregates: Vec<Arc<Aggregate>>,
+    pub having: Option<Arc<Expression>>,
+
+    //the ORDER BY, each key is evaluated against the merged row like the qualification
+    pub sorts: Vec<SortKey>,
 
-    //the join tree is to relate entries in the range tables to each other
-    pub joins: Vec<(JoinType, RangeRelation, RangeRelation)>,
-    //the others
-    //pub sorts: Vec<(SortType, TargetEntry)>,
+    //LIMIT and OFFSET, None means every row
+    pub limit: Option<usize>,
+    pub offset: usize,
 }
 
-#[derive(Clone, Copy, Debug)]
+/// Only the first four are planned, the rest are here so every statement has a command tag
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CommandType {
     Select,
     Insert,
     Update,
     Delete,
-    Utility,
+    CreateTable,
+    CreateType,
+    DropType,
+    Begin,
+    Commit,
+    Rollback,
 }
 
 #[derive(Clone, Debug)]
@@ -64,10 +95,7 @@ pub struct RangeRelationTable {
 //    Parameter(Attribute),
 //}
 
-#[derive(Clone, Debug)]
-pub enum WhereEntry {}
-
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum JoinType {
     Inner,
     OuterLeft,
@@ -75,8 +103,16 @@ pub enum JoinType {
     OuterFull,
 }
 
-//#[derive(Clone, Copy, Debug)]
-//pub enum SortType {
-//    Ascending,
-//   Descending,
-//}
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortType {
+    Ascending,
+    Descending,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortKey {
+    pub expression: Arc<Expression>,
+    pub sort_type: SortType,
+    //NULLs sort as larger than any value unless asked otherwise, same as postgres
+    pub nulls_first: bool,
+}
@@ -0,0 +1,134 @@
+//! Functions computed from their arguments one row at a time, see Aggregate for ones over a group.
+//! See here: https://www.postgresql.org/docs/current/functions-json.html#FUNCTIONS-JSON-CREATION-TABLE
+//! and here: https://www.postgresql.org/docs/current/functions-array.html
+use super::types::{array, BaseSqlTypes, BaseSqlTypesMapper, Jsonb, JsonbError};
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScalarFunction {
+    ///Alternating keys and values, jsonb_build_object('a', 1, 'b', 2)
+    JsonbBuildObject,
+    ///The length of a dimension of an array, array_length(a, 1)
+    ArrayLength,
+}
+
+impl ScalarFunction {
+    pub fn from_name(name: &str) -> Option<ScalarFunction> {
+        match name.to_lowercase().as_str() {
+            "jsonb_build_object" => Some(ScalarFunction::JsonbBuildObject),
+            "array_length" => Some(ScalarFunction::ArrayLength),
+            _ => None,
+        }
+    }
+
+    pub fn sql_type(&self) -> BaseSqlTypesMapper {
+        match self {
+            ScalarFunction::JsonbBuildObject => BaseSqlTypesMapper::Jsonb,
+            ScalarFunction::ArrayLength => BaseSqlTypesMapper::Integer,
+        }
+    }
+
+    /// Unlike operators a NULL argument doesn't make the result NULL, each function decides
+    pub fn evaluate(
+        &self,
+        args: Vec<Option<BaseSqlTypes>>,
+    ) -> Result<Option<BaseSqlTypes>, ScalarFunctionError> {
+        match self {
+            ScalarFunction::JsonbBuildObject => {
+                let mut pairs = Vec::with_capacity(args.len() / 2);
+                let mut args = args.into_iter().enumerate();
+                while let Some((i, key)) = args.next() {
+                    let key = key.ok_or(ScalarFunctionError::NullKey(*self, i + 1))?;
+                    let value = args.next().and_then(|(_, v)| v);
+                    pairs.push((key.to_string(), Jsonb::from_value(value.as_ref())?));
+                }
+                Ok(Some(BaseSqlTypes::Jsonb(Jsonb::object(pairs))))
+            }
+            //Same as postgres an empty array has no dimensions so any length is NULL
+            ScalarFunction::ArrayLength => match args.as_slice() {
+                [Some(BaseSqlTypes::Array(items)), Some(BaseSqlTypes::Integer(dimension))] => {
+                    let lengths = array::dimensions(items).unwrap_or_default();
+                    let length = dimension
+                        .checked_sub(1)
+                        .and_then(|d| usize::try_from(d).ok())
+                        .filter(|_| !items.is_empty())
+                        .and_then(|d| lengths.get(d));
+                    match length {
+                        Some(l) => Ok(Some(BaseSqlTypes::Integer(i32::try_from(*l)?))),
+                        None => Ok(None),
+                    }
+                }
+                [Some(v), Some(_)] if !matches!(v, BaseSqlTypes::Array(_)) => {
+                    Err(ScalarFunctionError::InvalidArgument(*self, v.clone()))
+                }
+                _ => Ok(None),
+            },
+        }
+    }
+}
+
+impl Display for ScalarFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ScalarFunction::JsonbBuildObject => write!(f, "jsonb_build_object"),
+            ScalarFunction::ArrayLength => write!(f, "array_length"),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ScalarFunctionError {
+    #[error("Function {0} does not take {1}")]
+    InvalidArgument(ScalarFunction, BaseSqlTypes),
+    #[error(transparent)]
+    JsonbError(#[from] JsonbError),
+    #[error("Argument {1} of {0} can not be null")]
+    NullKey(ScalarFunction, usize),
+    #[error(transparent)]
+    TryFromIntError(#[from] std::num::TryFromIntError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jsonb_build_object() -> Result<(), Box<dyn std::error::Error>> {
+        let text = |s: &str| Some(BaseSqlTypes::Text(s.to_string()));
+        let result = ScalarFunction::JsonbBuildObject.evaluate(vec![
+            text("b"),
+            Some(BaseSqlTypes::Integer(1)),
+            text("a"),
+            None,
+        ])?;
+        assert_eq!(
+            result,
+            Some(BaseSqlTypes::Jsonb(r#"{"a": null, "b": 1}"#.parse()?))
+        );
+
+        assert!(matches!(
+            ScalarFunction::JsonbBuildObject.evaluate(vec![None, text("a")]),
+            Err(ScalarFunctionError::NullKey(_, 1))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_length() -> Result<(), Box<dyn std::error::Error>> {
+        let items = array::parse_array("{{1,2,3},{4,5,6}}", &BaseSqlTypesMapper::Integer)?;
+        let length = |items: &[Option<BaseSqlTypes>], d: i32| {
+            ScalarFunction::ArrayLength.evaluate(vec![
+                Some(BaseSqlTypes::Array(items.to_vec())),
+                Some(BaseSqlTypes::Integer(d)),
+            ])
+        };
+        assert_eq!(length(&items, 1)?, Some(BaseSqlTypes::Integer(2)));
+        assert_eq!(length(&items, 2)?, Some(BaseSqlTypes::Integer(3)));
+        assert_eq!(length(&items, 3)?, None);
+        assert_eq!(length(&items, 0)?, None);
+        assert_eq!(length(&[], 1)?, None);
+        Ok(())
+    }
+}
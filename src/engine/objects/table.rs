@@ -9,6 +9,7 @@ use uuid::Uuid;
 #[derive(Clone, Debug, PartialEq)]
 pub struct Table {
     pub id: Uuid,
+    pub oid: u32, //What clients see in RowDescription
     pub name: String,
     pub attributes: Vec<Attribute>,
     pub constraints: Vec<Constraint>,
@@ -19,6 +20,7 @@ pub struct Table {
 impl Table {
     pub fn new(
         id: Uuid,
+        oid: u32,
         name: String,
         attributes: Vec<Attribute>,
         constraints: Vec<Constraint>,
@@ -27,6 +29,7 @@ impl Table {
         let sql_type = Arc::new(SqlTypeDefinition::new(&attributes));
         Table {
             id,
+            oid,
             name,
             attributes,
             constraints,
@@ -3,8 +3,37 @@ pub use base_sql_types::BaseSqlTypes;
 pub use base_sql_types::BaseSqlTypesError;
 pub use base_sql_types::BaseSqlTypesMapper;
 
+pub mod array;
+
+pub mod bytea;
+
+pub mod date_time;
+
+mod enum_type;
+pub use enum_type::EnumLabel;
+pub use enum_type::EnumType;
+
+mod interval;
+pub use interval::Interval;
+pub use interval::IntervalError;
+
+mod jsonb;
+pub use jsonb::Jsonb;
+pub use jsonb::JsonbError;
+
+mod numeric;
+pub use numeric::Numeric;
+pub use numeric::NumericError;
+
 mod parse_type;
 pub use parse_type::parse_type;
 
+mod sql_float;
+pub use sql_float::SqlFloat;
+
 mod sql_type_definition;
 pub use sql_type_definition::SqlTypeDefinition;
+
+mod type_modifier;
+pub use type_modifier::TypeModifier;
+pub use type_modifier::TypeModifierError;
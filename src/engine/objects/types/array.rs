@@ -0,0 +1,297 @@
+//! Arrays and their text format, see here: https://www.postgresql.org/docs/current/arrays.html
+//!
+//! A multi-dimensional array is stored as an array of sub-arrays that all have the same shape.
+//! Same as postgres the column type only names the element type, so int[] and int[][] are the same.
+use super::{BaseSqlTypes, BaseSqlTypesError, BaseSqlTypesMapper};
+use std::convert::TryFrom;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Same limit as postgres' MAXDIM
+const MAX_DIMENSIONS: usize = 6;
+
+pub fn parse_array(
+    input: &str,
+    element: &BaseSqlTypesMapper,
+) -> Result<Vec<Option<BaseSqlTypes>>, BaseSqlTypesError> {
+    let mut parser = ArrayParser {
+        input,
+        chars: input.chars().peekable(),
+        element,
+    };
+    parser.skip_whitespace();
+    let items = parser.array(1)?;
+    parser.skip_whitespace();
+    if parser.chars.peek().is_some() || dimensions(&items).is_none() {
+        return Err(parser.invalid());
+    }
+    Ok(items)
+}
+
+/// Elements are quoted when they would otherwise be read back differently, such as the text NULL
+pub fn format_array(items: &[Option<BaseSqlTypes>]) -> String {
+    let mut output = String::from("{");
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            output.push(',');
+        }
+        match item {
+            None => output.push_str("NULL"),
+            Some(BaseSqlTypes::Array(sub)) => output.push_str(&format_array(sub)),
+            Some(value) => {
+                let text = value.to_string();
+                let quote = text.is_empty()
+                    || text.eq_ignore_ascii_case("null")
+                    || text
+                        .chars()
+                        .any(|c| matches!(c, '{' | '}' | ',' | '"' | '\\') || c.is_whitespace());
+                if !quote {
+                    output.push_str(&text);
+                    continue;
+                }
+                output.push('"');
+                for c in text.chars() {
+                    if c == '"' || c == '\\' {
+                        output.push('\\');
+                    }
+                    output.push(c);
+                }
+                output.push('"');
+            }
+        }
+    }
+    output.push('}');
+    output
+}
+
+/// The length of each dimension, None if the sub-arrays don't all have the same shape
+pub fn dimensions(items: &[Option<BaseSqlTypes>]) -> Option<Vec<usize>> {
+    let mut inner: Option<Vec<usize>> = None;
+    let mut has_elements = false;
+    for item in items {
+        match item {
+            Some(BaseSqlTypes::Array(sub)) => {
+                let shape = dimensions(sub)?;
+                match &inner {
+                    _ if has_elements => return None,
+                    Some(s) if *s != shape => return None,
+                    Some(_) => {}
+                    None => inner = Some(shape),
+                }
+            }
+            _ if inner.is_some() => return None,
+            _ => has_elements = true,
+        }
+    }
+    let mut dims = vec![items.len()];
+    dims.extend(inner.unwrap_or_default());
+    Some(dims)
+}
+
+/// Every element in storage order, the sub-arrays of a multi-dimensional array are flattened
+pub fn elements(items: &[Option<BaseSqlTypes>]) -> Vec<Option<&BaseSqlTypes>> {
+    let mut output = Vec::with_capacity(items.len());
+    for item in items {
+        match item {
+            Some(BaseSqlTypes::Array(sub)) => output.extend(elements(sub)),
+            _ => output.push(item.as_ref()),
+        }
+    }
+    output
+}
+
+/// Subscripts start at one, anything outside the array or fewer subscripts than dimensions is NULL
+pub fn subscript<'a>(
+    items: &'a [Option<BaseSqlTypes>],
+    indexes: &[i64],
+) -> Option<&'a BaseSqlTypes> {
+    let (first, rest) = indexes.split_first()?;
+    let item = items
+        .get(usize::try_from(first.checked_sub(1)?).ok()?)?
+        .as_ref()?;
+    match (item, rest.is_empty()) {
+        (BaseSqlTypes::Array(sub), false) => subscript(sub, rest),
+        (BaseSqlTypes::Array(_), true) | (_, false) => None,
+        (value, true) => Some(value),
+    }
+}
+
+/// The @> operator, NULL elements are never equal to anything
+pub fn contains(left: &[Option<BaseSqlTypes>], right: &[Option<BaseSqlTypes>]) -> bool {
+    let left = elements(left);
+    elements(right)
+        .into_iter()
+        .all(|r| r.is_some() && left.contains(&r))
+}
+
+/// The && operator, true if any non NULL element is in both
+pub fn overlaps(left: &[Option<BaseSqlTypes>], right: &[Option<BaseSqlTypes>]) -> bool {
+    let left = elements(left);
+    elements(right)
+        .into_iter()
+        .any(|r| r.is_some() && left.contains(&r))
+}
+
+struct ArrayParser<'a> {
+    input: &'a str,
+    chars: Peekable<Chars<'a>>,
+    element: &'a BaseSqlTypesMapper,
+}
+
+impl<'a> ArrayParser<'a> {
+    fn invalid(&self) -> BaseSqlTypesError {
+        BaseSqlTypesError::InvalidArray(self.input.to_string())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    /// Dimension is how many braces deep this array is, counting its own
+    fn array(&mut self, dimension: usize) -> Result<Vec<Option<BaseSqlTypes>>, BaseSqlTypesError> {
+        if dimension > MAX_DIMENSIONS || self.chars.next_if_eq(&'{').is_none() {
+            return Err(self.invalid());
+        }
+        let mut items = vec![];
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'}').is_some() {
+            return Ok(items);
+        }
+        loop {
+            self.skip_whitespace();
+            let item = match self.chars.peek() {
+                Some('{') => Some(BaseSqlTypes::Array(self.array(dimension + 1)?)),
+                Some('"') => {
+                    let text = self.quoted()?;
+                    Some(BaseSqlTypes::parse(self.element.clone(), &text)?)
+                }
+                _ => match self.unquoted()? {
+                    (text, false) if text.eq_ignore_ascii_case("null") => None,
+                    (text, _) => Some(BaseSqlTypes::parse(self.element.clone(), &text)?),
+                },
+            };
+            items.push(item);
+
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(items),
+                _ => return Err(self.invalid()),
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, BaseSqlTypesError> {
+        self.chars.next();
+        let mut text = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => text.push(self.chars.next().ok_or_else(|| self.invalid())?),
+                Some(c) => text.push(c),
+                None => return Err(self.invalid()),
+            }
+        }
+    }
+
+    /// Also returns if anything was escaped, "NULL" with an escape in it is the text and not a NULL
+    fn unquoted(&mut self) -> Result<(String, bool), BaseSqlTypesError> {
+        let mut text = String::new();
+        let mut escaped = false;
+        while let Some(c) = self.chars.next_if(|c| *c != ',' && *c != '}') {
+            match c {
+                '\\' => {
+                    escaped = true;
+                    text.push(self.chars.next().ok_or_else(|| self.invalid())?);
+                }
+                '{' | '"' => return Err(self.invalid()),
+                c => text.push(c),
+            }
+        }
+        let text = text.trim_end().to_string();
+        if text.is_empty() {
+            return Err(self.invalid());
+        }
+        Ok((text, escaped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn integers(values: &[Option<i32>]) -> Vec<Option<BaseSqlTypes>> {
+        values
+            .iter()
+            .map(|v| v.map(BaseSqlTypes::Integer))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_and_format() -> Result<(), Box<dyn std::error::Error>> {
+        let items = parse_array(" { 1, NULL ,3 } ", &BaseSqlTypesMapper::Integer)?;
+        assert_eq!(items, integers(&[Some(1), None, Some(3)]));
+        assert_eq!(format_array(&items), "{1,NULL,3}");
+
+        let items = parse_array("{{1,2},{3,4}}", &BaseSqlTypesMapper::Integer)?;
+        assert_eq!(dimensions(&items), Some(vec![2, 2]));
+        assert_eq!(format_array(&items), "{{1,2},{3,4}}");
+
+        let items = parse_array(
+            r#"{"a b", "", "NULL", \NULL, c\,d, "e\"f"}"#,
+            &BaseSqlTypesMapper::Text,
+        )?;
+        let text: Vec<String> = items.iter().flatten().map(|t| t.to_string()).collect();
+        assert_eq!(text, vec!["a b", "", "NULL", "NULL", "c,d", "e\"f"]);
+        assert_eq!(
+            format_array(&items),
+            r#"{"a b","","NULL","NULL","c,d","e\"f"}"#
+        );
+
+        assert_eq!(parse_array("{}", &BaseSqlTypesMapper::Integer)?, vec![]);
+        let nested = |depth: usize| format!("{}1{}", "{".repeat(depth), "}".repeat(depth));
+        let items = parse_array(&nested(MAX_DIMENSIONS), &BaseSqlTypesMapper::Integer)?;
+        assert_eq!(dimensions(&items), Some(vec![1; MAX_DIMENSIONS]));
+        assert!(matches!(
+            parse_array(&nested(MAX_DIMENSIONS + 1), &BaseSqlTypesMapper::Integer),
+            Err(BaseSqlTypesError::InvalidArray(_))
+        ));
+        assert!(parse_array(&nested(100_000), &BaseSqlTypesMapper::Integer).is_err());
+        for bad in [
+            "",
+            "1,2",
+            "{1,2",
+            "{1,,2}",
+            "{{1,2},{3}}",
+            "{{1},2}",
+            "{1} x",
+            "{a}",
+        ] {
+            assert!(
+                parse_array(bad, &BaseSqlTypesMapper::Integer).is_err(),
+                "{} should not parse",
+                bad
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_operations() -> Result<(), Box<dyn std::error::Error>> {
+        let items = parse_array("{{1,2,3},{4,NULL,6}}", &BaseSqlTypesMapper::Integer)?;
+        assert_eq!(subscript(&items, &[2, 3]), Some(&BaseSqlTypes::Integer(6)));
+        assert_eq!(subscript(&items, &[2, 2]), None);
+        assert_eq!(subscript(&items, &[1]), None);
+        assert_eq!(subscript(&items, &[0, 1]), None);
+        assert_eq!(subscript(&items, &[1, 1, 1]), None);
+        assert_eq!(elements(&items).len(), 6);
+
+        let right = integers(&[Some(6), Some(1)]);
+        assert!(contains(&items, &right));
+        assert!(!contains(&items, &integers(&[None])));
+        assert!(!contains(&right, &items));
+        assert!(overlaps(&right, &integers(&[Some(5), Some(6)])));
+        assert!(!overlaps(&items, &integers(&[None, Some(7)])));
+        Ok(())
+    }
+}
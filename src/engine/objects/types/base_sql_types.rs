@@ -2,28 +2,53 @@ use crate::engine::io::{
     encode_size, expected_encoded_size, parse_size, SelfEncodedSize, SizeError,
 };
 use bytes::{Buf, BufMut};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use nom::{
+    combinator::all_consuming,
     error::{convert_error, VerboseError},
     Finish,
 };
 use std::{
+    convert::TryFrom,
     fmt::{self, Display, Formatter},
     mem::size_of,
-    num::ParseIntError,
+    num::{IntErrorKind, ParseFloatError, ParseIntError},
     str::{FromStr, ParseBoolError, Utf8Error},
     sync::Arc,
 };
 use thiserror::Error;
 use uuid::Uuid;
 
-use super::parse_type;
+use super::{
+    array, bytea, date_time, parse_type, EnumLabel, EnumType, Interval, IntervalError, Jsonb,
+    JsonbError, Numeric, NumericError, SqlFloat,
+};
+
+/// Each array element starts with one of these
+const ARRAY_ELEMENT_NULL: u8 = 0;
+const ARRAY_ELEMENT_VALUE: u8 = 1;
+const ARRAY_ELEMENT_ARRAY: u8 = 2;
 
-#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum BaseSqlTypes {
     //TODO consider making it an Arc since I don't mutate just copy
-    Array(Vec<BaseSqlTypes>),
+    ///A multi-dimensional array has arrays as its elements, see array::dimensions
+    Array(Vec<Option<BaseSqlTypes>>),
     Bool(bool),
-    Integer(u32),
+    Smallint(i16),
+    Integer(i32),
+    Bigint(i64),
+    Real(SqlFloat<f32>),
+    Double(SqlFloat<f64>),
+    Numeric(Numeric),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Timestamp(NaiveDateTime),
+    TimestampTz(DateTime<Utc>),
+    Interval(Interval),
+    Bytea(Vec<u8>),
+    Jsonb(Jsonb),
+    Enum(EnumLabel),
     //TODO consider making it an Arc since I don't mutate just copy
     Text(String),
     Uuid(uuid::Uuid),
@@ -36,7 +61,21 @@ pub enum BaseSqlTypes {
 pub enum BaseSqlTypesMapper {
     Array(Arc<BaseSqlTypesMapper>),
     Bool,
+    Smallint,
     Integer,
+    Bigint,
+    Real,
+    Double,
+    Numeric,
+    Date,
+    Time,
+    Timestamp,
+    TimestampTz,
+    Interval,
+    Bytea,
+    Jsonb,
+    ///User defined, the labels come from pg_enum
+    Enum(Arc<EnumType>),
     Text,
     Uuid,
 }
@@ -49,10 +88,18 @@ impl BaseSqlTypes {
         match target_type {
             BaseSqlTypesMapper::Array(a) => {
                 let count = parse_size(buffer)?;
-                let mut items = Vec::with_capacity(count);
+                let mut items = Vec::with_capacity(count.min(buffer.remaining()));
 
                 for _ in 0..count {
-                    items.push(Self::deserialize(a, buffer)?);
+                    if !buffer.has_remaining() {
+                        return Err(BaseSqlTypesError::InsufficentBuffer(1, 0));
+                    }
+                    let item = match buffer.get_u8() {
+                        ARRAY_ELEMENT_NULL => None,
+                        ARRAY_ELEMENT_ARRAY => Some(Self::deserialize(target_type, buffer)?),
+                        _ => Some(Self::deserialize(a, buffer)?),
+                    };
+                    items.push(item);
                 }
                 Ok(BaseSqlTypes::Array(items))
             }
@@ -71,17 +118,111 @@ impl BaseSqlTypes {
 
                 Ok(value)
             }
+            BaseSqlTypesMapper::Smallint => {
+                if buffer.remaining() < size_of::<i16>() {
+                    return Err(BaseSqlTypesError::InsufficentBuffer(
+                        size_of::<i16>(),
+                        buffer.remaining(),
+                    ));
+                }
+
+                let dest = buffer.get_i16_le();
+                Ok(BaseSqlTypes::Smallint(dest))
+            }
             BaseSqlTypesMapper::Integer => {
-                if buffer.remaining() < size_of::<u32>() {
+                if buffer.remaining() < size_of::<i32>() {
                     return Err(BaseSqlTypesError::InsufficentBuffer(
-                        size_of::<u32>(),
+                        size_of::<i32>(),
                         buffer.remaining(),
                     ));
                 }
 
-                let dest = buffer.get_u32_le();
+                let dest = buffer.get_i32_le();
                 Ok(BaseSqlTypes::Integer(dest))
             }
+            BaseSqlTypesMapper::Bigint => {
+                if buffer.remaining() < size_of::<i64>() {
+                    return Err(BaseSqlTypesError::InsufficentBuffer(
+                        size_of::<i64>(),
+                        buffer.remaining(),
+                    ));
+                }
+
+                let dest = buffer.get_i64_le();
+                Ok(BaseSqlTypes::Bigint(dest))
+            }
+            BaseSqlTypesMapper::Real => {
+                if buffer.remaining() < size_of::<f32>() {
+                    return Err(BaseSqlTypesError::InsufficentBuffer(
+                        size_of::<f32>(),
+                        buffer.remaining(),
+                    ));
+                }
+
+                let dest = buffer.get_f32_le();
+                Ok(BaseSqlTypes::Real(SqlFloat(dest)))
+            }
+            BaseSqlTypesMapper::Double => {
+                if buffer.remaining() < size_of::<f64>() {
+                    return Err(BaseSqlTypesError::InsufficentBuffer(
+                        size_of::<f64>(),
+                        buffer.remaining(),
+                    ));
+                }
+
+                let dest = buffer.get_f64_le();
+                Ok(BaseSqlTypes::Double(SqlFloat(dest)))
+            }
+            BaseSqlTypesMapper::Numeric => Ok(BaseSqlTypes::Numeric(Numeric::deserialize(buffer)?)),
+            BaseSqlTypesMapper::Date => {
+                if buffer.remaining() < size_of::<i32>() {
+                    return Err(BaseSqlTypesError::InsufficentBuffer(
+                        size_of::<i32>(),
+                        buffer.remaining(),
+                    ));
+                }
+
+                let dest = buffer.get_i32_le();
+                NaiveDate::from_num_days_from_ce_opt(dest)
+                    .map(BaseSqlTypes::Date)
+                    .ok_or_else(|| BaseSqlTypesError::DateTimeOutOfRange(target_type.clone()))
+            }
+            BaseSqlTypesMapper::Time
+            | BaseSqlTypesMapper::Timestamp
+            | BaseSqlTypesMapper::TimestampTz => {
+                if buffer.remaining() < size_of::<i64>() {
+                    return Err(BaseSqlTypesError::InsufficentBuffer(
+                        size_of::<i64>(),
+                        buffer.remaining(),
+                    ));
+                }
+
+                let dest = buffer.get_i64_le();
+                let value = match target_type {
+                    BaseSqlTypesMapper::Time => {
+                        date_time::micros_to_time(dest).map(BaseSqlTypes::Time)
+                    }
+                    BaseSqlTypesMapper::Timestamp => {
+                        date_time::micros_to_timestamp(dest).map(BaseSqlTypes::Timestamp)
+                    }
+                    _ => date_time::micros_to_timestamp(dest)
+                        .map(|t| BaseSqlTypes::TimestampTz(t.and_utc())),
+                };
+                value.ok_or_else(|| BaseSqlTypesError::DateTimeOutOfRange(target_type.clone()))
+            }
+            BaseSqlTypesMapper::Interval => {
+                Ok(BaseSqlTypes::Interval(Interval::deserialize(buffer)?))
+            }
+            BaseSqlTypesMapper::Jsonb => Ok(BaseSqlTypes::Jsonb(Jsonb::deserialize(buffer)?)),
+            BaseSqlTypesMapper::Enum(e) => {
+                if buffer.remaining() < size_of::<u32>() {
+                    return Err(BaseSqlTypesError::InsufficentBuffer(
+                        size_of::<u32>(),
+                        buffer.remaining(),
+                    ));
+                }
+                Ok(BaseSqlTypes::Enum(e.get(buffer.get_u32_le())?))
+            }
             BaseSqlTypesMapper::Uuid => {
                 if buffer.remaining() < size_of::<u128>() {
                     return Err(BaseSqlTypesError::InsufficentBuffer(
@@ -92,7 +233,7 @@ impl BaseSqlTypes {
                 let dest = buffer.get_u128_le();
                 Ok(BaseSqlTypes::Uuid(uuid::Uuid::from_u128(dest)))
             }
-            BaseSqlTypesMapper::Text => {
+            BaseSqlTypesMapper::Bytea | BaseSqlTypesMapper::Text => {
                 let length = parse_size(buffer)?;
 
                 if length > buffer.remaining() {
@@ -103,6 +244,9 @@ impl BaseSqlTypes {
                 }
 
                 let value_buff = buffer.copy_to_bytes(length);
+                if *target_type == BaseSqlTypesMapper::Bytea {
+                    return Ok(BaseSqlTypes::Bytea(value_buff.to_vec()));
+                }
                 let value_str = std::str::from_utf8(&value_buff)?;
 
                 Ok(BaseSqlTypes::Text(value_str.to_string()))
@@ -112,22 +256,104 @@ impl BaseSqlTypes {
 
     pub fn parse(target_type: BaseSqlTypesMapper, buffer: &str) -> Result<Self, BaseSqlTypesError> {
         match target_type {
-            //TODO Need to fix this to support array literal parsing
-            //See here: https://www.postgresql.org/docs/current/arrays.html
-            BaseSqlTypesMapper::Array(a) => todo!("Bed time, need to fix!"),
+            BaseSqlTypesMapper::Array(a) => {
+                Ok(BaseSqlTypes::Array(array::parse_array(buffer, &a)?))
+            }
             BaseSqlTypesMapper::Bool => Ok(BaseSqlTypes::Bool(buffer.parse::<bool>()?)),
-            BaseSqlTypesMapper::Integer => Ok(BaseSqlTypes::Integer(buffer.parse::<u32>()?)),
+            BaseSqlTypesMapper::Smallint => Ok(BaseSqlTypes::Smallint(Self::parse_integer(
+                &target_type,
+                buffer,
+            )?)),
+            BaseSqlTypesMapper::Integer => Ok(BaseSqlTypes::Integer(Self::parse_integer(
+                &target_type,
+                buffer,
+            )?)),
+            BaseSqlTypesMapper::Bigint => Ok(BaseSqlTypes::Bigint(Self::parse_integer(
+                &target_type,
+                buffer,
+            )?)),
+            BaseSqlTypesMapper::Real => {
+                let value = Self::parse_float(&target_type, buffer)?;
+                let narrowed = value as f32;
+                if narrowed.is_infinite() && !value.is_infinite() {
+                    return Err(BaseSqlTypesError::NumericOutOfRange(target_type));
+                }
+                Ok(BaseSqlTypes::Real(SqlFloat(narrowed)))
+            }
+            BaseSqlTypesMapper::Double => Ok(BaseSqlTypes::Double(SqlFloat(Self::parse_float(
+                &target_type,
+                buffer,
+            )?))),
+            BaseSqlTypesMapper::Numeric => Ok(BaseSqlTypes::Numeric(buffer.parse::<Numeric>()?)),
+            BaseSqlTypesMapper::Date => date_time::parse_date(buffer)
+                .map(BaseSqlTypes::Date)
+                .ok_or_else(|| BaseSqlTypesError::InvalidDateTime(target_type, buffer.to_string())),
+            BaseSqlTypesMapper::Time => date_time::parse_time(buffer)
+                .map(BaseSqlTypes::Time)
+                .ok_or_else(|| BaseSqlTypesError::InvalidDateTime(target_type, buffer.to_string())),
+            BaseSqlTypesMapper::Timestamp => date_time::parse_timestamp(buffer)
+                .map(BaseSqlTypes::Timestamp)
+                .ok_or_else(|| BaseSqlTypesError::InvalidDateTime(target_type, buffer.to_string())),
+            BaseSqlTypesMapper::TimestampTz => date_time::parse_timestamptz(buffer)
+                .map(BaseSqlTypes::TimestampTz)
+                .ok_or_else(|| BaseSqlTypesError::InvalidDateTime(target_type, buffer.to_string())),
+            BaseSqlTypesMapper::Interval => Ok(BaseSqlTypes::Interval(buffer.parse::<Interval>()?)),
+            BaseSqlTypesMapper::Bytea => bytea::parse_bytea(buffer)
+                .map(BaseSqlTypes::Bytea)
+                .ok_or_else(|| BaseSqlTypesError::InvalidBytea(buffer.to_string())),
+            BaseSqlTypesMapper::Jsonb => Ok(BaseSqlTypes::Jsonb(buffer.parse::<Jsonb>()?)),
+            BaseSqlTypesMapper::Enum(e) => Ok(BaseSqlTypes::Enum(e.parse(buffer)?)),
             BaseSqlTypesMapper::Uuid => Ok(BaseSqlTypes::Uuid(uuid::Uuid::parse_str(buffer)?)),
             BaseSqlTypesMapper::Text => Ok(BaseSqlTypes::Text(buffer.to_string())),
         }
     }
 
+    /// Overflowing the width of the column is reported as out of range instead of a parse failure
+    fn parse_integer<T: FromStr<Err = ParseIntError>>(
+        target_type: &BaseSqlTypesMapper,
+        buffer: &str,
+    ) -> Result<T, BaseSqlTypesError> {
+        buffer.parse::<T>().map_err(|e| match e.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                BaseSqlTypesError::NumericOutOfRange(target_type.clone())
+            }
+            _ => BaseSqlTypesError::InvalidInt(e),
+        })
+    }
+
+    /// Accepts the postgres spellings of the special values, a literal that is too large to
+    /// represent is out of range rather than silently becoming infinity.
+    fn parse_float(
+        target_type: &BaseSqlTypesMapper,
+        buffer: &str,
+    ) -> Result<f64, BaseSqlTypesError> {
+        let trimmed = buffer.trim();
+        match trimmed.to_lowercase().as_str() {
+            "nan" => return Ok(f64::NAN),
+            "infinity" | "+infinity" | "inf" | "+inf" => return Ok(f64::INFINITY),
+            "-infinity" | "-inf" => return Ok(f64::NEG_INFINITY),
+            _ => {}
+        }
+        let value = trimmed.parse::<f64>()?;
+        if value.is_infinite() {
+            return Err(BaseSqlTypesError::NumericOutOfRange(target_type.clone()));
+        }
+        Ok(value)
+    }
+
     pub fn serialize(&self, buffer: &mut impl BufMut) {
         match *self {
             Self::Array(ref value) => {
                 encode_size(buffer, value.len());
                 for v in value {
-                    v.serialize(buffer);
+                    match v {
+                        None => buffer.put_u8(ARRAY_ELEMENT_NULL),
+                        Some(Self::Array(_)) => buffer.put_u8(ARRAY_ELEMENT_ARRAY),
+                        Some(_) => buffer.put_u8(ARRAY_ELEMENT_VALUE),
+                    }
+                    if let Some(v) = v {
+                        v.serialize(buffer);
+                    }
                 }
             }
             Self::Bool(ref value) => {
@@ -137,8 +363,48 @@ impl BaseSqlTypes {
                     buffer.put_u8(0x0);
                 }
             }
+            Self::Smallint(ref value) => {
+                buffer.put_i16_le(*value);
+            }
             Self::Integer(ref value) => {
-                buffer.put_u32_le(*value);
+                buffer.put_i32_le(*value);
+            }
+            Self::Bigint(ref value) => {
+                buffer.put_i64_le(*value);
+            }
+            Self::Real(ref value) => {
+                buffer.put_f32_le(value.0);
+            }
+            Self::Double(ref value) => {
+                buffer.put_f64_le(value.0);
+            }
+            Self::Numeric(ref value) => {
+                value.serialize(buffer);
+            }
+            Self::Date(ref value) => {
+                buffer.put_i32_le(value.num_days_from_ce());
+            }
+            Self::Time(ref value) => {
+                buffer.put_i64_le(date_time::time_to_micros(value));
+            }
+            Self::Timestamp(ref value) => {
+                buffer.put_i64_le(date_time::timestamp_to_micros(value));
+            }
+            Self::TimestampTz(ref value) => {
+                buffer.put_i64_le(value.timestamp_micros());
+            }
+            Self::Interval(ref value) => {
+                value.serialize(buffer);
+            }
+            Self::Bytea(ref value) => {
+                encode_size(buffer, value.len());
+                buffer.put_slice(value);
+            }
+            Self::Jsonb(ref value) => {
+                value.serialize(buffer);
+            }
+            Self::Enum(ref value) => {
+                buffer.put_u32_le(value.position);
             }
             Self::Uuid(ref value) => {
                 buffer.put_u128_le(value.as_u128());
@@ -153,41 +419,304 @@ impl BaseSqlTypes {
     /// Used to map if we have the types linked up right.
     pub fn type_matches(&self, right: &BaseSqlTypesMapper) -> bool {
         match (self, right) {
-            //TODO unit test on arrays
             (Self::Array(a), BaseSqlTypesMapper::Array(b)) => {
-                if a.is_empty() {
-                    //We match if empty since this means we can still write it to disk
-                    return true;
-                }
-                match (&a[0], b.as_ref()) {
-                    (Self::Bool(_), BaseSqlTypesMapper::Bool) => true,
-                    (Self::Integer(_), BaseSqlTypesMapper::Integer) => true,
-                    (Self::Text(_), BaseSqlTypesMapper::Text) => true,
-                    (Self::Uuid(_), BaseSqlTypesMapper::Uuid) => true,
-                    (_, _) => false,
-                }
+                //Empty and all NULL arrays match since we can still write them to disk
+                array::elements(a)
+                    .into_iter()
+                    .flatten()
+                    .all(|v| v.type_matches(b))
             }
             (Self::Bool(_), BaseSqlTypesMapper::Bool) => true,
+            (Self::Smallint(_), BaseSqlTypesMapper::Smallint) => true,
             (Self::Integer(_), BaseSqlTypesMapper::Integer) => true,
+            (Self::Bigint(_), BaseSqlTypesMapper::Bigint) => true,
+            (Self::Real(_), BaseSqlTypesMapper::Real) => true,
+            (Self::Double(_), BaseSqlTypesMapper::Double) => true,
+            (Self::Numeric(_), BaseSqlTypesMapper::Numeric) => true,
+            (Self::Date(_), BaseSqlTypesMapper::Date) => true,
+            (Self::Time(_), BaseSqlTypesMapper::Time) => true,
+            (Self::Timestamp(_), BaseSqlTypesMapper::Timestamp) => true,
+            (Self::TimestampTz(_), BaseSqlTypesMapper::TimestampTz) => true,
+            (Self::Interval(_), BaseSqlTypesMapper::Interval) => true,
+            (Self::Bytea(_), BaseSqlTypesMapper::Bytea) => true,
+            (Self::Jsonb(_), BaseSqlTypesMapper::Jsonb) => true,
+            (Self::Enum(v), BaseSqlTypesMapper::Enum(e)) => e.contains(v),
             (Self::Text(_), BaseSqlTypesMapper::Text) => true,
             (Self::Uuid(_), BaseSqlTypesMapper::Uuid) => true,
             (_, _) => false,
         }
     }
+
+    pub fn checked_add(&self, right: &BaseSqlTypes) -> Result<BaseSqlTypes, BaseSqlTypesError> {
+        if self.is_date_time() || right.is_date_time() {
+            return date_time::add(self, right);
+        }
+        self.arithmetic(right, i64::checked_add, |l, r| l + r, Numeric::checked_add)
+    }
+
+    pub fn checked_sub(&self, right: &BaseSqlTypes) -> Result<BaseSqlTypes, BaseSqlTypesError> {
+        if self.is_date_time() || right.is_date_time() {
+            return date_time::subtract(self, right);
+        }
+        self.arithmetic(right, i64::checked_sub, |l, r| l - r, Numeric::checked_sub)
+    }
+
+    pub fn checked_mul(&self, right: &BaseSqlTypes) -> Result<BaseSqlTypes, BaseSqlTypesError> {
+        self.arithmetic(right, i64::checked_mul, |l, r| l * r, Numeric::checked_mul)
+    }
+
+    /// Integer division truncates towards zero, same as postgres
+    pub fn checked_div(&self, right: &BaseSqlTypes) -> Result<BaseSqlTypes, BaseSqlTypesError> {
+        if right.is_zero() {
+            return Err(BaseSqlTypesError::DivisionByZero());
+        }
+        self.arithmetic(right, i64::checked_div, |l, r| l / r, Numeric::checked_div)
+    }
+
+    fn is_date_time(&self) -> bool {
+        matches!(
+            self,
+            Self::Date(_)
+                | Self::Time(_)
+                | Self::Timestamp(_)
+                | Self::TimestampTz(_)
+                | Self::Interval(_)
+        )
+    }
+
+    fn is_zero(&self) -> bool {
+        match self {
+            Self::Smallint(v) => *v == 0,
+            Self::Integer(v) => *v == 0,
+            Self::Bigint(v) => *v == 0,
+            Self::Real(v) => v.0 == 0.0,
+            Self::Double(v) => v.0 == 0.0,
+            Self::Numeric(v) => v.is_zero(),
+            _ => false,
+        }
+    }
+
+    /// Both sides must be the same type, the narrower integers are computed as an i64 and then
+    /// checked to see if they still fit. Real is computed as a double for the same reason.
+    fn arithmetic(
+        &self,
+        right: &BaseSqlTypes,
+        int_op: fn(i64, i64) -> Option<i64>,
+        float_op: fn(f64, f64) -> f64,
+        numeric_op: fn(&Numeric, &Numeric) -> Result<Numeric, NumericError>,
+    ) -> Result<BaseSqlTypes, BaseSqlTypesError> {
+        match (self, right) {
+            (Self::Smallint(l), Self::Smallint(r)) => int_op(i64::from(*l), i64::from(*r))
+                .and_then(|v| i16::try_from(v).ok())
+                .map(Self::Smallint)
+                .ok_or(BaseSqlTypesError::NumericOutOfRange(
+                    BaseSqlTypesMapper::Smallint,
+                )),
+            (Self::Integer(l), Self::Integer(r)) => int_op(i64::from(*l), i64::from(*r))
+                .and_then(|v| i32::try_from(v).ok())
+                .map(Self::Integer)
+                .ok_or(BaseSqlTypesError::NumericOutOfRange(
+                    BaseSqlTypesMapper::Integer,
+                )),
+            (Self::Bigint(l), Self::Bigint(r)) => {
+                int_op(*l, *r)
+                    .map(Self::Bigint)
+                    .ok_or(BaseSqlTypesError::NumericOutOfRange(
+                        BaseSqlTypesMapper::Bigint,
+                    ))
+            }
+            (Self::Real(l), Self::Real(r)) => {
+                let value = float_op(l.as_f64(), r.as_f64()) as f32;
+                if value.is_infinite() && l.0.is_finite() && r.0.is_finite() {
+                    return Err(BaseSqlTypesError::NumericOutOfRange(
+                        BaseSqlTypesMapper::Real,
+                    ));
+                }
+                Ok(Self::Real(SqlFloat(value)))
+            }
+            (Self::Double(l), Self::Double(r)) => {
+                let value = float_op(l.0, r.0);
+                if value.is_infinite() && l.0.is_finite() && r.0.is_finite() {
+                    return Err(BaseSqlTypesError::NumericOutOfRange(
+                        BaseSqlTypesMapper::Double,
+                    ));
+                }
+                Ok(Self::Double(SqlFloat(value)))
+            }
+            (Self::Numeric(l), Self::Numeric(r)) => Ok(Self::Numeric(numeric_op(l, r)?)),
+            (_, _) => Err(BaseSqlTypesError::InvalidArithmetic(
+                self.clone(),
+                right.clone(),
+            )),
+        }
+    }
+
+    /// Casts between the numeric types and between the points in time, integers are range checked
+    /// when narrowing. See BaseSqlTypesMapper::converts_to for what is allowed.
+    pub fn convert(&self, target: &BaseSqlTypesMapper) -> Result<BaseSqlTypes, BaseSqlTypesError> {
+        if self.type_matches(target) {
+            return Ok(self.clone());
+        }
+
+        let out_of_range = || BaseSqlTypesError::NumericOutOfRange(target.clone());
+        let integer = match self {
+            Self::Smallint(v) => Some(i64::from(*v)),
+            Self::Integer(v) => Some(i64::from(*v)),
+            Self::Bigint(v) => Some(*v),
+            _ => None,
+        };
+
+        match (self, integer, target) {
+            (_, Some(v), BaseSqlTypesMapper::Smallint) => i16::try_from(v)
+                .map(Self::Smallint)
+                .map_err(|_| out_of_range()),
+            (_, Some(v), BaseSqlTypesMapper::Integer) => i32::try_from(v)
+                .map(Self::Integer)
+                .map_err(|_| out_of_range()),
+            (_, Some(v), BaseSqlTypesMapper::Bigint) => Ok(Self::Bigint(v)),
+            (_, Some(v), BaseSqlTypesMapper::Real) => Ok(Self::Real(SqlFloat(v as f32))),
+            (_, Some(v), BaseSqlTypesMapper::Double) => Ok(Self::Double(SqlFloat(v as f64))),
+            (_, Some(v), BaseSqlTypesMapper::Numeric) => Ok(Self::Numeric(Numeric::from(v))),
+            (Self::Real(v), _, BaseSqlTypesMapper::Real) => Ok(Self::Real(*v)),
+            (Self::Real(v), _, BaseSqlTypesMapper::Double) => {
+                Ok(Self::Double(SqlFloat(v.as_f64())))
+            }
+            (Self::Double(v), _, BaseSqlTypesMapper::Double) => Ok(Self::Double(*v)),
+            (Self::Date(d), _, BaseSqlTypesMapper::Timestamp) => {
+                Ok(Self::Timestamp(d.and_time(NaiveTime::MIN)))
+            }
+            (Self::Date(d), _, BaseSqlTypesMapper::TimestampTz) => {
+                Ok(Self::TimestampTz(d.and_time(NaiveTime::MIN).and_utc()))
+            }
+            (Self::Timestamp(t), _, BaseSqlTypesMapper::TimestampTz) => {
+                Ok(Self::TimestampTz(t.and_utc()))
+            }
+            (Self::TimestampTz(t), _, BaseSqlTypesMapper::Timestamp) => {
+                Ok(Self::Timestamp(t.naive_utc()))
+            }
+            (Self::Timestamp(t), _, BaseSqlTypesMapper::Date) => Ok(Self::Date(t.date())),
+            (Self::TimestampTz(t), _, BaseSqlTypesMapper::Date) => {
+                Ok(Self::Date(t.naive_utc().date()))
+            }
+            (_, _, _) => Err(BaseSqlTypesError::InvalidConversion(
+                self.clone(),
+                target.clone(),
+            )),
+        }
+    }
+}
+
+impl BaseSqlTypesMapper {
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            BaseSqlTypesMapper::Smallint | BaseSqlTypesMapper::Integer | BaseSqlTypesMapper::Bigint
+        )
+    }
+
+    /// The wider of two integer types, postgres implicitly casts smallint to integer to bigint
+    pub fn wider_integer(&self, other: &BaseSqlTypesMapper) -> Option<BaseSqlTypesMapper> {
+        let width = |t: &BaseSqlTypesMapper| match t {
+            BaseSqlTypesMapper::Smallint => Some(2),
+            BaseSqlTypesMapper::Integer => Some(4),
+            BaseSqlTypesMapper::Bigint => Some(8),
+            _ => None,
+        };
+        match width(self)? >= width(other)? {
+            true => Some(self.clone()),
+            false => Some(other.clone()),
+        }
+    }
+
+    pub fn is_date_time(&self) -> bool {
+        matches!(
+            self,
+            BaseSqlTypesMapper::Date
+                | BaseSqlTypesMapper::Time
+                | BaseSqlTypesMapper::Timestamp
+                | BaseSqlTypesMapper::TimestampTz
+                | BaseSqlTypesMapper::Interval
+        )
+    }
+
+    /// If BaseSqlTypes::convert can turn this type into the target, used to cast values on assignment
+    pub fn converts_to(&self, target: &BaseSqlTypesMapper) -> bool {
+        use BaseSqlTypesMapper::*;
+        self == target
+            || (self.is_integer() && target.is_numeric())
+            || matches!(
+                (self, target),
+                (Real, Double)
+                    | (Date, Timestamp)
+                    | (Date, TimestampTz)
+                    | (Timestamp, TimestampTz)
+                    | (TimestampTz, Timestamp)
+                    | (Timestamp, Date)
+                    | (TimestampTz, Date)
+            )
+    }
+
+    /// Every type that arithmetic is defined on
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer()
+            || matches!(
+                self,
+                BaseSqlTypesMapper::Real | BaseSqlTypesMapper::Double | BaseSqlTypesMapper::Numeric
+            )
+    }
 }
 
 impl Display for BaseSqlTypes {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             BaseSqlTypes::Array(ref value) => {
-                write!(f, "{:#?}", value)
+                write!(f, "{}", array::format_array(value))
             }
             BaseSqlTypes::Bool(ref value) => {
                 write!(f, "{}", value)
             }
+            BaseSqlTypes::Smallint(ref value) => {
+                write!(f, "{}", value)
+            }
             BaseSqlTypes::Integer(ref value) => {
                 write!(f, "{}", value)
             }
+            BaseSqlTypes::Bigint(ref value) => {
+                write!(f, "{}", value)
+            }
+            BaseSqlTypes::Real(ref value) => {
+                write!(f, "{}", value)
+            }
+            BaseSqlTypes::Double(ref value) => {
+                write!(f, "{}", value)
+            }
+            BaseSqlTypes::Numeric(ref value) => {
+                write!(f, "{}", value)
+            }
+            BaseSqlTypes::Date(ref value) => {
+                write!(f, "{}", value)
+            }
+            BaseSqlTypes::Time(ref value) => {
+                write!(f, "{}", date_time::format_time(value))
+            }
+            BaseSqlTypes::Timestamp(ref value) => {
+                write!(f, "{}", date_time::format_timestamp(value))
+            }
+            BaseSqlTypes::TimestampTz(ref value) => {
+                write!(f, "{}", date_time::format_timestamptz(value))
+            }
+            BaseSqlTypes::Interval(ref value) => {
+                write!(f, "{}", value)
+            }
+            BaseSqlTypes::Bytea(ref value) => {
+                write!(f, "{}", bytea::format_bytea(value))
+            }
+            BaseSqlTypes::Jsonb(ref value) => {
+                write!(f, "{}", value)
+            }
+            BaseSqlTypes::Enum(ref value) => {
+                write!(f, "{}", value.label)
+            }
             BaseSqlTypes::Uuid(ref value) => {
                 write!(f, "{}", value)
             }
@@ -205,16 +734,68 @@ impl Display for BaseSqlTypesMapper {
             BaseSqlTypesMapper::Array(ref a) => match a.as_ref() {
                 BaseSqlTypesMapper::Array(ref aa) => write!(f, "Array({})", **aa),
                 BaseSqlTypesMapper::Bool => write!(f, "Array(Bool)"),
+                BaseSqlTypesMapper::Smallint => write!(f, "Array(Smallint)"),
                 BaseSqlTypesMapper::Integer => write!(f, "Array(Integer)"),
+                BaseSqlTypesMapper::Bigint => write!(f, "Array(Bigint)"),
+                BaseSqlTypesMapper::Real => write!(f, "Array(Real)"),
+                BaseSqlTypesMapper::Double => write!(f, "Array(Double)"),
+                BaseSqlTypesMapper::Numeric => write!(f, "Array(Numeric)"),
+                BaseSqlTypesMapper::Date => write!(f, "Array(Date)"),
+                BaseSqlTypesMapper::Time => write!(f, "Array(Time)"),
+                BaseSqlTypesMapper::Timestamp => write!(f, "Array(Timestamp)"),
+                BaseSqlTypesMapper::TimestampTz => write!(f, "Array(TimestampTz)"),
+                BaseSqlTypesMapper::Interval => write!(f, "Array(Interval)"),
+                BaseSqlTypesMapper::Bytea => write!(f, "Array(Bytea)"),
+                BaseSqlTypesMapper::Jsonb => write!(f, "Array(Jsonb)"),
+                BaseSqlTypesMapper::Enum(ref e) => write!(f, "Array({})", e.name),
                 BaseSqlTypesMapper::Uuid => write!(f, "Array(Uuid)"),
                 BaseSqlTypesMapper::Text => write!(f, "Array(Text)"),
             },
             BaseSqlTypesMapper::Bool => {
                 write!(f, "Bool")
             }
+            BaseSqlTypesMapper::Smallint => {
+                write!(f, "Smallint")
+            }
             BaseSqlTypesMapper::Integer => {
                 write!(f, "Integer")
             }
+            BaseSqlTypesMapper::Bigint => {
+                write!(f, "Bigint")
+            }
+            BaseSqlTypesMapper::Real => {
+                write!(f, "Real")
+            }
+            BaseSqlTypesMapper::Double => {
+                write!(f, "Double")
+            }
+            BaseSqlTypesMapper::Numeric => {
+                write!(f, "Numeric")
+            }
+            BaseSqlTypesMapper::Date => {
+                write!(f, "Date")
+            }
+            BaseSqlTypesMapper::Time => {
+                write!(f, "Time")
+            }
+            BaseSqlTypesMapper::Timestamp => {
+                write!(f, "Timestamp")
+            }
+            BaseSqlTypesMapper::TimestampTz => {
+                write!(f, "TimestampTz")
+            }
+            BaseSqlTypesMapper::Interval => {
+                write!(f, "Interval")
+            }
+            BaseSqlTypesMapper::Bytea => {
+                write!(f, "Bytea")
+            }
+            BaseSqlTypesMapper::Jsonb => {
+                write!(f, "Jsonb")
+            }
+            BaseSqlTypesMapper::Enum(ref e) => {
+                write!(f, "{}", e.name)
+            }
             BaseSqlTypesMapper::Uuid => {
                 write!(f, "Uuid")
             }
@@ -225,10 +806,12 @@ impl Display for BaseSqlTypesMapper {
     }
 }
 
+/// Only knows the built in types, user defined ones are resolved through the catalog by
+/// DefinitionLookup::get_type. The whole name has to match so an enum called datetype isn't a date.
 impl FromStr for BaseSqlTypesMapper {
     type Err = BaseSqlTypesError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match parse_type::<VerboseError<&str>>(s).finish() {
+        match all_consuming(parse_type::<VerboseError<&str>>)(s).finish() {
             Ok((_, sql_type)) => Ok(sql_type),
             Err(e) => Err(BaseSqlTypesError::ParseError(convert_error(s, e))),
         }
@@ -241,10 +824,24 @@ impl SelfEncodedSize for BaseSqlTypes {
     fn encoded_size(&self) -> usize {
         match self {
             Self::Array(ref a) => {
-                expected_encoded_size(a.len()) + a.iter().fold(0, |acc, x| acc + x.encoded_size())
+                expected_encoded_size(a.len())
+                    + a.iter().fold(0, |acc, x| {
+                        acc + 1 + x.as_ref().map(|x| x.encoded_size()).unwrap_or(0)
+                    })
             }
             Self::Bool(_) => size_of::<bool>(),
-            Self::Integer(_) => size_of::<u32>(),
+            Self::Smallint(_) => size_of::<i16>(),
+            Self::Integer(_) => size_of::<i32>(),
+            Self::Bigint(_) => size_of::<i64>(),
+            Self::Real(_) => size_of::<f32>(),
+            Self::Double(_) => size_of::<f64>(),
+            Self::Numeric(ref n) => n.encoded_size(),
+            Self::Date(_) => size_of::<i32>(),
+            Self::Time(_) | Self::Timestamp(_) | Self::TimestampTz(_) => size_of::<i64>(),
+            Self::Interval(_) => Interval::encoded_size(),
+            Self::Bytea(ref b) => expected_encoded_size(b.len()) + b.len(),
+            Self::Jsonb(ref j) => j.encoded_size(),
+            Self::Enum(_) => size_of::<u32>(),
             Self::Uuid(_) => size_of::<Uuid>(),
             Self::Text(ref t) => expected_encoded_size(t.len()) + t.len(),
         }
@@ -255,16 +852,44 @@ impl SelfEncodedSize for BaseSqlTypes {
 pub enum BaseSqlTypesError {
     #[error(transparent)]
     Utf8Error(#[from] Utf8Error),
+    #[error("{0} out of range")]
+    DateTimeOutOfRange(BaseSqlTypesMapper),
+    #[error("Division by zero")]
+    DivisionByZero(),
     #[error("Length needed {0}, length found {1}")]
     InsufficentBuffer(usize, usize),
     #[error(transparent)]
     InvalidBool(#[from] ParseBoolError),
+    #[error("Malformed array literal: \"{0}\"")]
+    InvalidArray(String),
+    #[error("Invalid input syntax for type bytea: \"{0}\"")]
+    InvalidBytea(String),
+    #[error("Invalid input syntax for type {0}: \"{1}\"")]
+    InvalidDateTime(BaseSqlTypesMapper, String),
+    #[error("Invalid input value for enum {0}: \"{1}\"")]
+    InvalidEnumLabel(String, String),
+    #[error("Enum {0} has no label at position {1}")]
+    InvalidEnumPosition(String, u32),
+    #[error(transparent)]
+    InvalidFloat(#[from] ParseFloatError),
     #[error(transparent)]
     InvalidInt(#[from] ParseIntError),
+    #[error(transparent)]
+    InvalidInterval(#[from] IntervalError),
+    #[error("Unable to do arithmetic on {0} and {1}")]
+    InvalidArithmetic(BaseSqlTypes, BaseSqlTypes),
+    #[error("Unable to convert {0} to {1}")]
+    InvalidConversion(BaseSqlTypes, BaseSqlTypesMapper),
     #[error("Invalid type {0}")]
     InvalidType(String),
     #[error(transparent)]
     InvalidUuid(#[from] uuid::Error),
+    #[error(transparent)]
+    JsonbError(#[from] JsonbError),
+    #[error(transparent)]
+    NumericError(#[from] NumericError),
+    #[error("{0} out of range")]
+    NumericOutOfRange(BaseSqlTypesMapper),
     #[error("SQL Parse Error {0}")]
     ParseError(String),
     #[error(transparent)]
@@ -335,7 +960,9 @@ mod tests {
         assert_eq!(text.to_string(), "ee919e33-d905-4f48-8953-7ebb6cc911eb");
 
         assert_eq!(BaseSqlTypesMapper::Bool.to_string(), "Bool");
+        assert_eq!(BaseSqlTypesMapper::Smallint.to_string(), "Smallint");
         assert_eq!(BaseSqlTypesMapper::Integer.to_string(), "Integer");
+        assert_eq!(BaseSqlTypesMapper::Bigint.to_string(), "Bigint");
         assert_eq!(BaseSqlTypesMapper::Text.to_string(), "Text");
         assert_eq!(BaseSqlTypesMapper::Uuid.to_string(), "Uuid");
     }
@@ -374,9 +1001,206 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_integer_widths() -> Result<(), Box<dyn std::error::Error>> {
+        let values = vec![
+            (
+                BaseSqlTypes::Smallint(i16::MIN),
+                BaseSqlTypesMapper::Smallint,
+            ),
+            (BaseSqlTypes::Integer(-5), BaseSqlTypesMapper::Integer),
+            (BaseSqlTypes::Bigint(i64::MAX), BaseSqlTypesMapper::Bigint),
+        ];
+
+        for (value, sql_type) in values {
+            assert_eq!(
+                BaseSqlTypes::parse(sql_type.clone(), &value.to_string())?,
+                value
+            );
+
+            let mut buffer = BytesMut::with_capacity(value.encoded_size());
+            value.serialize(&mut buffer);
+            assert_eq!(buffer.len(), value.encoded_size());
+
+            let mut buffer = buffer.freeze();
+            assert_eq!(BaseSqlTypes::deserialize(&sql_type, &mut buffer)?, value);
+        }
+
+        assert!(matches!(
+            BaseSqlTypes::parse(BaseSqlTypesMapper::Smallint, "32768"),
+            Err(BaseSqlTypesError::NumericOutOfRange(
+                BaseSqlTypesMapper::Smallint
+            ))
+        ));
+        assert!(matches!(
+            BaseSqlTypes::parse(BaseSqlTypesMapper::Integer, "-2147483649"),
+            Err(BaseSqlTypesError::NumericOutOfRange(
+                BaseSqlTypesMapper::Integer
+            ))
+        ));
+        assert!(matches!(
+            BaseSqlTypes::parse(BaseSqlTypesMapper::Bigint, "foo"),
+            Err(BaseSqlTypesError::InvalidInt(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_integer_ordering() {
+        assert!(BaseSqlTypes::Integer(-1) < BaseSqlTypes::Integer(0));
+        assert!(BaseSqlTypes::Smallint(i16::MIN) < BaseSqlTypes::Smallint(i16::MAX));
+        assert!(BaseSqlTypes::Bigint(-10_000_000_000) < BaseSqlTypes::Bigint(-1));
+    }
+
+    #[test]
+    fn test_bytea() -> Result<(), Box<dyn std::error::Error>> {
+        let value = BaseSqlTypes::parse(BaseSqlTypesMapper::Bytea, "\\x00ff10")?;
+        assert_eq!(value, BaseSqlTypes::Bytea(vec![0x00, 0xff, 0x10]));
+        assert_eq!(value.to_string(), "\\x00ff10");
+        assert_eq!(
+            BaseSqlTypes::parse(BaseSqlTypesMapper::Bytea, "ab\\\\")?,
+            BaseSqlTypes::Bytea(b"ab\\".to_vec())
+        );
+        assert!(matches!(
+            BaseSqlTypes::parse(BaseSqlTypesMapper::Bytea, "\\xf"),
+            Err(BaseSqlTypesError::InvalidBytea(_))
+        ));
+
+        let mut buffer = BytesMut::with_capacity(value.encoded_size());
+        value.serialize(&mut buffer);
+        assert_eq!(buffer.len(), value.encoded_size());
+        let mut buffer = buffer.freeze();
+        assert_eq!(
+            BaseSqlTypes::deserialize(&BaseSqlTypesMapper::Bytea, &mut buffer)?,
+            value
+        );
+
+        //Byte by byte, a prefix sorts first
+        assert!(BaseSqlTypes::Bytea(vec![0x01]) < BaseSqlTypes::Bytea(vec![0x01, 0x00]));
+        assert!(BaseSqlTypes::Bytea(vec![0x01, 0xff]) < BaseSqlTypes::Bytea(vec![0x02]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_arithmetic() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(
+            BaseSqlTypes::Integer(-5).checked_add(&BaseSqlTypes::Integer(3))?,
+            BaseSqlTypes::Integer(-2)
+        );
+        assert_eq!(
+            BaseSqlTypes::Smallint(5).checked_sub(&BaseSqlTypes::Smallint(7))?,
+            BaseSqlTypes::Smallint(-2)
+        );
+        assert_eq!(
+            BaseSqlTypes::Bigint(i64::from(i32::MAX)).checked_mul(&BaseSqlTypes::Bigint(4))?,
+            BaseSqlTypes::Bigint(i64::from(i32::MAX) * 4)
+        );
+        assert_eq!(
+            BaseSqlTypes::Integer(-7).checked_div(&BaseSqlTypes::Integer(2))?,
+            BaseSqlTypes::Integer(-3)
+        );
+
+        assert!(matches!(
+            BaseSqlTypes::Smallint(i16::MAX).checked_add(&BaseSqlTypes::Smallint(1)),
+            Err(BaseSqlTypesError::NumericOutOfRange(
+                BaseSqlTypesMapper::Smallint
+            ))
+        ));
+        assert!(matches!(
+            BaseSqlTypes::Integer(i32::MIN).checked_div(&BaseSqlTypes::Integer(-1)),
+            Err(BaseSqlTypesError::NumericOutOfRange(
+                BaseSqlTypesMapper::Integer
+            ))
+        ));
+        assert!(matches!(
+            BaseSqlTypes::Bigint(i64::MIN).checked_sub(&BaseSqlTypes::Bigint(1)),
+            Err(BaseSqlTypesError::NumericOutOfRange(
+                BaseSqlTypesMapper::Bigint
+            ))
+        ));
+        assert!(matches!(
+            BaseSqlTypes::Bigint(1).checked_div(&BaseSqlTypes::Bigint(0)),
+            Err(BaseSqlTypesError::DivisionByZero())
+        ));
+        assert!(matches!(
+            BaseSqlTypes::Integer(1).checked_add(&BaseSqlTypes::Bigint(1)),
+            Err(BaseSqlTypesError::InvalidArithmetic(_, _))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_floating_and_numeric() -> Result<(), Box<dyn std::error::Error>> {
+        for (mapper, text) in [
+            (BaseSqlTypesMapper::Real, "1.5"),
+            (BaseSqlTypesMapper::Double, "-0.125"),
+            (BaseSqlTypesMapper::Double, "NaN"),
+            (BaseSqlTypesMapper::Numeric, "12345678901234567890.0100"),
+        ] {
+            let value = BaseSqlTypes::parse(mapper.clone(), text)?;
+            assert_eq!(value.to_string(), text);
+
+            let mut buffer = BytesMut::with_capacity(value.encoded_size());
+            value.serialize(&mut buffer);
+            assert_eq!(buffer.len(), value.encoded_size());
+            assert_eq!(
+                BaseSqlTypes::deserialize(&mapper, &mut buffer.freeze())?,
+                value
+            );
+        }
+
+        assert!(matches!(
+            BaseSqlTypes::parse(BaseSqlTypesMapper::Real, "1e39"),
+            Err(BaseSqlTypesError::NumericOutOfRange(
+                BaseSqlTypesMapper::Real
+            ))
+        ));
+        assert!(matches!(
+            BaseSqlTypes::Double(SqlFloat(f64::MAX))
+                .checked_mul(&BaseSqlTypes::Double(SqlFloat(2.0))),
+            Err(BaseSqlTypesError::NumericOutOfRange(
+                BaseSqlTypesMapper::Double
+            ))
+        ));
+        assert!(matches!(
+            BaseSqlTypes::Double(SqlFloat(1.0)).checked_div(&BaseSqlTypes::Double(SqlFloat(0.0))),
+            Err(BaseSqlTypesError::DivisionByZero())
+        ));
+
+        //Exact numerics do not pick up binary rounding errors
+        let left = BaseSqlTypes::parse(BaseSqlTypesMapper::Numeric, "0.1")?;
+        let right = BaseSqlTypes::parse(BaseSqlTypesMapper::Numeric, "0.2")?;
+        assert_eq!(left.checked_add(&right)?.to_string(), "0.3");
+
+        assert_eq!(
+            BaseSqlTypes::Integer(7).convert(&BaseSqlTypesMapper::Numeric)?,
+            BaseSqlTypes::parse(BaseSqlTypesMapper::Numeric, "7")?
+        );
+        assert!(matches!(
+            BaseSqlTypes::Bigint(40000).convert(&BaseSqlTypesMapper::Smallint),
+            Err(BaseSqlTypesError::NumericOutOfRange(
+                BaseSqlTypesMapper::Smallint
+            ))
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn test_array_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
-        let array = BaseSqlTypes::Array(vec![BaseSqlTypes::Integer(1), BaseSqlTypes::Integer(2)]);
+        let array = BaseSqlTypes::Array(vec![
+            Some(BaseSqlTypes::Array(vec![
+                Some(BaseSqlTypes::Integer(1)),
+                None,
+            ])),
+            Some(BaseSqlTypes::Array(vec![
+                Some(BaseSqlTypes::Integer(3)),
+                Some(BaseSqlTypes::Integer(4)),
+            ])),
+        ]);
+        assert_eq!(array.to_string(), "{{1,NULL},{3,4}}");
 
         let mut buffer = BytesMut::new();
         array.serialize(&mut buffer);
@@ -417,26 +1241,73 @@ mod tests {
         assert!(!BaseSqlTypes::Text("foo".to_string()).type_matches(&BaseSqlTypesMapper::Integer));
         assert!(!BaseSqlTypes::Text("foo".to_string()).type_matches(&BaseSqlTypesMapper::Uuid));
         assert!(BaseSqlTypes::Text("foo".to_string()).type_matches(&BaseSqlTypesMapper::Text));
+
+        let int_array = BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Integer));
+        assert!(BaseSqlTypes::Array(vec![]).type_matches(&int_array));
+        assert!(BaseSqlTypes::Array(vec![
+            None,
+            Some(BaseSqlTypes::Array(vec![Some(BaseSqlTypes::Integer(1))]))
+        ])
+        .type_matches(&int_array));
+        assert!(
+            !BaseSqlTypes::Array(vec![None, Some(BaseSqlTypes::Bool(true))])
+                .type_matches(&int_array)
+        );
+        assert!(!BaseSqlTypes::Integer(0).type_matches(&int_array));
     }
 
     #[test]
     pub fn test_encoded_size() {
         assert_eq!(
-            BaseSqlTypes::Array(vec![BaseSqlTypes::Integer(1), BaseSqlTypes::Integer(2)])
-                .encoded_size(),
-            9
+            BaseSqlTypes::Array(vec![
+                Some(BaseSqlTypes::Integer(1)),
+                Some(BaseSqlTypes::Integer(2))
+            ])
+            .encoded_size(),
+            11
         );
         assert_eq!(
             BaseSqlTypes::Array(vec![
-                BaseSqlTypes::Text("Test".to_string()),
-                BaseSqlTypes::Text("Test".to_string())
+                Some(BaseSqlTypes::Text("Test".to_string())),
+                None,
+                Some(BaseSqlTypes::Text("Test".to_string()))
             ])
             .encoded_size(),
-            11
+            14
         );
         assert_eq!(BaseSqlTypes::Bool(true).encoded_size(), 1);
+        assert_eq!(BaseSqlTypes::Smallint(1).encoded_size(), 2);
         assert_eq!(BaseSqlTypes::Integer(1).encoded_size(), 4);
+        assert_eq!(BaseSqlTypes::Bigint(1).encoded_size(), 8);
         assert_eq!(BaseSqlTypes::Text("Test".to_string()).encoded_size(), 5);
         assert_eq!(BaseSqlTypes::Uuid(Uuid::new_v4()).encoded_size(), 16);
     }
+
+    #[test]
+    fn test_enum() -> Result<(), Box<dyn std::error::Error>> {
+        let mood = Arc::new(EnumType::new(
+            Uuid::new_v4(),
+            16384,
+            16385,
+            "mood".to_string(),
+            vec!["sad".to_string(), "happy".to_string()],
+        ));
+        let mapper = BaseSqlTypesMapper::Enum(mood);
+
+        let value = BaseSqlTypes::parse(mapper.clone(), "happy")?;
+        assert!(value.type_matches(&mapper));
+        assert_eq!(value.to_string(), "happy");
+        assert_eq!(value.encoded_size(), 4);
+
+        let mut buffer = BytesMut::with_capacity(value.encoded_size());
+        value.serialize(&mut buffer);
+        let mut buffer = buffer.freeze();
+        assert_eq!(BaseSqlTypes::deserialize(&mapper, &mut buffer)?, value);
+
+        assert!(BaseSqlTypes::parse(mapper.clone(), "angry").is_err());
+        assert!(!BaseSqlTypes::Text("happy".to_string()).type_matches(&mapper));
+        assert!(BaseSqlTypesMapper::from_str("mood[]").is_err());
+        assert!(BaseSqlTypesMapper::from_str("datetype").is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,97 @@
+//! Text formats for bytea, see here: https://www.postgresql.org/docs/current/datatype-binary.html
+//!
+//! Output is always the hex format, input accepts both hex and the older escape format.
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+pub fn parse_bytea(input: &str) -> Option<Vec<u8>> {
+    match input
+        .strip_prefix("\\x")
+        .or_else(|| input.strip_prefix("\\X"))
+    {
+        Some(hex) => parse_hex(hex),
+        None => parse_escape(input),
+    }
+}
+
+pub fn format_bytea(value: &[u8]) -> String {
+    let mut output = String::with_capacity(2 + value.len() * 2);
+    output.push_str("\\x");
+    for b in value {
+        //Writing to a String cannot fail
+        let _ = write!(output, "{:02x}", b);
+    }
+    output
+}
+
+/// Pairs of hex digits, whitespace is allowed between the pairs but not inside them
+fn parse_hex(input: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len() / 2);
+    let mut chars = input.chars();
+    while let Some(high) = chars.next() {
+        if high.is_whitespace() {
+            continue;
+        }
+        let low = chars.next()?;
+        output.push(u8::try_from(high.to_digit(16)? << 4 | low.to_digit(16)?).ok()?);
+    }
+    Some(output)
+}
+
+/// Backslashes must be doubled or start a three digit octal escape, everything else is taken as is
+fn parse_escape(input: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut bytes = input.bytes();
+    while let Some(b) = bytes.next() {
+        if b != b'\\' {
+            output.push(b);
+            continue;
+        }
+        match bytes.next()? {
+            b'\\' => output.push(b'\\'),
+            first @ b'0'..=b'3' => {
+                let mut value = first - b'0';
+                for _ in 0..2 {
+                    match bytes.next()? {
+                        d @ b'0'..=b'7' => value = value << 3 | (d - b'0'),
+                        _ => return None,
+                    }
+                }
+                output.push(value);
+            }
+            _ => return None,
+        }
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(
+            parse_bytea("\\xDEADbeef"),
+            Some(vec![0xde, 0xad, 0xbe, 0xef])
+        );
+        assert_eq!(parse_bytea("\\x de ad"), Some(vec![0xde, 0xad]));
+        assert_eq!(parse_bytea("\\x"), Some(vec![]));
+        assert_eq!(parse_bytea("\\xabc"), None);
+        assert_eq!(parse_bytea("\\xa bc"), None);
+        assert_eq!(parse_bytea("\\xzz"), None);
+
+        assert_eq!(format_bytea(&[0xde, 0xad, 0x01]), "\\xdead01");
+        assert_eq!(format_bytea(&[]), "\\x");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(parse_bytea("abc"), Some(b"abc".to_vec()));
+        assert_eq!(parse_bytea("a\\\\b"), Some(b"a\\b".to_vec()));
+        assert_eq!(parse_bytea("\\000\\377'"), Some(vec![0, 255, b'\'']));
+        assert_eq!(parse_bytea("\\400"), None);
+        assert_eq!(parse_bytea("\\01"), None);
+        assert_eq!(parse_bytea("\\n"), None);
+    }
+}
@@ -0,0 +1,358 @@
+//! Text formats, storage and arithmetic for the date and time types, see here: https://www.postgresql.org/docs/current/datatype-datetime.html
+//!
+//! Everything is kept to microsecond precision same as postgres. There is no session time zone
+//! yet so timestamptz values are always shown in UTC.
+use super::interval::{self, MICROSECONDS_PER_DAY};
+use super::{BaseSqlTypes, BaseSqlTypesError, BaseSqlTypesMapper};
+use chrono::{
+    DateTime, Duration, FixedOffset, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Timelike, Utc,
+};
+use std::convert::TryFrom;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMATS: [&str; 2] = ["%H:%M:%S%.f", "%H:%M"];
+
+pub fn parse_date(input: &str) -> Option<NaiveDate> {
+    let input = input.trim();
+    NaiveDate::parse_from_str(input, DATE_FORMAT)
+        .ok()
+        .or_else(|| parse_timestamp(input).map(|t| t.date()))
+}
+
+pub fn parse_time(input: &str) -> Option<NaiveTime> {
+    let input = input.trim();
+    TIME_FORMATS
+        .iter()
+        .find_map(|f| NaiveTime::parse_from_str(input, f).ok())
+        .map(truncate_time)
+}
+
+/// Same as postgres, a time zone on a timestamp without one is ignored
+pub fn parse_timestamp(input: &str) -> Option<NaiveDateTime> {
+    let (local, _) = split_offset(input.trim())?;
+    parse_local(local)
+}
+
+/// Input without a time zone is taken to be UTC
+pub fn parse_timestamptz(input: &str) -> Option<DateTime<Utc>> {
+    let (local, offset) = split_offset(input.trim())?;
+    let offset = FixedOffset::east_opt(offset.unwrap_or(0))?;
+    offset
+        .from_local_datetime(&parse_local(local)?)
+        .single()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn parse_local(input: &str) -> Option<NaiveDateTime> {
+    if let Ok(date) = NaiveDate::parse_from_str(input, DATE_FORMAT) {
+        return Some(date.and_time(NaiveTime::MIN));
+    }
+    let (date, time) = input.split_at(input.find([' ', 'T'])?);
+    let date = NaiveDate::parse_from_str(date, DATE_FORMAT).ok()?;
+    Some(date.and_time(parse_time(&time[1..])?))
+}
+
+/// Splits a trailing Z, +hh, +hh:mm or +hhmm from the rest of a timestamp, the offset is in seconds
+fn split_offset(input: &str) -> Option<(&str, Option<i32>)> {
+    if let Some(local) = input
+        .strip_suffix('Z')
+        .or_else(|| input.strip_suffix('z'))
+        .or_else(|| input.strip_suffix("UTC"))
+    {
+        return Some((local.trim_end(), Some(0)));
+    }
+
+    //Past the date so its dashes are not mistaken for an offset
+    let start = match input.find(':') {
+        Some(s) => s,
+        None => return Some((input, None)),
+    };
+    let sign_at = match input[start..].rfind(['+', '-']) {
+        Some(i) => start + i,
+        None => return Some((input, None)),
+    };
+    let (local, offset) = input.split_at(sign_at);
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let digits: String = offset[1..].chars().filter(|c| *c != ':').collect();
+    if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = digits.split_at(digits.len().min(2));
+    let hours = hours.parse::<i32>().ok()?;
+    let minutes = match minutes {
+        "" => 0,
+        m => m.parse::<i32>().ok()?,
+    };
+    Some((local.trim_end(), Some(sign * (hours * 3600 + minutes * 60))))
+}
+
+fn truncate_time(time: NaiveTime) -> NaiveTime {
+    time.with_nanosecond(time.nanosecond() / 1000 * 1000)
+        .unwrap_or(time)
+}
+
+/// Now, cut down to the precision we store
+pub fn now() -> DateTime<Utc> {
+    let now = Utc::now();
+    now.with_nanosecond(now.nanosecond() / 1000 * 1000)
+        .unwrap_or(now)
+}
+
+pub fn format_time(time: &NaiveTime) -> String {
+    let mut output = time.format("%H:%M:%S").to_string();
+    let micros = time.nanosecond() / 1000;
+    if micros != 0 {
+        output.push_str(format!(".{:06}", micros).trim_end_matches('0'));
+    }
+    output
+}
+
+pub fn format_timestamp(timestamp: &NaiveDateTime) -> String {
+    format!(
+        "{} {}",
+        timestamp.date().format(DATE_FORMAT),
+        format_time(&timestamp.time())
+    )
+}
+
+pub fn format_timestamptz(timestamp: &DateTime<Utc>) -> String {
+    format!("{}+00", format_timestamp(&timestamp.naive_utc()))
+}
+
+pub fn time_to_micros(time: &NaiveTime) -> i64 {
+    i64::from(time.num_seconds_from_midnight()) * 1_000_000 + i64::from(time.nanosecond() / 1000)
+}
+
+pub fn micros_to_time(micros: i64) -> Option<NaiveTime> {
+    let seconds = u32::try_from(micros.div_euclid(1_000_000)).ok()?;
+    let nanos = u32::try_from(micros.rem_euclid(1_000_000) * 1000).ok()?;
+    NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanos)
+}
+
+pub fn timestamp_to_micros(timestamp: &NaiveDateTime) -> i64 {
+    timestamp.and_utc().timestamp_micros()
+}
+
+pub fn micros_to_timestamp(micros: i64) -> Option<NaiveDateTime> {
+    DateTime::from_timestamp_micros(micros).map(|t| t.naive_utc())
+}
+
+/// Months are added first, clamping to the end of the month, then days and then the time
+fn add_interval(timestamp: &NaiveDateTime, span: &interval::Interval) -> Option<NaiveDateTime> {
+    let months = Months::new(span.months.unsigned_abs());
+    let shifted = match span.months < 0 {
+        true => timestamp.checked_sub_months(months)?,
+        false => timestamp.checked_add_months(months)?,
+    };
+    shifted
+        .checked_add_signed(Duration::days(i64::from(span.days)))?
+        .checked_add_signed(Duration::microseconds(span.microseconds))
+}
+
+/// Differences are shown as days and time, never months, same as postgres
+fn difference(left: &NaiveDateTime, right: &NaiveDateTime) -> Option<interval::Interval> {
+    let micros = (*left - *right).num_microseconds()?;
+    Some(interval::Interval::new(
+        0,
+        i32::try_from(micros / MICROSECONDS_PER_DAY).ok()?,
+        micros % MICROSECONDS_PER_DAY,
+    ))
+}
+
+/// Time of day wraps around midnight, only the time part of an interval applies
+fn add_to_time(time: &NaiveTime, microseconds: i64) -> Option<NaiveTime> {
+    micros_to_time(
+        (time_to_micros(time) + microseconds % MICROSECONDS_PER_DAY)
+            .rem_euclid(MICROSECONDS_PER_DAY),
+    )
+}
+
+pub fn add(left: &BaseSqlTypes, right: &BaseSqlTypes) -> Result<BaseSqlTypes, BaseSqlTypesError> {
+    use BaseSqlTypes::*;
+    let result = match (left, right) {
+        (Date(d), Integer(i)) | (Integer(i), Date(d)) => d
+            .checked_add_signed(Duration::days(i64::from(*i)))
+            .map(Date),
+        (Date(d), Time(t)) | (Time(t), Date(d)) => Some(Timestamp(d.and_time(*t))),
+        (Date(d), Interval(i)) | (Interval(i), Date(d)) => {
+            add_interval(&d.and_time(NaiveTime::MIN), i).map(Timestamp)
+        }
+        (Time(t), Interval(i)) | (Interval(i), Time(t)) => add_to_time(t, i.microseconds).map(Time),
+        (Timestamp(t), Interval(i)) | (Interval(i), Timestamp(t)) => {
+            add_interval(t, i).map(Timestamp)
+        }
+        (TimestampTz(t), Interval(i)) | (Interval(i), TimestampTz(t)) => {
+            add_interval(&t.naive_utc(), i).map(|t| TimestampTz(t.and_utc()))
+        }
+        (Interval(l), Interval(r)) => l.checked_add(r).map(Interval),
+        (_, _) => {
+            return Err(BaseSqlTypesError::InvalidArithmetic(
+                left.clone(),
+                right.clone(),
+            ))
+        }
+    };
+    result.ok_or_else(|| out_of_range(left, right))
+}
+
+pub fn subtract(
+    left: &BaseSqlTypes,
+    right: &BaseSqlTypes,
+) -> Result<BaseSqlTypes, BaseSqlTypesError> {
+    use BaseSqlTypes::*;
+    let result = match (left, right) {
+        (Date(d), Integer(i)) => d
+            .checked_sub_signed(Duration::days(i64::from(*i)))
+            .map(Date),
+        (Date(l), Date(r)) => i32::try_from((*l - *r).num_days()).ok().map(Integer),
+        (Date(d), Interval(i)) => i
+            .checked_neg()
+            .and_then(|i| add_interval(&d.and_time(NaiveTime::MIN), &i))
+            .map(Timestamp),
+        (Time(t), Interval(i)) => add_to_time(t, -i.microseconds).map(Time),
+        (Time(l), Time(r)) => Some(Interval(interval::Interval::new(
+            0,
+            0,
+            time_to_micros(l) - time_to_micros(r),
+        ))),
+        (Timestamp(t), Interval(i)) => i
+            .checked_neg()
+            .and_then(|i| add_interval(t, &i))
+            .map(Timestamp),
+        (TimestampTz(t), Interval(i)) => i
+            .checked_neg()
+            .and_then(|i| add_interval(&t.naive_utc(), &i))
+            .map(|t| TimestampTz(t.and_utc())),
+        (Timestamp(l), Timestamp(r)) => difference(l, r).map(Interval),
+        (TimestampTz(l), TimestampTz(r)) => {
+            difference(&l.naive_utc(), &r.naive_utc()).map(Interval)
+        }
+        (Interval(l), Interval(r)) => l.checked_sub(r).map(Interval),
+        (_, _) => {
+            return Err(BaseSqlTypesError::InvalidArithmetic(
+                left.clone(),
+                right.clone(),
+            ))
+        }
+    };
+    result.ok_or_else(|| out_of_range(left, right))
+}
+
+/// Blames whichever side is the point in time, otherwise the interval
+fn out_of_range(left: &BaseSqlTypes, right: &BaseSqlTypes) -> BaseSqlTypesError {
+    let mapper = match (left, right) {
+        (BaseSqlTypes::Date(_), BaseSqlTypes::Integer(_))
+        | (BaseSqlTypes::Integer(_), BaseSqlTypes::Date(_)) => BaseSqlTypesMapper::Date,
+        (BaseSqlTypes::Date(_), BaseSqlTypes::Date(_)) => BaseSqlTypesMapper::Integer,
+        (BaseSqlTypes::Time(_), _) | (_, BaseSqlTypes::Time(_)) => BaseSqlTypesMapper::Time,
+        (BaseSqlTypes::TimestampTz(_), _) | (_, BaseSqlTypes::TimestampTz(_)) => {
+            BaseSqlTypesMapper::TimestampTz
+        }
+        (BaseSqlTypes::Interval(_), BaseSqlTypes::Interval(_))
+        | (BaseSqlTypes::Timestamp(_), BaseSqlTypes::Timestamp(_)) => BaseSqlTypesMapper::Interval,
+        (_, _) => BaseSqlTypesMapper::Timestamp,
+    };
+    BaseSqlTypesError::DateTimeOutOfRange(mapper)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp(s: &str) -> NaiveDateTime {
+        parse_timestamp(s).unwrap()
+    }
+
+    #[test]
+    fn test_text_formats() {
+        let date = parse_date("2024-02-29").unwrap();
+        assert_eq!(date.to_string(), "2024-02-29");
+        assert!(parse_date("2023-02-29").is_none());
+
+        let time = parse_time("13:45:06.123456789").unwrap();
+        assert_eq!(format_time(&time), "13:45:06.123456");
+        assert_eq!(format_time(&parse_time("07:30").unwrap()), "07:30:00");
+
+        assert_eq!(
+            format_timestamp(&timestamp("2024-01-15T13:45:00.5")),
+            "2024-01-15 13:45:00.5"
+        );
+        assert_eq!(
+            format_timestamp(&timestamp("2024-01-15")),
+            "2024-01-15 00:00:00"
+        );
+        //The zone is ignored without time zone
+        assert_eq!(
+            format_timestamp(&timestamp("2024-01-15 13:45:00+02")),
+            "2024-01-15 13:45:00"
+        );
+
+        let with_zone = parse_timestamptz("2024-01-15 13:45:00-05:30").unwrap();
+        assert_eq!(format_timestamptz(&with_zone), "2024-01-15 19:15:00+00");
+        let utc = parse_timestamptz("2024-01-15T13:45:00Z").unwrap();
+        assert_eq!(format_timestamptz(&utc), "2024-01-15 13:45:00+00");
+        assert!(parse_timestamptz("2024-01-15 13:45:00+2:00:00").is_none());
+    }
+
+    #[test]
+    fn test_storage() {
+        let time = parse_time("23:59:59.999999").unwrap();
+        assert_eq!(micros_to_time(time_to_micros(&time)), Some(time));
+
+        let before_epoch = timestamp("1969-12-31 23:59:59.25");
+        assert_eq!(
+            micros_to_timestamp(timestamp_to_micros(&before_epoch)),
+            Some(before_epoch)
+        );
+    }
+
+    #[test]
+    fn test_arithmetic() -> Result<(), Box<dyn std::error::Error>> {
+        let date = |s| BaseSqlTypes::Date(parse_date(s).unwrap());
+        let ts = |s| BaseSqlTypes::Timestamp(timestamp(s));
+        let interval = |s: &str| BaseSqlTypes::Interval(s.parse().unwrap());
+
+        assert_eq!(
+            add(&date("2024-02-28"), &BaseSqlTypes::Integer(2))?,
+            date("2024-03-01")
+        );
+        assert_eq!(
+            subtract(&date("2024-03-01"), &date("2024-02-01"))?,
+            BaseSqlTypes::Integer(29)
+        );
+        //Month arithmetic clamps to the end of the month
+        assert_eq!(
+            add(&ts("2024-01-31 10:00:00"), &interval("1 month 1 hour"))?,
+            ts("2024-02-29 11:00:00")
+        );
+        assert_eq!(
+            subtract(&date("2024-03-01"), &interval("1 day"))?,
+            ts("2024-02-29 00:00:00")
+        );
+        assert_eq!(
+            subtract(&ts("2024-01-02 03:00:00"), &ts("2024-01-01 00:00:00"))?.to_string(),
+            "1 day 03:00:00"
+        );
+        assert_eq!(
+            add(
+                &BaseSqlTypes::Time(parse_time("23:00").unwrap()),
+                &interval("2 hours")
+            )?
+            .to_string(),
+            "01:00:00"
+        );
+
+        assert!(matches!(
+            add(&date("2024-01-01"), &BaseSqlTypes::Integer(i32::MAX)),
+            Err(BaseSqlTypesError::DateTimeOutOfRange(
+                BaseSqlTypesMapper::Date
+            ))
+        ));
+        assert!(matches!(
+            add(&date("2024-01-01"), &date("2024-01-01")),
+            Err(BaseSqlTypesError::InvalidArithmetic(_, _))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+//! User defined enums, see here: https://www.postgresql.org/docs/current/datatype-enum.html
+//!
+//! The labels are stored in pg_enum and looked up by the analyzer, see DefinitionLookup::get_type.
+//! Values only hold their position in the label list so they sort in declaration order.
+use super::BaseSqlTypesError;
+use std::convert::TryFrom;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumType {
+    pub id: Uuid,
+    ///The oids from pg_type, clients see them in RowDescription
+    pub oid: u32,
+    pub array_oid: u32,
+    pub name: String,
+    pub labels: Vec<String>,
+}
+
+impl EnumType {
+    pub fn new(id: Uuid, oid: u32, array_oid: u32, name: String, labels: Vec<String>) -> EnumType {
+        EnumType {
+            id,
+            oid,
+            array_oid,
+            name,
+            labels,
+        }
+    }
+
+    pub fn parse(&self, label: &str) -> Result<EnumLabel, BaseSqlTypesError> {
+        self.labels
+            .iter()
+            .position(|l| l == label)
+            .and_then(|p| u32::try_from(p).ok())
+            .map(|position| EnumLabel {
+                position,
+                label: label.to_string(),
+            })
+            .ok_or_else(|| {
+                BaseSqlTypesError::InvalidEnumLabel(self.name.clone(), label.to_string())
+            })
+    }
+
+    pub fn get(&self, position: u32) -> Result<EnumLabel, BaseSqlTypesError> {
+        usize::try_from(position)
+            .ok()
+            .and_then(|p| self.labels.get(p))
+            .map(|label| EnumLabel {
+                position,
+                label: label.clone(),
+            })
+            .ok_or_else(|| BaseSqlTypesError::InvalidEnumPosition(self.name.clone(), position))
+    }
+
+    pub fn contains(&self, value: &EnumLabel) -> bool {
+        self.get(value.position)
+            .map(|l| l.label == value.label)
+            .unwrap_or(false)
+    }
+}
+
+/// The position is first so the derived ordering follows the declaration order
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct EnumLabel {
+    pub position: u32,
+    pub label: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enum_type() -> Result<(), Box<dyn std::error::Error>> {
+        let mood = EnumType::new(
+            Uuid::new_v4(),
+            16384,
+            16385,
+            "mood".to_string(),
+            vec!["sad".to_string(), "ok".to_string(), "happy".to_string()],
+        );
+
+        let happy = mood.parse("happy")?;
+        let sad = mood.parse("sad")?;
+        assert_eq!(happy.position, 2);
+        assert!(sad < happy);
+        assert_eq!(mood.get(2)?, happy);
+        assert!(mood.contains(&sad));
+
+        assert!(mood.parse("HAPPY").is_err());
+        assert!(mood.get(3).is_err());
+        assert!(!mood.contains(&EnumLabel {
+            position: 0,
+            label: "ok".to_string()
+        }));
+        Ok(())
+    }
+}
@@ -0,0 +1,374 @@
+//! Spans of time for the interval type, see here: https://www.postgresql.org/docs/current/datatype-datetime.html#DATATYPE-INTERVAL-INPUT
+//!
+//! Same as postgres months, days and microseconds are kept apart since neither a month nor a day
+//! has a fixed length until it is added to a point in time.
+use bytes::{Buf, BufMut};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
+use std::str::FromStr;
+use thiserror::Error;
+
+pub const MICROSECONDS_PER_SECOND: i64 = 1_000_000;
+pub const MICROSECONDS_PER_DAY: i64 = 86_400 * MICROSECONDS_PER_SECOND;
+///Only used to compare intervals, same as postgres
+const DAYS_PER_MONTH: i64 = 30;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub microseconds: i64,
+}
+
+impl Interval {
+    pub fn new(months: i32, days: i32, microseconds: i64) -> Interval {
+        Interval {
+            months,
+            days,
+            microseconds,
+        }
+    }
+
+    /// The length used for ordering, a month counts as 30 days
+    fn span(&self) -> i128 {
+        (i128::from(self.months) * i128::from(DAYS_PER_MONTH) + i128::from(self.days))
+            * i128::from(MICROSECONDS_PER_DAY)
+            + i128::from(self.microseconds)
+    }
+
+    pub fn checked_add(&self, right: &Interval) -> Option<Interval> {
+        Some(Interval {
+            months: self.months.checked_add(right.months)?,
+            days: self.days.checked_add(right.days)?,
+            microseconds: self.microseconds.checked_add(right.microseconds)?,
+        })
+    }
+
+    pub fn checked_sub(&self, right: &Interval) -> Option<Interval> {
+        self.checked_add(&right.checked_neg()?)
+    }
+
+    pub fn checked_neg(&self) -> Option<Interval> {
+        Some(Interval {
+            months: self.months.checked_neg()?,
+            days: self.days.checked_neg()?,
+            microseconds: self.microseconds.checked_neg()?,
+        })
+    }
+
+    pub fn serialize(&self, buffer: &mut impl BufMut) {
+        buffer.put_i32_le(self.months);
+        buffer.put_i32_le(self.days);
+        buffer.put_i64_le(self.microseconds);
+    }
+
+    pub fn deserialize(buffer: &mut impl Buf) -> Result<Interval, IntervalError> {
+        if buffer.remaining() < Self::encoded_size() {
+            return Err(IntervalError::InsufficentBuffer(
+                Self::encoded_size(),
+                buffer.remaining(),
+            ));
+        }
+        Ok(Interval {
+            months: buffer.get_i32_le(),
+            days: buffer.get_i32_le(),
+            microseconds: buffer.get_i64_le(),
+        })
+    }
+
+    pub fn encoded_size() -> usize {
+        size_of::<i32>() + size_of::<i32>() + size_of::<i64>()
+    }
+
+    /// Fractions of a unit spill down into the smaller fields, so 1.5 months is 1 month 15 days
+    fn add_unit(&mut self, amount: f64, unit: &str) -> Result<(), IntervalError> {
+        let (months, days, microseconds) = match unit {
+            "millennium" | "millennia" | "millenniums" | "mil" | "mils" => {
+                (amount * 12000.0, 0.0, 0.0)
+            }
+            "century" | "centuries" | "cent" | "c" => (amount * 1200.0, 0.0, 0.0),
+            "decade" | "decades" | "dec" | "decs" => (amount * 120.0, 0.0, 0.0),
+            "year" | "years" | "yr" | "yrs" | "y" => (amount * 12.0, 0.0, 0.0),
+            "month" | "months" | "mon" | "mons" => (amount, 0.0, 0.0),
+            "week" | "weeks" | "w" => (0.0, amount * 7.0, 0.0),
+            "day" | "days" | "d" => (0.0, amount, 0.0),
+            "hour" | "hours" | "hr" | "hrs" | "h" => (0.0, 0.0, amount * 3600.0e6),
+            "minute" | "minutes" | "min" | "mins" | "m" => (0.0, 0.0, amount * 60.0e6),
+            "second" | "seconds" | "sec" | "secs" | "s" => (0.0, 0.0, amount * 1.0e6),
+            "millisecond" | "milliseconds" | "msec" | "msecs" | "ms" => (0.0, 0.0, amount * 1.0e3),
+            "microsecond" | "microseconds" | "usec" | "usecs" | "us" => (0.0, 0.0, amount),
+            u => return Err(IntervalError::UnknownUnit(u.to_string())),
+        };
+
+        let days = days + months.fract() * DAYS_PER_MONTH as f64;
+        let microseconds = microseconds + days.fract() * MICROSECONDS_PER_DAY as f64;
+        let addition = Interval {
+            months: to_field(months.trunc())?,
+            days: to_field(days.trunc())?,
+            microseconds: to_field(microseconds.round())?,
+        };
+        *self = self
+            .checked_add(&addition)
+            .ok_or(IntervalError::OutOfRange())?;
+        Ok(())
+    }
+}
+
+fn to_field<T: TryFrom<i64>>(value: f64) -> Result<T, IntervalError> {
+    if !value.is_finite() || value.abs() >= i64::MAX as f64 {
+        return Err(IntervalError::OutOfRange());
+    }
+    T::try_from(value as i64).map_err(|_| IntervalError::OutOfRange())
+}
+
+/// Parses the [-]hh:mm[:ss[.ffffff]] part of an interval into microseconds
+fn parse_clock(input: &str) -> Result<i64, IntervalError> {
+    let invalid = || IntervalError::InvalidInterval(input.to_string());
+    let (negative, unsigned) = match input.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, input.strip_prefix('+').unwrap_or(input)),
+    };
+
+    let mut parts = unsigned.split(':');
+    let hours = parts.next().ok_or_else(invalid)?;
+    let minutes = parts.next().ok_or_else(invalid)?;
+    let seconds = parts.next().unwrap_or("0");
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+
+    let hours = hours.parse::<i64>().map_err(|_| invalid())?;
+    let minutes = minutes.parse::<i64>().map_err(|_| invalid())?;
+    let seconds = seconds.parse::<f64>().map_err(|_| invalid())?;
+    if !(0..60).contains(&minutes) || !(0.0..60.0).contains(&seconds) {
+        return Err(invalid());
+    }
+
+    let total = hours
+        .checked_mul(3600)
+        .and_then(|s| s.checked_add(minutes * 60))
+        .and_then(|s| s.checked_mul(MICROSECONDS_PER_SECOND))
+        .and_then(|m| m.checked_add((seconds * 1.0e6).round() as i64))
+        .ok_or(IntervalError::OutOfRange())?;
+    Ok(match negative {
+        true => -total,
+        false => total,
+    })
+}
+
+impl FromStr for Interval {
+    type Err = IntervalError;
+
+    /// Accepts postgres' own output along with the verbose "@ 1 day ago" form
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lowered = s.trim().to_lowercase();
+        let lowered = lowered.strip_prefix('@').unwrap_or(&lowered);
+        let mut tokens: Vec<&str> = lowered.split_whitespace().collect();
+        if tokens.is_empty() {
+            return Err(IntervalError::InvalidInterval(s.to_string()));
+        }
+
+        let ago = tokens.last() == Some(&"ago");
+        if ago {
+            tokens.pop();
+        }
+
+        let mut interval = Interval::default();
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            if token.contains(':') {
+                let clock = Interval::new(0, 0, parse_clock(token)?);
+                interval = interval
+                    .checked_add(&clock)
+                    .ok_or(IntervalError::OutOfRange())?;
+                continue;
+            }
+
+            //The unit can be attached to the number, such as 10min
+            let split = token
+                .find(|c: char| c.is_alphabetic())
+                .unwrap_or(token.len());
+            let (number, attached_unit) = token.split_at(split);
+            let amount = number
+                .parse::<f64>()
+                .map_err(|_| IntervalError::InvalidInterval(s.to_string()))?;
+
+            let unit = match attached_unit {
+                "" => match tokens.peek() {
+                    Some(u) if u.starts_with(|c: char| c.is_alphabetic()) => {
+                        tokens.next().unwrap_or("s")
+                    }
+                    //A bare number is seconds
+                    _ => "s",
+                },
+                u => u,
+            };
+            interval.add_unit(amount, unit)?;
+        }
+
+        match ago {
+            true => interval.checked_neg().ok_or(IntervalError::OutOfRange()),
+            false => Ok(interval),
+        }
+    }
+}
+
+impl Display for Interval {
+    /// Matches postgres' default output style, a positive field after a negative one gets a plus sign
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        let mut negative_seen = false;
+        let mut push_field = |parts: &mut Vec<String>, value: i64, unit: &str| {
+            if value == 0 {
+                return;
+            }
+            let sign = if value > 0 && negative_seen { "+" } else { "" };
+            negative_seen |= value < 0;
+            let plural = if value == 1 { "" } else { "s" };
+            parts.push(format!("{}{} {}{}", sign, value, unit, plural));
+        };
+
+        let months = i64::from(self.months);
+        push_field(&mut parts, months / 12, "year");
+        push_field(&mut parts, months % 12, "mon");
+        push_field(&mut parts, i64::from(self.days), "day");
+
+        if self.microseconds != 0 || parts.is_empty() {
+            let sign = if self.microseconds < 0 {
+                "-"
+            } else if negative_seen {
+                "+"
+            } else {
+                ""
+            };
+            let total = self.microseconds.unsigned_abs();
+            let micros_per_second = MICROSECONDS_PER_SECOND.unsigned_abs();
+            let seconds = total / micros_per_second;
+            let mut clock = format!(
+                "{}{:02}:{:02}:{:02}",
+                sign,
+                seconds / 3600,
+                (seconds / 60) % 60,
+                seconds % 60
+            );
+            let fraction = total % micros_per_second;
+            if fraction != 0 {
+                clock.push_str(format!(".{:06}", fraction).trim_end_matches('0'));
+            }
+            parts.push(clock);
+        }
+
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl Ord for Interval {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.span().cmp(&other.span())
+    }
+}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Interval {
+    fn eq(&self, other: &Self) -> bool {
+        self.span() == other.span()
+    }
+}
+
+impl Eq for Interval {}
+
+impl Hash for Interval {
+    /// 1 month and 30 days are equal so they have to hash the same
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.span().hash(state);
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum IntervalError {
+    #[error("Length needed {0}, length found {1}")]
+    InsufficentBuffer(usize, usize),
+    #[error("Invalid input syntax for type interval: \"{0}\"")]
+    InvalidInterval(String),
+    #[error("Interval out of range")]
+    OutOfRange(),
+    #[error("Interval unit \"{0}\" not recognized")]
+    UnknownUnit(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+
+    fn interval(s: &str) -> Interval {
+        s.parse::<Interval>().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() -> Result<(), Box<dyn std::error::Error>> {
+        let cases = [
+            ("1 day", "1 day"),
+            (
+                "1 year 2 months 3 days 04:05:06.5",
+                "1 year 2 mons 3 days 04:05:06.5",
+            ),
+            ("2 hours 30 minutes", "02:30:00"),
+            ("-14 months", "-1 years -2 mons"),
+            ("-1 day 2 hours", "-1 days +02:00:00"),
+            ("1.5 months", "1 mon 15 days"),
+            ("@ 3 days ago", "-3 days"),
+            ("10min", "00:10:00"),
+            ("90", "00:01:30"),
+            ("0", "00:00:00"),
+            ("100:00:00", "100:00:00"),
+        ];
+        for (input, output) in cases {
+            let parsed = input.parse::<Interval>()?;
+            assert_eq!(parsed.to_string(), output);
+            assert_eq!(output.parse::<Interval>()?.to_string(), output);
+        }
+
+        assert!("1 fortnight".parse::<Interval>().is_err());
+        assert!("".parse::<Interval>().is_err());
+        assert!("1:75".parse::<Interval>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert_eq!(interval("1 month"), interval("30 days"));
+        assert!(interval("1 day") < interval("25 hours"));
+        assert!(interval("-1 year") < interval("0"));
+        assert_eq!(
+            interval("1 day").checked_add(&interval("2 hours")),
+            Some(interval("1 day 02:00:00"))
+        );
+        assert_eq!(
+            interval("1 day").checked_sub(&interval("1 day")),
+            Some(interval("0"))
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let value = interval("-1 years 3 days 00:00:00.000001");
+        let mut buffer = BytesMut::new();
+        value.serialize(&mut buffer);
+        assert_eq!(buffer.len(), Interval::encoded_size());
+
+        let parsed = Interval::deserialize(&mut buffer.freeze())?;
+        assert_eq!(parsed.months, value.months);
+        assert_eq!(parsed.days, value.days);
+        assert_eq!(parsed.microseconds, value.microseconds);
+        Ok(())
+    }
+}
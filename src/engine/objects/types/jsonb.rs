@@ -0,0 +1,764 @@
+//! The jsonb type, see here: https://www.postgresql.org/docs/current/datatype-json.html
+//!
+//! Text is parsed once on the way in and the tree is what gets stored, so the operators never
+//! have to parse again. Same as postgres numbers are kept as numeric, object keys are sorted by
+//! length then bytes and a duplicate key keeps the last value.
+use super::{BaseSqlTypes, Numeric, NumericError};
+use crate::engine::io::{encode_size, expected_encoded_size, parse_size, SizeError};
+use bytes::{Buf, BufMut};
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+use thiserror::Error;
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_NUMBER: u8 = 3;
+const TAG_STRING: u8 = 4;
+const TAG_ARRAY: u8 = 5;
+const TAG_OBJECT: u8 = 6;
+
+/// How many arrays and objects can be nested inside each other, parsing and reading back recurse once per level
+const MAX_DEPTH: usize = 512;
+
+/// The order of the variants is the sort order between kinds, same as postgres
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Jsonb {
+    Null,
+    String(String),
+    Number(Numeric),
+    Bool(bool),
+    Array(Vec<Jsonb>),
+    ///Always sorted and without duplicates, build with Jsonb::object
+    Object(Vec<(String, Jsonb)>),
+}
+
+impl Jsonb {
+    pub fn object(mut pairs: Vec<(String, Jsonb)>) -> Jsonb {
+        //A stable sort keeps duplicates in the order given so the last one can win
+        pairs.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        let mut deduped: Vec<(String, Jsonb)> = Vec::with_capacity(pairs.len());
+        for (key, value) in pairs {
+            match deduped.last_mut() {
+                Some(last) if last.0 == key => last.1 = value,
+                _ => deduped.push((key, value)),
+            }
+        }
+        Jsonb::Object(deduped)
+    }
+
+    /// Converts a sql value the same way postgres' to_jsonb does, NULL becomes a json null
+    pub fn from_value(value: Option<&BaseSqlTypes>) -> Result<Jsonb, JsonbError> {
+        Ok(match value {
+            None => Jsonb::Null,
+            Some(BaseSqlTypes::Jsonb(j)) => j.clone(),
+            Some(BaseSqlTypes::Bool(b)) => Jsonb::Bool(*b),
+            Some(BaseSqlTypes::Smallint(i)) => Jsonb::Number(Numeric::from(i64::from(*i))),
+            Some(BaseSqlTypes::Integer(i)) => Jsonb::Number(Numeric::from(i64::from(*i))),
+            Some(BaseSqlTypes::Bigint(i)) => Jsonb::Number(Numeric::from(*i)),
+            //Json has no NaN or infinity so those stay text
+            Some(v @ BaseSqlTypes::Real(_))
+            | Some(v @ BaseSqlTypes::Double(_))
+            | Some(v @ BaseSqlTypes::Numeric(_)) => match v.to_string().parse::<Numeric>() {
+                Ok(n @ Numeric::Finite { .. }) => Jsonb::Number(n),
+                _ => Jsonb::String(v.to_string()),
+            },
+            Some(BaseSqlTypes::Array(a)) => Jsonb::Array(
+                a.iter()
+                    .map(|v| Jsonb::from_value(v.as_ref()))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Some(BaseSqlTypes::Text(t)) => Jsonb::String(t.clone()),
+            Some(v) => Jsonb::String(v.to_string()),
+        })
+    }
+
+    /// The -> operator on an object
+    pub fn field(&self, key: &str) -> Option<&Jsonb> {
+        match self {
+            Jsonb::Object(pairs) => pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// The -> operator on an array, negative indexes count back from the end
+    pub fn element(&self, index: i64) -> Option<&Jsonb> {
+        match self {
+            Jsonb::Array(items) => {
+                let index = match index < 0 {
+                    true => (items.len() as i64).checked_add(index)?,
+                    false => index,
+                };
+                items.get(usize::try_from(index).ok()?)
+            }
+            _ => None,
+        }
+    }
+
+    /// The #> operator, each step is a key or an array index depending on what it is applied to
+    pub fn path(&self, steps: &[String]) -> Option<&Jsonb> {
+        steps.iter().try_fold(self, |current, step| match current {
+            Jsonb::Array(_) => current.element(step.parse::<i64>().ok()?),
+            _ => current.field(step),
+        })
+    }
+
+    /// The ->> operator's text form of a value, strings lose their quotes and null is a sql NULL
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            Jsonb::Null => None,
+            Jsonb::String(s) => Some(s.clone()),
+            _ => Some(self.to_string()),
+        }
+    }
+
+    /// The ? operator, a string that is a top level key or array element
+    pub fn has_key(&self, key: &str) -> bool {
+        match self {
+            Jsonb::Object(_) => self.field(key).is_some(),
+            Jsonb::Array(items) => items
+                .iter()
+                .any(|i| matches!(i, Jsonb::String(s) if s == key)),
+            Jsonb::String(s) => s == key,
+            _ => false,
+        }
+    }
+
+    /// The @> operator, see here: https://www.postgresql.org/docs/current/datatype-json.html#JSON-CONTAINMENT
+    ///
+    /// The only time the kinds don't need to match is a top level array containing a scalar.
+    pub fn contains(&self, other: &Jsonb) -> bool {
+        match (self, other) {
+            (Jsonb::Array(_), Jsonb::Array(_)) | (Jsonb::Object(_), Jsonb::Object(_)) => {
+                self.contains_nested(other)
+            }
+            (Jsonb::Array(items), scalar) => items.contains(scalar),
+            (_, _) => self == other,
+        }
+    }
+
+    fn contains_nested(&self, other: &Jsonb) -> bool {
+        match (self, other) {
+            (Jsonb::Object(_), Jsonb::Object(wanted)) => wanted.iter().all(|(key, value)| {
+                self.field(key)
+                    .map(|v| v.contains_nested(value))
+                    .unwrap_or(false)
+            }),
+            (Jsonb::Array(items), Jsonb::Array(wanted)) => wanted
+                .iter()
+                .all(|w| items.iter().any(|i| i.contains_nested(w))),
+            (_, _) => self == other,
+        }
+    }
+
+    /// Paths are written as a text array such as '{a,0,b}', an element can be double quoted
+    pub fn parse_path(input: &str) -> Result<Vec<String>, JsonbError> {
+        let invalid = || JsonbError::InvalidPath(input.to_string());
+        let inner = input
+            .trim()
+            .strip_prefix('{')
+            .and_then(|i| i.strip_suffix('}'))
+            .ok_or_else(invalid)?;
+        if inner.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut steps = vec![];
+        let mut chars = inner.chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            let mut step = String::new();
+            if chars.next_if_eq(&'"').is_some() {
+                loop {
+                    match chars.next().ok_or_else(invalid)? {
+                        '"' => break,
+                        '\\' => step.push(chars.next().ok_or_else(invalid)?),
+                        c => step.push(c),
+                    }
+                }
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            } else {
+                while let Some(c) = chars.next_if(|c| *c != ',') {
+                    step.push(c);
+                }
+                step = step.trim_end().to_string();
+                if step.is_empty() {
+                    return Err(invalid());
+                }
+            }
+            steps.push(step);
+
+            match chars.next() {
+                Some(',') => continue,
+                None => return Ok(steps),
+                Some(_) => return Err(invalid()),
+            }
+        }
+    }
+
+    pub fn serialize(&self, buffer: &mut impl BufMut) {
+        match self {
+            Jsonb::Null => buffer.put_u8(TAG_NULL),
+            Jsonb::Bool(false) => buffer.put_u8(TAG_FALSE),
+            Jsonb::Bool(true) => buffer.put_u8(TAG_TRUE),
+            Jsonb::Number(n) => {
+                buffer.put_u8(TAG_NUMBER);
+                n.serialize(buffer);
+            }
+            Jsonb::String(s) => {
+                buffer.put_u8(TAG_STRING);
+                Self::serialize_string(s, buffer);
+            }
+            Jsonb::Array(items) => {
+                buffer.put_u8(TAG_ARRAY);
+                encode_size(buffer, items.len());
+                for i in items {
+                    i.serialize(buffer);
+                }
+            }
+            Jsonb::Object(pairs) => {
+                buffer.put_u8(TAG_OBJECT);
+                encode_size(buffer, pairs.len());
+                for (key, value) in pairs {
+                    Self::serialize_string(key, buffer);
+                    value.serialize(buffer);
+                }
+            }
+        }
+    }
+
+    fn serialize_string(value: &str, buffer: &mut impl BufMut) {
+        encode_size(buffer, value.len());
+        buffer.put_slice(value.as_bytes());
+    }
+
+    pub fn deserialize(buffer: &mut impl Buf) -> Result<Jsonb, JsonbError> {
+        Self::deserialize_nested(buffer, 0)
+    }
+
+    /// Depth counts the arrays and objects this value is inside of
+    fn deserialize_nested(buffer: &mut impl Buf, depth: usize) -> Result<Jsonb, JsonbError> {
+        if !buffer.has_remaining() {
+            return Err(JsonbError::InsufficentBuffer(1, 0));
+        }
+        Ok(match buffer.get_u8() {
+            TAG_NULL => Jsonb::Null,
+            TAG_FALSE => Jsonb::Bool(false),
+            TAG_TRUE => Jsonb::Bool(true),
+            TAG_NUMBER => Jsonb::Number(Numeric::deserialize(buffer)?),
+            TAG_STRING => Jsonb::String(Self::deserialize_string(buffer)?),
+            TAG_ARRAY | TAG_OBJECT if depth >= MAX_DEPTH => {
+                return Err(JsonbError::TooDeep(MAX_DEPTH))
+            }
+            TAG_ARRAY => {
+                let count = parse_size(buffer)?;
+                let mut items = Vec::with_capacity(count.min(buffer.remaining()));
+                for _ in 0..count {
+                    items.push(Self::deserialize_nested(buffer, depth + 1)?);
+                }
+                Jsonb::Array(items)
+            }
+            TAG_OBJECT => {
+                let count = parse_size(buffer)?;
+                let mut pairs = Vec::with_capacity(count.min(buffer.remaining()));
+                for _ in 0..count {
+                    let key = Self::deserialize_string(buffer)?;
+                    pairs.push((key, Self::deserialize_nested(buffer, depth + 1)?));
+                }
+                Jsonb::Object(pairs)
+            }
+            tag => return Err(JsonbError::InvalidTag(tag)),
+        })
+    }
+
+    fn deserialize_string(buffer: &mut impl Buf) -> Result<String, JsonbError> {
+        let length = parse_size(buffer)?;
+        if length > buffer.remaining() {
+            return Err(JsonbError::InsufficentBuffer(length, buffer.remaining()));
+        }
+        let bytes = buffer.copy_to_bytes(length);
+        Ok(std::str::from_utf8(&bytes)
+            .map_err(|_| JsonbError::NotUtf8())?
+            .to_string())
+    }
+
+    pub fn encoded_size(&self) -> usize {
+        let string_size = |s: &str| expected_encoded_size(s.len()) + s.len();
+        1 + match self {
+            Jsonb::Null | Jsonb::Bool(_) => 0,
+            Jsonb::Number(n) => n.encoded_size(),
+            Jsonb::String(s) => string_size(s),
+            Jsonb::Array(items) => {
+                expected_encoded_size(items.len())
+                    + items.iter().map(|i| i.encoded_size()).sum::<usize>()
+            }
+            Jsonb::Object(pairs) => {
+                expected_encoded_size(pairs.len())
+                    + pairs
+                        .iter()
+                        .map(|(k, v)| string_size(k) + v.encoded_size())
+                        .sum::<usize>()
+            }
+        }
+    }
+
+    fn write_string(value: &str, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "\"")?;
+        for c in value.chars() {
+            match c {
+                '"' => write!(f, "\\\"")?,
+                '\\' => write!(f, "\\\\")?,
+                '\n' => write!(f, "\\n")?,
+                '\r' => write!(f, "\\r")?,
+                '\t' => write!(f, "\\t")?,
+                '\u{8}' => write!(f, "\\b")?,
+                '\u{c}' => write!(f, "\\f")?,
+                c if c.is_control() && (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+impl FromStr for Jsonb {
+    type Err = JsonbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = JsonParser {
+            input: s,
+            chars: s.char_indices().peekable(),
+        };
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+        match parser.chars.peek() {
+            None => Ok(value),
+            Some(_) => Err(parser.invalid()),
+        }
+    }
+}
+
+/// Recursive descent over the json grammar: https://www.json.org/json-en.html
+struct JsonParser<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> JsonParser<'a> {
+    fn invalid(&self) -> JsonbError {
+        JsonbError::InvalidJson(self.input.to_string())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .next_if(|(_, c)| matches!(c, ' ' | '\t' | '\n' | '\r'))
+            .is_some()
+        {}
+    }
+
+    fn expect(&mut self, wanted: char) -> Result<(), JsonbError> {
+        match self.chars.next() {
+            Some((_, c)) if c == wanted => Ok(()),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Jsonb) -> Result<Jsonb, JsonbError> {
+        for wanted in word.chars() {
+            self.expect(wanted)?;
+        }
+        Ok(value)
+    }
+
+    /// Depth counts the arrays and objects this value is inside of
+    fn value(&mut self, depth: usize) -> Result<Jsonb, JsonbError> {
+        self.skip_whitespace();
+        let first = match self.chars.peek() {
+            Some((_, c)) => *c,
+            None => return Err(self.invalid()),
+        };
+        match first {
+            '{' => self.object(depth),
+            '[' => self.array(depth),
+            '"' => Ok(Jsonb::String(self.string()?)),
+            't' => self.keyword("true", Jsonb::Bool(true)),
+            'f' => self.keyword("false", Jsonb::Bool(false)),
+            'n' => self.keyword("null", Jsonb::Null),
+            '-' | '0'..='9' => self.number(),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Jsonb, JsonbError> {
+        if depth >= MAX_DEPTH {
+            return Err(self.invalid());
+        }
+        self.expect('{')?;
+        let mut pairs = vec![];
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+            return Ok(Jsonb::object(pairs));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            pairs.push((key, self.value(depth + 1)?));
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, '}')) => return Ok(Jsonb::object(pairs)),
+                _ => return Err(self.invalid()),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Jsonb, JsonbError> {
+        if depth >= MAX_DEPTH {
+            return Err(self.invalid());
+        }
+        self.expect('[')?;
+        let mut items = vec![];
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+            return Ok(Jsonb::Array(items));
+        }
+        loop {
+            items.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, ']')) => return Ok(Jsonb::Array(items)),
+                _ => return Err(self.invalid()),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonbError> {
+        self.expect('"')?;
+        let mut output = String::new();
+        loop {
+            let c = match self.chars.next() {
+                Some((_, c)) => c,
+                None => return Err(self.invalid()),
+            };
+            match c {
+                '"' => return Ok(output),
+                '\\' => {
+                    let escaped = match self.chars.next() {
+                        Some((_, c)) => c,
+                        None => return Err(self.invalid()),
+                    };
+                    output.push(match escaped {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => self.unicode_escape()?,
+                        _ => return Err(self.invalid()),
+                    });
+                }
+                c if (c as u32) < 0x20 => return Err(self.invalid()),
+                c => output.push(c),
+            }
+        }
+    }
+
+    /// Characters outside the basic plane are written as a surrogate pair of escapes
+    fn unicode_escape(&mut self) -> Result<char, JsonbError> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.invalid());
+        }
+        self.expect('\\')?;
+        self.expect('u')?;
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.invalid());
+        }
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+            .ok_or_else(|| self.invalid())
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonbError> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let digit = match self.chars.next() {
+                Some((_, c)) => c.to_digit(16),
+                None => None,
+            };
+            value = value * 16 + digit.ok_or_else(|| self.invalid())?;
+        }
+        Ok(value)
+    }
+
+    /// Json is stricter than numeric about what a number looks like, so check that before parsing
+    fn number(&mut self) -> Result<Jsonb, JsonbError> {
+        let start = match self.chars.peek() {
+            Some((i, _)) => *i,
+            None => return Err(self.invalid()),
+        };
+        self.chars.next_if(|(_, c)| *c == '-');
+        match self.chars.next() {
+            Some((_, '0')) => {}
+            Some((_, '1'..='9')) => self.digits(),
+            _ => return Err(self.invalid()),
+        }
+        if self.chars.next_if(|(_, c)| *c == '.').is_some() {
+            self.required_digits()?;
+        }
+        if self
+            .chars
+            .next_if(|(_, c)| matches!(c, 'e' | 'E'))
+            .is_some()
+        {
+            self.chars.next_if(|(_, c)| matches!(c, '+' | '-'));
+            self.required_digits()?;
+        }
+        let end = self
+            .chars
+            .peek()
+            .map(|(i, _)| *i)
+            .unwrap_or_else(|| self.input.len());
+        Ok(Jsonb::Number(self.input[start..end].parse::<Numeric>()?))
+    }
+
+    fn digits(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_ascii_digit()).is_some() {}
+    }
+
+    fn required_digits(&mut self) -> Result<(), JsonbError> {
+        match self.chars.next_if(|(_, c)| c.is_ascii_digit()) {
+            Some(_) => {
+                self.digits();
+                Ok(())
+            }
+            None => Err(self.invalid()),
+        }
+    }
+}
+
+/// What is left to write when displaying a jsonb value
+enum Pending<'a> {
+    Value(&'a Jsonb),
+    Key(&'a str),
+    Text(&'static str),
+}
+
+impl Display for Jsonb {
+    /// Matches postgres' output, a space after each colon and comma.
+    ///
+    /// Works from a stack instead of recursing since a display error would panic in to_string,
+    /// so nesting that jsonb_agg built up past MAX_DEPTH still prints.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut pending = vec![Pending::Value(self)];
+        while let Some(next) = pending.pop() {
+            match next {
+                Pending::Text(t) => write!(f, "{}", t)?,
+                Pending::Key(k) => {
+                    Jsonb::write_string(k, f)?;
+                    write!(f, ": ")?;
+                }
+                Pending::Value(Jsonb::Null) => write!(f, "null")?,
+                Pending::Value(Jsonb::Bool(b)) => write!(f, "{}", b)?,
+                Pending::Value(Jsonb::Number(n)) => write!(f, "{}", n)?,
+                Pending::Value(Jsonb::String(s)) => Jsonb::write_string(s, f)?,
+                Pending::Value(Jsonb::Array(items)) => {
+                    write!(f, "[")?;
+                    pending.push(Pending::Text("]"));
+                    for (i, item) in items.iter().enumerate().rev() {
+                        pending.push(Pending::Value(item));
+                        if i > 0 {
+                            pending.push(Pending::Text(", "));
+                        }
+                    }
+                }
+                Pending::Value(Jsonb::Object(pairs)) => {
+                    write!(f, "{{")?;
+                    pending.push(Pending::Text("}"));
+                    for (i, (key, value)) in pairs.iter().enumerate().rev() {
+                        pending.push(Pending::Value(value));
+                        pending.push(Pending::Key(key));
+                        if i > 0 {
+                            pending.push(Pending::Text(", "));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum JsonbError {
+    #[error("Length needed {0}, length found {1}")]
+    InsufficentBuffer(usize, usize),
+    #[error("Invalid input syntax for type json: \"{0}\"")]
+    InvalidJson(String),
+    #[error("Malformed json path: \"{0}\"")]
+    InvalidPath(String),
+    #[error("Unknown jsonb tag {0}")]
+    InvalidTag(u8),
+    #[error("Stored jsonb string is not utf8")]
+    NotUtf8(),
+    #[error(transparent)]
+    NumericError(#[from] NumericError),
+    #[error(transparent)]
+    SizeError(#[from] SizeError),
+    #[error("Stored jsonb is nested more than {0} levels deep")]
+    TooDeep(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+
+    fn json(s: &str) -> Jsonb {
+        s.parse::<Jsonb>().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() -> Result<(), Box<dyn std::error::Error>> {
+        let cases = [
+            ("null", "null"),
+            (" true ", "true"),
+            ("-1.50", "-1.50"),
+            ("1e3", "1000"),
+            (r#""a\"b\\c\n\u00e9\ud83d\ude00""#, "\"a\\\"b\\\\c\\né😀\""),
+            ("[1,[],{}]", "[1, [], {}]"),
+            (
+                r#"{"bb": 1, "a": {"c": null}, "bb": [true]}"#,
+                r#"{"a": {"c": null}, "bb": [true]}"#,
+            ),
+        ];
+        for (input, output) in cases {
+            assert_eq!(input.parse::<Jsonb>()?.to_string(), output);
+        }
+
+        for bad in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "01",
+            "1.",
+            "-",
+            "tru",
+            "'a'",
+            "1 2",
+            "\"\\x\"",
+        ] {
+            assert!(bad.parse::<Jsonb>().is_err(), "{} should not parse", bad);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let value = json(r#"{"id": 7, "tags": ["x", "y"], "ok": false, "none": null}"#);
+        let mut buffer = BytesMut::with_capacity(value.encoded_size());
+        value.serialize(&mut buffer);
+        assert_eq!(buffer.len(), value.encoded_size());
+
+        let mut buffer = buffer.freeze();
+        assert_eq!(Jsonb::deserialize(&mut buffer)?, value);
+        Ok(())
+    }
+
+    #[test]
+    fn test_depth_limit() -> Result<(), Box<dyn std::error::Error>> {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let deepest = json(&nested(MAX_DEPTH));
+        assert_eq!(deepest.to_string(), nested(MAX_DEPTH));
+        json(&format!(
+            "{}{{\"a\": 1}}{}",
+            "[".repeat(MAX_DEPTH - 1),
+            "]".repeat(MAX_DEPTH - 1)
+        ));
+        assert!(matches!(
+            nested(MAX_DEPTH + 1).parse::<Jsonb>(),
+            Err(JsonbError::InvalidJson(_))
+        ));
+
+        let mut buffer = BytesMut::new();
+        deepest.serialize(&mut buffer);
+        assert_eq!(Jsonb::deserialize(&mut buffer.freeze())?, deepest);
+
+        let too_deep = Jsonb::Array(vec![deepest]);
+        assert_eq!(too_deep.to_string(), nested(MAX_DEPTH + 1));
+        let mut buffer = BytesMut::new();
+        too_deep.serialize(&mut buffer);
+        assert!(matches!(
+            Jsonb::deserialize(&mut buffer.freeze()),
+            Err(JsonbError::TooDeep(MAX_DEPTH))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_operators() -> Result<(), Box<dyn std::error::Error>> {
+        let value = json(r#"{"a": {"b": [1, "two", {"c": 3}]}, "d": "e"}"#);
+        assert_eq!(value.field("d"), Some(&json("\"e\"")));
+        assert_eq!(value.field("z"), None);
+        assert_eq!(
+            value.path(&Jsonb::parse_path("{a, b, -1, c}")?),
+            Some(&json("3"))
+        );
+        assert_eq!(value.path(&Jsonb::parse_path("{a,b,x}")?), None);
+        assert_eq!(
+            value
+                .field("a")
+                .and_then(|a| a.field("b"))
+                .and_then(|b| b.element(1)),
+            Some(&json("\"two\""))
+        );
+        assert_eq!(json("\"e\"").as_text(), Some("e".to_string()));
+        assert_eq!(json("null").as_text(), None);
+        assert_eq!(json("[1]").as_text(), Some("[1]".to_string()));
+
+        assert!(value.has_key("a"));
+        assert!(!value.has_key("b"));
+        assert!(json(r#"["a", 1]"#).has_key("a"));
+
+        assert!(value.contains(&json(r#"{"a": {"b": [{"c": 3}]}}"#)));
+        assert!(value.contains(&json("{}")));
+        assert!(!value.contains(&json(r#"{"a": {"b": [4]}}"#)));
+        assert!(json("[1, 2, [3]]").contains(&json("[[3], 1, 1]")));
+        assert!(json(r#"["foo", "bar"]"#).contains(&json("\"foo\"")));
+        assert!(!json("[[1]]").contains(&json("1")));
+
+        assert_eq!(Jsonb::parse_path(r#"{"a,b", c}"#)?, vec!["a,b", "c"]);
+        assert!(Jsonb::parse_path("a,b").is_err());
+        assert!(Jsonb::parse_path("{a,,b}").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_value() -> Result<(), Box<dyn std::error::Error>> {
+        use super::super::SqlFloat;
+        assert_eq!(Jsonb::from_value(None)?, Jsonb::Null);
+        assert_eq!(
+            Jsonb::from_value(Some(&BaseSqlTypes::Integer(-4)))?,
+            json("-4")
+        );
+        assert_eq!(
+            Jsonb::from_value(Some(&BaseSqlTypes::Double(SqlFloat(f64::NAN))))?,
+            json("\"NaN\"")
+        );
+        assert_eq!(
+            Jsonb::from_value(Some(&BaseSqlTypes::Text("hi".to_string())))?,
+            json("\"hi\"")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,685 @@
+//! Exact decimal numbers for the numeric / decimal type, see here: https://www.postgresql.org/docs/current/datatype-numeric.html#DATATYPE-NUMERIC-DECIMAL
+//!
+//! Values are kept as their unscaled decimal digits plus how many of those digits are after the
+//! decimal point, so nothing is ever approximated the way a float would be.
+use crate::engine::io::{encode_size, expected_encoded_size, parse_size, SizeError};
+use bytes::{Buf, BufMut};
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
+use std::str::FromStr;
+use thiserror::Error;
+
+///Division keeps at least this many significant digits, same as postgres
+const MIN_SIGNIFICANT_DIGITS: i64 = 16;
+///Bounds how far division and exponents can push the scale, same as postgres
+const MAX_SCALE: i64 = 1000;
+
+const FLAG_POSITIVE: u8 = 0x0;
+const FLAG_NEGATIVE: u8 = 0x1;
+const FLAG_NAN: u8 = 0x2;
+///Zero has no digits to count, only a scale
+const FLAG_ZERO: u8 = 0x3;
+
+#[derive(Clone, Debug)]
+pub enum Numeric {
+    ///Same as postgres, NaN equals itself and sorts above every other value
+    NaN,
+    Finite {
+        negative: bool,
+        ///Unscaled digits, most significant first without leading zeros so zero is empty
+        digits: Vec<u8>,
+        ///How many of the digits are after the decimal point
+        scale: u16,
+    },
+}
+
+impl Numeric {
+    fn finite(negative: bool, mut digits: Vec<u8>, scale: u16) -> Numeric {
+        let leading = digits.iter().take_while(|d| **d == 0).count();
+        digits.drain(..leading);
+        Numeric::Finite {
+            negative: negative && !digits.is_empty(),
+            digits,
+            scale,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        matches!(self, Numeric::Finite { digits, .. } if digits.is_empty())
+    }
+
+    pub fn negate(&self) -> Numeric {
+        match self {
+            Numeric::NaN => Numeric::NaN,
+            Numeric::Finite {
+                negative,
+                digits,
+                scale,
+            } => Numeric::finite(!negative, digits.clone(), *scale),
+        }
+    }
+
+    pub fn checked_add(&self, right: &Numeric) -> Result<Numeric, NumericError> {
+        let (l_neg, l_digits, l_scale, r_neg, r_digits, r_scale) = match (self, right) {
+            (
+                Numeric::Finite {
+                    negative: l_neg,
+                    digits: l_digits,
+                    scale: l_scale,
+                },
+                Numeric::Finite {
+                    negative: r_neg,
+                    digits: r_digits,
+                    scale: r_scale,
+                },
+            ) => (l_neg, l_digits, l_scale, r_neg, r_digits, r_scale),
+            (_, _) => return Ok(Numeric::NaN),
+        };
+
+        let scale = *l_scale.max(r_scale);
+        let left = align(l_digits, *l_scale, scale);
+        let right = align(r_digits, *r_scale, scale);
+
+        if l_neg == r_neg {
+            return Ok(Numeric::finite(*l_neg, add_magnitude(&left, &right), scale));
+        }
+        match compare_magnitude(&left, &right) {
+            Ordering::Less => Ok(Numeric::finite(*r_neg, sub_magnitude(&right, &left), scale)),
+            _ => Ok(Numeric::finite(*l_neg, sub_magnitude(&left, &right), scale)),
+        }
+    }
+
+    pub fn checked_sub(&self, right: &Numeric) -> Result<Numeric, NumericError> {
+        self.checked_add(&right.negate())
+    }
+
+    pub fn checked_mul(&self, right: &Numeric) -> Result<Numeric, NumericError> {
+        match (self, right) {
+            (
+                Numeric::Finite {
+                    negative: l_neg,
+                    digits: l_digits,
+                    scale: l_scale,
+                },
+                Numeric::Finite {
+                    negative: r_neg,
+                    digits: r_digits,
+                    scale: r_scale,
+                },
+            ) => {
+                let scale = i64::from(*l_scale) + i64::from(*r_scale);
+                let product = Numeric::finite(
+                    l_neg != r_neg,
+                    mul_magnitude(l_digits, r_digits),
+                    checked_scale(scale)?,
+                );
+                Ok(product)
+            }
+            (_, _) => Ok(Numeric::NaN),
+        }
+    }
+
+    /// The result keeps at least 16 significant digits and at least as many decimal places as
+    /// either side, the last digit is rounded half away from zero.
+    pub fn checked_div(&self, right: &Numeric) -> Result<Numeric, NumericError> {
+        if right.is_zero() {
+            return Err(NumericError::DivisionByZero());
+        }
+        match (self, right) {
+            (
+                Numeric::Finite {
+                    negative: l_neg,
+                    digits: l_digits,
+                    scale: l_scale,
+                },
+                Numeric::Finite {
+                    negative: r_neg,
+                    digits: r_digits,
+                    scale: r_scale,
+                },
+            ) => {
+                //Weight is where the first digit sits relative to the decimal point
+                let l_weight = l_digits.len() as i64 - i64::from(*l_scale);
+                let r_weight = r_digits.len() as i64 - i64::from(*r_scale);
+                let scale = (MIN_SIGNIFICANT_DIGITS - (l_weight - r_weight))
+                    .max(i64::from(*l_scale))
+                    .max(i64::from(*r_scale))
+                    .clamp(0, MAX_SCALE);
+
+                //Compute one extra digit so the result can be rounded
+                let shift = scale + 1 + i64::from(*r_scale) - i64::from(*l_scale);
+                let mut dividend = l_digits.clone();
+                dividend.extend(std::iter::repeat_n(0, shift as usize));
+                let quotient = Numeric::finite(
+                    l_neg != r_neg,
+                    div_magnitude(&dividend, r_digits),
+                    checked_scale(scale + 1)?,
+                );
+                Ok(quotient.round(checked_scale(scale)?))
+            }
+            (_, _) => Ok(Numeric::NaN),
+        }
+    }
+
+    /// Changes the number of decimal places, dropped digits round half away from zero
+    pub fn round(&self, new_scale: u16) -> Numeric {
+        let (negative, digits, scale) = match self {
+            Numeric::NaN => return Numeric::NaN,
+            Numeric::Finite {
+                negative,
+                digits,
+                scale,
+            } => (*negative, digits, *scale),
+        };
+
+        if new_scale >= scale {
+            return Numeric::finite(negative, align(digits, scale, new_scale), new_scale);
+        }
+
+        let dropped = usize::from(scale - new_scale);
+        if dropped > digits.len() {
+            return Numeric::finite(negative, vec![], new_scale);
+        }
+        let (kept, rest) = digits.split_at(digits.len() - dropped);
+        let kept = match rest.first() {
+            Some(d) if *d >= 5 => add_magnitude(kept, &[1]),
+            _ => kept.to_vec(),
+        };
+        Numeric::finite(negative, kept, new_scale)
+    }
+
+    /// Fits the value into a numeric(precision, scale) column, failing if the integer part is too large
+    pub fn fit(&self, precision: u16, scale: u16) -> Result<Numeric, NumericError> {
+        let rounded = self.round(scale);
+        if let Numeric::Finite { digits, .. } = &rounded {
+            if digits.len() > usize::from(precision) {
+                return Err(NumericError::Overflow(precision, scale));
+            }
+        }
+        Ok(rounded)
+    }
+
+    pub fn serialize(&self, buffer: &mut impl BufMut) {
+        match self {
+            Numeric::NaN => buffer.put_u8(FLAG_NAN),
+            Numeric::Finite {
+                negative,
+                digits,
+                scale,
+            } => {
+                if digits.is_empty() {
+                    buffer.put_u8(FLAG_ZERO);
+                    buffer.put_u16_le(*scale);
+                    return;
+                }
+                buffer.put_u8(match negative {
+                    true => FLAG_NEGATIVE,
+                    false => FLAG_POSITIVE,
+                });
+                buffer.put_u16_le(*scale);
+                encode_size(buffer, digits.len());
+
+                //Two digits to a byte, an odd count leaves the last low nibble empty
+                for pair in digits.chunks(2) {
+                    let low = pair.get(1).copied().unwrap_or(0);
+                    buffer.put_u8((pair[0] << 4) | low);
+                }
+            }
+        }
+    }
+
+    pub fn deserialize(buffer: &mut impl Buf) -> Result<Numeric, NumericError> {
+        if buffer.remaining() < size_of::<u8>() {
+            return Err(NumericError::InsufficentBuffer(
+                size_of::<u8>(),
+                buffer.remaining(),
+            ));
+        }
+        let flag = buffer.get_u8();
+        let negative = match flag {
+            FLAG_NAN => return Ok(Numeric::NaN),
+            FLAG_POSITIVE | FLAG_ZERO => false,
+            FLAG_NEGATIVE => true,
+            f => return Err(NumericError::InvalidFlag(f)),
+        };
+
+        if buffer.remaining() < size_of::<u16>() {
+            return Err(NumericError::InsufficentBuffer(
+                size_of::<u16>(),
+                buffer.remaining(),
+            ));
+        }
+        let scale = buffer.get_u16_le();
+        if flag == FLAG_ZERO {
+            return Ok(Numeric::finite(false, vec![], scale));
+        }
+
+        let count = parse_size(buffer)?;
+        let packed = count.div_ceil(2);
+        if buffer.remaining() < packed {
+            return Err(NumericError::InsufficentBuffer(packed, buffer.remaining()));
+        }
+
+        let mut digits = Vec::with_capacity(count);
+        for _ in 0..packed {
+            let pair = buffer.get_u8();
+            digits.push(pair >> 4);
+            digits.push(pair & 0xf);
+        }
+        digits.truncate(count);
+        if digits.iter().any(|d| *d > 9) {
+            return Err(NumericError::InvalidDigits());
+        }
+
+        Ok(Numeric::finite(negative, digits, scale))
+    }
+
+    pub fn encoded_size(&self) -> usize {
+        match self {
+            Numeric::NaN => size_of::<u8>(),
+            Numeric::Finite { digits, .. } if digits.is_empty() => {
+                size_of::<u8>() + size_of::<u16>()
+            }
+            Numeric::Finite { digits, .. } => {
+                size_of::<u8>()
+                    + size_of::<u16>()
+                    + expected_encoded_size(digits.len())
+                    + digits.len().div_ceil(2)
+            }
+        }
+    }
+}
+
+/// Appends zeros so the digits have the new, larger, scale
+fn align(digits: &[u8], scale: u16, new_scale: u16) -> Vec<u8> {
+    let mut aligned = digits.to_vec();
+    if !aligned.is_empty() {
+        aligned.extend(std::iter::repeat_n(0, usize::from(new_scale - scale)));
+    }
+    aligned
+}
+
+fn checked_scale(scale: i64) -> Result<u16, NumericError> {
+    match scale {
+        s if (0..=MAX_SCALE).contains(&s) => Ok(s as u16),
+        s => Err(NumericError::ScaleOutOfRange(s)),
+    }
+}
+
+/// Both sides must be free of leading zeros
+fn compare_magnitude(left: &[u8], right: &[u8]) -> Ordering {
+    left.len().cmp(&right.len()).then_with(|| left.cmp(right))
+}
+
+fn add_magnitude(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(left.len().max(right.len()) + 1);
+    let mut carry = 0;
+    let mut l = left.iter().rev();
+    let mut r = right.iter().rev();
+    loop {
+        let (ld, rd) = (l.next(), r.next());
+        if ld.is_none() && rd.is_none() {
+            break;
+        }
+        let sum = ld.unwrap_or(&0) + rd.unwrap_or(&0) + carry;
+        result.push(sum % 10);
+        carry = sum / 10;
+    }
+    if carry > 0 {
+        result.push(carry);
+    }
+    result.reverse();
+    result
+}
+
+/// Left must be at least as large as right, the result can have leading zeros
+fn sub_magnitude(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(left.len());
+    let mut borrow = 0;
+    let mut r = right.iter().rev();
+    for ld in left.iter().rev() {
+        let subtrahend = r.next().unwrap_or(&0) + borrow;
+        if *ld >= subtrahend {
+            result.push(ld - subtrahend);
+            borrow = 0;
+        } else {
+            result.push(ld + 10 - subtrahend);
+            borrow = 1;
+        }
+    }
+    result.reverse();
+    result
+}
+
+fn mul_magnitude(left: &[u8], right: &[u8]) -> Vec<u8> {
+    if left.is_empty() || right.is_empty() {
+        return vec![];
+    }
+    let mut result = vec![0u32; left.len() + right.len()];
+    for (i, ld) in left.iter().enumerate().rev() {
+        for (j, rd) in right.iter().enumerate().rev() {
+            result[i + j + 1] += u32::from(*ld) * u32::from(*rd);
+        }
+    }
+    for i in (1..result.len()).rev() {
+        result[i - 1] += result[i] / 10;
+        result[i] %= 10;
+    }
+    result.into_iter().map(|d| d as u8).collect()
+}
+
+/// Schoolbook long division, the remainder is thrown away
+fn div_magnitude(dividend: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut quotient = Vec::with_capacity(dividend.len());
+    let mut remainder: Vec<u8> = vec![];
+    for d in dividend {
+        remainder.push(*d);
+        let leading = remainder.iter().take_while(|d| **d == 0).count();
+        remainder.drain(..leading);
+
+        let mut count = 0;
+        while compare_magnitude(&remainder, divisor) != Ordering::Less {
+            remainder = sub_magnitude(&remainder, divisor);
+            let leading = remainder.iter().take_while(|d| **d == 0).count();
+            remainder.drain(..leading);
+            count += 1;
+        }
+        quotient.push(count);
+    }
+    quotient
+}
+
+impl From<i64> for Numeric {
+    fn from(value: i64) -> Self {
+        Numeric::from(i128::from(value))
+    }
+}
+
+impl From<i128> for Numeric {
+    fn from(value: i128) -> Self {
+        let digits = value
+            .unsigned_abs()
+            .to_string()
+            .bytes()
+            .map(|b| b - b'0')
+            .collect();
+        Numeric::finite(value < 0, digits, 0)
+    }
+}
+
+impl FromStr for Numeric {
+    type Err = NumericError;
+
+    /// Accepts the same forms as postgres: an optional sign, digits with an optional
+    /// decimal point, an optional exponent or NaN.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || NumericError::InvalidNumeric(s.to_string());
+        let trimmed = s.trim();
+        if trimmed.eq_ignore_ascii_case("nan") {
+            return Ok(Numeric::NaN);
+        }
+
+        let (negative, unsigned) = match trimmed.as_bytes().first() {
+            Some(b'-') => (true, &trimmed[1..]),
+            Some(b'+') => (false, &trimmed[1..]),
+            _ => (false, trimmed),
+        };
+        let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+            Some(i) => (
+                &unsigned[..i],
+                unsigned[i + 1..].parse::<i64>().map_err(|_| invalid())?,
+            ),
+            None => (unsigned, 0),
+        };
+        let (integer, fraction) = match mantissa.split_once('.') {
+            Some((i, f)) => (i, f),
+            None => (mantissa, ""),
+        };
+        if (integer.is_empty() && fraction.is_empty())
+            || !integer
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let mut digits: Vec<u8> = integer
+            .bytes()
+            .chain(fraction.bytes())
+            .map(|b| b - b'0')
+            .collect();
+        let scale = (fraction.len() as i64).saturating_sub(exponent);
+        if scale < 0 {
+            if -scale > MAX_SCALE {
+                return Err(NumericError::ScaleOutOfRange(scale));
+            }
+            digits.extend(std::iter::repeat_n(0, -scale as usize));
+        }
+
+        Ok(Numeric::finite(
+            negative,
+            digits,
+            checked_scale(scale.max(0))?,
+        ))
+    }
+}
+
+impl Display for Numeric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (negative, digits, scale) = match self {
+            Numeric::NaN => return write!(f, "NaN"),
+            Numeric::Finite {
+                negative,
+                digits,
+                scale,
+            } => (*negative, digits, usize::from(*scale)),
+        };
+
+        let mut text: String = digits.iter().map(|d| char::from(b'0' + d)).collect();
+        if text.len() <= scale {
+            text = "0".repeat(scale + 1 - text.len()) + &text;
+        }
+        if scale > 0 {
+            text.insert(text.len() - scale, '.');
+        }
+        if negative {
+            text.insert(0, '-');
+        }
+        write!(f, "{}", text)
+    }
+}
+
+impl Ord for Numeric {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Numeric::NaN, Numeric::NaN) => Ordering::Equal,
+            (Numeric::NaN, _) => Ordering::Greater,
+            (_, Numeric::NaN) => Ordering::Less,
+            (
+                Numeric::Finite {
+                    negative: l_neg,
+                    digits: l_digits,
+                    scale: l_scale,
+                },
+                Numeric::Finite {
+                    negative: r_neg,
+                    digits: r_digits,
+                    scale: r_scale,
+                },
+            ) => {
+                if l_neg != r_neg {
+                    return match l_neg {
+                        true => Ordering::Less,
+                        false => Ordering::Greater,
+                    };
+                }
+                let scale = *l_scale.max(r_scale);
+                let magnitude = compare_magnitude(
+                    &align(l_digits, *l_scale, scale),
+                    &align(r_digits, *r_scale, scale),
+                );
+                match l_neg {
+                    true => magnitude.reverse(),
+                    false => magnitude,
+                }
+            }
+        }
+    }
+}
+
+impl PartialOrd for Numeric {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Numeric {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Numeric {}
+
+impl Hash for Numeric {
+    /// 1.5 and 1.50 are equal so trailing zeros after the decimal point can't be part of the hash
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Numeric::NaN => state.write_u8(FLAG_NAN),
+            Numeric::Finite {
+                negative,
+                digits,
+                scale,
+            } => {
+                let trailing = digits
+                    .iter()
+                    .rev()
+                    .take_while(|d| **d == 0)
+                    .count()
+                    .min(usize::from(*scale));
+                negative.hash(state);
+                digits[..digits.len() - trailing].hash(state);
+                (usize::from(*scale) - trailing).hash(state);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum NumericError {
+    #[error("Division by zero")]
+    DivisionByZero(),
+    #[error("Length needed {0}, length found {1}")]
+    InsufficentBuffer(usize, usize),
+    #[error("Stored numeric has a digit larger than 9")]
+    InvalidDigits(),
+    #[error("Unknown numeric flag {0}")]
+    InvalidFlag(u8),
+    #[error("Invalid input syntax for type numeric: {0}")]
+    InvalidNumeric(String),
+    #[error("Numeric field overflow, a field with precision {0} and scale {1} is too small")]
+    Overflow(u16, u16),
+    #[error("Numeric scale {0} is outside of the supported range")]
+    ScaleOutOfRange(i64),
+    #[error(transparent)]
+    SizeError(#[from] SizeError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+
+    fn num(s: &str) -> Numeric {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_text_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        for (input, output) in [
+            ("0", "0"),
+            ("-0.00", "0.00"),
+            ("12.50", "12.50"),
+            ("-0.05", "-0.05"),
+            (".5", "0.5"),
+            ("+7.", "7"),
+            ("1.5e3", "1500"),
+            ("25e-3", "0.025"),
+            (
+                "123456789012345678901234567890.123",
+                "123456789012345678901234567890.123",
+            ),
+            ("nan", "NaN"),
+        ] {
+            assert_eq!(input.parse::<Numeric>()?.to_string(), output);
+        }
+
+        for bad in ["", "-", ".", "1.2.3", "abc", "1e", "--1"] {
+            assert!(bad.parse::<Numeric>().is_err(), "{} should not parse", bad);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert_eq!(num("1.5"), num("1.500"));
+        assert!(num("-2") < num("-1.99"));
+        assert!(num("-0.1") < num("0"));
+        assert!(num("10") > num("9.999"));
+        assert!(num("NaN") > num("1e100"));
+        assert_eq!(num("NaN"), num("NaN"));
+    }
+
+    #[test]
+    fn test_arithmetic() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(num("0.1").checked_add(&num("0.2"))?.to_string(), "0.3");
+        assert_eq!(num("1.05").checked_sub(&num("2"))?.to_string(), "-0.95");
+        assert_eq!(num("-1.5").checked_mul(&num("2.25"))?.to_string(), "-3.375");
+        assert_eq!(
+            num("1").checked_div(&num("3"))?.to_string(),
+            "0.3333333333333333"
+        );
+        assert_eq!(
+            num("2").checked_div(&num("-3"))?.to_string(),
+            "-0.6666666666666667"
+        );
+        assert_eq!(
+            num("99999999999999999999")
+                .checked_add(&num("1"))?
+                .to_string(),
+            "100000000000000000000"
+        );
+        assert_eq!(num("NaN").checked_add(&num("1"))?, Numeric::NaN);
+        assert!(matches!(
+            num("1").checked_div(&num("0.00")),
+            Err(NumericError::DivisionByZero())
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fit() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(num("1.005").fit(5, 2)?.to_string(), "1.01");
+        assert_eq!(num("-1.004").fit(5, 2)?.to_string(), "-1.00");
+        assert!(num("999.995").fit(5, 2).is_err());
+        assert_eq!(num("12").fit(5, 2)?.to_string(), "12.00");
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        for value in ["0", "0.00", "-123.45", "7", "0.001", "NaN"] {
+            let value = num(value);
+            let mut buffer = BytesMut::new();
+            value.serialize(&mut buffer);
+            assert_eq!(buffer.len(), value.encoded_size());
+
+            let parsed = Numeric::deserialize(&mut buffer.freeze())?;
+            assert_eq!(parsed.to_string(), value.to_string());
+        }
+        Ok(())
+    }
+}
@@ -2,41 +2,163 @@ use std::sync::Arc;
 
 use nom::{
     branch::alt,
-    bytes::complete::tag_no_case,
+    bytes::complete::{tag, tag_no_case},
+    character::complete::{multispace1, satisfy},
+    combinator::not,
     error::{make_error, ContextError, ErrorKind, ParseError},
+    multi::many0_count,
+    sequence::terminated,
     IResult,
 };
 
 use super::BaseSqlTypesMapper;
 
+/// Every spelling of the built in types, matched as whole words so bool doesn't match the front of
+/// boolean. A spelling has to come before any shorter one that starts with the same words, any
+/// whitespace can separate the words.
+const TYPE_NAMES: &[&str] = &[
+    "boolean",
+    "bool",
+    "smallint",
+    "int2",
+    "integer",
+    "int4",
+    "int",
+    "bigint",
+    "int8",
+    "real",
+    "float4",
+    "double precision",
+    "double",
+    "float8",
+    "float",
+    "numeric",
+    "decimal",
+    "date",
+    "timestamp with time zone",
+    "timestamp without time zone",
+    "timestamptz",
+    "timestamp",
+    "time without time zone",
+    "time",
+    "interval",
+    "text",
+    "character varying",
+    "varchar",
+    "character",
+    "char",
+    "bpchar",
+    "bytea",
+    "jsonb",
+    "json",
+    "uuid",
+];
+
 pub fn parse_type<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, BaseSqlTypesMapper, E> {
     let (input, matched) = alt((
-        tag_no_case("bool"),
-        tag_no_case("integer"),
-        tag_no_case("text"),
-        tag_no_case("uuid"),
-        tag_no_case("array(bool)"),
-        tag_no_case("array(integer)"),
-        tag_no_case("array(text)"),
-        tag_no_case("array(uuid)"),
+        parse_type_name,
+        alt((
+            tag_no_case("array(bool)"),
+            tag_no_case("array(smallint)"),
+            tag_no_case("array(integer)"),
+            tag_no_case("array(bigint)"),
+            tag_no_case("array(real)"),
+            tag_no_case("array(double)"),
+            tag_no_case("array(numeric)"),
+            tag_no_case("array(date)"),
+            tag_no_case("array(timestamptz)"),
+            tag_no_case("array(timestamp)"),
+            tag_no_case("array(time)"),
+            tag_no_case("array(interval)"),
+            tag_no_case("array(bytea)"),
+            tag_no_case("array(jsonb)"),
+            tag_no_case("array(text)"),
+            tag_no_case("array(uuid)"),
+        )),
     ))(input)?;
 
-    let sql_type = match matched {
-        "bool" => BaseSqlTypesMapper::Bool,
-        "integer" => BaseSqlTypesMapper::Integer,
-        "text" => BaseSqlTypesMapper::Text,
+    let sql_type = match matched.to_lowercase().as_str() {
+        "boolean" | "bool" => BaseSqlTypesMapper::Bool,
+        "smallint" | "int2" => BaseSqlTypesMapper::Smallint,
+        "integer" | "int4" | "int" => BaseSqlTypesMapper::Integer,
+        "bigint" | "int8" => BaseSqlTypesMapper::Bigint,
+        "real" | "float4" => BaseSqlTypesMapper::Real,
+        "double precision" | "double" | "float8" | "float" => BaseSqlTypesMapper::Double,
+        "numeric" | "decimal" => BaseSqlTypesMapper::Numeric,
+        "date" => BaseSqlTypesMapper::Date,
+        "time" | "time without time zone" => BaseSqlTypesMapper::Time,
+        "timestamp" | "timestamp without time zone" => BaseSqlTypesMapper::Timestamp,
+        "timestamptz" | "timestamp with time zone" => BaseSqlTypesMapper::TimestampTz,
+        "interval" => BaseSqlTypesMapper::Interval,
+        //The length limits of these are type modifiers on the column, see TypeModifier
+        "text" | "character varying" | "varchar" | "character" | "char" | "bpchar" => {
+            BaseSqlTypesMapper::Text
+        }
+        "bytea" => BaseSqlTypesMapper::Bytea,
+        //There is only the binary form, json columns are stored as jsonb
+        "jsonb" | "json" => BaseSqlTypesMapper::Jsonb,
         "uuid" => BaseSqlTypesMapper::Uuid,
         "array(bool)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Bool)),
+        "array(smallint)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Smallint)),
         "array(integer)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Integer)),
+        "array(bigint)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Bigint)),
+        "array(real)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Real)),
+        "array(double)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Double)),
+        "array(numeric)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Numeric)),
+        "array(date)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Date)),
+        "array(time)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Time)),
+        "array(timestamp)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Timestamp)),
+        "array(timestamptz)" => {
+            BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::TimestampTz))
+        }
+        "array(interval)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Interval)),
+        "array(bytea)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Bytea)),
+        "array(jsonb)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Jsonb)),
         "array(text)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Text)),
         "array(uuid)" => BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Uuid)),
         _ => {
             return Err(nom::Err::Failure(make_error(input, ErrorKind::Fix)));
         }
     };
-    Ok((input, sql_type))
+
+    //Same as postgres int[][] is the same type as int[], the dimensions are part of the value
+    let (input, dimensions) = many0_count(tag("[]"))(input)?;
+    match sql_type {
+        BaseSqlTypesMapper::Array(_) if dimensions > 0 => {
+            Err(nom::Err::Failure(make_error(input, ErrorKind::Fix)))
+        }
+        _ if dimensions > 0 => Ok((input, BaseSqlTypesMapper::Array(Arc::new(sql_type)))),
+        _ => Ok((input, sql_type)),
+    }
+}
+
+/// Matches the first of TYPE_NAMES that fits, giving back its spelling from the list
+fn parse_type_name<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    'names: for name in TYPE_NAMES {
+        let mut rest = input;
+        for (i, word) in name.split(' ').enumerate() {
+            if i > 0 {
+                match multispace1::<&'a str, E>(rest) {
+                    Ok((r, _)) => rest = r,
+                    Err(_) => continue 'names,
+                }
+            }
+            match terminated(
+                tag_no_case::<&'a str, &'a str, E>(word),
+                not(satisfy(|c: char| c.is_alphanumeric() || c == '_')),
+            )(rest)
+            {
+                Ok((r, _)) => rest = r,
+                Err(_) => continue 'names,
+            }
+        }
+        return Ok((rest, name));
+    }
+    Err(nom::Err::Error(make_error(input, ErrorKind::Tag)))
 }
 
 #[cfg(test)]
@@ -80,4 +202,34 @@ mod tests {
         Ok(())
     }
     */
+
+    #[test]
+    fn test_whole_words() -> Result<(), Box<dyn std::error::Error>> {
+        let (rest, res) = parse_type::<VerboseError<&str>>("boolean").finish()?;
+        assert_eq!((rest, res), ("", BaseSqlTypesMapper::Bool));
+        let (rest, res) = parse_type::<VerboseError<&str>>("int8[]").finish()?;
+        assert_eq!(
+            (rest, res),
+            (
+                "",
+                BaseSqlTypesMapper::Array(Arc::new(BaseSqlTypesMapper::Bigint))
+            )
+        );
+        let (rest, res) = parse_type::<VerboseError<&str>>("Character  Varying").finish()?;
+        assert_eq!((rest, res), ("", BaseSqlTypesMapper::Text));
+        let (rest, res) =
+            parse_type::<VerboseError<&str>>("timestamp with time zone '2020-01-01'").finish()?;
+        assert_eq!(
+            (rest, res),
+            (" '2020-01-01'", BaseSqlTypesMapper::TimestampTz)
+        );
+        let (rest, res) = parse_type::<VerboseError<&str>>("time zone").finish()?;
+        assert_eq!((rest, res), (" zone", BaseSqlTypesMapper::Time));
+
+        assert!(parse_type::<VerboseError<&str>>("integers")
+            .finish()
+            .is_err());
+        assert!(parse_type::<VerboseError<&str>>("int_id").finish().is_err());
+        Ok(())
+    }
 }
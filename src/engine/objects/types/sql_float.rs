@@ -0,0 +1,140 @@
+//! Floats have no total order but sorting and indexing need one.
+//!
+//! Same as postgres, NaN equals itself and sorts above every other value and -0 equals 0.
+//! See here: https://www.postgresql.org/docs/current/datatype-numeric.html#DATATYPE-FLOAT
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter, LowerExp};
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SqlFloat<T>(pub T);
+
+impl<T: Copy + Into<f64>> SqlFloat<T> {
+    pub fn as_f64(&self) -> f64 {
+        self.0.into()
+    }
+}
+
+impl<T: Copy + Into<f64>> Ord for SqlFloat<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (left, right) = (self.as_f64(), other.as_f64());
+        match (left.is_nan(), right.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => left.partial_cmp(&right).unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+impl<T: Copy + Into<f64>> PartialOrd for SqlFloat<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Copy + Into<f64>> PartialEq for SqlFloat<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Copy + Into<f64>> Eq for SqlFloat<T> {}
+
+impl<T: Copy + Into<f64>> Hash for SqlFloat<T> {
+    /// Every value that compares equal has to hash the same, so NaN and zero are made canonical
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let value = self.as_f64();
+        let bits = if value.is_nan() {
+            f64::NAN.to_bits()
+        } else if value == 0.0 {
+            0
+        } else {
+            value.to_bits()
+        };
+        state.write_u64(bits);
+    }
+}
+
+/// Same as postgres' float4out and float8out, the shortest digits that read back as the same value.
+/// Exponents below -4 or from the type's significant digits up (6 for real, 15 for double) switch
+/// to exponent notation, such as 1e+308.
+impl<T: Copy + Into<f64> + Display + LowerExp> Display for SqlFloat<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let value = self.as_f64();
+        if value.is_nan() {
+            return write!(f, "NaN");
+        } else if value.is_infinite() && value > 0.0 {
+            return write!(f, "Infinity");
+        } else if value.is_infinite() {
+            return write!(f, "-Infinity");
+        }
+
+        let significant_digits = match size_of::<T>() {
+            4 => 6,
+            _ => 15,
+        };
+        let scientific = format!("{:e}", self.0);
+        let (mantissa, exponent) = match scientific.split_once('e') {
+            Some((m, e)) => (m, e.parse::<i32>().map_err(|_| fmt::Error)?),
+            None => return write!(f, "{}", self.0),
+        };
+        if exponent < -4 || exponent >= significant_digits {
+            let sign = if exponent < 0 { '-' } else { '+' };
+            write!(f, "{}e{}{:02}", mantissa, sign, exponent.abs())
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash(value: SqlFloat<f64>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_total_order() {
+        let mut values = [
+            SqlFloat(f64::NAN),
+            SqlFloat(1.5),
+            SqlFloat(f64::INFINITY),
+            SqlFloat(-0.0),
+            SqlFloat(f64::NEG_INFINITY),
+        ];
+        values.sort();
+        assert_eq!(
+            values.iter().map(|v| v.to_string()).collect::<Vec<_>>(),
+            vec!["-Infinity", "-0", "1.5", "Infinity", "NaN"]
+        );
+
+        assert_eq!(SqlFloat(f32::NAN), SqlFloat(f32::NAN));
+        assert_eq!(SqlFloat(0.0f32), SqlFloat(-0.0f32));
+        assert_eq!(hash(SqlFloat(0.0)), hash(SqlFloat(-0.0)));
+        assert_eq!(hash(SqlFloat(f64::NAN)), hash(SqlFloat(-f64::NAN)));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(SqlFloat(1e308).to_string(), "1e+308");
+        assert_eq!(SqlFloat(-1.5e-5).to_string(), "-1.5e-05");
+        assert_eq!(SqlFloat(0.0001).to_string(), "0.0001");
+        assert_eq!(SqlFloat(0.1 + 0.2).to_string(), "0.30000000000000004");
+        assert_eq!(SqlFloat(123456789012345.0).to_string(), "123456789012345");
+        assert_eq!(
+            SqlFloat(1234567890123456.0).to_string(),
+            "1.234567890123456e+15"
+        );
+        assert_eq!(SqlFloat(100000.0f32).to_string(), "100000");
+        assert_eq!(SqlFloat(1000000.0f32).to_string(), "1e+06");
+        assert_eq!(SqlFloat(0.1f32).to_string(), "0.1");
+        assert_eq!(SqlFloat(-0.0).to_string(), "-0");
+    }
+}
@@ -0,0 +1,242 @@
+//! Type modifiers are the numbers in parentheses after a type name, such as varchar(20) or numeric(10, 2).
+//!
+//! They are stored the same way postgres stores atttypmod: https://www.postgresql.org/docs/current/catalog-pg-attribute.html
+use super::{BaseSqlTypes, BaseSqlTypesMapper, NumericError};
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Postgres adds the size of a varlena header to the stored modifier
+const TYPMOD_HEADER: i32 = 4;
+const MAX_LENGTH: u32 = 10_485_760;
+const MAX_PRECISION: u16 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TypeModifier {
+    ///Longer values are an error unless the excess is all spaces
+    Varchar(u32),
+    ///Same as varchar except trailing spaces don't count, they are stored trimmed and padded back on output
+    Char(u32),
+    ///Precision then scale, values are rounded to the scale
+    Numeric(u16, u16),
+}
+
+impl TypeModifier {
+    /// Builds the modifier from the type name and the numbers that followed it.
+    ///
+    /// char on its own is char(1), same as postgres. Types that don't take a modifier must not have one.
+    /// The modifier of an array type such as varchar(20)[] applies to each element.
+    pub fn new(type_name: &str, values: &[i32]) -> Result<Option<TypeModifier>, TypeModifierError> {
+        let element_name = type_name.trim_end_matches("[]");
+        let invalid = || TypeModifierError::InvalidModifier(type_name.to_string(), values.to_vec());
+        let length = |v: i32| match u32::try_from(v) {
+            Ok(l) if l > 0 && l <= MAX_LENGTH => Ok(l),
+            _ => Err(invalid()),
+        };
+
+        match (element_name.to_lowercase().as_str(), values) {
+            ("varchar", []) => Ok(None),
+            ("varchar", [l]) => Ok(Some(TypeModifier::Varchar(length(*l)?))),
+            ("char" | "character" | "bpchar", []) => Ok(Some(TypeModifier::Char(1))),
+            ("char" | "character" | "bpchar", [l]) => Ok(Some(TypeModifier::Char(length(*l)?))),
+            (_, []) => Ok(None),
+            (name, _)
+                if !matches!(
+                    BaseSqlTypesMapper::from_str(name),
+                    Ok(BaseSqlTypesMapper::Numeric)
+                ) =>
+            {
+                Err(TypeModifierError::NotAllowed(type_name.to_string()))
+            }
+            (_, [p]) => Ok(Some(TypeModifier::numeric(*p, 0).ok_or_else(invalid)?)),
+            (_, [p, s]) => Ok(Some(TypeModifier::numeric(*p, *s).ok_or_else(invalid)?)),
+            (_, _) => Err(invalid()),
+        }
+    }
+
+    fn numeric(precision: i32, scale: i32) -> Option<TypeModifier> {
+        let precision = u16::try_from(precision).ok()?;
+        let scale = u16::try_from(scale).ok()?;
+        if precision == 0 || precision > MAX_PRECISION || scale > precision {
+            return None;
+        }
+        Some(TypeModifier::Numeric(precision, scale))
+    }
+
+    /// Packs the modifier into a single integer for pg_attribute
+    pub fn to_typmod(&self) -> i32 {
+        match self {
+            TypeModifier::Varchar(l) | TypeModifier::Char(l) => {
+                //Lengths are capped well below i32::MAX in new
+                i32::try_from(*l).unwrap_or(i32::MAX - TYPMOD_HEADER) + TYPMOD_HEADER
+            }
+            TypeModifier::Numeric(p, s) => ((i32::from(*p) << 16) | i32::from(*s)) + TYPMOD_HEADER,
+        }
+    }
+
+    /// Reverses to_typmod, the type name says how to unpack it
+    pub fn from_typmod(type_name: &str, typmod: i32) -> Result<TypeModifier, TypeModifierError> {
+        let packed = typmod - TYPMOD_HEADER;
+        let values = match BaseSqlTypesMapper::from_str(type_name.trim_end_matches("[]")) {
+            Ok(BaseSqlTypesMapper::Numeric) => vec![packed >> 16, packed & 0xFFFF],
+            _ => vec![packed],
+        };
+        TypeModifier::new(type_name, &values)?
+            .ok_or_else(|| TypeModifierError::InvalidModifier(type_name.to_string(), values))
+    }
+
+    /// Makes a value fit the modifier, failing if that would lose information
+    pub fn apply(&self, value: BaseSqlTypes) -> Result<BaseSqlTypes, TypeModifierError> {
+        match (self, value) {
+            (TypeModifier::Varchar(l), BaseSqlTypes::Text(t)) => {
+                Ok(BaseSqlTypes::Text(self.truncate_spaces(t, *l)?))
+            }
+            (TypeModifier::Char(l), BaseSqlTypes::Text(t)) => {
+                let t = self.truncate_spaces(t, *l)?;
+                Ok(BaseSqlTypes::Text(t.trim_end_matches(' ').to_string()))
+            }
+            (TypeModifier::Numeric(p, s), BaseSqlTypes::Numeric(n)) => {
+                Ok(BaseSqlTypes::Numeric(n.fit(*p, *s)?))
+            }
+            (_, BaseSqlTypes::Array(items)) => Ok(BaseSqlTypes::Array(
+                items
+                    .into_iter()
+                    .map(|i| i.map(|v| self.apply(v)).transpose())
+                    .collect::<Result<_, _>>()?,
+            )),
+            (_, value) => Err(TypeModifierError::WrongType(*self, value)),
+        }
+    }
+
+    /// Pads a char(n) value back out to n characters for the client, anything else is left alone
+    pub fn pad(&self, value: BaseSqlTypes) -> BaseSqlTypes {
+        match (self, value) {
+            (TypeModifier::Char(l), BaseSqlTypes::Text(mut t)) => {
+                let length = usize::try_from(*l).unwrap_or(usize::MAX);
+                let padding = length.saturating_sub(t.chars().count());
+                t.push_str(&" ".repeat(padding));
+                BaseSqlTypes::Text(t)
+            }
+            (_, BaseSqlTypes::Array(items)) => {
+                BaseSqlTypes::Array(items.into_iter().map(|i| i.map(|v| self.pad(v))).collect())
+            }
+            (_, value) => value,
+        }
+    }
+
+    /// Only spaces may be cut off the end, anything else is too long
+    fn truncate_spaces(&self, text: String, length: u32) -> Result<String, TypeModifierError> {
+        let length = usize::try_from(length).unwrap_or(usize::MAX);
+        match text.char_indices().nth(length) {
+            None => Ok(text),
+            Some((cut, _)) if text[cut..].chars().all(|c| c == ' ') => Ok(text[..cut].to_string()),
+            Some(_) => Err(TypeModifierError::ValueTooLong(*self)),
+        }
+    }
+}
+
+impl Display for TypeModifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeModifier::Varchar(l) => write!(f, "character varying({})", l),
+            TypeModifier::Char(l) => write!(f, "character({})", l),
+            TypeModifier::Numeric(p, s) => write!(f, "numeric({},{})", p, s),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TypeModifierError {
+    #[error("Invalid type modifier {1:?} for type {0}")]
+    InvalidModifier(String, Vec<i32>),
+    #[error("Type modifier is not allowed for type {0}")]
+    NotAllowed(String),
+    #[error(transparent)]
+    NumericError(#[from] NumericError),
+    #[error("Value too long for type {0}")]
+    ValueTooLong(TypeModifier),
+    #[error("Type modifier {0} does not apply to {1}")]
+    WrongType(TypeModifier, BaseSqlTypes),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Numeric;
+    use super::*;
+
+    fn text(t: &str) -> BaseSqlTypes {
+        BaseSqlTypes::Text(t.to_string())
+    }
+
+    #[test]
+    fn test_new_and_typmod() -> Result<(), Box<dyn std::error::Error>> {
+        let cases = vec![
+            ("varchar", vec![10], TypeModifier::Varchar(10)),
+            ("char", vec![], TypeModifier::Char(1)),
+            ("character", vec![3], TypeModifier::Char(3)),
+            ("numeric", vec![8, 2], TypeModifier::Numeric(8, 2)),
+            ("decimal", vec![5], TypeModifier::Numeric(5, 0)),
+        ];
+        for (name, values, expected) in cases {
+            let modifier = TypeModifier::new(name, &values)?;
+            assert_eq!(modifier, Some(expected));
+            assert_eq!(
+                TypeModifier::from_typmod(name, expected.to_typmod())?,
+                expected
+            );
+        }
+        assert_eq!(TypeModifier::Varchar(10).to_typmod(), 14);
+        assert_eq!(TypeModifier::Numeric(8, 2).to_typmod(), 524_294);
+
+        assert_eq!(TypeModifier::new("varchar", &[])?, None);
+        assert_eq!(TypeModifier::new("integer", &[])?, None);
+        assert!(TypeModifier::new("integer", &[4]).is_err());
+        assert!(TypeModifier::new("varchar", &[0]).is_err());
+        assert!(TypeModifier::new("numeric", &[2, 3]).is_err());
+        assert!(TypeModifier::new("numeric", &[2, 1, 0]).is_err());
+        assert_eq!(
+            TypeModifier::new("varchar[]", &[10])?,
+            Some(TypeModifier::Varchar(10))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply() -> Result<(), Box<dyn std::error::Error>> {
+        let varchar = TypeModifier::Varchar(3);
+        assert_eq!(varchar.apply(text("ab"))?, text("ab"));
+        assert_eq!(varchar.apply(text("abc   "))?, text("abc"));
+        assert_eq!(varchar.apply(text("ééé"))?, text("ééé"));
+        assert!(matches!(
+            varchar.apply(text("abcd")),
+            Err(TypeModifierError::ValueTooLong(_))
+        ));
+
+        let char = TypeModifier::Char(3);
+        assert_eq!(char.apply(text("a"))?, text("a"));
+        assert_eq!(char.apply(text("a    "))?, text("a"));
+        assert!(char.apply(text("abcd")).is_err());
+        assert_eq!(
+            char.apply(BaseSqlTypes::Array(vec![Some(text("a ")), None]))?,
+            BaseSqlTypes::Array(vec![Some(text("a")), None])
+        );
+        assert_eq!(char.pad(text("a")), text("a  "));
+        assert_eq!(
+            char.pad(BaseSqlTypes::Array(vec![Some(text("a")), None])),
+            BaseSqlTypes::Array(vec![Some(text("a  ")), None])
+        );
+        assert_eq!(varchar.pad(text("a")), text("a"));
+
+        let numeric = TypeModifier::Numeric(4, 2);
+        assert_eq!(
+            numeric.apply(BaseSqlTypes::Numeric(Numeric::from_str("12.345")?))?,
+            BaseSqlTypes::Numeric(Numeric::from_str("12.35")?)
+        );
+        assert!(numeric
+            .apply(BaseSqlTypes::Numeric(Numeric::from_str("123.4")?))
+            .is_err());
+        assert!(numeric.apply(BaseSqlTypes::Integer(1)).is_err());
+        Ok(())
+    }
+}
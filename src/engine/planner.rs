@@ -1,9 +1,16 @@
 //! The planner takes a parsed query and makes it into a set of commands that can be sequentially executed.
+mod index_selection;
+use index_selection::choose_index;
+
+mod join_selection;
+use join_selection::choose_join;
+
 use super::objects::{
-    CommandType, JoinType, ModifyTablePlan, Plan, PlannedCommon, PlannedStatement, QueryTree,
-    RangeRelation,
+    CommandType, DeletePlan, Expression, FilterPlan, HashAggregatePlan, LimitPlan, ModifyTablePlan,
+    Plan, PlannedCommon, PlannedStatement, ProjectionPlan, QueryTree, RangeRelation, SortPlan,
+    Table, UpdatePlan,
 };
-use crate::engine::objects::FullTableScan;
+use crate::engine::objects::{FullTableScan, IndexScan};
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -12,8 +19,10 @@ pub struct Planner {}
 impl Planner {
     pub fn plan(query_tree: QueryTree) -> Result<PlannedStatement, PlannerError> {
         match query_tree.command_type {
+            CommandType::Delete => Planner::plan_delete(query_tree),
             CommandType::Insert => Planner::plan_insert(query_tree),
             CommandType::Select => Planner::plan_select(query_tree),
+            CommandType::Update => Planner::plan_update(query_tree),
             _ => Err(PlannerError::NotImplemented()),
         }
     }
@@ -21,68 +30,178 @@ impl Planner {
     fn plan_insert(query_tree: QueryTree) -> Result<PlannedStatement, PlannerError> {
         //So we know we want to insert, now the question is into what.
         //I'm going to start with a simple insert and let it evolve.
+        match query_tree.range_tables.as_slice() {
+            [RangeRelation::Table(t), RangeRelation::AnonymousTable(at)] => Ok(PlannedStatement {
+                common: PlannedCommon {},
+                plan: Arc::new(Plan::ModifyTable(ModifyTablePlan {
+                    table: t.table.clone(),
+                    source: Arc::new(Plan::StaticData(at.clone())),
+                })),
+            }),
+            [] => Err(PlannerError::NoDataProvided()),
+            _ => Err(PlannerError::NotImplemented()),
+        }
+    }
 
-        //So we should be able to find a join for our table target
-        let join = query_tree
-            .joins
-            .first()
-            .ok_or_else(|| PlannerError::TooManyJoins(query_tree.joins.len()))?;
-
-        match join {
-            (JoinType::Inner, RangeRelation::Table(t), RangeRelation::AnonymousTable(at)) => {
-                Ok(PlannedStatement {
-                    common: PlannedCommon {},
-                    plan: Arc::new(Plan::ModifyTable(ModifyTablePlan {
-                        table: t.table.clone(),
-                        source: Arc::new(Plan::StaticData(at.clone())),
-                    })),
-                })
-            }
-            (_, _, _) => Err(PlannerError::NotImplemented()),
+    fn plan_update(query_tree: QueryTree) -> Result<PlannedStatement, PlannerError> {
+        let table = Planner::single_target_table(&query_tree)?;
+
+        Ok(PlannedStatement {
+            common: PlannedCommon {},
+            plan: Arc::new(Plan::Update(UpdatePlan {
+                table,
+                qualification: query_tree.qualification,
+                assignments: query_tree.assignments,
+            })),
+        })
+    }
+
+    fn plan_delete(query_tree: QueryTree) -> Result<PlannedStatement, PlannerError> {
+        let table = Planner::single_target_table(&query_tree)?;
+
+        Ok(PlannedStatement {
+            common: PlannedCommon {},
+            plan: Arc::new(Plan::Delete(DeletePlan {
+                table,
+                qualification: query_tree.qualification,
+            })),
+        })
+    }
+
+    /// Updates and deletes only support modifying a single table right now
+    fn single_target_table(query_tree: &QueryTree) -> Result<Arc<Table>, PlannerError> {
+        match query_tree.range_tables.as_slice() {
+            [RangeRelation::Table(rrt)] => Ok(rrt.table.clone()),
+            [] => Err(PlannerError::NoDataProvided()),
+            _ => Err(PlannerError::NotImplemented()),
         }
     }
 
     fn plan_select(query_tree: QueryTree) -> Result<PlannedStatement, PlannerError> {
-        //TODO I'm ignoring joins at the moment
+        let range_tables = query_tree.range_tables;
+        if range_tables.is_empty() {
+            return Err(PlannerError::NoDataProvided());
+        } else if range_tables.len() != query_tree.joins.len() + 1 {
+            return Err(PlannerError::TooManyJoins(query_tree.joins.len()));
+        }
+
+        //An index can only answer the qualification when there is a single table
+        let index_qual = match query_tree.joins.is_empty() {
+            true => query_tree.qualification.as_deref(),
+            false => None,
+        };
+        let (mut source, mut width) = Planner::plan_scan(&range_tables[0], index_qual);
+
+        //Joins are left deep, each one adds the next table onto the rows so far
+        for ((join_type, condition), rr) in query_tree.joins.into_iter().zip(&range_tables[1..]) {
+            let (right, right_width) = Planner::plan_scan(rr, None);
+            source = Arc::new(choose_join(
+                join_type,
+                source,
+                right,
+                condition,
+                width,
+                right_width,
+            ));
+            width += right_width;
+        }
 
-        //let mut targets = vec![];
-        //for t in query_tree.targets {
-        //    match t {
-        //        TargetEntry::Parameter(p) => targets.push(p),
-        //    }
-        //}
+        //The qualification needs the whole row so we project after filtering
+        if let Some(qual) = query_tree.qualification {
+            source = Arc::new(Plan::Filter(FilterPlan {
+                source,
+                qualification: qual,
+            }));
+        }
 
-        let mut unjoined = vec![];
-        for rr in query_tree.range_tables {
+        //Columns of the rows flowing into the sort, needed to read them back if it spills
+        let mut columns = vec![];
+        for rr in range_tables.iter() {
             match rr {
                 RangeRelation::Table(rrt) => {
-                    unjoined.push(Arc::new(Plan::FullTableScan(FullTableScan {
-                        src_table: rrt.table,
-                        target_type: query_tree.targets.clone(), //TODO I know not every table needs every column
-                    })));
-                }
-                RangeRelation::AnonymousTable(anon_tbl) => {
-                    unjoined.push(Arc::new(Plan::StaticData(anon_tbl.clone())));
+                    columns.extend(rrt.table.attributes.iter().map(|a| a.sql_type.clone()))
                 }
+                RangeRelation::AnonymousTable(_) => return Err(PlannerError::NotImplemented()),
             }
         }
 
-        if unjoined.is_empty() {
-            Err(PlannerError::NoDataProvided())
-        } else if unjoined.len() == 1 {
-            Ok(PlannedStatement {
-                common: PlannedCommon {},
-                plan: unjoined[0].clone(),
-            })
-        } else {
-            //let cart_joins = return Ok(PlannedStatement {
-            //    common: PlannedCommon {},
-            //    plan: Arc::new(Plan::CrossProduct(CrossProduct {
-            //        columns: targets,
-            //        source: unjoined,
-            //    })),
-            //});
-            Err(PlannerError::NotImplemented())
+        if !query_tree.group_by.is_empty()
+            || !query_tree.aggregates.is_empty()
+            || query_tree.having.is_some()
+        {
+            columns = query_tree
+                .group_by
+                .iter()
+                .map(|g| g.sql_type())
+                .chain(query_tree.aggregates.iter().map(|a| a.sql_type.clone()))
+                .collect();
+            source = Arc::new(Plan::HashAggregate(HashAggregatePlan {
+                source,
+                group_by: query_tree.group_by,
+                aggregates: query_tree.aggregates,
+            }));
+
+            if let Some(having) = query_tree.having {
+                source = Arc::new(Plan::Filter(FilterPlan {
+                    source,
+                    qualification: having,
+                }));
+            }
+        }
+
+        //Sort keys can use columns that aren't output so sorting also happens before the projection
+        if !query_tree.sorts.is_empty() {
+            source = Arc::new(Plan::Sort(SortPlan {
+                source,
+                keys: query_tree.sorts,
+                columns,
+            }));
+        }
+
+        let mut plan = Arc::new(Plan::Projection(ProjectionPlan {
+            source,
+            target_entries: query_tree.target_entries,
+        }));
+
+        if query_tree.limit.is_some() || query_tree.offset > 0 {
+            plan = Arc::new(Plan::Limit(LimitPlan {
+                source: plan,
+                offset: query_tree.offset,
+                count: query_tree.limit,
+            }));
+        }
+
+        Ok(PlannedStatement {
+            common: PlannedCommon {},
+            plan,
+        })
+    }
+
+    /// Reads every column of a range table, also returning how many columns that is
+    fn plan_scan(rr: &RangeRelation, qualification: Option<&Expression>) -> (Arc<Plan>, usize) {
+        match rr {
+            RangeRelation::Table(rrt) => {
+                let width = rrt.table.attributes.len();
+                //The index only narrows the rows down, the full qualification still applies
+                let scan = match qualification.and_then(|q| choose_index(&rrt.table, q)) {
+                    Some(choice) => Plan::IndexScan(IndexScan {
+                        src_table: rrt.table.clone(),
+                        index: choice.index,
+                        lower: choice.lower,
+                        upper: choice.upper,
+                        target_type: rrt.table.sql_type.clone(),
+                    }),
+                    None => Plan::FullTableScan(FullTableScan {
+                        src_table: rrt.table.clone(),
+                        target_type: rrt.table.sql_type.clone(),
+                    }),
+                };
+                (Arc::new(scan), width)
+            }
+            RangeRelation::AnonymousTable(anon_tbl) => {
+                let width = anon_tbl.first().map(|r| r.len()).unwrap_or(0);
+                (Arc::new(Plan::StaticData(anon_tbl.clone())), width)
+            }
         }
     }
 }
@@ -0,0 +1,248 @@
+//! Works out if a qualification can be answered by one of a table's indexes.
+//!
+//! Only comparisons between a column and a constant that are joined by AND are considered. Equality
+//! has to cover the leading columns of the index in order, a range is only usable on the column after them.
+use crate::engine::objects::types::BaseSqlTypes;
+use crate::engine::objects::{BinaryOperator, Expression, Index, SqlTuple, Table};
+use std::ops::Bound;
+use std::sync::Arc;
+
+pub struct IndexChoice {
+    pub index: Arc<Index>,
+    pub lower: Bound<SqlTuple>,
+    pub upper: Bound<SqlTuple>,
+}
+
+/// Picks the index that matches the most leading columns, preferring equality over a range
+pub fn choose_index(table: &Table, qualification: &Expression) -> Option<IndexChoice> {
+    let mut conjuncts = vec![];
+    flatten_and(qualification, &mut conjuncts);
+    let comparisons: Vec<_> = conjuncts
+        .into_iter()
+        .filter_map(column_comparison)
+        .collect();
+
+    let mut best: Option<(usize, IndexChoice)> = None;
+    for index in table.indexes.iter() {
+        if let Some((score, choice)) = match_index(table, index, &comparisons) {
+            if best.as_ref().map(|(s, _)| score > *s).unwrap_or(true) {
+                best = Some((score, choice));
+            }
+        }
+    }
+
+    best.map(|(_, choice)| choice)
+}
+
+fn match_index(
+    table: &Table,
+    index: &Arc<Index>,
+    comparisons: &[(usize, BinaryOperator, BaseSqlTypes)],
+) -> Option<(usize, IndexChoice)> {
+    let mut prefix = vec![];
+    let mut lower = None;
+    let mut upper = None;
+
+    for (name, _) in index.columns.iter() {
+        let offset = match table.sql_type.iter().position(|(n, _)| n == name) {
+            Some(o) => o,
+            None => break,
+        };
+        let on_column: Vec<_> = comparisons.iter().filter(|c| c.0 == offset).collect();
+
+        if let Some((_, _, value)) = on_column.iter().find(|c| c.1 == BinaryOperator::Equal) {
+            prefix.push(Some(value.clone()));
+            continue;
+        }
+
+        for (_, op, value) in on_column {
+            let mut key = prefix.clone();
+            key.push(Some(value.clone()));
+            let key = SqlTuple(key);
+            match op {
+                BinaryOperator::GreaterThan if lower.is_none() => {
+                    lower = Some(Bound::Excluded(key))
+                }
+                BinaryOperator::GreaterThanOrEqual if lower.is_none() => {
+                    lower = Some(Bound::Included(key))
+                }
+                BinaryOperator::LessThan if upper.is_none() => upper = Some(Bound::Excluded(key)),
+                BinaryOperator::LessThanOrEqual if upper.is_none() => {
+                    upper = Some(Bound::Included(key))
+                }
+                _ => {}
+            }
+        }
+        break;
+    }
+
+    let has_range = lower.is_some() || upper.is_some();
+    if prefix.is_empty() && !has_range {
+        return None;
+    }
+
+    //Whatever side the range didn't set is limited by the equality prefix alone
+    let prefix_bound = || match prefix.is_empty() {
+        true => Bound::Unbounded,
+        false => Bound::Included(SqlTuple(prefix.clone())),
+    };
+    let score = prefix.len() * 2 + usize::from(has_range);
+    Some((
+        score,
+        IndexChoice {
+            index: index.clone(),
+            lower: lower.unwrap_or_else(prefix_bound),
+            upper: upper.unwrap_or_else(prefix_bound),
+        },
+    ))
+}
+
+fn flatten_and<'a>(expr: &'a Expression, conjuncts: &mut Vec<&'a Expression>) {
+    match expr {
+        Expression::BinaryOperation(left, BinaryOperator::And, right) => {
+            flatten_and(left, conjuncts);
+            flatten_and(right, conjuncts);
+        }
+        e => conjuncts.push(e),
+    }
+}
+
+/// Normalizes a comparison so the column is always on the left
+fn column_comparison(expr: &Expression) -> Option<(usize, BinaryOperator, BaseSqlTypes)> {
+    let (left, op, right) = match expr {
+        Expression::BinaryOperation(l, op, r) => (l.as_ref(), *op, r.as_ref()),
+        _ => return None,
+    };
+
+    match (left, right) {
+        (Expression::Column(c, _), Expression::Constant(Some(v), _)) => Some((*c, op, v.clone())),
+        (Expression::Constant(Some(v), _), Expression::Column(c, _)) => {
+            let flipped = match op {
+                BinaryOperator::LessThan => BinaryOperator::GreaterThan,
+                BinaryOperator::LessThanOrEqual => BinaryOperator::GreaterThanOrEqual,
+                BinaryOperator::GreaterThan => BinaryOperator::LessThan,
+                BinaryOperator::GreaterThanOrEqual => BinaryOperator::LessThanOrEqual,
+                o => o,
+            };
+            Some((*c, flipped, v.clone()))
+        }
+        (_, _) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{Nullable, FIRST_USER_OID};
+    use crate::engine::objects::types::{BaseSqlTypesMapper, SqlTypeDefinition};
+    use crate::engine::objects::Attribute;
+    use uuid::Uuid;
+
+    fn get_table() -> Table {
+        let attributes = vec![
+            Attribute::new(
+                "a".to_string(),
+                BaseSqlTypesMapper::Integer,
+                Nullable::NotNull,
+                None,
+            ),
+            Attribute::new(
+                "b".to_string(),
+                BaseSqlTypesMapper::Integer,
+                Nullable::NotNull,
+                None,
+            ),
+            Attribute::new(
+                "c".to_string(),
+                BaseSqlTypesMapper::Text,
+                Nullable::Null,
+                None,
+            ),
+        ];
+        let index = Arc::new(Index {
+            id: Uuid::new_v4(),
+            name: "test_index".to_string(),
+            columns: Arc::new(SqlTypeDefinition::new(&attributes[0..2])),
+            unique: true,
+        });
+        Table::new(
+            Uuid::new_v4(),
+            FIRST_USER_OID,
+            "test".to_string(),
+            attributes,
+            vec![],
+            vec![index],
+        )
+    }
+
+    fn compare(column: usize, op: BinaryOperator, value: i32) -> Expression {
+        Expression::BinaryOperation(
+            Box::new(Expression::Column(column, BaseSqlTypesMapper::Integer)),
+            op,
+            Box::new(Expression::Constant(
+                Some(BaseSqlTypes::Integer(value)),
+                BaseSqlTypesMapper::Integer,
+            )),
+        )
+    }
+
+    fn and(left: Expression, right: Expression) -> Expression {
+        Expression::BinaryOperation(Box::new(left), BinaryOperator::And, Box::new(right))
+    }
+
+    fn key(values: &[i32]) -> SqlTuple {
+        SqlTuple(
+            values
+                .iter()
+                .map(|v| Some(BaseSqlTypes::Integer(*v)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_equality_prefix() {
+        let table = get_table();
+
+        let choice = choose_index(
+            &table,
+            &and(
+                compare(1, BinaryOperator::Equal, 2),
+                compare(0, BinaryOperator::Equal, 1),
+            ),
+        )
+        .unwrap();
+        assert_eq!(choice.lower, Bound::Included(key(&[1, 2])));
+        assert_eq!(choice.upper, Bound::Included(key(&[1, 2])));
+
+        let choice = choose_index(
+            &table,
+            &and(
+                compare(0, BinaryOperator::Equal, 1),
+                compare(1, BinaryOperator::GreaterThan, 5),
+            ),
+        )
+        .unwrap();
+        assert_eq!(choice.lower, Bound::Excluded(key(&[1, 5])));
+        assert_eq!(choice.upper, Bound::Included(key(&[1])));
+    }
+
+    #[test]
+    fn test_range_and_unusable() {
+        let table = get_table();
+
+        let choice = choose_index(&table, &compare(0, BinaryOperator::LessThan, 3)).unwrap();
+        assert_eq!(choice.lower, Bound::Unbounded);
+        assert_eq!(choice.upper, Bound::Excluded(key(&[3])));
+
+        //Not the leading column
+        assert!(choose_index(&table, &compare(1, BinaryOperator::Equal, 3)).is_none());
+
+        //OR could match rows outside of either side
+        let or = Expression::BinaryOperation(
+            Box::new(compare(0, BinaryOperator::Equal, 1)),
+            BinaryOperator::Or,
+            Box::new(compare(0, BinaryOperator::Equal, 2)),
+        );
+        assert!(choose_index(&table, &or).is_none());
+    }
+}
@@ -0,0 +1,206 @@
+//! Picks how two inputs get joined.
+//!
+//! Equalities between a column of each side become hash join keys, anything else in the condition is
+//! checked after the keys match. Without any usable equality it falls back to a nested loop.
+use crate::engine::objects::{
+    BinaryOperator, Expression, HashJoinPlan, JoinType, NestedLoopJoinPlan, Plan,
+};
+use std::sync::Arc;
+
+pub fn choose_join(
+    join_type: JoinType,
+    left: Arc<Plan>,
+    right: Arc<Plan>,
+    condition: Option<Arc<Expression>>,
+    left_width: usize,
+    right_width: usize,
+) -> Plan {
+    let mut conjuncts = vec![];
+    if let Some(c) = &condition {
+        flatten_and(c, &mut conjuncts);
+    }
+
+    let mut left_keys = vec![];
+    let mut right_keys = vec![];
+    let mut residual = vec![];
+    for c in conjuncts {
+        match equi_key(&c, left_width) {
+            Some((l, r)) => {
+                left_keys.push(Arc::new(l));
+                right_keys.push(Arc::new(r));
+            }
+            None => residual.push(c),
+        }
+    }
+
+    if left_keys.is_empty() {
+        return Plan::NestedLoopJoin(NestedLoopJoinPlan {
+            join_type,
+            left,
+            right,
+            condition,
+            left_width,
+            right_width,
+        });
+    }
+
+    let residual = residual
+        .into_iter()
+        .reduce(|l, r| Expression::BinaryOperation(Box::new(l), BinaryOperator::And, Box::new(r)));
+    Plan::HashJoin(HashJoinPlan {
+        join_type,
+        left,
+        right,
+        left_keys,
+        right_keys,
+        residual: residual.map(Arc::new),
+        left_width,
+        right_width,
+    })
+}
+
+fn flatten_and(expr: &Expression, conjuncts: &mut Vec<Expression>) {
+    match expr {
+        Expression::BinaryOperation(left, BinaryOperator::And, right) => {
+            flatten_and(left, conjuncts);
+            flatten_and(right, conjuncts);
+        }
+        e => conjuncts.push(e.clone()),
+    }
+}
+
+/// A column of the left equal to a column of the right, the right column is rebased onto the right row
+fn equi_key(expr: &Expression, left_width: usize) -> Option<(Expression, Expression)> {
+    let (a, b) = match expr {
+        Expression::BinaryOperation(a, BinaryOperator::Equal, b) => (a.as_ref(), b.as_ref()),
+        _ => return None,
+    };
+
+    let (left, right) = match (key_column(a)?, key_column(b)?) {
+        (l, r) if l < left_width && r >= left_width => (a, b),
+        (r, l) if l < left_width && r >= left_width => (b, a),
+        (_, _) => return None,
+    };
+    Some((left.clone(), rebase(right, left_width)))
+}
+
+/// Keys are columns, or columns cast to a wider integer so both sides hash as the same type
+fn key_column(expr: &Expression) -> Option<usize> {
+    match expr {
+        Expression::Column(c, _) => Some(*c),
+        Expression::Cast(e, _) => key_column(e),
+        _ => None,
+    }
+}
+
+fn rebase(expr: &Expression, by: usize) -> Expression {
+    match expr {
+        Expression::Column(c, t) => Expression::Column(c - by, t.clone()),
+        Expression::Cast(e, t) => Expression::Cast(Box::new(rebase(e, by)), t.clone()),
+        e => e.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::objects::types::{BaseSqlTypes, BaseSqlTypesMapper};
+
+    fn column(offset: usize) -> Box<Expression> {
+        Box::new(Expression::Column(offset, BaseSqlTypesMapper::Integer))
+    }
+
+    fn empty() -> Arc<Plan> {
+        Arc::new(Plan::StaticData(Arc::new(vec![])))
+    }
+
+    #[test]
+    fn test_hash_join_keys() {
+        //left has 2 columns, right has 2 columns
+        let reversed_equal =
+            Expression::BinaryOperation(column(3), BinaryOperator::Equal, column(0));
+        let other = Expression::BinaryOperation(
+            column(1),
+            BinaryOperator::LessThan,
+            Box::new(Expression::Constant(
+                Some(BaseSqlTypes::Integer(5)),
+                BaseSqlTypesMapper::Integer,
+            )),
+        );
+        let condition = Expression::BinaryOperation(
+            Box::new(reversed_equal),
+            BinaryOperator::And,
+            Box::new(other.clone()),
+        );
+
+        match choose_join(
+            JoinType::OuterLeft,
+            empty(),
+            empty(),
+            Some(Arc::new(condition)),
+            2,
+            2,
+        ) {
+            Plan::HashJoin(hj) => {
+                assert_eq!(*hj.left_keys[0], *column(0));
+                assert_eq!(*hj.right_keys[0], *column(1));
+                assert_eq!(hj.residual.as_deref(), Some(&other));
+            }
+            _ => panic!("Expected a hash join"),
+        }
+    }
+
+    #[test]
+    fn test_widened_join_keys() {
+        //An integer on the right joined to a bigint on the left
+        let widened = Expression::Cast(column(2), BaseSqlTypesMapper::Bigint);
+        let condition = Expression::BinaryOperation(
+            Box::new(widened),
+            BinaryOperator::Equal,
+            Box::new(Expression::Column(0, BaseSqlTypesMapper::Bigint)),
+        );
+
+        match choose_join(
+            JoinType::Inner,
+            empty(),
+            empty(),
+            Some(Arc::new(condition)),
+            2,
+            2,
+        ) {
+            Plan::HashJoin(hj) => {
+                assert_eq!(
+                    *hj.left_keys[0],
+                    Expression::Column(0, BaseSqlTypesMapper::Bigint)
+                );
+                assert_eq!(
+                    *hj.right_keys[0],
+                    Expression::Cast(column(0), BaseSqlTypesMapper::Bigint)
+                );
+            }
+            _ => panic!("Expected a hash join"),
+        }
+    }
+
+    #[test]
+    fn test_nested_loop_fallback() {
+        //Both columns on the left side can't be a join key
+        let same_side = Expression::BinaryOperation(column(0), BinaryOperator::Equal, column(1));
+        assert!(matches!(
+            choose_join(
+                JoinType::Inner,
+                empty(),
+                empty(),
+                Some(Arc::new(same_side)),
+                2,
+                2
+            ),
+            Plan::NestedLoopJoin(_)
+        ));
+
+        assert!(matches!(
+            choose_join(JoinType::Inner, empty(), empty(), None, 2, 2),
+            Plan::NestedLoopJoin(_)
+        ));
+    }
+}
@@ -15,48 +15,164 @@
 mod commands;
 mod common;
 mod constants;
+mod expressions;
+mod syntax_error;
+pub use syntax_error::SyntaxError;
 
+use self::commands::delete::parse_delete;
+use self::commands::drop::parse_drop_type;
 use self::commands::select::parse_select;
+use self::commands::transaction::parse_transaction;
+use self::commands::update::parse_update;
 
 use super::objects::ParseTree;
-use commands::create::parse_create_table;
+use commands::create::{parse_create_table, parse_create_type};
 use commands::insert::parse_insert;
+use common::maybe_take_whitespace;
 use constants::parse_sql_string;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::combinator::{complete, opt};
-use nom::error::{convert_error, ContextError, ParseError, VerboseError};
+use nom::combinator::{complete, eof};
+use nom::error::{context, ContextError, ParseError};
+use nom::multi::many0;
 use nom::sequence::tuple;
 use nom::Finish;
 use nom::IResult;
+use syntax_error::FurthestError;
 use thiserror::Error;
 
 pub struct SqlParser {}
 
 impl SqlParser {
+    /// For places that only take one statement, such as a prepared statement
     pub fn parse(input: &str) -> Result<ParseTree, SqlParserError> {
-        match SqlParser::nom_parse::<VerboseError<&str>>(input).finish() {
-            Ok((_, cmd)) => Ok(cmd),
-            Err(e) => Err(SqlParserError::ParseError(convert_error(input, e))),
+        let mut statements = SqlParser::parse_statements(input)?;
+        match statements.len() {
+            1 => Ok(statements.remove(0)),
+            n => Err(SqlParserError::ExpectedOneStatement(n)),
+        }
+    }
+
+    /// Splits a query on semicolons, empty statements are dropped so an empty query gives an empty list
+    pub fn parse_statements(input: &str) -> Result<Vec<ParseTree>, SqlParserError> {
+        match SqlParser::nom_parse_statements::<FurthestError>(input).finish() {
+            Ok((_, statements)) => Ok(statements),
+            Err(e) => Err(SqlParserError::ParseError(SyntaxError::new(input, e))),
+        }
+    }
+
+    fn nom_parse_statements<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+        mut input: &'a str,
+    ) -> IResult<&'a str, Vec<ParseTree>, E> {
+        let mut statements = vec![];
+        loop {
+            let (rest, _) = tuple((
+                many0(tuple((maybe_take_whitespace, tag(";")))),
+                maybe_take_whitespace,
+            ))(input)?;
+            if rest.is_empty() {
+                return Ok((rest, statements));
+            }
+
+            //Statements are parsed one at a time so a syntax error is reported where it happened
+            let (rest, (statement, _, _)) = complete(tuple((
+                SqlParser::nom_parse,
+                maybe_take_whitespace,
+                context(";", alt((tag(";"), eof))),
+            )))(rest)?;
+            statements.push(statement);
+            input = rest;
         }
     }
 
     fn nom_parse<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
         input: &'a str,
     ) -> IResult<&'a str, ParseTree, E> {
-        //TODO Had to remove all consuming since it was throwing EOF issues
-        let (input, (result, _)) = complete(tuple((
-            alt((parse_create_table, parse_insert, parse_select)),
-            opt(tag(";")),
-        )))(input)?;
-        Ok((input, result))
+        alt((
+            parse_create_table,
+            parse_create_type,
+            parse_delete,
+            parse_drop_type,
+            parse_insert,
+            parse_select,
+            parse_transaction,
+            parse_update,
+        ))(input)
     }
 }
 
 #[derive(Debug, Error)]
 pub enum SqlParserError {
-    #[error("SQL Parse Error {0}")]
-    ParseError(String),
+    #[error(transparent)]
+    ParseError(SyntaxError),
+    #[error("Expected a single statement, got {0}")]
+    ExpectedOneStatement(usize),
     #[error("Got an incomplete on {0} which shouldn't be possible")]
     Incomplete(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::objects::TransactionCommand;
+
+    #[test]
+    fn test_parse_statements() -> Result<(), Box<dyn std::error::Error>> {
+        let statements = SqlParser::parse_statements(
+            "begin; insert into foo (bar) values('a;b');\n select bar from foo ;; commit",
+        )?;
+        assert_eq!(statements.len(), 4);
+        assert!(matches!(
+            statements[0],
+            ParseTree::Transaction(TransactionCommand::Begin)
+        ));
+        assert!(matches!(statements[1], ParseTree::Insert(_)));
+        assert!(matches!(statements[2], ParseTree::Select(_)));
+        assert!(matches!(
+            statements[3],
+            ParseTree::Transaction(TransactionCommand::Commit)
+        ));
+
+        assert!(SqlParser::parse_statements(" ; ;\n")?.is_empty());
+        assert!(SqlParser::parse_statements("select bar from foo select bar from foo").is_err());
+
+        //The position points at the start of the bad token, in characters from one
+        match SqlParser::parse_statements("select 'éé' from foo;\n garbage") {
+            Err(SqlParserError::ParseError(e)) => {
+                assert_eq!(e.offset, 25);
+                assert_eq!(e.position, 24);
+                assert_eq!(e.token, Some("garbage".to_string()));
+            }
+            r => panic!("Expected a syntax error, got {:?}", r.map(|s| s.len())),
+        }
+        match SqlParser::parse_statements("select bar frm foo") {
+            Err(SqlParserError::ParseError(e)) => {
+                assert_eq!(e.offset, 11);
+                assert_eq!(e.expected, vec!["from".to_string()]);
+                assert_eq!(
+                    e.to_string(),
+                    "syntax error at or near \"frm\", expected \"from\""
+                );
+            }
+            r => panic!("Expected a syntax error, got {:?}", r.map(|s| s.len())),
+        }
+        match SqlParser::parse_statements("create table foo (bar integer null baz text)") {
+            Err(SqlParserError::ParseError(e)) => {
+                assert_eq!(e.token, Some("baz".to_string()));
+                assert_eq!(e.expected, vec![")".to_string()]);
+            }
+            r => panic!("Expected a syntax error, got {:?}", r.map(|s| s.len())),
+        }
+
+        assert!(SqlParser::parse("select bar from foo;").is_ok());
+        assert!(matches!(
+            SqlParser::parse("commit; commit"),
+            Err(SqlParserError::ExpectedOneStatement(2))
+        ));
+        assert!(matches!(
+            SqlParser::parse(""),
+            Err(SqlParserError::ExpectedOneStatement(0))
+        ));
+        Ok(())
+    }
+}
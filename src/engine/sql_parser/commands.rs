@@ -1,3 +1,7 @@
 pub mod create;
+pub mod delete;
+pub mod drop;
 pub mod insert;
 pub mod select;
+pub mod transaction;
+pub mod update;
@@ -5,15 +5,17 @@ use crate::engine::objects::{ParseTree, RawColumn};
 
 use super::super::super::super::objects::RawCreateTableCommand;
 use super::super::super::common::{
-    match_close_paren, match_comma, match_open_paren, maybe_take_whitespace, parse_sql_identifier,
+    match_close_paren, match_comma, match_open_paren, maybe_take_whitespace, parse_sql_name,
     take_whitespace,
 };
 use super::match_create;
-use nom::bytes::complete::tag_no_case;
-use nom::combinator::{cut, opt};
-use nom::error::{ContextError, ParseError};
-use nom::multi::separated_list1;
-use nom::sequence::tuple;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case};
+use nom::character::complete::digit1;
+use nom::combinator::{cut, map, opt};
+use nom::error::{context, ContextError, ErrorKind, ParseError};
+use nom::multi::{many0_count, separated_list1};
+use nom::sequence::{delimited, tuple};
 use nom::IResult;
 
 pub fn parse_create_table<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
@@ -24,7 +26,7 @@ pub fn parse_create_table<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
         match_table,
         cut(tuple((
             take_whitespace,
-            parse_sql_identifier,
+            parse_sql_name,
             maybe_take_whitespace,
             match_open_paren,
             match_columns,
@@ -44,7 +46,7 @@ pub fn parse_create_table<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
 fn match_table<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, (), E> {
-    let (input, _) = tag_no_case("table")(input)?;
+    let (input, _) = context("table", tag_no_case("table"))(input)?;
     Ok((input, ()))
 }
 
@@ -57,11 +59,18 @@ fn match_columns<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
 fn match_column_attribute<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, RawColumn, E> {
-    let (input, (_, name, _, sql_type, _, is_null, _, is_primary_key, _)) = tuple((
+    let (
+        input,
+        (_, name, _, sql_type, _, type_modifiers, _, is_array, _, is_null, _, is_primary_key, _),
+    ) = tuple((
         maybe_take_whitespace,
-        parse_sql_identifier,
+        parse_sql_name,
         take_whitespace,
-        parse_sql_identifier,
+        match_column_type,
+        maybe_take_whitespace,
+        match_type_modifiers,
+        maybe_take_whitespace,
+        match_array_bounds,
         maybe_take_whitespace,
         is_null,
         maybe_take_whitespace,
@@ -72,13 +81,100 @@ fn match_column_attribute<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
         input,
         RawColumn {
             name: name.to_string(),
-            sql_type: sql_type.to_string(),
+            sql_type: if is_array {
+                format!("{}[]", sql_type)
+            } else {
+                sql_type
+            },
+            type_modifiers,
             null: is_null,
             primary_key: is_primary_key,
         },
     ))
 }
 
+/// Types are a single word except for a few standard sql spellings, which are normalized
+fn match_column_type<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, String, E> {
+    alt((
+        map(match_words(&["double", "precision"]), |_| {
+            "double precision".to_string()
+        }),
+        map(match_words(&["timestamp", "with", "time", "zone"]), |_| {
+            "timestamptz".to_string()
+        }),
+        map(
+            match_words(&["timestamp", "without", "time", "zone"]),
+            |_| "timestamp".to_string(),
+        ),
+        map(match_words(&["time", "without", "time", "zone"]), |_| {
+            "time".to_string()
+        }),
+        map(match_words(&["character", "varying"]), |_| {
+            "varchar".to_string()
+        }),
+        parse_sql_name,
+    ))(input)
+}
+
+/// The optional numbers in parentheses after a type, such as varchar(20) or numeric(10, 2)
+fn match_type_modifiers<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, Vec<i32>, E> {
+    let (input, modifiers) = opt(delimited(
+        match_open_paren,
+        separated_list1(
+            match_comma,
+            delimited(
+                maybe_take_whitespace,
+                match_type_modifier,
+                maybe_take_whitespace,
+            ),
+        ),
+        match_close_paren,
+    ))(input)?;
+    Ok((input, modifiers.unwrap_or_default()))
+}
+
+/// Array columns such as int[] or int[3][3], same as postgres the sizes are not enforced
+fn match_array_bounds<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, bool, E> {
+    let (input, dimensions) = many0_count(tuple((
+        tag("["),
+        maybe_take_whitespace,
+        opt(digit1),
+        maybe_take_whitespace,
+        tag("]"),
+    )))(input)?;
+    Ok((input, dimensions > 0))
+}
+
+fn match_type_modifier<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, i32, E> {
+    let (rest, digits) = digit1(input)?;
+    match digits.parse::<i32>() {
+        Ok(value) => Ok((rest, value)),
+        Err(_) => Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Digit))),
+    }
+}
+
+fn match_words<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    words: &'static [&'static str],
+) -> impl FnMut(&'a str) -> IResult<&'a str, (), E> {
+    move |mut input: &'a str| {
+        for (i, word) in words.iter().enumerate() {
+            if i > 0 {
+                input = take_whitespace(input)?.0;
+            }
+            input = tag_no_case(*word)(input)?.0;
+        }
+        Ok((input, ()))
+    }
+}
+
 fn is_null<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, bool, E> {
@@ -148,12 +244,14 @@ mod tests {
             RawColumn {
                 name: "bar".to_string(),
                 sql_type: "text".to_string(),
+                type_modifiers: vec![],
                 null: true,
                 primary_key: true,
             },
             RawColumn {
                 name: "baz".to_string(),
                 sql_type: "text".to_string(),
+                type_modifiers: vec![],
                 null: false,
                 primary_key: false,
             },
@@ -162,6 +260,82 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_multiple_word_types() -> Result<(), Box<dyn std::error::Error>> {
+        let test_string = "create table foo (bar double  precision not null, baz numeric, created timestamp with time zone)";
+
+        let (_, result) = parse_create_table::<VerboseError<&str>>(test_string)?;
+
+        let result = match result {
+            ParseTree::CreateTable(c) => c,
+            _ => panic!("Wrong type"),
+        };
+
+        assert_eq!("double precision", result.provided_columns[0].sql_type);
+        assert!(!result.provided_columns[0].null);
+        assert_eq!("numeric", result.provided_columns[1].sql_type);
+        assert_eq!("timestamptz", result.provided_columns[2].sql_type);
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_modifiers() -> Result<(), Box<dyn std::error::Error>> {
+        let test_string = "create table foo (bar varchar(20) not null, baz numeric( 10 , 2 ), qux character varying, quux char)";
+
+        let (_, result) = parse_create_table::<VerboseError<&str>>(test_string)?;
+
+        let result = match result {
+            ParseTree::CreateTable(c) => c,
+            _ => panic!("Wrong type"),
+        };
+
+        let types: Vec<(&str, Vec<i32>)> = result
+            .provided_columns
+            .iter()
+            .map(|c| (c.sql_type.as_str(), c.type_modifiers.clone()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("varchar", vec![20]),
+                ("numeric", vec![10, 2]),
+                ("varchar", vec![]),
+                ("char", vec![])
+            ]
+        );
+        assert!(!result.provided_columns[0].null);
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_types() -> Result<(), Box<dyn std::error::Error>> {
+        let test_string =
+            "create table foo (bar integer[] not null, baz varchar(5) [3][3], qux text)";
+
+        let (_, result) = parse_create_table::<VerboseError<&str>>(test_string)?;
+
+        let result = match result {
+            ParseTree::CreateTable(c) => c,
+            _ => panic!("Wrong type"),
+        };
+
+        let types: Vec<(&str, Vec<i32>)> = result
+            .provided_columns
+            .iter()
+            .map(|c| (c.sql_type.as_str(), c.type_modifiers.clone()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("integer[]", vec![]),
+                ("varchar[]", vec![5]),
+                ("text", vec![])
+            ]
+        );
+        assert!(!result.provided_columns[0].null);
+        Ok(())
+    }
+
     #[test]
     fn test_nullable_columns() -> Result<(), Box<dyn std::error::Error>> {
         let test_string = "create table foo (bar text, test text null)";
@@ -0,0 +1,81 @@
+//! Format here: https://www.postgresql.org/docs/current/sql-createtype.html
+//! Only enums are supported, composite and range types will come later
+
+use super::super::super::common::{
+    match_close_paren, match_comma, match_open_paren, maybe_take_whitespace, parse_sql_name,
+};
+use super::super::super::constants::parse_sql_string;
+use super::super::super::expressions::match_keyword;
+use super::match_create;
+use crate::engine::objects::{ParseExpression, ParseTree, RawCreateTypeCommand};
+use nom::combinator::{cut, map_opt};
+use nom::error::{ContextError, ParseError};
+use nom::multi::separated_list0;
+use nom::sequence::{delimited, tuple};
+use nom::IResult;
+
+pub fn parse_create_type<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseTree, E> {
+    let (input, (_, _, (type_name, _, _, _, labels, _))) = tuple((
+        match_create,
+        match_keyword("type"),
+        cut(tuple((
+            parse_sql_name,
+            match_keyword("as"),
+            match_keyword("enum"),
+            match_open_paren,
+            separated_list0(match_comma, match_label),
+            match_close_paren,
+        ))),
+    ))(input)?;
+
+    Ok((
+        input,
+        ParseTree::CreateType(RawCreateTypeCommand {
+            type_name: type_name.to_string(),
+            labels,
+        }),
+    ))
+}
+
+fn match_label<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, String, E> {
+    delimited(
+        maybe_take_whitespace,
+        map_opt(parse_sql_string, |s| match s {
+            ParseExpression::String(s) => Some(s),
+            _ => None,
+        }),
+        maybe_take_whitespace,
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::error::VerboseError;
+
+    #[test]
+    fn test_create_type() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, result) = parse_create_type::<VerboseError<&str>>(
+            "CREATE TYPE mood AS ENUM ('sad', 'ok' , 'very happy')",
+        )?;
+        assert_eq!(remaining, "");
+
+        let result = match result {
+            ParseTree::CreateType(c) => c,
+            _ => panic!("Wrong type"),
+        };
+        assert_eq!(result.type_name, "mood");
+        assert_eq!(result.labels, vec!["sad", "ok", "very happy"]);
+
+        let (_, result) = parse_create_type::<VerboseError<&str>>("create type empty as enum ()")?;
+        assert!(matches!(result, ParseTree::CreateType(c) if c.labels.is_empty()));
+
+        assert!(parse_create_type::<VerboseError<&str>>("create table foo (bar text)").is_err());
+        assert!(parse_create_type::<VerboseError<&str>>("create type mood as enum (sad)").is_err());
+        Ok(())
+    }
+}
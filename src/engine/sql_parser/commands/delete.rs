@@ -0,0 +1,63 @@
+//! Format here: https://www.postgresql.org/docs/current/sql-delete.html
+//! This is only implementing a basic delete, fancy will come later
+
+use super::super::common::parse_sql_name;
+use super::super::expressions::match_keyword;
+use super::select::{match_from, parse_where};
+use crate::engine::objects::{ParseTree, RawDeleteCommand};
+use nom::combinator::{cut, opt};
+use nom::error::{ContextError, ParseError};
+use nom::sequence::tuple;
+use nom::IResult;
+
+pub fn parse_delete<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseTree, E> {
+    let (input, (_, (_, table_name, where_clause))) = tuple((
+        match_keyword("delete"),
+        cut(tuple((match_from, parse_sql_name, opt(parse_where)))),
+    ))(input)?;
+
+    let raw_delete = RawDeleteCommand {
+        table_name: table_name.to_string(),
+        where_clause,
+    };
+
+    Ok((input, ParseTree::Delete(raw_delete)))
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::error::VerboseError;
+
+    use super::*;
+    use crate::engine::objects::{BinaryOperator, ParseExpression};
+
+    #[test]
+    fn test_delete_parser() -> Result<(), Box<dyn std::error::Error>> {
+        let test = "delete from foo where bar = 'baz'";
+
+        let (output, value) = parse_delete::<VerboseError<&str>>(test)?;
+
+        let value = match value {
+            ParseTree::Delete(d) => d,
+            _ => panic!("Wrong type"),
+        };
+        assert_eq!(output.len(), 0);
+
+        let expected = RawDeleteCommand {
+            table_name: "foo".to_string(),
+            where_clause: Some(ParseExpression::BinaryOperation(
+                Box::new(ParseExpression::Identifier(vec!["bar".to_string()])),
+                BinaryOperator::Equal,
+                Box::new(ParseExpression::String("baz".to_string())),
+            )),
+        };
+        assert_eq!(expected, value);
+
+        let (_, value) = parse_delete::<VerboseError<&str>>("DELETE FROM foo")?;
+        assert!(matches!(value, ParseTree::Delete(d) if d.where_clause.is_none()));
+
+        Ok(())
+    }
+}
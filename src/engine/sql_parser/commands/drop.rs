@@ -0,0 +1,51 @@
+//! Format here: https://www.postgresql.org/docs/current/sql-droptype.html
+//! Only dropping a single type is supported, CASCADE will come later
+
+use super::super::common::parse_sql_name;
+use super::super::expressions::match_keyword;
+use crate::engine::objects::{ParseTree, RawDropTypeCommand};
+use nom::combinator::{cut, opt};
+use nom::error::{ContextError, ParseError};
+use nom::sequence::tuple;
+use nom::IResult;
+
+pub fn parse_drop_type<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseTree, E> {
+    let (input, (_, _, (if_exists, type_name))) = tuple((
+        match_keyword("drop"),
+        match_keyword("type"),
+        cut(tuple((
+            opt(tuple((match_keyword("if"), match_keyword("exists")))),
+            parse_sql_name,
+        ))),
+    ))(input)?;
+
+    Ok((
+        input,
+        ParseTree::DropType(RawDropTypeCommand {
+            type_name: type_name.to_string(),
+            if_exists: if_exists.is_some(),
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::error::VerboseError;
+
+    use super::*;
+
+    #[test]
+    fn test_drop_type() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, result) = parse_drop_type::<VerboseError<&str>>("DROP TYPE mood")?;
+        assert_eq!(remaining, "");
+        assert!(matches!(result, ParseTree::DropType(d) if d.type_name == "mood" && !d.if_exists));
+
+        let (_, result) = parse_drop_type::<VerboseError<&str>>("drop type if exists mood")?;
+        assert!(matches!(result, ParseTree::DropType(d) if d.type_name == "mood" && d.if_exists));
+
+        assert!(parse_drop_type::<VerboseError<&str>>("drop table foo").is_err());
+        Ok(())
+    }
+}
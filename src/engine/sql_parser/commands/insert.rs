@@ -6,11 +6,12 @@ use crate::engine::objects::{ParseExpression, ParseTree};
 use super::super::super::objects::RawInsertCommand;
 use super::super::common::{
     match_close_paren, match_comma, match_open_paren, maybe_take_whitespace, parse_column_names,
-    parse_expression, parse_sql_identifier, take_whitespace,
+    parse_sql_name, take_whitespace,
 };
+use super::super::expressions::parse_expression;
 use nom::bytes::complete::tag_no_case;
 use nom::combinator::{cut, opt};
-use nom::error::{ContextError, ParseError};
+use nom::error::{context, ContextError, ParseError};
 use nom::multi::separated_list0;
 use nom::sequence::tuple;
 use nom::IResult;
@@ -22,7 +23,7 @@ pub fn parse_insert<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
         match_insert_into,
         cut(tuple((
             take_whitespace,
-            parse_sql_identifier,
+            parse_sql_name,
             maybe_take_whitespace,
             opt(parse_column_names),
             match_values,
@@ -42,8 +43,11 @@ pub fn parse_insert<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
 fn match_insert_into<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, (), E> {
-    let (input, (_, _, _)) =
-        tuple((tag_no_case("insert"), take_whitespace, tag_no_case("into")))(input)?;
+    let (input, (_, _, _)) = tuple((
+        context("insert", tag_no_case("insert")),
+        take_whitespace,
+        context("into", tag_no_case("into")),
+    ))(input)?;
     Ok((input, ()))
 }
 
@@ -52,7 +56,7 @@ fn match_values<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
 ) -> IResult<&'a str, (), E> {
     let (input, (_, _, _, _)) = tuple((
         maybe_take_whitespace,
-        tag_no_case("values"),
+        context("values", tag_no_case("values")),
         maybe_take_whitespace,
         match_open_paren,
     ))(input)?;
@@ -1,58 +1,280 @@
 use nom::{
+    branch::alt,
     bytes::complete::tag_no_case,
-    combinator::cut,
-    error::{ContextError, ParseError},
-    multi::separated_list0,
-    sequence::tuple,
+    combinator::{consumed, cut, map, opt, value, verify},
+    error::{context, ContextError, ParseError},
+    multi::{many0, separated_list0, separated_list1},
+    sequence::{pair, preceded, terminated, tuple},
     IResult,
 };
 
-use crate::engine::objects::{ParseTree, RawSelectCommand};
-
-use super::super::common::{
-    match_column_name, match_comma, maybe_take_whitespace, parse_sql_identifier, take_whitespace,
+use crate::engine::objects::{
+    JoinType, ParseExpression, ParseTree, RawJoin, RawOrderBy, RawSelectCommand, RawTableReference,
+    SortType,
 };
 
+use super::super::common::{match_comma, maybe_take_whitespace, parse_sql_name, take_whitespace};
+use super::super::expressions::{match_keyword, parse_expression};
+
+//Words that can follow a table name, so they can't be taken as its alias
+const RESERVED: &[&str] = &[
+    "cross", "full", "group", "having", "inner", "join", "left", "limit", "offset", "on", "order",
+    "outer", "right", "where",
+];
+
 pub fn parse_select<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, ParseTree, E> {
-    let (input, (_, (columns, _, _, table))) = tuple((
+    let (
+        input,
+        (
+            _,
+            (
+                columns,
+                _,
+                _,
+                table,
+                joins,
+                where_clause,
+                group_by,
+                having,
+                order_by,
+                (limit, offset),
+            ),
+        ),
+    ) = tuple((
         match_select,
         cut(tuple((
-            separated_list0(match_comma, match_column_name),
+            separated_list0(match_comma, parse_expression),
             maybe_take_whitespace,
             match_from,
-            parse_sql_identifier,
+            parse_table_reference,
+            many0(parse_join),
+            opt(parse_where),
+            opt(parse_group_by),
+            opt(preceded(match_keyword("having"), cut(parse_expression))),
+            opt(parse_order_by),
+            parse_limit_offset,
         ))),
     ))(input)?;
 
     let raw_sel = RawSelectCommand {
-        table: table.to_string(),
         columns,
+        table,
+        joins,
+        where_clause,
+        group_by: group_by.unwrap_or_default(),
+        having,
+        order_by: order_by.unwrap_or_default(),
+        limit,
+        offset,
+    };
+
+    Ok((input, ParseTree::Select(Box::new(raw_sel))))
+}
+
+fn parse_table_reference<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, RawTableReference, E> {
+    let (input, (name, alias)) = pair(
+        parse_sql_name,
+        opt(preceded(
+            pair(take_whitespace, opt(match_keyword("as"))),
+            verify(parse_sql_name, |a: &str| {
+                !RESERVED.contains(&a.to_lowercase().as_str())
+            }),
+        )),
+    )(input)?;
+
+    Ok((
+        input,
+        RawTableReference {
+            name: name.to_string(),
+            alias: alias.map(|a| a.to_string()),
+        },
+    ))
+}
+
+fn parse_join<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, RawJoin, E> {
+    let comma = map(
+        preceded(
+            tuple((maybe_take_whitespace, match_comma, maybe_take_whitespace)),
+            parse_table_reference,
+        ),
+        |table| RawJoin {
+            join_type: JoinType::Inner,
+            table,
+            condition: None,
+        },
+    );
+    let cross = map(
+        preceded(
+            pair(match_keyword("cross"), match_keyword("join")),
+            parse_table_reference,
+        ),
+        |table| RawJoin {
+            join_type: JoinType::Inner,
+            table,
+            condition: None,
+        },
+    );
+    let qualified = map(
+        tuple((
+            parse_join_type,
+            match_keyword("join"),
+            cut(parse_table_reference),
+            cut(preceded(match_keyword("on"), parse_expression)),
+        )),
+        |(join_type, _, table, condition)| RawJoin {
+            join_type,
+            table,
+            condition: Some(condition),
+        },
+    );
+
+    alt((comma, cross, qualified))(input)
+}
+
+fn parse_join_type<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, JoinType, E> {
+    let outer = |join_type| {
+        value(
+            join_type,
+            pair(
+                match_keyword(match join_type {
+                    JoinType::OuterLeft => "left",
+                    JoinType::OuterRight => "right",
+                    _ => "full",
+                }),
+                opt(match_keyword("outer")),
+            ),
+        )
     };
 
-    Ok((input, ParseTree::Select(raw_sel)))
+    alt((
+        outer(JoinType::OuterLeft),
+        outer(JoinType::OuterRight),
+        outer(JoinType::OuterFull),
+        value(JoinType::Inner, opt(match_keyword("inner"))),
+    ))(input)
 }
 
 pub(super) fn match_select<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, (), E> {
-    let (input, (_, _)) = tuple((tag_no_case("select"), take_whitespace))(input)?;
+    let (input, (_, _)) =
+        tuple((context("select", tag_no_case("select")), take_whitespace))(input)?;
     Ok((input, ()))
 }
 
 pub(super) fn match_from<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, (), E> {
-    let (input, (_, _)) = tuple((tag_no_case("from"), take_whitespace))(input)?;
+    let (input, (_, _)) = tuple((context("from", tag_no_case("from")), take_whitespace))(input)?;
     Ok((input, ()))
 }
 
+pub(super) fn parse_where<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    preceded(match_keyword("where"), cut(parse_expression))(input)
+}
+
+fn parse_group_by<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, Vec<ParseExpression>, E> {
+    preceded(
+        pair(match_keyword("group"), match_keyword("by")),
+        cut(separated_list1(match_comma, parse_expression)),
+    )(input)
+}
+
+fn parse_order_by<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, Vec<RawOrderBy>, E> {
+    let sort_type = alt((
+        value(SortType::Ascending, match_keyword("asc")),
+        value(SortType::Descending, match_keyword("desc")),
+    ));
+    let nulls = preceded(
+        match_keyword("nulls"),
+        cut(alt((
+            value(true, match_keyword("first")),
+            value(false, match_keyword("last")),
+        ))),
+    );
+
+    preceded(
+        pair(match_keyword("order"), match_keyword("by")),
+        cut(separated_list1(
+            match_comma,
+            map(
+                tuple((consumed(parse_expression), opt(sort_type), opt(nulls))),
+                |((text, expression), sort_type, nulls_first)| RawOrderBy {
+                    expression,
+                    position: sort_position(text),
+                    sort_type: sort_type.unwrap_or(SortType::Ascending),
+                    nulls_first,
+                },
+            ),
+        )),
+    )(input)
+}
+
+/// Only an integer literal is a position, a quoted '2' or an expression such as 1 + 1 is a value
+fn sort_position(text: &str) -> Option<usize> {
+    let text = text.trim();
+    if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) {
+        text.parse::<usize>().ok()
+    } else {
+        None
+    }
+}
+
+/// LIMIT and OFFSET can come in either order
+fn parse_limit_offset<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, (Option<ParseExpression>, Option<ParseExpression>), E> {
+    let (input, limit) = opt(parse_limit)(input)?;
+    let (input, offset) = opt(parse_offset)(input)?;
+    match (limit, offset) {
+        (None, Some(offset)) => {
+            let (input, limit) = opt(parse_limit)(input)?;
+            Ok((input, (limit, Some(offset))))
+        }
+        (limit, offset) => Ok((input, (limit, offset))),
+    }
+}
+
+fn parse_limit<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    preceded(
+        match_keyword("limit"),
+        cut(alt((
+            value(ParseExpression::Null(), match_keyword("all")),
+            parse_expression,
+        ))),
+    )(input)
+}
+
+fn parse_offset<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    terminated(
+        preceded(match_keyword("offset"), cut(parse_expression)),
+        opt(alt((match_keyword("rows"), match_keyword("row")))),
+    )(input)
+}
+
 #[cfg(test)]
 mod tests {
     use nom::error::VerboseError;
 
-    use crate::engine::objects::RawSelectCommand;
+    use crate::engine::objects::{BinaryOperator, RawSelectCommand};
 
     use super::*;
 
@@ -63,17 +285,238 @@ mod tests {
         let (output, value) = parse_select::<VerboseError<&str>>(test)?;
 
         let value = match value {
-            ParseTree::Select(s) => s,
+            ParseTree::Select(s) => *s,
+            _ => panic!("Wrong type"),
+        };
+        assert_eq!(output.len(), 0);
+
+        let expected = RawSelectCommand {
+            table: RawTableReference {
+                name: "baz".to_string(),
+                alias: None,
+            },
+            columns: vec![
+                ParseExpression::Identifier(vec!["foo".to_string()]),
+                ParseExpression::Identifier(vec!["bar".to_string()]),
+            ],
+            joins: vec![],
+            where_clause: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
+        };
+        assert_eq!(expected, value);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_where_parser() -> Result<(), Box<dyn std::error::Error>> {
+        let test = "select foo from baz where foo = 'bar' and bar is not null";
+
+        let (output, value) = parse_select::<VerboseError<&str>>(test)?;
+
+        let value = match value {
+            ParseTree::Select(s) => *s,
+            _ => panic!("Wrong type"),
+        };
+        assert_eq!(output.len(), 0);
+
+        let expected = RawSelectCommand {
+            table: RawTableReference {
+                name: "baz".to_string(),
+                alias: None,
+            },
+            columns: vec![ParseExpression::Identifier(vec!["foo".to_string()])],
+            joins: vec![],
+            where_clause: Some(ParseExpression::BinaryOperation(
+                Box::new(ParseExpression::BinaryOperation(
+                    Box::new(ParseExpression::Identifier(vec!["foo".to_string()])),
+                    BinaryOperator::Equal,
+                    Box::new(ParseExpression::String("bar".to_string())),
+                )),
+                BinaryOperator::And,
+                Box::new(ParseExpression::IsNotNull(Box::new(
+                    ParseExpression::Identifier(vec!["bar".to_string()]),
+                ))),
+            )),
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
+        };
+        assert_eq!(expected, value);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_join_parser() -> Result<(), Box<dyn std::error::Error>> {
+        let test = "select f.bar, b.baz from foo as f left outer join bar b on f.id = b.id, other cross join last where f.bar is null";
+
+        let (output, value) = parse_select::<VerboseError<&str>>(test)?;
+
+        let value = match value {
+            ParseTree::Select(s) => *s,
             _ => panic!("Wrong type"),
         };
         assert_eq!(output.len(), 0);
 
+        let table = |name: &str, alias: Option<&str>| RawTableReference {
+            name: name.to_string(),
+            alias: alias.map(|a| a.to_string()),
+        };
         let expected = RawSelectCommand {
-            table: "baz".to_string(),
-            columns: vec!["foo".to_string(), "bar".to_string()],
+            table: table("foo", Some("f")),
+            columns: vec![
+                ParseExpression::Identifier(vec!["f".to_string(), "bar".to_string()]),
+                ParseExpression::Identifier(vec!["b".to_string(), "baz".to_string()]),
+            ],
+            joins: vec![
+                RawJoin {
+                    join_type: JoinType::OuterLeft,
+                    table: table("bar", Some("b")),
+                    condition: Some(ParseExpression::BinaryOperation(
+                        Box::new(ParseExpression::Identifier(vec![
+                            "f".to_string(),
+                            "id".to_string(),
+                        ])),
+                        BinaryOperator::Equal,
+                        Box::new(ParseExpression::Identifier(vec![
+                            "b".to_string(),
+                            "id".to_string(),
+                        ])),
+                    )),
+                },
+                RawJoin {
+                    join_type: JoinType::Inner,
+                    table: table("other", None),
+                    condition: None,
+                },
+                RawJoin {
+                    join_type: JoinType::Inner,
+                    table: table("last", None),
+                    condition: None,
+                },
+            ],
+            where_clause: Some(ParseExpression::IsNull(Box::new(
+                ParseExpression::Identifier(vec!["f".to_string(), "bar".to_string()]),
+            ))),
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
         };
         assert_eq!(expected, value);
 
+        //Inner is optional and outer joins need a condition
+        let (_, value) = parse_select::<VerboseError<&str>>("select a from foo join bar on a = b")?;
+        match value {
+            ParseTree::Select(s) => assert_eq!(s.joins[0].join_type, JoinType::Inner),
+            _ => panic!("Wrong type"),
+        };
+        assert!(parse_select::<VerboseError<&str>>("select a from foo full join bar").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_order_limit_parser() -> Result<(), Box<dyn std::error::Error>> {
+        let test = "select foo from baz where foo = 'bar' order by foo desc nulls last, bar limit 10 offset $1";
+
+        let (output, value) = parse_select::<VerboseError<&str>>(test)?;
+
+        let value = match value {
+            ParseTree::Select(s) => *s,
+            _ => panic!("Wrong type"),
+        };
+        assert_eq!(output.len(), 0);
+        assert_eq!(
+            value.order_by,
+            vec![
+                RawOrderBy {
+                    expression: ParseExpression::Identifier(vec!["foo".to_string()]),
+                    position: None,
+                    sort_type: SortType::Descending,
+                    nulls_first: Some(false),
+                },
+                RawOrderBy {
+                    expression: ParseExpression::Identifier(vec!["bar".to_string()]),
+                    position: None,
+                    sort_type: SortType::Ascending,
+                    nulls_first: None,
+                },
+            ]
+        );
+        assert_eq!(value.limit, Some(ParseExpression::String("10".to_string())));
+        assert_eq!(value.offset, Some(ParseExpression::Parameter(1)));
+
+        //Offset can come first and limit all means no limit
+        let (output, value) =
+            parse_select::<VerboseError<&str>>("select foo from baz offset 5 rows limit all")?;
+        let value = match value {
+            ParseTree::Select(s) => *s,
+            _ => panic!("Wrong type"),
+        };
+        assert_eq!(output.len(), 0);
+        assert_eq!(value.limit, Some(ParseExpression::Null()));
+        assert_eq!(value.offset, Some(ParseExpression::String("5".to_string())));
+
+        assert!(parse_select::<VerboseError<&str>>("select foo from baz order by").is_err());
+
+        //Only bare integers are output column positions
+        let (_, value) = parse_select::<VerboseError<&str>>(
+            "select foo, bar from baz order by 2 desc, '2', 1 + 1",
+        )?;
+        let value = match value {
+            ParseTree::Select(s) => *s,
+            _ => panic!("Wrong type"),
+        };
+        let positions: Vec<_> = value.order_by.iter().map(|o| o.position).collect();
+        assert_eq!(positions, vec![Some(2), None, None]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_group_by_parser() -> Result<(), Box<dyn std::error::Error>> {
+        let test = "select foo, count(*) from baz group by foo having count(*) > 1 order by foo";
+
+        let (output, value) = parse_select::<VerboseError<&str>>(test)?;
+
+        let value = match value {
+            ParseTree::Select(s) => *s,
+            _ => panic!("Wrong type"),
+        };
+        assert_eq!(output.len(), 0);
+
+        let count =
+            ParseExpression::FunctionCall("count".to_string(), vec![ParseExpression::Wildcard()]);
+        assert_eq!(
+            value.columns,
+            vec![
+                ParseExpression::Identifier(vec!["foo".to_string()]),
+                count.clone()
+            ]
+        );
+        assert_eq!(
+            value.group_by,
+            vec![ParseExpression::Identifier(vec!["foo".to_string()])]
+        );
+        assert_eq!(
+            value.having,
+            Some(ParseExpression::BinaryOperation(
+                Box::new(count),
+                BinaryOperator::GreaterThan,
+                Box::new(ParseExpression::String("1".to_string()))
+            ))
+        );
+        assert_eq!(value.order_by.len(), 1);
+
         Ok(())
     }
 }
@@ -0,0 +1,89 @@
+//! Transaction control, formats here:
+//! * https://www.postgresql.org/docs/current/sql-begin.html
+//! * https://www.postgresql.org/docs/current/sql-commit.html
+//! * https://www.postgresql.org/docs/current/sql-rollback.html
+//!
+//! Isolation levels and other transaction modes are not supported yet
+
+use super::super::expressions::match_keyword;
+use crate::engine::objects::{ParseTree, TransactionCommand};
+use nom::branch::alt;
+use nom::combinator::{opt, value};
+use nom::error::{ContextError, ParseError};
+use nom::sequence::{terminated, tuple};
+use nom::IResult;
+
+pub fn parse_transaction<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseTree, E> {
+    let (input, command) = alt((
+        value(
+            TransactionCommand::Begin,
+            terminated(match_keyword("begin"), match_optional_noise),
+        ),
+        value(
+            TransactionCommand::Begin,
+            tuple((match_keyword("start"), match_keyword("transaction"))),
+        ),
+        value(
+            TransactionCommand::Commit,
+            terminated(
+                alt((match_keyword("commit"), match_keyword("end"))),
+                match_optional_noise,
+            ),
+        ),
+        value(
+            TransactionCommand::Rollback,
+            terminated(
+                alt((match_keyword("rollback"), match_keyword("abort"))),
+                match_optional_noise,
+            ),
+        ),
+    ))(input)?;
+
+    Ok((input, ParseTree::Transaction(command)))
+}
+
+/// Postgres allows WORK or TRANSACTION after most of these commands, they don't change anything
+fn match_optional_noise<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, (), E> {
+    let (input, _) = opt(alt((match_keyword("work"), match_keyword("transaction"))))(input)?;
+    Ok((input, ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::error::VerboseError;
+
+    use super::*;
+
+    fn parse(input: &str) -> Result<TransactionCommand, Box<dyn std::error::Error + '_>> {
+        let (remaining, value) = parse_transaction::<VerboseError<&str>>(input)?;
+        assert_eq!(remaining, "");
+        match value {
+            ParseTree::Transaction(t) => Ok(t),
+            _ => panic!("Wrong type"),
+        }
+    }
+
+    #[test]
+    fn test_transaction_parser() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(parse("begin").unwrap(), TransactionCommand::Begin);
+        assert_eq!(parse("BEGIN WORK").unwrap(), TransactionCommand::Begin);
+        assert_eq!(
+            parse("start transaction").unwrap(),
+            TransactionCommand::Begin
+        );
+        assert_eq!(parse("commit").unwrap(), TransactionCommand::Commit);
+        assert_eq!(
+            parse("END TRANSACTION").unwrap(),
+            TransactionCommand::Commit
+        );
+        assert_eq!(parse("rollback").unwrap(), TransactionCommand::Rollback);
+        assert_eq!(parse("abort work").unwrap(), TransactionCommand::Rollback);
+
+        assert!(parse_transaction::<VerboseError<&str>>("beginning").is_err());
+        Ok(())
+    }
+}
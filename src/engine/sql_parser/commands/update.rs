@@ -0,0 +1,108 @@
+//! Format here: https://www.postgresql.org/docs/current/sql-update.html
+//! This is only implementing a basic update, fancy will come later
+
+use super::super::common::{match_column_name, match_comma, parse_sql_name};
+use super::super::expressions::{match_keyword, parse_expression};
+use super::select::parse_where;
+use crate::engine::objects::{ParseExpression, ParseTree, RawUpdateCommand};
+use nom::bytes::complete::tag;
+use nom::combinator::{cut, opt};
+use nom::error::{ContextError, ParseError};
+use nom::multi::separated_list1;
+use nom::sequence::tuple;
+use nom::IResult;
+
+pub fn parse_update<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseTree, E> {
+    let (input, (_, (table_name, _, assignments, where_clause))) = tuple((
+        match_keyword("update"),
+        cut(tuple((
+            parse_sql_name,
+            match_keyword("set"),
+            separated_list1(match_comma, parse_assignment),
+            opt(parse_where),
+        ))),
+    ))(input)?;
+
+    let raw_update = RawUpdateCommand {
+        table_name: table_name.to_string(),
+        assignments,
+        where_clause,
+    };
+
+    Ok((input, ParseTree::Update(raw_update)))
+}
+
+fn parse_assignment<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, (String, ParseExpression), E> {
+    let (input, (column, _, value)) =
+        tuple((match_column_name, tag("="), parse_expression))(input)?;
+    Ok((input, (column, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::error::VerboseError;
+
+    use super::*;
+    use crate::engine::objects::BinaryOperator;
+
+    #[test]
+    fn test_update_parser() -> Result<(), Box<dyn std::error::Error>> {
+        let test = "update foo set bar = 'one', baz=2 where baz is null";
+
+        let (output, value) = parse_update::<VerboseError<&str>>(test)?;
+
+        let value = match value {
+            ParseTree::Update(u) => u,
+            _ => panic!("Wrong type"),
+        };
+        assert_eq!(output.len(), 0);
+
+        let expected = RawUpdateCommand {
+            table_name: "foo".to_string(),
+            assignments: vec![
+                (
+                    "bar".to_string(),
+                    ParseExpression::String("one".to_string()),
+                ),
+                ("baz".to_string(), ParseExpression::String("2".to_string())),
+            ],
+            where_clause: Some(ParseExpression::IsNull(Box::new(
+                ParseExpression::Identifier(vec!["baz".to_string()]),
+            ))),
+        };
+        assert_eq!(expected, value);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_boolean_value() -> Result<(), Box<dyn std::error::Error>> {
+        let test = "update foo set bar = baz = 'one'";
+
+        let (_, value) = parse_update::<VerboseError<&str>>(test)?;
+
+        let value = match value {
+            ParseTree::Update(u) => u,
+            _ => panic!("Wrong type"),
+        };
+
+        assert_eq!(
+            value.assignments,
+            vec![(
+                "bar".to_string(),
+                ParseExpression::BinaryOperation(
+                    Box::new(ParseExpression::Identifier(vec!["baz".to_string()])),
+                    BinaryOperator::Equal,
+                    Box::new(ParseExpression::String("one".to_string())),
+                )
+            )]
+        );
+        assert_eq!(value.where_clause, None);
+
+        Ok(())
+    }
+}
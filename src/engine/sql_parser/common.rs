@@ -1,52 +1,53 @@
+//! The lexical pieces shared by the parsers, see here: https://www.postgresql.org/docs/current/sql-syntax-lexical.html
 use nom::branch::alt;
-use nom::bytes::complete::{escaped_transform, is_a, tag, tag_no_case};
-use nom::character::complete::{alphanumeric1, digit1, multispace0, multispace1, none_of};
-use nom::combinator::{cut, map_parser, recognize};
-use nom::error::{ContextError, ParseError};
-use nom::multi::{many0, separated_list0, separated_list1};
-use nom::sequence::{delimited, tuple};
+use nom::bytes::complete::{is_not, tag, take_while1};
+use nom::character::complete::{anychar, multispace1, not_line_ending};
+use nom::combinator::{map, not, recognize, value, verify};
+use nom::error::{context, ContextError, ParseError};
+use nom::multi::{fold_many0, many0, many0_count, many1, separated_list0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, tuple};
 use nom::IResult;
 
-use crate::engine::objects::ParseExpression;
-
-use super::constants::parse_sql_string;
-
+/// Qualified names such as foo.bar come back a part at a time, each part is folded like parse_sql_name.
+/// Splitting after the fact would break up a quoted name with a dot in it.
 pub(super) fn parse_sql_identifier<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
-) -> IResult<&'a str, &'a str, E> {
-    is_a("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789.")(input)
-}
-
-// This parser is designed to capture valid postgres expressions and values
-// Examples:
-// * 'foo'
-// * 'foo bar'
-// * 1
-// Fancier expressions will be evolved in over time
-// Will consume all input so be careful!
-pub(super) fn parse_expression<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
-    input: &'a str,
-) -> IResult<&'a str, ParseExpression, E> {
-    cut(alt((parse_sql_string, parse_sql_integer, parse_sql_null)))(input)
+) -> IResult<&'a str, Vec<String>, E> {
+    separated_list1(tag("."), parse_sql_name)(input)
 }
 
-fn parse_sql_integer<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+/// Unquoted names are folded to lower case, double quoted ones are kept as written, same as postgres
+pub(super) fn parse_sql_name<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
-) -> IResult<&'a str, ParseExpression, E> {
-    let (input, (_, num, _)) =
-        tuple((maybe_take_whitespace, digit1, maybe_take_whitespace))(input)?;
-    Ok((input, ParseExpression::String(num.to_string())))
+) -> IResult<&'a str, String, E> {
+    alt((
+        parse_quoted_name,
+        map(
+            take_while1(|c: char| c.is_alphanumeric() || c == '_'),
+            |name: &str| name.to_lowercase(),
+        ),
+    ))(input)
 }
 
-fn parse_sql_null<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+/// Two double quotes inside the name stand for one, an empty name is not allowed
+fn parse_quoted_name<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
-) -> IResult<&'a str, ParseExpression, E> {
-    let (input, (_, _, _)) = tuple((
-        maybe_take_whitespace,
-        tag_no_case("null"),
-        maybe_take_whitespace,
-    ))(input)?;
-    Ok((input, ParseExpression::Null()))
+) -> IResult<&'a str, String, E> {
+    delimited(
+        tag("\""),
+        verify(
+            fold_many0(
+                alt((is_not("\""), value("\"", tag("\"\"")))),
+                String::new,
+                |mut name, part| {
+                    name.push_str(part);
+                    name
+                },
+            ),
+            |name: &String| !name.is_empty(),
+        ),
+        tag("\""),
+    )(input)
 }
 
 pub(super) fn parse_column_names<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
@@ -63,37 +64,68 @@ pub(super) fn parse_column_names<'a, E: ParseError<&'a str> + ContextError<&'a s
 pub(super) fn match_column_name<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, String, E> {
-    let (input, (_, name, _)) =
-        tuple((maybe_take_whitespace, alphanumeric1, maybe_take_whitespace))(input)?;
-    Ok((input, name.to_string()))
+    delimited(maybe_take_whitespace, parse_sql_name, maybe_take_whitespace)(input)
 }
 
+/// Comments count as whitespace, so they can go anywhere whitespace can
 pub(super) fn maybe_take_whitespace<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, &'a str, E> {
-    multispace0(input)
+    recognize(many0_count(alt((
+        value((), multispace1),
+        parse_line_comment,
+        parse_block_comment,
+    ))))(input)
 }
 pub(super) fn take_whitespace<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, &'a str, E> {
-    multispace1(input)
+    recognize(many1(alt((
+        value((), multispace1),
+        parse_line_comment,
+        parse_block_comment,
+    ))))(input)
+}
+
+/// Runs to the end of the line, the line ending is left as whitespace
+fn parse_line_comment<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, (), E> {
+    value((), pair(tag("--"), not_line_ending))(input)
+}
+
+/// Block comments nest, same as postgres
+fn parse_block_comment<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, (), E> {
+    value(
+        (),
+        delimited(
+            tag("/*"),
+            many0(alt((
+                parse_block_comment,
+                value((), preceded(not(tag("*/")), anychar)),
+            ))),
+            tag("*/"),
+        ),
+    )(input)
 }
 
 pub(super) fn match_open_paren<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, &'a str, E> {
-    tag("(")(input)
+    context("(", tag("("))(input)
 }
 pub(super) fn match_close_paren<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, &'a str, E> {
-    tag(")")(input)
+    context(")", tag(")"))(input)
 }
 
 pub(super) fn match_comma<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, &'a str, E> {
-    tag(",")(input)
+    context(",", tag(","))(input)
 }
 
 #[cfg(test)]
@@ -110,8 +142,38 @@ mod tests {
         assert!(res.is_ok());
         let (output, value) = res.unwrap();
         assert_eq!(output.len(), 0);
-        assert_eq!(test, value);
-    }
+        assert_eq!(value, vec!["bar", "foo"]);
+
+        //The system tables all have underscores
+        let (output, value) = parse_sql_identifier::<VerboseError<&str>>("pg_type x").unwrap();
+        assert_eq!(output, " x");
+        assert_eq!(value, vec!["pg_type"]);
 
+        let (output, value) =
+            parse_sql_identifier::<VerboseError<&str>>(r#"Foo."Bar ""Baz"""."#).unwrap();
+        assert_eq!(output, ".");
+        assert_eq!(value, vec!["foo", r#"Bar "Baz""#]);
 
+        let (_, value) = parse_sql_identifier::<VerboseError<&str>>(r#"f."a.b""#).unwrap();
+        assert_eq!(value, vec!["f", "a.b"]);
+
+        assert!(parse_sql_identifier::<VerboseError<&str>>(r#""""#).is_err());
+        assert!(parse_sql_identifier::<VerboseError<&str>>(r#""foo"#).is_err());
+    }
+
+    #[test]
+    fn test_comments() -> Result<(), Box<dyn std::error::Error>> {
+        let (output, _) =
+            maybe_take_whitespace::<VerboseError<&str>>(" -- one\n/* two /* three */ */\t--\nfoo")?;
+        assert_eq!(output, "foo");
+
+        let (output, _) = maybe_take_whitespace::<VerboseError<&str>>("foo")?;
+        assert_eq!(output, "foo");
+        assert!(take_whitespace::<VerboseError<&str>>("foo").is_err());
+
+        //An unfinished comment is not whitespace
+        let (output, _) = maybe_take_whitespace::<VerboseError<&str>>(" /* foo")?;
+        assert_eq!(output, "/* foo");
+        Ok(())
+    }
 }
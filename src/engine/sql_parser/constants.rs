@@ -1,69 +1,144 @@
-use std::iter::FromIterator;
-
+//! String constants, see here: https://www.postgresql.org/docs/current/sql-syntax-lexical.html#SQL-SYNTAX-CONSTANTS
 use crate::engine::objects::ParseExpression;
-use nom::{
-    branch::alt,
-    bytes::complete::{
-        escaped_transform, is_not, tag, take_till, take_until,
-        take_until_parser_matches_and_consume,
-    },
-    character::complete::{anychar, line_ending, none_of, space0},
-    combinator::{map_parser, not, peek, recognize, rest, value},
-    error::{ContextError, ParseError},
-    multi::{fold_many1, many0, many1, many_till, separated_list1},
-    sequence::{delimited, terminated, tuple},
-    IResult,
-};
+use nom::branch::alt;
+use nom::bytes::complete::{is_not, tag, tag_no_case, take_until, take_while, take_while_m_n};
+use nom::character::complete::{anychar, line_ending, satisfy, space0};
+use nom::combinator::{cut, map, map_opt, opt, peek, recognize, value};
+use nom::error::{ContextError, ParseError};
+use nom::multi::{fold_many0, many1};
+use nom::sequence::{delimited, pair, preceded, tuple};
+use nom::IResult;
 
 /// SQL string constants have wacky syntax, they are surrounded by single quotes
 ///
 /// Two single quotes escape into a single quote
 ///
 /// Two single quotes with a newline + any other whitespace merge into a single string
+///
+/// An E before the first quote turns on C style backslash escapes, and $$ or $tag$ quoting takes the
+/// text between as is
 pub fn parse_sql_string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, ParseExpression, E> {
-    let (input, _) = tag("'")(input)?;
-    let (input, buffer) = take_until_parser_matches_and_consume(sql_string_end_tag)(input)?;
-
-    let (_, buffer) = escaped_transform(is_not("'"), '\'', value("'", tag("'")))(buffer)?;
-    /*let (input, _) = tag("'")(input)?;
-    let mut buffer = String::new();
-
-    loop {
-        let (input, chunk) = take_until("'")(input)?;
-        buffer += chunk;
-
-        if is_end_tag::<'a, E>(input) {
-            return Ok(("", ParseExpression::String(buffer)));
-        } else {
-            buffer += "'";
-        }
-    }
-    */
+    let (input, value) = alt((parse_dollar_quoted, parse_quoted))(input)?;
+    Ok((input, ParseExpression::String(value)))
+}
 
-    //let raw_sql_string = String::from_iter(raw_sql_string);
-    //let raw_sql_str = raw_sql_string.as_str();
+/// The continued parts of an E string take escapes too
+fn parse_quoted<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, String, E> {
+    let (input, escapes) = map(opt(tag_no_case("e")), |e| e.is_some())(input)?;
+    let segment = move |input| match escapes {
+        true => parse_escape_segment(input),
+        false => parse_standard_segment(input),
+    };
+    let (input, first) = segment(input)?;
+    fold_many0(
+        preceded(whitespace_with_newline, segment),
+        move || first.clone(),
+        |mut value, segment| {
+            value.push_str(&segment);
+            value
+        },
+    )(input)
+}
 
-    //We have a sql string but now need to clean up the escapes
-    //let (input, sql_quote_escape) =
-    //    escaped_transform(none_of("'"), '\'', tag("'"))(raw_sql_string)?;
+fn parse_standard_segment<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, String, E> {
+    delimited(
+        tag("'"),
+        fold_many0(
+            alt((is_not("'"), value("'", tag("''")))),
+            String::new,
+            |mut value, part| {
+                value.push_str(part);
+                value
+            },
+        ),
+        tag("'"),
+    )(input)
+}
 
-    //let seq = recognize(separated_list1(tag("''"), many0(none_of("'"))));
-    //let unquote = escaped_transform(none_of("'"), '\'', tag("'"));
-    //let (input, sql_value) =
-    //   tuple((delimited(tag("'"), map_parser(seq, unquote), tag("'")),))(input)?;
-    Ok((input, ParseExpression::String(buffer)))
+fn parse_escape_segment<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, String, E> {
+    delimited(
+        tag("'"),
+        fold_many0(
+            alt((
+                map(is_not("'\\"), |s: &str| s.to_string()),
+                value("'".to_string(), tag("''")),
+                map(preceded(tag("\\"), parse_escape), |c| c.to_string()),
+            )),
+            String::new,
+            |mut value, part| {
+                value.push_str(&part);
+                value
+            },
+        ),
+        tag("'"),
+    )(input)
 }
 
-fn sql_string_end_tag<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+/// Any character without a special meaning stands for itself, so \\ and \' work too.
+/// A code point that can't be in a string is an error instead of being taken as text.
+fn parse_escape<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
-) -> IResult<&'a str, (), E> {
-    let (input, _) = tag("'")(input)?;
-    let (_, _) = peek(not(tag("'")))(input)?;
-    let (_, _) = peek(not(tuple((whitespace_with_newline, tag("'")))))(input)?;
+) -> IResult<&'a str, char, E> {
+    let octal = |c: char| c.is_digit(8);
+    let hex = |c: char| c.is_ascii_hexdigit();
+    alt((
+        value('\u{8}', tag("b")),
+        value('\u{c}', tag("f")),
+        value('\n', tag("n")),
+        value('\r', tag("r")),
+        value('\t', tag("t")),
+        preceded(
+            peek(satisfy(octal)),
+            cut(map_opt(take_while_m_n(1, 3, octal), |o| code_point(o, 8))),
+        ),
+        preceded(
+            pair(tag("x"), peek(satisfy(hex))),
+            cut(map_opt(take_while_m_n(1, 2, hex), |h| code_point(h, 16))),
+        ),
+        preceded(
+            tag("u"),
+            cut(map_opt(take_while_m_n(4, 4, hex), |h| code_point(h, 16))),
+        ),
+        preceded(
+            tag("U"),
+            cut(map_opt(take_while_m_n(8, 8, hex), |h| code_point(h, 16))),
+        ),
+        anychar,
+    ))(input)
+}
 
-    Ok((input, ()))
+/// Zero is not allowed in a string, same as postgres
+fn code_point(digits: &str, radix: u32) -> Option<char> {
+    u32::from_str_radix(digits, radix)
+        .ok()
+        .filter(|c| *c != 0)
+        .and_then(char::from_u32)
+}
+
+/// The tag follows the rules for a name except it can't have a $ in it
+fn parse_dollar_quoted<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, String, E> {
+    let (input, name) = delimited(
+        tag("$"),
+        recognize(opt(pair(
+            satisfy(|c: char| c.is_alphabetic() || c == '_'),
+            take_while(|c: char| c.is_alphanumeric() || c == '_'),
+        ))),
+        tag("$"),
+    )(input)?;
+    let delimiter = format!("${}$", name);
+    let (input, value) = take_until(delimiter.as_str())(input)?;
+    let (input, _) = tag(delimiter.as_str())(input)?;
+    Ok((input, value.to_string()))
 }
 
 fn whitespace_with_newline<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
@@ -124,14 +199,53 @@ mod tests {
 
     #[test]
     fn test_parse_end_tags() -> Result<(), Box<dyn std::error::Error>> {
-        let test = "'";
-        let (remaining, _) = sql_string_end_tag::<VerboseError<&str>>(test)?;
+        //Without a newline the strings are not merged
+        let test = "'one' 'two'";
+        let (remaining, parsed) = parse_sql_string::<VerboseError<&str>>(test)?;
+        assert_eq!(remaining, " 'two'");
+        assert_eq!(parsed, ParseExpression::String("one".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_escape_string() -> Result<(), Box<dyn std::error::Error>> {
+        //The continuation of an E string takes escapes too
+        let test = concat!(
+            r"E'a\tb\nc\\d\'e''f\x41\101\u00e9\U0001F600\q' ",
+            "\n",
+            r" '\n'"
+        );
+        let expected = ParseExpression::String("a\tb\nc\\d'e'fAA\u{e9}\u{1F600}q\n".to_string());
+
+        let (remaining, parsed) = parse_sql_string::<VerboseError<&str>>(test)?;
         assert_eq!(remaining, "");
+        assert_eq!(expected, parsed);
+
+        //Backslashes are only special in E strings
+        let (_, parsed) = parse_sql_string::<VerboseError<&str>>(r"'a\n'")?;
+        assert_eq!(parsed, ParseExpression::String(r"a\n".to_string()));
+
+        assert!(parse_sql_string::<VerboseError<&str>>(r"e'\0'").is_err());
+        assert!(parse_sql_string::<VerboseError<&str>>(r"E'\uD800'").is_err());
+        assert!(parse_sql_string::<VerboseError<&str>>(r"E'\'").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_dollar_quoted() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, parsed) = parse_sql_string::<VerboseError<&str>>("$$it's a \\ $$ ")?;
+        assert_eq!(remaining, " ");
+        assert_eq!(parsed, ParseExpression::String(r"it's a \ ".to_string()));
 
-        let test = "' \n '";
-        let res = sql_string_end_tag::<VerboseError<&str>>(test);
-        assert!(res.is_err());
+        let (remaining, parsed) =
+            parse_sql_string::<VerboseError<&str>>("$fn_1$has $$ inside$fn_1$")?;
+        assert_eq!(remaining, "");
+        assert_eq!(parsed, ParseExpression::String("has $$ inside".to_string()));
 
+        //Parameters are not strings
+        assert!(parse_sql_string::<VerboseError<&str>>("$1").is_err());
+        assert!(parse_sql_string::<VerboseError<&str>>("$a$unfinished").is_err());
         Ok(())
     }
 }
@@ -0,0 +1,791 @@
+//! Parses sql expressions into a tree, the precedence is the same as postgres
+//! See here: https://www.postgresql.org/docs/current/sql-syntax-lexical.html#SQL-PRECEDENCE
+//!
+//! Lowest to highest:
+//! * OR
+//! * AND
+//! * NOT
+//! * IS NULL / IS NOT NULL
+//! * Comparisons (=, <>, !=, <, <=, >, >=), optionally against ANY/SOME/ALL of an array
+//! * All other operators (->, ->>, #>, @>, ?, &&)
+//! * Addition and subtraction
+//! * Multiplication and division
+//! * Array subscripts
+//! * Values, arrays, function calls, column names and parenthesized expressions
+use super::common::{
+    match_close_paren, match_comma, match_open_paren, maybe_take_whitespace, parse_sql_identifier,
+    parse_sql_name,
+};
+use super::constants::parse_sql_string;
+use crate::engine::objects::types::{parse_type, BaseSqlTypesMapper};
+use crate::engine::objects::{BinaryOperator, ParseExpression, Quantifier};
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case};
+use nom::character::complete::{digit0, digit1, one_of, satisfy};
+use nom::combinator::{map, not, opt, recognize, value};
+use nom::error::{context, ContextError, ErrorKind, ParseError};
+use nom::multi::{many0, separated_list0};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+
+pub(super) fn parse_expression<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    parse_or(input)
+}
+
+/// Matches a keyword that is not the prefix of a longer word, eats the whitespace around it
+pub(super) fn match_keyword<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    keyword: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (), E> {
+    move |input: &'a str| {
+        let (input, _) = maybe_take_whitespace(input)?;
+        let (input, (_, _, _)) = context(
+            keyword,
+            tuple((
+                tag_no_case(keyword),
+                not(satisfy(|c: char| c.is_alphanumeric() || c == '_')),
+                maybe_take_whitespace,
+            )),
+        )(input)?;
+        Ok((input, ()))
+    }
+}
+
+fn parse_or<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, first) = parse_and(input)?;
+    let (input, rest) = many0(preceded(match_keyword("or"), parse_and))(input)?;
+    Ok((input, fold_binary(first, BinaryOperator::Or, rest)))
+}
+
+fn parse_and<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, first) = parse_not(input)?;
+    let (input, rest) = many0(preceded(match_keyword("and"), parse_not))(input)?;
+    Ok((input, fold_binary(first, BinaryOperator::And, rest)))
+}
+
+fn fold_binary(
+    first: ParseExpression,
+    op: BinaryOperator,
+    rest: Vec<ParseExpression>,
+) -> ParseExpression {
+    rest.into_iter().fold(first, |left, right| {
+        ParseExpression::BinaryOperation(Box::new(left), op, Box::new(right))
+    })
+}
+
+fn parse_not<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    alt((
+        map(preceded(match_keyword("not"), parse_not), |e| {
+            ParseExpression::Not(Box::new(e))
+        }),
+        parse_is_null,
+    ))(input)
+}
+
+fn parse_is_null<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, expr) = parse_comparison(input)?;
+    let (input, is_null) = opt(tuple((
+        match_keyword("is"),
+        opt(match_keyword("not")),
+        match_keyword("null"),
+    )))(input)?;
+
+    match is_null {
+        Some((_, Some(_), _)) => Ok((input, ParseExpression::IsNotNull(Box::new(expr)))),
+        Some((_, None, _)) => Ok((input, ParseExpression::IsNull(Box::new(expr)))),
+        None => Ok((input, expr)),
+    }
+}
+
+fn parse_comparison<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, left) = parse_other_operator(input)?;
+    let (input, right) = opt(tuple((
+        match_comparison_operator,
+        alt((
+            map(parse_quantified, |(q, right)| (Some(q), right)),
+            map(parse_other_operator, |right| (None, right)),
+        )),
+    )))(input)?;
+
+    match right {
+        Some((op, (Some(quantifier), right))) => Ok((
+            input,
+            ParseExpression::ArrayComparison(Box::new(left), op, quantifier, Box::new(right)),
+        )),
+        Some((op, (None, right))) => Ok((
+            input,
+            ParseExpression::BinaryOperation(Box::new(left), op, Box::new(right)),
+        )),
+        None => Ok((input, left)),
+    }
+}
+
+fn parse_quantified<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, (Quantifier, ParseExpression), E> {
+    tuple((
+        alt((
+            value(Quantifier::Any, match_keyword("any")),
+            value(Quantifier::Any, match_keyword("some")),
+            value(Quantifier::All, match_keyword("all")),
+        )),
+        parse_parenthesized,
+    ))(input)
+}
+
+fn match_comparison_operator<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, BinaryOperator, E> {
+    let (input, (_, op, _)) = tuple((
+        maybe_take_whitespace,
+        alt((
+            value(BinaryOperator::LessThanOrEqual, tag("<=")),
+            value(BinaryOperator::GreaterThanOrEqual, tag(">=")),
+            value(BinaryOperator::NotEqual, tag("<>")),
+            value(BinaryOperator::NotEqual, tag("!=")),
+            value(BinaryOperator::Equal, tag("=")),
+            value(BinaryOperator::LessThan, tag("<")),
+            value(BinaryOperator::GreaterThan, tag(">")),
+        )),
+        maybe_take_whitespace,
+    ))(input)?;
+    Ok((input, op))
+}
+
+/// Postgres puts every operator without its own precedence at the same level, these are the jsonb
+/// and array ones
+fn parse_other_operator<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, first) = parse_additive(input)?;
+    let (input, rest) = many0(tuple((
+        match_operator(alt((
+            value(BinaryOperator::JsonFieldText, tag("->>")),
+            value(BinaryOperator::JsonField, tag("->")),
+            value(BinaryOperator::JsonPath, tag("#>")),
+            value(BinaryOperator::Contains, tag("@>")),
+            value(BinaryOperator::JsonHasKey, tag("?")),
+            value(BinaryOperator::Overlap, tag("&&")),
+        ))),
+        parse_additive,
+    )))(input)?;
+    Ok((input, fold_operators(first, rest)))
+}
+
+fn parse_additive<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, first) = parse_multiplicative(input)?;
+    let (input, rest) = many0(tuple((
+        match_operator(alt((
+            value(BinaryOperator::Add, tag("+")),
+            value(BinaryOperator::Subtract, tag("-")),
+        ))),
+        parse_multiplicative,
+    )))(input)?;
+    Ok((input, fold_operators(first, rest)))
+}
+
+fn parse_multiplicative<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, first) = parse_operand(input)?;
+    let (input, rest) = many0(tuple((
+        match_operator(alt((
+            value(BinaryOperator::Multiply, tag("*")),
+            value(BinaryOperator::Divide, tag("/")),
+        ))),
+        parse_operand,
+    )))(input)?;
+    Ok((input, fold_operators(first, rest)))
+}
+
+fn match_operator<'a, E: ParseError<&'a str> + ContextError<&'a str>, F>(
+    operator: F,
+) -> impl FnMut(&'a str) -> IResult<&'a str, BinaryOperator, E>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, BinaryOperator, E>,
+{
+    delimited(maybe_take_whitespace, operator, maybe_take_whitespace)
+}
+
+/// Same as fold_binary but each step can have a different operator, all left associative
+fn fold_operators(
+    first: ParseExpression,
+    rest: Vec<(BinaryOperator, ParseExpression)>,
+) -> ParseExpression {
+    rest.into_iter().fold(first, |left, (op, right)| {
+        ParseExpression::BinaryOperation(Box::new(left), op, Box::new(right))
+    })
+}
+
+fn parse_operand<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, (_, operand, _, subscripts)) = tuple((
+        maybe_take_whitespace,
+        alt((
+            parse_parenthesized,
+            parse_sql_string,
+            parse_sql_number,
+            parse_sql_null,
+            parse_sql_boolean,
+            parse_current_timestamp,
+            parse_parameter,
+            parse_array_constructor,
+            parse_typed_string,
+            parse_function_call,
+            parse_column_reference,
+        )),
+        maybe_take_whitespace,
+        many0(terminated(
+            delimited(
+                tag("["),
+                parse_expression,
+                tuple((maybe_take_whitespace, tag("]"))),
+            ),
+            maybe_take_whitespace,
+        )),
+    ))(input)?;
+    match subscripts.is_empty() {
+        true => Ok((input, operand)),
+        false => Ok((
+            input,
+            ParseExpression::Subscript(Box::new(operand), subscripts),
+        )),
+    }
+}
+
+fn parse_parenthesized<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    delimited(
+        match_open_paren,
+        parse_expression,
+        tuple((maybe_take_whitespace, match_close_paren)),
+    )(input)
+}
+
+/// ARRAY[1, 2], a multi-dimensional array only needs the keyword once: ARRAY[[1, 2], [3, 4]]
+fn parse_array_constructor<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    preceded(
+        tuple((tag_no_case("array"), maybe_take_whitespace)),
+        parse_array_brackets,
+    )(input)
+}
+
+fn parse_array_brackets<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, items) = delimited(
+        tag("["),
+        separated_list0(
+            match_comma,
+            alt((
+                delimited(
+                    maybe_take_whitespace,
+                    parse_array_brackets,
+                    maybe_take_whitespace,
+                ),
+                parse_expression,
+            )),
+        ),
+        tuple((maybe_take_whitespace, tag("]"))),
+    )(input)?;
+    Ok((input, ParseExpression::Array(items)))
+}
+
+/// A leading minus is kept with the digits so the most negative value of each width still parses.
+/// Decimals and exponents are left as text too, the type they become depends on where they are used.
+fn parse_sql_number<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, num) = recognize(tuple((
+        opt(tag("-")),
+        alt((
+            recognize(pair(digit1, opt(pair(tag("."), digit0)))),
+            recognize(pair(tag("."), digit1)),
+        )),
+        opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
+    )))(input)?;
+    Ok((input, ParseExpression::String(num.to_string())))
+}
+
+fn parse_sql_null<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, _) = match_keyword("null")(input)?;
+    Ok((input, ParseExpression::Null()))
+}
+
+fn parse_sql_boolean<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    alt((
+        value(
+            ParseExpression::String("true".to_string()),
+            match_keyword("true"),
+        ),
+        value(
+            ParseExpression::String("false".to_string()),
+            match_keyword("false"),
+        ),
+    ))(input)
+}
+
+/// The standard spells some functions as bare keywords, this is the same as calling now()
+fn parse_current_timestamp<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    value(
+        ParseExpression::FunctionCall("current_timestamp".to_string(), vec![]),
+        match_keyword("current_timestamp"),
+    )(input)
+}
+
+/// Only the date and time types can be written this way, such as timestamp '2020-01-01 00:00'
+fn parse_typed_string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (rest, (sql_type, _, literal)) =
+        tuple((parse_type, maybe_take_whitespace, parse_sql_string))(input)?;
+    match (sql_type, literal) {
+        (
+            sql_type @ (BaseSqlTypesMapper::Date
+            | BaseSqlTypesMapper::Time
+            | BaseSqlTypesMapper::Timestamp
+            | BaseSqlTypesMapper::TimestampTz
+            | BaseSqlTypesMapper::Interval),
+            ParseExpression::String(s),
+        ) => Ok((rest, ParseExpression::TypedString(sql_type, s))),
+        _ => Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Tag))),
+    }
+}
+
+/// Positional parameters ($1, $2, ...) are filled in when a prepared statement is bound
+fn parse_parameter<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (rest, digits) = preceded(tag("$"), digit1)(input)?;
+    match digits.parse::<usize>() {
+        Ok(index) if index > 0 => Ok((rest, ParseExpression::Parameter(index))),
+        _ => Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Digit))),
+    }
+}
+
+/// count(*) is the only place a * is allowed as an argument
+fn parse_function_call<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, (name, _, _, args, _, _)) = tuple((
+        parse_sql_name,
+        maybe_take_whitespace,
+        match_open_paren,
+        alt((
+            map(
+                delimited(maybe_take_whitespace, tag("*"), maybe_take_whitespace),
+                |_| vec![ParseExpression::Wildcard()],
+            ),
+            separated_list0(match_comma, parse_expression),
+        )),
+        maybe_take_whitespace,
+        match_close_paren,
+    ))(input)?;
+    Ok((input, ParseExpression::FunctionCall(name, args)))
+}
+
+fn parse_column_reference<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ParseExpression, E> {
+    let (input, name) = parse_sql_identifier(input)?;
+    Ok((input, ParseExpression::Identifier(name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::error::VerboseError;
+
+    use super::*;
+
+    fn ident(name: &str) -> Box<ParseExpression> {
+        Box::new(ParseExpression::Identifier(
+            name.split('.').map(|p| p.to_string()).collect(),
+        ))
+    }
+
+    fn string(value: &str) -> Box<ParseExpression> {
+        Box::new(ParseExpression::String(value.to_string()))
+    }
+
+    #[test]
+    fn test_values() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>(" 'foo' ")?;
+        assert_eq!(remaining, "");
+        assert_eq!(parsed, *string("foo"));
+
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("2")?;
+        assert_eq!(remaining, "");
+        assert_eq!(parsed, *string("2"));
+
+        for number in ["-1.25", ".5", "3.", "6.02e23", "1E-7"] {
+            let (remaining, parsed) = parse_expression::<VerboseError<&str>>(number)?;
+            assert_eq!(remaining, "");
+            assert_eq!(parsed, *string(number));
+        }
+
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("NULL")?;
+        assert_eq!(remaining, "");
+        assert_eq!(parsed, ParseExpression::Null());
+
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("nullable")?;
+        assert_eq!(remaining, "");
+        assert_eq!(parsed, *ident("nullable"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_comparison() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("foo>='bar'")?;
+        assert_eq!(remaining, "");
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(
+                ident("foo"),
+                BinaryOperator::GreaterThanOrEqual,
+                string("bar")
+            )
+        );
+
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("foo <> 1")?;
+        assert_eq!(remaining, "");
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(ident("foo"), BinaryOperator::NotEqual, string("1"))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_arithmetic() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("a - -5 * b/2 + 1 >= 3")?;
+        assert_eq!(remaining, "");
+
+        let product = Box::new(ParseExpression::BinaryOperation(
+            Box::new(ParseExpression::BinaryOperation(
+                string("-5"),
+                BinaryOperator::Multiply,
+                ident("b"),
+            )),
+            BinaryOperator::Divide,
+            string("2"),
+        ));
+        let sum = Box::new(ParseExpression::BinaryOperation(
+            Box::new(ParseExpression::BinaryOperation(
+                ident("a"),
+                BinaryOperator::Subtract,
+                product,
+            )),
+            BinaryOperator::Add,
+            string("1"),
+        ));
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(sum, BinaryOperator::GreaterThanOrEqual, string("3"))
+        );
+
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("a-1")?;
+        assert_eq!(remaining, "");
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(ident("a"), BinaryOperator::Subtract, string("1"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_operators() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, parsed) =
+            parse_expression::<VerboseError<&str>>("doc->'a'->>0 = 'x' and doc?'b'")?;
+        assert_eq!(remaining, "");
+
+        let field = Box::new(ParseExpression::BinaryOperation(
+            Box::new(ParseExpression::BinaryOperation(
+                ident("doc"),
+                BinaryOperator::JsonField,
+                string("a"),
+            )),
+            BinaryOperator::JsonFieldText,
+            string("0"),
+        ));
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(
+                Box::new(ParseExpression::BinaryOperation(
+                    field,
+                    BinaryOperator::Equal,
+                    string("x")
+                )),
+                BinaryOperator::And,
+                Box::new(ParseExpression::BinaryOperation(
+                    ident("doc"),
+                    BinaryOperator::JsonHasKey,
+                    string("b")
+                )),
+            )
+        );
+
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("doc #> '{a,b}' @> '1'")?;
+        assert_eq!(remaining, "");
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(
+                Box::new(ParseExpression::BinaryOperation(
+                    ident("doc"),
+                    BinaryOperator::JsonPath,
+                    string("{a,b}"),
+                )),
+                BinaryOperator::Contains,
+                string("1"),
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrays() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, parsed) =
+            parse_expression::<VerboseError<&str>>("ARRAY[[1, 2], [a[1]]] && b [ 1 ][2]")?;
+        assert_eq!(remaining, "");
+
+        let subscript = ParseExpression::Subscript(ident("a"), vec![*string("1")]);
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(
+                Box::new(ParseExpression::Array(vec![
+                    ParseExpression::Array(vec![*string("1"), *string("2")]),
+                    ParseExpression::Array(vec![subscript]),
+                ])),
+                BinaryOperator::Overlap,
+                Box::new(ParseExpression::Subscript(
+                    ident("b"),
+                    vec![*string("1"), *string("2")]
+                )),
+            )
+        );
+
+        let (remaining, parsed) =
+            parse_expression::<VerboseError<&str>>("a = any(b) and 1 < ALL ('{2,3}') or c = all")?;
+        assert_eq!(remaining, "");
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(
+                Box::new(ParseExpression::BinaryOperation(
+                    Box::new(ParseExpression::ArrayComparison(
+                        ident("a"),
+                        BinaryOperator::Equal,
+                        Quantifier::Any,
+                        ident("b")
+                    )),
+                    BinaryOperator::And,
+                    Box::new(ParseExpression::ArrayComparison(
+                        string("1"),
+                        BinaryOperator::LessThan,
+                        Quantifier::All,
+                        string("{2,3}")
+                    )),
+                )),
+                BinaryOperator::Or,
+                Box::new(ParseExpression::BinaryOperation(
+                    ident("c"),
+                    BinaryOperator::Equal,
+                    ident("all")
+                )),
+            )
+        );
+
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("array[]")?;
+        assert_eq!(remaining, "");
+        assert_eq!(parsed, ParseExpression::Array(vec![]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_precedence() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>(
+            "a = 1 or not b is null and (c = 2 or d is not null)",
+        )?;
+        assert_eq!(remaining, "");
+
+        let a = Box::new(ParseExpression::BinaryOperation(
+            ident("a"),
+            BinaryOperator::Equal,
+            string("1"),
+        ));
+        let not_b = Box::new(ParseExpression::Not(Box::new(ParseExpression::IsNull(
+            ident("b"),
+        ))));
+        let c_or_d = Box::new(ParseExpression::BinaryOperation(
+            Box::new(ParseExpression::BinaryOperation(
+                ident("c"),
+                BinaryOperator::Equal,
+                string("2"),
+            )),
+            BinaryOperator::Or,
+            Box::new(ParseExpression::IsNotNull(ident("d"))),
+        ));
+        let expected = ParseExpression::BinaryOperation(
+            a,
+            BinaryOperator::Or,
+            Box::new(ParseExpression::BinaryOperation(
+                not_b,
+                BinaryOperator::And,
+                c_or_d,
+            )),
+        );
+
+        assert_eq!(parsed, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameters() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("foo = $1 and $12")?;
+        assert_eq!(remaining, "");
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(
+                Box::new(ParseExpression::BinaryOperation(
+                    ident("foo"),
+                    BinaryOperator::Equal,
+                    Box::new(ParseExpression::Parameter(1))
+                )),
+                BinaryOperator::And,
+                Box::new(ParseExpression::Parameter(12))
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_call() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, parsed) =
+            parse_expression::<VerboseError<&str>>("count(*) > sum( foo ) and max(a, 'b')")?;
+        assert_eq!(remaining, "");
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(
+                Box::new(ParseExpression::BinaryOperation(
+                    Box::new(ParseExpression::FunctionCall(
+                        "count".to_string(),
+                        vec![ParseExpression::Wildcard()]
+                    )),
+                    BinaryOperator::GreaterThan,
+                    Box::new(ParseExpression::FunctionCall(
+                        "sum".to_string(),
+                        vec![*ident("foo")]
+                    )),
+                )),
+                BinaryOperator::And,
+                Box::new(ParseExpression::FunctionCall(
+                    "max".to_string(),
+                    vec![*ident("a"), *string("b")]
+                ))
+            )
+        );
+
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("now()")?;
+        assert_eq!(remaining, "");
+        assert_eq!(
+            parsed,
+            ParseExpression::FunctionCall("now".to_string(), vec![])
+        );
+
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("jsonb_agg(a)")?;
+        assert_eq!(remaining, "");
+        assert_eq!(
+            parsed,
+            ParseExpression::FunctionCall("jsonb_agg".to_string(), vec![*ident("a")])
+        );
+
+        let (remaining, parsed) =
+            parse_expression::<VerboseError<&str>>("current_timestamp - created")?;
+        assert_eq!(remaining, "");
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(
+                Box::new(ParseExpression::FunctionCall(
+                    "current_timestamp".to_string(),
+                    vec![]
+                )),
+                BinaryOperator::Subtract,
+                ident("created")
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_literals() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>(
+            "timestamp with time zone '2020-01-01 00:00Z' + interval '1 hour'",
+        )?;
+        assert_eq!(remaining, "");
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(
+                Box::new(ParseExpression::TypedString(
+                    BaseSqlTypesMapper::TimestampTz,
+                    "2020-01-01 00:00Z".to_string()
+                )),
+                BinaryOperator::Add,
+                Box::new(ParseExpression::TypedString(
+                    BaseSqlTypesMapper::Interval,
+                    "1 hour".to_string()
+                ))
+            )
+        );
+
+        //Columns named after a type are still columns
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("date = '2020-01-01'")?;
+        assert_eq!(remaining, "");
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(
+                ident("date"),
+                BinaryOperator::Equal,
+                string("2020-01-01")
+            )
+        );
+
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("text 'abc'")?;
+        assert_eq!(remaining, "'abc'");
+        assert_eq!(parsed, *ident("text"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyword_prefix() -> Result<(), Box<dyn std::error::Error>> {
+        let (remaining, parsed) = parse_expression::<VerboseError<&str>>("a = b order")?;
+        assert_eq!(remaining, "order");
+        assert_eq!(
+            parsed,
+            ParseExpression::BinaryOperation(ident("a"), BinaryOperator::Equal, ident("b"))
+        );
+        Ok(())
+    }
+}
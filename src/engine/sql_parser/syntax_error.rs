@@ -0,0 +1,154 @@
+//! Turns a failed parse into something a person can act on, the same idea as postgres' "syntax error at or near"
+//!
+//! nom only reports the last alternative it tried, so parsing is done with an error type that keeps
+//! whichever failure got the furthest into the query. That is almost always the real mistake.
+use nom::error::{ContextError, ErrorKind, ParseError};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// The offset is in bytes from the start of the query, the position is in characters starting at one
+/// since that is what the P field of an ErrorResponse holds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxError {
+    pub offset: usize,
+    pub position: usize,
+    pub expected: Vec<String>,
+    //None when the query ended too early
+    pub token: Option<String>,
+}
+
+impl SyntaxError {
+    pub(super) fn new(query: &str, error: FurthestError) -> SyntaxError {
+        //Point at the token, not the whitespace in front of it
+        let remaining = error.input.trim_start();
+        let offset = query.len() - remaining.len();
+        let token = match remaining.chars().next() {
+            None => None,
+            Some(c) if c.is_alphanumeric() || c == '_' => Some(
+                remaining
+                    .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+            ),
+            Some(c) => Some(c.to_string()),
+        };
+
+        SyntaxError {
+            offset,
+            position: query[..offset].chars().count() + 1,
+            expected: error.expected,
+            token,
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.token {
+            Some(t) => write!(f, "syntax error at or near \"{}\"", t)?,
+            None => write!(f, "syntax error at end of input")?,
+        }
+        if !self.expected.is_empty() {
+            let expected: Vec<String> =
+                self.expected.iter().map(|e| format!("\"{}\"", e)).collect();
+            write!(f, ", expected {}", expected.join(" or "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+/// Contexts are only added around keywords and punctuation, they say what would have been accepted
+pub(super) struct FurthestError<'a> {
+    input: &'a str,
+    expected: Vec<String>,
+}
+
+impl<'a> ParseError<&'a str> for FurthestError<'a> {
+    fn from_error_kind(input: &'a str, _kind: ErrorKind) -> Self {
+        FurthestError {
+            input,
+            expected: vec![],
+        }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: &'a str, c: char) -> Self {
+        FurthestError {
+            input,
+            expected: vec![c.to_string()],
+        }
+    }
+
+    /// Less input left means the parser got further
+    fn or(mut self, other: Self) -> Self {
+        match self.input.len().cmp(&other.input.len()) {
+            Ordering::Less => self,
+            Ordering::Greater => other,
+            Ordering::Equal => {
+                for e in other.expected {
+                    if !self.expected.contains(&e) {
+                        self.expected.push(e);
+                    }
+                }
+                self
+            }
+        }
+    }
+}
+
+impl<'a> ContextError<&'a str> for FurthestError<'a> {
+    /// The innermost context is the most specific, outer ones don't replace it
+    fn add_context(_input: &'a str, ctx: &'static str, mut other: Self) -> Self {
+        if other.expected.is_empty() {
+            other.expected.push(ctx.to_string());
+        }
+        other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syntax_error() {
+        let query = "select é frm foo";
+        let error = FurthestError::from_error_kind(&query[9..], ErrorKind::Tag);
+        let error = FurthestError::add_context(&query[9..], "from", error);
+        let shallow = FurthestError::add_context(
+            query,
+            "insert",
+            FurthestError::from_error_kind(query, ErrorKind::Tag),
+        );
+
+        let syntax_error = SyntaxError::new(query, shallow.or(error));
+        assert_eq!(
+            syntax_error,
+            SyntaxError {
+                offset: 10,
+                position: 10,
+                expected: vec!["from".to_string()],
+                token: Some("frm".to_string()),
+            }
+        );
+        assert_eq!(
+            syntax_error.to_string(),
+            "syntax error at or near \"frm\", expected \"from\""
+        );
+
+        let error = FurthestError::from_char(&query[query.len()..], ')')
+            .or(FurthestError::from_char(&query[query.len()..], ','));
+        let syntax_error = SyntaxError::new(query, error);
+        assert_eq!(syntax_error.token, None);
+        assert_eq!(
+            syntax_error.to_string(),
+            "syntax error at end of input, expected \")\" or \",\""
+        );
+    }
+}
@@ -2,7 +2,7 @@
 
 use super::objects::{SqlTuple, Table};
 use crate::{
-    constants::Nullable,
+    constants::{Nullable, FIRST_USER_OID},
     engine::objects::{
         types::{BaseSqlTypes, BaseSqlTypesMapper},
         Attribute,
@@ -21,6 +21,7 @@ pub fn get_row(input: String) -> SqlTuple {
 pub fn get_table() -> Arc<Table> {
     Arc::new(Table::new(
         uuid::Uuid::new_v4(),
+        FIRST_USER_OID,
         "test_table".to_string(),
         vec![
             Attribute::new(
@@ -1,3 +1,6 @@
+mod commit_log;
+pub use commit_log::CommitLogError;
+
 mod transaction_id;
 pub use transaction_id::TransactionId;
 pub use transaction_id::TransactionIdError;
@@ -0,0 +1,184 @@
+//! The on disk form of the transaction statuses (clog in postgres).
+//!
+//! Each transaction gets 2 bits, addressed directly by its id, so a page covers PAGE_SIZE * 4
+//! transactions. Pages are written through the file manager so they are covered by the
+//! write ahead log, only the byte holding the status is logged once the page has an image there.
+use super::{TransactionId, TransactionStatus};
+use crate::constants::PAGE_SIZE;
+use crate::engine::io::block_layer::file_manager2::{FileManager2, FileManager2Error};
+use crate::engine::io::page_formats::{PageId, PageOffset, PageType};
+use bytes::BytesMut;
+use hex_literal::hex;
+use std::convert::TryFrom;
+use std::num::TryFromIntError;
+use std::sync::Arc;
+use thiserror::Error;
+use uuid::Uuid;
+
+pub const ID: Uuid = Uuid::from_bytes(hex!("B6D5C9A2E8F14B3C9D0A7E6F5C4B3A29"));
+
+const STATUSES_PER_BYTE: usize = 4;
+const STATUSES_PER_PAGE: usize = PAGE_SIZE as usize * STATUSES_PER_BYTE;
+
+//Zero is left for ids that were never handed out
+const STATUS_UNUSED: u8 = 0;
+const STATUS_IN_PROGRESS: u8 = 1;
+const STATUS_COMMITED: u8 = 2;
+const STATUS_ABORTED: u8 = 3;
+
+#[derive(Clone)]
+pub struct CommitLog {
+    file_manager: Arc<FileManager2>,
+}
+
+impl CommitLog {
+    pub fn new(file_manager: Arc<FileManager2>) -> CommitLog {
+        CommitLog { file_manager }
+    }
+
+    /// Reads every status on disk, indexed by transaction id. Ids that were never used come back as None
+    /// and trailing unused ids are dropped.
+    pub async fn load(&self) -> Result<Vec<Option<TransactionStatus>>, CommitLogError> {
+        let page_id = Self::page_id();
+        let mut statuses = vec![];
+
+        let mut offset = PageOffset(0);
+        loop {
+            let page = match self.file_manager.get_page(&page_id, &offset).await {
+                Ok((page, _read_guard)) => page,
+                Err(FileManager2Error::PageDoesNotExist(_)) => break,
+                Err(e) => return Err(CommitLogError::FileManager2Error(e)),
+            };
+
+            for byte in page.iter() {
+                for i in 0..STATUSES_PER_BYTE {
+                    statuses.push(Self::decode((byte >> (i * 2)) & 0x3)?);
+                }
+            }
+            offset += PageOffset(1);
+        }
+
+        while let Some(None) = statuses.last() {
+            statuses.pop();
+        }
+
+        Ok(statuses)
+    }
+
+    pub async fn set_status(
+        &self,
+        tran_id: TransactionId,
+        status: TransactionStatus,
+    ) -> Result<(), CommitLogError> {
+        let page_id = Self::page_id();
+        let index = usize::try_from(tran_id.get_u64())?;
+        let offset = PageOffset(index / STATUSES_PER_PAGE);
+        let in_page = index % STATUSES_PER_PAGE;
+
+        let (mut buffer, guard) = match self
+            .file_manager
+            .get_page_for_update(&page_id, &offset)
+            .await
+        {
+            Ok((page, guard)) => {
+                let mut buffer = BytesMut::with_capacity(PAGE_SIZE as usize);
+                buffer.extend_from_slice(&page);
+                (buffer, guard)
+            }
+            Err(FileManager2Error::PageDoesNotExist(_)) => {
+                let guard = self
+                    .file_manager
+                    .get_page_for_write(&page_id, &offset)
+                    .await;
+                let mut buffer = BytesMut::with_capacity(PAGE_SIZE as usize);
+                buffer.resize(PAGE_SIZE as usize, 0);
+                (buffer, guard)
+            }
+            Err(e) => return Err(CommitLogError::FileManager2Error(e)),
+        };
+
+        let byte_index = in_page / STATUSES_PER_BYTE;
+        let shift = (in_page % STATUSES_PER_BYTE) * 2;
+        buffer[byte_index] =
+            (buffer[byte_index] & !(0x3 << shift)) | (Self::encode(status) << shift);
+
+        self.file_manager
+            .update_page_range(guard, buffer.freeze(), byte_index..byte_index + 1)
+            .await?;
+        Ok(())
+    }
+
+    fn page_id() -> PageId {
+        PageId {
+            resource_key: ID,
+            page_type: PageType::CommitLog,
+        }
+    }
+
+    fn encode(status: TransactionStatus) -> u8 {
+        match status {
+            TransactionStatus::InProgress => STATUS_IN_PROGRESS,
+            TransactionStatus::Commited => STATUS_COMMITED,
+            TransactionStatus::Aborted => STATUS_ABORTED,
+        }
+    }
+
+    fn decode(bits: u8) -> Result<Option<TransactionStatus>, CommitLogError> {
+        match bits {
+            STATUS_UNUSED => Ok(None),
+            STATUS_IN_PROGRESS => Ok(Some(TransactionStatus::InProgress)),
+            STATUS_COMMITED => Ok(Some(TransactionStatus::Commited)),
+            STATUS_ABORTED => Ok(Some(TransactionStatus::Aborted)),
+            u => Err(CommitLogError::UnknownStatus(u)),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CommitLogError {
+    #[error(transparent)]
+    FileManager2Error(#[from] FileManager2Error),
+    #[error(transparent)]
+    TryFromIntError(#[from] TryFromIntError),
+    #[error("Unknown transaction status {0} in the commit log")]
+    UnknownStatus(u8),
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_commit_log_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = TempDir::new()?;
+        let fm = Arc::new(FileManager2::new(tmp.path().as_os_str().to_os_string())?);
+        let clog = CommitLog::new(fm);
+
+        assert!(clog.load().await?.is_empty());
+
+        let far = STATUSES_PER_PAGE + 5;
+        clog.set_status(TransactionId::new(2), TransactionStatus::InProgress)
+            .await?;
+        clog.set_status(TransactionId::new(3), TransactionStatus::Aborted)
+            .await?;
+        clog.set_status(TransactionId::new(2), TransactionStatus::Commited)
+            .await?;
+        clog.set_status(
+            TransactionId::new(far as u64),
+            TransactionStatus::InProgress,
+        )
+        .await?;
+
+        let statuses = clog.load().await?;
+        assert_eq!(statuses.len(), far + 1);
+        assert_eq!(statuses[1], None);
+        assert_eq!(statuses[2], Some(TransactionStatus::Commited));
+        assert_eq!(statuses[3], Some(TransactionStatus::Aborted));
+        assert_eq!(statuses[4], None);
+        assert_eq!(statuses[far], Some(TransactionStatus::InProgress));
+
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@ use thiserror::Error;
 
 use crate::engine::io::ConstEncodedSize;
 
-#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd)]
 pub struct TransactionId(u64);
 
 impl TransactionId {
@@ -1,31 +1,88 @@
 //! This is the interface to transaction visability (clog in postgres).
+//!
+//! Statuses are kept in memory and written through to the commit log so they survive a restart.
+use super::commit_log::{CommitLog, CommitLogError};
 use super::{TransactionId, TransactionIdError, TransactionStatus};
+use crate::engine::io::block_layer::file_manager2::{FileManager2, FileManager2Error};
+use crate::engine::objects::types::date_time;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::RwLock;
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct TransactionManager {
+    file_manager: Arc<FileManager2>,
+    commit_log: CommitLog,
     tran_min: TransactionId, //Used to index the known transactions array
     known_trans: Arc<RwLock<Vec<TransactionStatus>>>,
+    ///Only kept while a transaction is in progress, it is what now() returns
+    start_times: Arc<RwLock<HashMap<TransactionId, DateTime<Utc>>>>,
 }
 
 impl TransactionManager {
-    pub fn new() -> TransactionManager {
+    /// Loads the commit log, new transaction ids continue after the highest one ever handed out.
+    ///
+    /// Anything that was still in progress when the server stopped can never commit, so it is aborted.
+    pub async fn new(
+        file_manager: Arc<FileManager2>,
+    ) -> Result<TransactionManager, TransactionManagerError> {
         let tran_min = TransactionId::new(1); //Must start at 1 since 0 is used for active rows
-        let known_trans = Arc::new(RwLock::new(vec![TransactionStatus::Aborted])); //First transaction will be cancelled
-        TransactionManager {
-            tran_min,
-            known_trans,
+        let commit_log = CommitLog::new(file_manager.clone());
+
+        let mut known_trans: Vec<TransactionStatus> = commit_log
+            .load()
+            .await?
+            .into_iter()
+            .skip(tran_min.checked_sub(TransactionId::new(0))?)
+            .map(|s| match s {
+                Some(TransactionStatus::Commited) => TransactionStatus::Commited,
+                _ => TransactionStatus::Aborted,
+            })
+            .collect();
+        if known_trans.is_empty() {
+            known_trans.push(TransactionStatus::Aborted); //First transaction will be cancelled
         }
+
+        Ok(TransactionManager {
+            file_manager,
+            commit_log,
+            tran_min,
+            known_trans: Arc::new(RwLock::new(known_trans)),
+            start_times: Arc::new(RwLock::new(HashMap::new())),
+        })
     }
 
     pub async fn start_trans(&mut self) -> Result<TransactionId, TransactionManagerError> {
         let mut known_trans = self.known_trans.write().await;
 
+        let tran_id = self.tran_min.checked_add(known_trans.len())?;
+
+        //Has to be on disk before the id is used so a restart won't hand it out again
+        self.commit_log
+            .set_status(tran_id, TransactionStatus::InProgress)
+            .await?;
         known_trans.push(TransactionStatus::InProgress);
+        self.start_times
+            .write()
+            .await
+            .insert(tran_id, date_time::now());
 
-        Ok(self.tran_min.checked_add(known_trans.len() - 1)?)
+        Ok(tran_id)
+    }
+
+    /// When the transaction started, postgres keeps this stable for the whole transaction
+    pub async fn start_time(
+        &self,
+        tran_id: TransactionId,
+    ) -> Result<DateTime<Utc>, TransactionManagerError> {
+        self.start_times
+            .read()
+            .await
+            .get(&tran_id)
+            .copied()
+            .ok_or(TransactionManagerError::NoStartTime(tran_id))
     }
 
     pub async fn get_status(
@@ -51,6 +108,7 @@ impl TransactionManager {
         Ok(known_trans[index])
     }
 
+    /// Only updates memory, the commit log must already have the new status
     async fn update_trans(
         &mut self,
         tran_id: TransactionId,
@@ -84,35 +142,55 @@ impl TransactionManager {
         Ok(())
     }
 
+    /// The commit record is durable in the write ahead log before anyone can see the commit
     pub async fn commit_trans(
         &mut self,
         tran_id: TransactionId,
     ) -> Result<(), TransactionManagerError> {
+        let status = self.get_status(tran_id).await?;
+        if status != TransactionStatus::InProgress {
+            return Err(TransactionManagerError::NotInProgress(tran_id, status));
+        }
+
+        //Syncing the commit record also makes the commit log page logged ahead of it durable
+        self.commit_log
+            .set_status(tran_id, TransactionStatus::Commited)
+            .await?;
+        self.file_manager.log_commit(tran_id).await?;
+
         self.update_trans(tran_id, TransactionStatus::Commited)
-            .await
+            .await?;
+        self.start_times.write().await.remove(&tran_id);
+        Ok(())
     }
 
     pub async fn abort_trans(
         &mut self,
         tran_id: TransactionId,
     ) -> Result<(), TransactionManagerError> {
-        self.update_trans(tran_id, TransactionStatus::Aborted).await
-    }
-
-    //TODO work on figuring out how to save / load this
-    pub fn serialize() {}
+        let status = self.get_status(tran_id).await?;
+        if status != TransactionStatus::InProgress {
+            return Err(TransactionManagerError::NotInProgress(tran_id, status));
+        }
 
-    pub fn parse() {}
-}
+        self.commit_log
+            .set_status(tran_id, TransactionStatus::Aborted)
+            .await?;
+        self.update_trans(tran_id, TransactionStatus::Aborted)
+            .await?;
+        self.start_times.write().await.remove(&tran_id);
 
-impl Default for TransactionManager {
-    fn default() -> Self {
-        Self::new()
+        self.file_manager.log_abort(tran_id).await?;
+        Ok(())
     }
 }
 
 #[derive(Error, Debug)]
 pub enum TransactionManagerError {
+    #[error(transparent)]
+    CommitLogError(#[from] CommitLogError),
+    #[error(transparent)]
+    FileManager2Error(#[from] FileManager2Error),
     #[error(transparent)]
     TransactionIdError(#[from] TransactionIdError),
     #[error("Transaction Id {0} too low compared to {1}")]
@@ -121,16 +199,23 @@ pub enum TransactionManagerError {
     InTheFuture(TransactionId, TransactionId, usize),
     #[error("Transaction Id {0} not in progress, found {1}")]
     NotInProgress(TransactionId, TransactionStatus),
+    #[error("Transaction Id {0} has no start time, it was not started by this server")]
+    NoStartTime(TransactionId),
 }
 
 #[cfg(test)]
 mod tests {
     #![allow(unused_must_use)]
+    use tempfile::TempDir;
+
     use super::*;
 
     #[tokio::test]
     async fn tran_man_statuses() -> Result<(), Box<dyn std::error::Error>> {
-        let mut tm = TransactionManager::new();
+        let tmp = TempDir::new()?;
+        let fm = Arc::new(FileManager2::new(tmp.path().as_os_str().to_os_string())?);
+
+        let mut tm = TransactionManager::new(fm).await?;
         let tran1 = tm.start_trans().await?;
         let tran2 = tm.start_trans().await?;
 
@@ -140,8 +225,11 @@ mod tests {
         assert_eq!(tm.get_status(tran1).await?, TransactionStatus::InProgress);
         assert_eq!(tm.get_status(tran2).await?, TransactionStatus::InProgress);
 
+        assert!(tm.start_time(tran1).await? <= tm.start_time(tran2).await?);
+
         assert!(tm.commit_trans(tran1).await.is_ok());
         assert!(tm.commit_trans(tran1).await.is_err());
+        assert!(tm.start_time(tran1).await.is_err());
 
         assert_eq!(tm.get_status(tran1).await?, TransactionStatus::Commited);
         assert_eq!(tm.get_status(tran2).await?, TransactionStatus::InProgress);
@@ -154,4 +242,34 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn tran_man_restart() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = TempDir::new()?;
+        let tmp_dir = tmp.path().as_os_str().to_os_string();
+
+        let fm = Arc::new(FileManager2::new(tmp_dir.clone())?);
+        let mut tm = TransactionManager::new(fm).await?;
+        let tran1 = tm.start_trans().await?;
+        let tran2 = tm.start_trans().await?;
+        let tran3 = tm.start_trans().await?;
+        tm.commit_trans(tran1).await?;
+        tm.abort_trans(tran2).await?;
+        drop(tm);
+
+        let fm = Arc::new(FileManager2::new(tmp_dir)?);
+        fm.recover().await?;
+        let mut tm = TransactionManager::new(fm).await?;
+
+        assert_eq!(tm.get_status(tran1).await?, TransactionStatus::Commited);
+        assert_eq!(tm.get_status(tran2).await?, TransactionStatus::Aborted);
+        //Never finished before the restart
+        assert_eq!(tm.get_status(tran3).await?, TransactionStatus::Aborted);
+
+        let tran4 = tm.start_trans().await?;
+        assert!(tran4 > tran3);
+        assert_eq!(tm.get_status(tran4).await?, TransactionStatus::InProgress);
+
+        Ok(())
+    }
 }
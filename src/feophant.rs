@@ -2,8 +2,8 @@ use crate::{
     codec::{NetworkFrame, PgCodec},
     engine::{
         io::block_layer::file_manager2::{FileManager2, FileManager2Error},
-        transactions::TransactionManager,
-        Engine,
+        transactions::{TransactionManager, TransactionManagerError},
+        Engine, EngineError,
     },
     processor::ClientProcessor,
 };
@@ -30,8 +30,17 @@ pub struct FeOphant {
 impl FeOphant {
     pub async fn new(data_dir: OsString, port: u16) -> Result<FeOphant, FeOphantError> {
         let file_manager = Arc::new(FileManager2::new(data_dir)?);
-        let transaction_manager = TransactionManager::new();
-        let engine = Engine::new(file_manager.clone(), transaction_manager.clone());
+
+        //Anything left in the write ahead log is from a crash and has to be put back first
+        file_manager.recover().await?;
+
+        let mut transaction_manager = TransactionManager::new(file_manager.clone()).await?;
+        let mut engine = Engine::new(file_manager.clone(), transaction_manager.clone());
+
+        //The built in types are rows in pg_type, a new database needs them before anything else
+        let tran_id = transaction_manager.start_trans().await?;
+        engine.bootstrap(tran_id).await?;
+        transaction_manager.commit_trans(tran_id).await?;
 
         let listener = TcpListener::bind(format!("{}{}", "127.0.0.1:", port)).await?;
         let port = listener.local_addr()?.port();
@@ -45,6 +54,12 @@ impl FeOphant {
         })
     }
 
+    /// Sets how many bytes of rows each sort can hold before spilling to disk
+    pub fn with_sort_memory(mut self, sort_memory: usize) -> FeOphant {
+        self.engine = self.engine.with_sort_memory(sort_memory);
+        self
+    }
+
     /// Starts up the actual server, should be started as its own task
     /// Send on the shutdown_recv to shut it down.
     pub async fn start(&self, shutdown_recv: UnboundedReceiver<Sender<()>>) {
@@ -94,6 +109,10 @@ impl FeOphant {
                                     }
                                 }
                             }
+
+                            if let Err(e) = process.close().await {
+                                warn!("Unable to clean up the connection {}", e);
+                            }
                         });
                     } else if let Err(e) = listen_res {
                         error!("Got error receiving a connection. {0}", e);
@@ -123,6 +142,8 @@ pub enum FeOphantError {
     #[error("Can't start the FeOphant twice")]
     CantStartTwice(),
     #[error(transparent)]
+    EngineError(#[from] EngineError),
+    #[error(transparent)]
     FileManager2Error(#[from] FileManager2Error),
     #[error(transparent)]
     IOError(#[from] std::io::Error),
@@ -130,4 +151,6 @@ pub enum FeOphantError {
     RecvError(#[from] RecvError),
     #[error(transparent)]
     ShutdownSendError(#[from] SendError<Sender<()>>),
+    #[error(transparent)]
+    TransactionManagerError(#[from] TransactionManagerError),
 }
@@ -3,5 +3,9 @@
 mod client_processor;
 pub use client_processor::ClientProcessor;
 
+mod error_mapping;
+
+mod extended_query_parser;
+
 pub mod ssl_and_gssapi_parser;
 pub mod startup_parser;
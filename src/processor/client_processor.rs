@@ -1,16 +1,71 @@
 use bytes::Bytes;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
 use thiserror::Error;
 
-use super::super::engine::transactions::{TransactionManager, TransactionManagerError};
-use super::super::engine::{Engine, EngineError};
+use super::super::engine::objects::types::{BaseSqlTypesMapper, SqlTypeDefinition};
+use super::super::engine::objects::{
+    ColumnOrigin, CommandType, ParseTree, QueryResult, SqlTuple, TransactionCommand,
+};
+use super::super::engine::transactions::{
+    TransactionId, TransactionManager, TransactionManagerError,
+};
+use super::super::engine::{Engine, EngineError, SqlParser};
+use super::error_mapping::describe_error;
+use super::extended_query_parser::{
+    parse_bind_message, parse_execute_message, parse_parse_message, parse_target_message,
+    BindMessage, ExecuteMessage, ParseMessage, Target,
+};
 use super::ssl_and_gssapi_parser;
 use super::startup_parser;
-use crate::codec::{NetworkFrame, NetworkFrameError};
-use crate::constants::{PgErrorCodes, PgErrorLevels};
+use crate::codec::{
+    ErrorFields, NetworkFrame, NetworkFrameError, ValueEncoding, ValueEncodingError,
+};
+use crate::constants::{
+    PgErrorCodes, PgErrorLevels, PgFormatCode, PgFormatCodeError, PgTransactionStatus,
+};
 
 pub struct ClientProcessor {
     engine: Engine,
     transaction_manager: TransactionManager,
+    state: ConnectionState,
+    statements: HashMap<String, Arc<PreparedStatement>>,
+    portals: HashMap<String, Portal>,
+    //After an extended query error, messages are discarded until the client syncs
+    skip_until_sync: bool,
+}
+
+/// Tracks the transaction a connection is in between queries
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ConnectionState {
+    /// Each query runs in its own transaction
+    Idle,
+    /// Running the statements of a simple query, or the messages of an extended query up to Sync,
+    /// they share a transaction that ends with them
+    Implicit(TransactionId),
+    /// Inside a transaction block started by BEGIN
+    InTransaction(TransactionId),
+    /// A query in the transaction block failed, everything is refused until it is ended
+    Failed(TransactionId),
+}
+
+/// A statement from a Parse message, already analyzed so it can be described
+struct PreparedStatement {
+    //None for an empty query
+    parse_tree: Option<ParseTree>,
+    parameters: Vec<BaseSqlTypesMapper>,
+    columns: SqlTypeDefinition,
+    origins: Vec<ColumnOrigin>,
+}
+
+/// A prepared statement with its parameters bound, ready to execute
+struct Portal {
+    statement: Arc<PreparedStatement>,
+    parse_tree: Option<ParseTree>,
+    result_formats: Vec<PgFormatCode>,
+    //Filled in by the first execute, rows are handed out from here if the client asks for them in batches
+    results: Option<(Vec<SqlTuple>, NetworkFrame)>,
 }
 
 impl ClientProcessor {
@@ -18,7 +73,25 @@ impl ClientProcessor {
         ClientProcessor {
             engine,
             transaction_manager,
+            state: ConnectionState::Idle,
+            statements: HashMap::new(),
+            portals: HashMap::new(),
+            skip_until_sync: false,
+        }
+    }
+
+    /// Called when the connection goes away, any open transaction is rolled back
+    pub async fn close(&mut self) -> Result<(), ClientProcessorError> {
+        match self.state {
+            ConnectionState::Idle => {}
+            ConnectionState::Implicit(t)
+            | ConnectionState::InTransaction(t)
+            | ConnectionState::Failed(t) => {
+                self.transaction_manager.abort_trans(t).await?;
+            }
         }
+        self.state = ConnectionState::Idle;
+        Ok(())
     }
 
     pub async fn process(
@@ -46,29 +119,60 @@ impl ClientProcessor {
             info!("Just going to let {:?} in", message.get("user"));
             return Ok(vec![
                 NetworkFrame::authentication_ok(),
-                NetworkFrame::ready_for_query(),
+                NetworkFrame::ready_for_query(self.transaction_status()),
             ]);
         }
 
-        //Support basic query
-        if frame.message_type == b'Q' {
-            debug!("Got query {:?}", payload_buff);
-
-            let result = match self.process_single_query(payload_buff).await {
-                Ok(o) => o,
-                Err(e) => {
-                    return Ok(vec![
-                        NetworkFrame::error_response(
-                            PgErrorLevels::Error,
-                            PgErrorCodes::SystemError,
-                            e.to_string(),
-                        ),
-                        NetworkFrame::ready_for_query(),
-                    ]);
+        match frame.message_type {
+            //Support basic query
+            b'Q' => {
+                debug!("Got query {:?}", payload_buff);
+
+                let mut result = vec![];
+                if let Err(e) = self.process_simple_query(payload_buff, &mut result).await {
+                    result.push(self.error_response(e));
                 }
-            };
+                result.push(NetworkFrame::ready_for_query(self.transaction_status()));
+
+                return Ok(result);
+            }
+            //Extended query protocol
+            b'P' | b'B' | b'D' | b'E' | b'C' => {
+                if self.skip_until_sync {
+                    return Ok(vec![]);
+                }
+
+                return match self
+                    .process_extended_query(frame.message_type, payload_buff)
+                    .await
+                {
+                    Ok(o) => Ok(o),
+                    Err(e) => {
+                        self.skip_until_sync = true;
+                        if let ConnectionState::Implicit(t) = self.state {
+                            self.state = ConnectionState::Idle;
+                            self.transaction_manager.abort_trans(t).await?;
+                        }
+                        Ok(vec![self.error_response(e)])
+                    }
+                };
+            }
+            b'S' => {
+                self.skip_until_sync = false;
 
-            return Ok(result);
+                let mut result = vec![];
+                if let ConnectionState::Implicit(t) = self.state {
+                    self.state = ConnectionState::Idle;
+                    if let Err(e) = self.transaction_manager.commit_trans(t).await {
+                        result.push(self.error_response(e.into()));
+                    }
+                }
+                result.push(NetworkFrame::ready_for_query(self.transaction_status()));
+                return Ok(result);
+            }
+            //Flush, nothing is held back so there is nothing to do
+            b'H' => return Ok(vec![]),
+            _ => {}
         }
 
         warn!(
@@ -77,62 +181,466 @@ impl ClientProcessor {
         );
         Ok(vec![NetworkFrame::error_response(
             PgErrorLevels::Error,
-            PgErrorCodes::SystemError,
+            PgErrorCodes::FeatureNotSupported,
             "Got an unimplemented message".to_string(),
+            &ErrorFields::default(),
         )])
     }
 
-    async fn process_single_query(
+    /// An error inside a transaction block poisons the rest of it
+    fn error_response(&mut self, e: ClientProcessorError) -> NetworkFrame {
+        if let ConnectionState::InTransaction(t) = self.state {
+            self.state = ConnectionState::Failed(t);
+        }
+
+        let (code, fields) = describe_error(&e);
+        NetworkFrame::error_response(PgErrorLevels::Error, code, e.to_string(), &fields)
+    }
+
+    /// A simple query can hold several statements, they run in one transaction unless they start and end
+    /// their own. Results of the statements before an error are still sent, the same as postgres.
+    async fn process_simple_query(
+        &mut self,
+        payload_buff: &[u8],
+        frames: &mut Vec<NetworkFrame>,
+    ) -> Result<(), ClientProcessorError> {
+        //Convert to utf8, the query is sent as a null terminated string
+        let query = payload_buff.strip_suffix(&[0]).unwrap_or(payload_buff);
+        let query_str = String::from_utf8(query.to_vec())?;
+
+        //Nothing is run if any of the statements don't parse
+        let parse_trees = SqlParser::parse_statements(&query_str).map_err(EngineError::from)?;
+        if parse_trees.is_empty() {
+            frames.push(NetworkFrame::empty_query_response());
+            return Ok(());
+        }
+
+        for parse_tree in parse_trees {
+            if let Err(e) = self.process_statement(parse_tree, frames).await {
+                if let ConnectionState::Implicit(t) = self.state {
+                    self.state = ConnectionState::Idle;
+                    self.transaction_manager.abort_trans(t).await?;
+                }
+                return Err(e);
+            }
+        }
+
+        if let ConnectionState::Implicit(t) = self.state {
+            self.state = ConnectionState::Idle;
+            self.transaction_manager.commit_trans(t).await?;
+        }
+        Ok(())
+    }
+
+    async fn process_statement(
+        &mut self,
+        parse_tree: ParseTree,
+        frames: &mut Vec<NetworkFrame>,
+    ) -> Result<(), ClientProcessorError> {
+        if let ParseTree::Transaction(tc) = parse_tree {
+            let command_type = self.process_transaction_command(tc).await?;
+            frames.push(NetworkFrame::command_complete(command_type, 0));
+            return Ok(());
+        }
+
+        let query_res = self.run_parse_tree(parse_tree).await?;
+
+        if !query_res.columns.is_empty() {
+            frames.push(NetworkFrame::row_description(
+                &query_res.columns,
+                &query_res.origins,
+                &[],
+            )?);
+        }
+
+        if !query_res.columns.is_empty() {
+            frames.append(&mut NetworkFrame::data_rows(query_res.rows, &[])?);
+        }
+        frames.push(NetworkFrame::command_complete(
+            query_res.command_type,
+            query_res.row_count,
+        ));
+
+        Ok(())
+    }
+
+    /// Runs a statement in the connection's transaction, starting an implicit one if there isn't one.
+    /// Whoever sent the statement ends the implicit transaction, at the end of a simple query or at Sync.
+    async fn run_parse_tree(
+        &mut self,
+        parse_tree: ParseTree,
+    ) -> Result<QueryResult, ClientProcessorError> {
+        let txid = match self.state {
+            ConnectionState::Idle => {
+                let txid = self.transaction_manager.start_trans().await?;
+                self.state = ConnectionState::Implicit(txid);
+                txid
+            }
+            ConnectionState::Implicit(txid) | ConnectionState::InTransaction(txid) => txid,
+            ConnectionState::Failed(_) => return Err(ClientProcessorError::InFailedTransaction()),
+        };
+
+        Ok(self.engine.process_parse_tree(txid, parse_tree).await?)
+    }
+
+    /// Handles Parse, Bind, Describe, Execute and Close, see here for the flow:
+    /// https://www.postgresql.org/docs/current/protocol-flow.html#PROTOCOL-FLOW-EXT-QUERY
+    async fn process_extended_query(
         &mut self,
+        message_type: u8,
         payload_buff: &[u8],
     ) -> Result<Vec<NetworkFrame>, ClientProcessorError> {
-        //Convert to utf8
-        let query_str = String::from_utf8(payload_buff.to_vec())?;
+        let malformed = |_| ClientProcessorError::MalformedMessage(char::from(message_type));
+        match message_type {
+            b'P' => {
+                let message = parse_parse_message(payload_buff).map_err(malformed)?;
+                self.process_parse(message).await
+            }
+            b'B' => {
+                let message = parse_bind_message(payload_buff).map_err(malformed)?;
+                self.process_bind(message)
+            }
+            b'D' => {
+                let target = parse_target_message(payload_buff).map_err(malformed)?;
+                self.process_describe(target)
+            }
+            b'E' => {
+                let message = parse_execute_message(payload_buff).map_err(malformed)?;
+                self.process_execute(message).await
+            }
+            _ => {
+                match parse_target_message(payload_buff).map_err(malformed)? {
+                    Target::Statement(name) => self.statements.remove(&name),
+                    Target::Portal(name) => self.portals.remove(&name).map(|p| p.statement),
+                };
+                Ok(vec![NetworkFrame::close_complete()])
+            }
+        }
+    }
+
+    async fn process_parse(
+        &mut self,
+        message: ParseMessage,
+    ) -> Result<Vec<NetworkFrame>, ClientProcessorError> {
+        //The unnamed statement is replaced freely, named ones have to be closed first
+        if !message.statement.is_empty() && self.statements.contains_key(&message.statement) {
+            return Err(ClientProcessorError::DuplicatePreparedStatement(
+                message.statement,
+            ));
+        }
 
-        let txid = self.transaction_manager.start_trans().await?;
+        let declared_parameters = message
+            .parameter_types
+            .into_iter()
+            .map(ValueEncoding::type_from_oid)
+            .collect::<Result<Vec<_>, _>>()?;
 
-        let query_res = match self.engine.process_query(txid, query_str).await {
-            Ok(o) => {
-                self.transaction_manager.commit_trans(txid).await?;
-                o
+        let statement = if message.query.trim().is_empty() {
+            PreparedStatement {
+                parse_tree: None,
+                parameters: vec![],
+                columns: SqlTypeDefinition(vec![]),
+                origins: vec![],
             }
-            Err(e) => {
+        } else {
+            let parse_tree = SqlParser::parse(&message.query).map_err(EngineError::from)?;
+            let (parameters, columns, origins) = match parse_tree {
+                ParseTree::Transaction(_) => (vec![], SqlTypeDefinition(vec![]), vec![]),
+                _ => {
+                    self.describe(parse_tree.clone(), declared_parameters)
+                        .await?
+                }
+            };
+            PreparedStatement {
+                parse_tree: Some(parse_tree),
+                parameters,
+                columns,
+                origins,
+            }
+        };
+
+        self.statements
+            .insert(message.statement, Arc::new(statement));
+        Ok(vec![NetworkFrame::parse_complete()])
+    }
+
+    /// Describing needs the catalog, so outside of a transaction block it borrows a transaction to do it
+    async fn describe(
+        &mut self,
+        parse_tree: ParseTree,
+        declared_parameters: Vec<Option<BaseSqlTypesMapper>>,
+    ) -> Result<
+        (
+            Vec<BaseSqlTypesMapper>,
+            SqlTypeDefinition,
+            Vec<ColumnOrigin>,
+        ),
+        ClientProcessorError,
+    > {
+        match self.state {
+            ConnectionState::Idle => {
+                let txid = self.transaction_manager.start_trans().await?;
+                let result = self
+                    .engine
+                    .describe(txid, parse_tree, declared_parameters)
+                    .await;
                 self.transaction_manager.abort_trans(txid).await?;
-                return Err(ClientProcessorError::EngineError(e));
+                Ok(result?)
             }
+            ConnectionState::Implicit(txid) | ConnectionState::InTransaction(txid) => Ok(self
+                .engine
+                .describe(txid, parse_tree, declared_parameters)
+                .await?),
+            ConnectionState::Failed(_) => Err(ClientProcessorError::InFailedTransaction()),
+        }
+    }
+
+    fn process_bind(
+        &mut self,
+        message: BindMessage,
+    ) -> Result<Vec<NetworkFrame>, ClientProcessorError> {
+        let statement = self
+            .statements
+            .get(&message.statement)
+            .ok_or_else(|| {
+                ClientProcessorError::UnknownPreparedStatement(message.statement.clone())
+            })?
+            .clone();
+
+        if message.parameters.len() != statement.parameters.len() {
+            return Err(ClientProcessorError::ParameterCountMismatch(
+                statement.parameters.len(),
+                message.parameters.len(),
+            ));
+        }
+
+        let parameter_formats = ClientProcessor::format_codes(&message.parameter_formats)?;
+        let mut values = vec![];
+        for (i, (value, sql_type)) in message
+            .parameters
+            .iter()
+            .zip(statement.parameters.iter())
+            .enumerate()
+        {
+            let format = PgFormatCode::for_column(&parameter_formats, i);
+            values.push(match value {
+                Some(v) => Some(ValueEncoding::decode_parameter(v, format, sql_type)?),
+                None => None,
+            });
+        }
+
+        let parse_tree = match &statement.parse_tree {
+            Some(pt) => Some(
+                pt.clone()
+                    .bind_parameters(&values)
+                    .map_err(EngineError::from)?,
+            ),
+            None => None,
         };
 
-        let mut frames = vec![];
-        if !query_res.columns.is_empty() {
-            frames.push(NetworkFrame::row_description(query_res.columns)?);
+        let portal = Portal {
+            statement,
+            parse_tree,
+            result_formats: ClientProcessor::format_codes(&message.result_formats)?,
+            results: None,
+        };
+        self.portals.insert(message.portal, portal);
+
+        Ok(vec![NetworkFrame::bind_complete()])
+    }
+
+    fn process_describe(&self, target: Target) -> Result<Vec<NetworkFrame>, ClientProcessorError> {
+        match target {
+            Target::Statement(name) => {
+                let statement = self
+                    .statements
+                    .get(&name)
+                    .ok_or(ClientProcessorError::UnknownPreparedStatement(name))?;
+                Ok(vec![
+                    NetworkFrame::parameter_description(&statement.parameters)?,
+                    ClientProcessor::describe_columns(statement, &[])?,
+                ])
+            }
+            Target::Portal(name) => {
+                let portal = self
+                    .portals
+                    .get(&name)
+                    .ok_or(ClientProcessorError::UnknownPortal(name))?;
+                Ok(vec![ClientProcessor::describe_columns(
+                    &portal.statement,
+                    &portal.result_formats,
+                )?])
+            }
         }
+    }
 
-        let results_rows = query_res.rows.len();
-        if !query_res.rows.is_empty() {
-            frames.append(&mut NetworkFrame::data_rows(query_res.rows)?);
+    fn describe_columns(
+        statement: &PreparedStatement,
+        formats: &[PgFormatCode],
+    ) -> Result<NetworkFrame, ClientProcessorError> {
+        if statement.columns.is_empty() {
+            Ok(NetworkFrame::no_data())
+        } else {
+            Ok(NetworkFrame::row_description(
+                &statement.columns,
+                &statement.origins,
+                formats,
+            )?)
         }
+    }
 
-        frames.push(NetworkFrame::command_complete(format!(
-            "SELECT {}",
-            results_rows
-        )));
+    async fn process_execute(
+        &mut self,
+        message: ExecuteMessage,
+    ) -> Result<Vec<NetworkFrame>, ClientProcessorError> {
+        //Taken out while running so the rest of the connection state can be changed
+        let mut portal = self
+            .portals
+            .remove(&message.portal)
+            .ok_or_else(|| ClientProcessorError::UnknownPortal(message.portal.clone()))?;
 
-        frames.push(NetworkFrame::ready_for_query());
+        let result = self.execute_portal(&mut portal, message.max_rows).await;
+        self.portals.insert(message.portal, portal);
+        result
+    }
 
+    /// A max_rows of zero means no limit, otherwise the portal is suspended once that many rows are sent
+    async fn execute_portal(
+        &mut self,
+        portal: &mut Portal,
+        max_rows: i32,
+    ) -> Result<Vec<NetworkFrame>, ClientProcessorError> {
+        if portal.results.is_none() {
+            match &portal.parse_tree {
+                None => return Ok(vec![NetworkFrame::empty_query_response()]),
+                Some(ParseTree::Transaction(tc)) => {
+                    let command_type = self.process_transaction_command(*tc).await?;
+                    return Ok(vec![NetworkFrame::command_complete(command_type, 0)]);
+                }
+                Some(pt) => {
+                    let query_res = self.run_parse_tree(pt.clone()).await?;
+                    let complete =
+                        NetworkFrame::command_complete(query_res.command_type, query_res.row_count);
+                    let rows = match query_res.columns.is_empty() {
+                        true => vec![],
+                        false => query_res.rows,
+                    };
+                    portal.results = Some((rows, complete));
+                }
+            }
+        }
+
+        let (rows, complete) = match portal.results.as_mut() {
+            Some(r) => r,
+            None => return Ok(vec![]),
+        };
+
+        let limit = usize::try_from(max_rows).unwrap_or(0);
+        if limit > 0 && rows.len() > limit {
+            let batch = rows.drain(..limit).collect();
+            let mut frames = NetworkFrame::data_rows(batch, &portal.result_formats)?;
+            frames.push(NetworkFrame::portal_suspended());
+            return Ok(frames);
+        }
+
+        let mut frames = NetworkFrame::data_rows(std::mem::take(rows), &portal.result_formats)?;
+        frames.push(complete.clone());
         Ok(frames)
     }
+
+    fn format_codes(codes: &[i16]) -> Result<Vec<PgFormatCode>, ClientProcessorError> {
+        Ok(codes
+            .iter()
+            .map(|c| PgFormatCode::from_code(*c))
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Moves the connection between transaction states, returning what to tag the command as
+    async fn process_transaction_command(
+        &mut self,
+        command: TransactionCommand,
+    ) -> Result<CommandType, ClientProcessorError> {
+        match (command, self.state) {
+            (TransactionCommand::Begin, ConnectionState::Idle) => {
+                let txid = self.transaction_manager.start_trans().await?;
+                self.state = ConnectionState::InTransaction(txid);
+            }
+            //BEGIN in the middle of a simple query turns its statements so far into the block
+            (TransactionCommand::Begin, ConnectionState::Implicit(t)) => {
+                self.state = ConnectionState::InTransaction(t);
+            }
+            (TransactionCommand::Begin, ConnectionState::InTransaction(_)) => {
+                warn!("There is already a transaction in progress");
+            }
+            (TransactionCommand::Begin, ConnectionState::Failed(_)) => {
+                return Err(ClientProcessorError::InFailedTransaction());
+            }
+            (TransactionCommand::Commit, ConnectionState::Implicit(t))
+            | (TransactionCommand::Commit, ConnectionState::InTransaction(t)) => {
+                self.transaction_manager.commit_trans(t).await?;
+                self.state = ConnectionState::Idle;
+            }
+            //Committing a failed transaction rolls it back, same as postgres
+            (TransactionCommand::Commit, ConnectionState::Failed(t)) => {
+                self.transaction_manager.abort_trans(t).await?;
+                self.state = ConnectionState::Idle;
+                return Ok(CommandType::Rollback);
+            }
+            (TransactionCommand::Rollback, ConnectionState::Implicit(t))
+            | (TransactionCommand::Rollback, ConnectionState::InTransaction(t))
+            | (TransactionCommand::Rollback, ConnectionState::Failed(t)) => {
+                self.transaction_manager.abort_trans(t).await?;
+                self.state = ConnectionState::Idle;
+            }
+            (_, ConnectionState::Idle) => {
+                warn!("There is no transaction in progress");
+            }
+        }
+
+        Ok(match command {
+            TransactionCommand::Begin => CommandType::Begin,
+            TransactionCommand::Commit => CommandType::Commit,
+            TransactionCommand::Rollback => CommandType::Rollback,
+        })
+    }
+
+    fn transaction_status(&self) -> PgTransactionStatus {
+        match self.state {
+            ConnectionState::Idle => PgTransactionStatus::Idle,
+            ConnectionState::Implicit(_) | ConnectionState::InTransaction(_) => {
+                PgTransactionStatus::InTransaction
+            }
+            ConnectionState::Failed(_) => PgTransactionStatus::Failed,
+        }
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum ClientProcessorError {
     #[error("Malformed Startup Packet")]
     BadStartup(),
+    #[error("Prepared statement {0} already exists")]
+    DuplicatePreparedStatement(String),
     #[error(transparent)]
     EngineError(#[from] EngineError),
+    #[error("current transaction is aborted, commands ignored until end of transaction block")]
+    InFailedTransaction(),
+    #[error("Malformed {0} message")]
+    MalformedMessage(char),
     #[error(transparent)]
     NetworkFrameError(#[from] NetworkFrameError),
+    #[error("Statement needs {0} parameters, got {1}")]
+    ParameterCountMismatch(usize, usize),
+    #[error(transparent)]
+    PgFormatCodeError(#[from] PgFormatCodeError),
     #[error(transparent)]
     QueryNotUtf8(#[from] std::string::FromUtf8Error),
     #[error(transparent)]
     TransactionManagerError(#[from] TransactionManagerError),
+    #[error("Portal {0} does not exist")]
+    UnknownPortal(String),
+    #[error("Prepared statement {0} does not exist")]
+    UnknownPreparedStatement(String),
+    #[error(transparent)]
+    ValueEncodingError(#[from] ValueEncodingError),
 }
@@ -0,0 +1,387 @@
+//! Works out the SQLSTATE and the extra ErrorResponse fields for our errors.
+//!
+//! Codes come from here: https://www.postgresql.org/docs/current/errcodes-appendix.html
+//! Anything that isn't caused by what the client sent stays a SystemError.
+use super::client_processor::ClientProcessorError;
+use crate::codec::{ErrorFields, NetworkFrameError, ValueEncodingError};
+use crate::constants::PgErrorCodes;
+use crate::engine::analyzer::DefinitionLookupError;
+use crate::engine::executor::ExternalSortError;
+use crate::engine::io::row_formats::RowDataError;
+use crate::engine::io::ConstraintManagerError;
+use crate::engine::objects::types::{
+    BaseSqlTypesError, IntervalError, JsonbError, NumericError, TypeModifierError,
+};
+use crate::engine::objects::{
+    AggregateError, ExpressionError, ParseExpressionError, ScalarFunctionError,
+};
+use crate::engine::{AnalyzerError, EngineError, ExecutorError, PlannerError, SqlParserError};
+use std::num::IntErrorKind;
+
+const NO_FUNCTION_HINT: &str =
+    "No function matches the given name and argument types. You might need to add explicit type casts.";
+const NO_OPERATOR_HINT: &str =
+    "No operator matches the given name and argument types. You might need to add explicit type casts.";
+
+pub fn describe_error(e: &ClientProcessorError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        ClientProcessorError::BadStartup()
+        | ClientProcessorError::MalformedMessage(_)
+        | ClientProcessorError::ParameterCountMismatch(_, _)
+        | ClientProcessorError::PgFormatCodeError(_) => code(PgErrorCodes::ProtocolViolation),
+        ClientProcessorError::DuplicatePreparedStatement(_) => {
+            code(PgErrorCodes::DuplicatePreparedStatement)
+        }
+        ClientProcessorError::EngineError(e) => engine_error(e),
+        ClientProcessorError::InFailedTransaction() => code(PgErrorCodes::InFailedSqlTransaction),
+        ClientProcessorError::NetworkFrameError(NetworkFrameError::TooManyFields(_)) => {
+            code(PgErrorCodes::ProgramLimitExceeded)
+        }
+        ClientProcessorError::NetworkFrameError(NetworkFrameError::ValueEncodingError(e))
+        | ClientProcessorError::ValueEncodingError(e) => value_encoding_error(e),
+        ClientProcessorError::QueryNotUtf8(_) => code(PgErrorCodes::CharacterNotInRepertoire),
+        ClientProcessorError::TransactionManagerError(_) => code(PgErrorCodes::SystemError),
+        ClientProcessorError::UnknownPortal(_) => code(PgErrorCodes::InvalidCursorName),
+        ClientProcessorError::UnknownPreparedStatement(_) => {
+            code(PgErrorCodes::InvalidSqlStatementName)
+        }
+    }
+}
+
+fn code(code: PgErrorCodes) -> (PgErrorCodes, ErrorFields) {
+    (code, ErrorFields::default())
+}
+
+fn with_column(code: PgErrorCodes, column: &str) -> (PgErrorCodes, ErrorFields) {
+    (
+        code,
+        ErrorFields {
+            column: Some(column.to_string()),
+            ..ErrorFields::default()
+        },
+    )
+}
+
+fn with_hint(code: PgErrorCodes, hint: &str) -> (PgErrorCodes, ErrorFields) {
+    (
+        code,
+        ErrorFields {
+            hint: Some(hint.to_string()),
+            ..ErrorFields::default()
+        },
+    )
+}
+
+fn engine_error(e: &EngineError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        EngineError::AnalyzerError(e) => analyzer_error(e),
+        EngineError::ExecutorError(e) => executor_error(e),
+        EngineError::ParseExpressionError(ParseExpressionError::MissingParameter(_, _)) => {
+            code(PgErrorCodes::UndefinedParameter)
+        }
+        EngineError::QueryNotUtf8(_) => code(PgErrorCodes::CharacterNotInRepertoire),
+        EngineError::RewriterError(_) => code(PgErrorCodes::SystemError),
+        EngineError::ParseError(SqlParserError::ParseError(e)) => (
+            PgErrorCodes::SyntaxError,
+            ErrorFields {
+                position: Some(e.position),
+                ..ErrorFields::default()
+            },
+        ),
+        EngineError::ParseError(_) => code(PgErrorCodes::SyntaxError),
+        EngineError::PlannerError(PlannerError::NotImplemented()) => {
+            code(PgErrorCodes::FeatureNotSupported)
+        }
+        EngineError::PlannerError(PlannerError::TooManyJoins(_)) => {
+            code(PgErrorCodes::ProgramLimitExceeded)
+        }
+        EngineError::PlannerError(PlannerError::NoDataProvided()) => {
+            code(PgErrorCodes::SystemError)
+        }
+    }
+}
+
+fn analyzer_error(e: &AnalyzerError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        AnalyzerError::DefinitionLookupError(e) => definition_lookup_error(e),
+        AnalyzerError::BaseSqlTypesError(e) => base_sql_types_error(e),
+        AnalyzerError::ColumnVsColumnMismatch(_, _)
+        | AnalyzerError::ValueVsColumnMismatch(_, _)
+        | AnalyzerError::UnexpectedWildcard() => code(PgErrorCodes::SyntaxError),
+        AnalyzerError::MultipleAssignments(c) => with_column(PgErrorCodes::DuplicateColumn, c),
+        AnalyzerError::MissingColumn(a) => with_column(PgErrorCodes::NotNullViolation, &a.name),
+        AnalyzerError::AmbiguousColumn(_, c) => with_column(PgErrorCodes::AmbiguousColumn, c),
+        AnalyzerError::DuplicateTableName(_) => code(PgErrorCodes::DuplicateAlias),
+        AnalyzerError::UnknownColumn(_, c) => with_column(PgErrorCodes::UndefinedColumn, c),
+        AnalyzerError::UnknownColumns(c) => match c.as_slice() {
+            [c] => with_column(PgErrorCodes::UndefinedColumn, c),
+            _ => code(PgErrorCodes::UndefinedColumn),
+        },
+        AnalyzerError::ExpressionError(e) => expression_error(e),
+        AnalyzerError::ExpressionTypeMismatch(_, _) | AnalyzerError::NotAnArray(_) => {
+            code(PgErrorCodes::DatatypeMismatch)
+        }
+        AnalyzerError::OperatorTypeMismatch(_, _, _) => {
+            with_hint(PgErrorCodes::UndefinedFunction, NO_OPERATOR_HINT)
+        }
+        AnalyzerError::AggregateNotAllowed(_) | AnalyzerError::NestedAggregate(_) => {
+            code(PgErrorCodes::GroupingError)
+        }
+        AnalyzerError::UngroupedColumn(_, c) => with_column(PgErrorCodes::GroupingError, c),
+        AnalyzerError::AggregateTypeMismatch(_, _)
+        | AnalyzerError::UnknownFunction(_)
+        | AnalyzerError::WrongArgumentCount(_, _) => {
+            with_hint(PgErrorCodes::UndefinedFunction, NO_FUNCTION_HINT)
+        }
+        AnalyzerError::UnexpectedSetFunction(_) | AnalyzerError::NotImplemented() => {
+            code(PgErrorCodes::FeatureNotSupported)
+        }
+        AnalyzerError::NoTransactionStart(_) => code(PgErrorCodes::ObjectNotInPrerequisiteState),
+        AnalyzerError::InvalidSortPosition(_) | AnalyzerError::NonConstantRowCount() => {
+            code(PgErrorCodes::InvalidColumnReference)
+        }
+        AnalyzerError::NegativeRowCount(_) => code(PgErrorCodes::InvalidRowCountInLimitClause),
+        AnalyzerError::UnboundParameter(_) => code(PgErrorCodes::UndefinedParameter),
+    }
+}
+
+fn definition_lookup_error(e: &DefinitionLookupError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        DefinitionLookupError::TableDoesNotExist(t) => (
+            PgErrorCodes::UndefinedTable,
+            ErrorFields {
+                table: Some(t.clone()),
+                ..ErrorFields::default()
+            },
+        ),
+        DefinitionLookupError::TypeDoesNotExist(_) => code(PgErrorCodes::UndefinedObject),
+        DefinitionLookupError::BaseSqlTypesError(e) => base_sql_types_error(e),
+        DefinitionLookupError::TypeModifierError(e) => type_modifier_error(e),
+        _ => code(PgErrorCodes::SystemError),
+    }
+}
+
+fn executor_error(e: &ExecutorError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        ExecutorError::DuplicateEnumLabel(_) | ExecutorError::TypeAlreadyExists(_) => {
+            code(PgErrorCodes::DuplicateObject)
+        }
+        ExecutorError::TypeDoesNotExist(_) => code(PgErrorCodes::UndefinedObject),
+        ExecutorError::SystemType(_) => code(PgErrorCodes::DependentObjectsStillExist),
+        ExecutorError::TypeInUse(_) => with_hint(
+            PgErrorCodes::DependentObjectsStillExist,
+            "Drop or change the columns using the type first.",
+        ),
+        ExecutorError::AggregateError(e) => aggregate_error(e),
+        ExecutorError::ExpressionError(e)
+        | ExecutorError::ExternalSortError(ExternalSortError::ExpressionError(e)) => {
+            expression_error(e)
+        }
+        ExecutorError::ExternalSortError(ExternalSortError::BaseSqlTypesError(e))
+        | ExecutorError::RowDataError(RowDataError::BaseSqlTypes(e)) => base_sql_types_error(e),
+        ExecutorError::ConstraintManagerError(e) => constraint_manager_error(e),
+        ExecutorError::TypeModifierError(e) => type_modifier_error(e),
+        _ => code(PgErrorCodes::SystemError),
+    }
+}
+
+fn constraint_manager_error(e: &ConstraintManagerError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        ConstraintManagerError::PrimaryKeyViolation(table, key) => (
+            PgErrorCodes::UniqueViolation,
+            ErrorFields {
+                detail: Some(format!("Key {} already exists.", key)),
+                table: Some(table.clone()),
+                ..ErrorFields::default()
+            },
+        ),
+        ConstraintManagerError::UnexpectedNull(table, column) => (
+            PgErrorCodes::NotNullViolation,
+            ErrorFields {
+                table: Some(table.clone()),
+                column: Some(column.clone()),
+                ..ErrorFields::default()
+            },
+        ),
+        ConstraintManagerError::TableRowTypeMismatch(_, _) => code(PgErrorCodes::DatatypeMismatch),
+        ConstraintManagerError::TypeModifierError(e) => type_modifier_error(e),
+        _ => code(PgErrorCodes::SystemError),
+    }
+}
+
+fn aggregate_error(e: &AggregateError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        AggregateError::BaseSqlTypesError(e) => base_sql_types_error(e),
+        AggregateError::ExpressionError(e) => expression_error(e),
+        AggregateError::JsonbError(e) => jsonb_error(e),
+        AggregateError::NumericError(e) => numeric_error(e),
+        AggregateError::OutOfRange(_, _) => code(PgErrorCodes::NumericValueOutOfRange),
+        AggregateError::UnsupportedType(_, _) | AggregateError::UnsupportedValue(_, _) => {
+            code(PgErrorCodes::DatatypeMismatch)
+        }
+    }
+}
+
+fn expression_error(e: &ExpressionError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        ExpressionError::ArrayDimensionMismatch() => code(PgErrorCodes::ArraySubscriptError),
+        ExpressionError::BaseSqlTypesError(e) => base_sql_types_error(e),
+        ExpressionError::IncomparableTypes(_, _)
+        | ExpressionError::InvalidJsonOperand(_, _)
+        | ExpressionError::InvalidSubscript(_)
+        | ExpressionError::NotArray(_)
+        | ExpressionError::NotBoolean(_)
+        | ExpressionError::NotJsonb(_) => code(PgErrorCodes::DatatypeMismatch),
+        ExpressionError::InvalidQuantifiedOperator(_) => code(PgErrorCodes::WrongObjectType),
+        ExpressionError::JsonbError(e) => jsonb_error(e),
+        ExpressionError::ScalarFunctionError(e) => match e {
+            ScalarFunctionError::InvalidArgument(_, _) => code(PgErrorCodes::InvalidParameterValue),
+            ScalarFunctionError::JsonbError(e) => jsonb_error(e),
+            ScalarFunctionError::NullKey(_, _) => code(PgErrorCodes::NullValueNotAllowed),
+            ScalarFunctionError::TryFromIntError(_) => code(PgErrorCodes::NumericValueOutOfRange),
+        },
+        ExpressionError::UnexpectedSetFunction() => code(PgErrorCodes::FeatureNotSupported),
+        ExpressionError::ColumnOutOfRange(_, _) | ExpressionError::UnplannedAggregate(_) => {
+            code(PgErrorCodes::SystemError)
+        }
+    }
+}
+
+fn base_sql_types_error(e: &BaseSqlTypesError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        BaseSqlTypesError::Utf8Error(_) => code(PgErrorCodes::CharacterNotInRepertoire),
+        BaseSqlTypesError::DateTimeOutOfRange(_) => code(PgErrorCodes::DatetimeFieldOverflow),
+        BaseSqlTypesError::DivisionByZero() => code(PgErrorCodes::DivisionByZero),
+        BaseSqlTypesError::InvalidInt(i)
+            if matches!(
+                i.kind(),
+                IntErrorKind::PosOverflow | IntErrorKind::NegOverflow
+            ) =>
+        {
+            code(PgErrorCodes::NumericValueOutOfRange)
+        }
+        BaseSqlTypesError::InvalidBool(_)
+        | BaseSqlTypesError::InvalidArray(_)
+        | BaseSqlTypesError::InvalidBytea(_)
+        | BaseSqlTypesError::InvalidEnumLabel(_, _)
+        | BaseSqlTypesError::InvalidFloat(_)
+        | BaseSqlTypesError::InvalidInt(_)
+        | BaseSqlTypesError::InvalidUuid(_)
+        | BaseSqlTypesError::ParseError(_) => code(PgErrorCodes::InvalidTextRepresentation),
+        BaseSqlTypesError::InvalidDateTime(_, _) => code(PgErrorCodes::InvalidDatetimeFormat),
+        BaseSqlTypesError::InvalidInterval(IntervalError::OutOfRange()) => {
+            code(PgErrorCodes::IntervalFieldOverflow)
+        }
+        BaseSqlTypesError::InvalidInterval(IntervalError::InvalidInterval(_))
+        | BaseSqlTypesError::InvalidInterval(IntervalError::UnknownUnit(_)) => {
+            code(PgErrorCodes::InvalidDatetimeFormat)
+        }
+        BaseSqlTypesError::InvalidArithmetic(_, _) => code(PgErrorCodes::DatatypeMismatch),
+        BaseSqlTypesError::InvalidConversion(_, _) => code(PgErrorCodes::CannotCoerce),
+        BaseSqlTypesError::InvalidType(_) => code(PgErrorCodes::UndefinedObject),
+        BaseSqlTypesError::JsonbError(e) => jsonb_error(e),
+        BaseSqlTypesError::NumericError(e) => numeric_error(e),
+        BaseSqlTypesError::NumericOutOfRange(_) => code(PgErrorCodes::NumericValueOutOfRange),
+        _ => code(PgErrorCodes::SystemError),
+    }
+}
+
+fn jsonb_error(e: &JsonbError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        JsonbError::InvalidJson(_) | JsonbError::InvalidPath(_) => {
+            code(PgErrorCodes::InvalidTextRepresentation)
+        }
+        JsonbError::NumericError(e) => numeric_error(e),
+        JsonbError::TooDeep(_) => code(PgErrorCodes::ProgramLimitExceeded),
+        _ => code(PgErrorCodes::SystemError),
+    }
+}
+
+fn numeric_error(e: &NumericError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        NumericError::DivisionByZero() => code(PgErrorCodes::DivisionByZero),
+        NumericError::InvalidNumeric(_) => code(PgErrorCodes::InvalidTextRepresentation),
+        NumericError::Overflow(_, _) | NumericError::ScaleOutOfRange(_) => {
+            code(PgErrorCodes::NumericValueOutOfRange)
+        }
+        _ => code(PgErrorCodes::SystemError),
+    }
+}
+
+fn type_modifier_error(e: &TypeModifierError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        TypeModifierError::InvalidModifier(_, _) => code(PgErrorCodes::InvalidParameterValue),
+        TypeModifierError::NotAllowed(_) => code(PgErrorCodes::SyntaxError),
+        TypeModifierError::NumericError(e) => numeric_error(e),
+        TypeModifierError::ValueTooLong(_) => code(PgErrorCodes::StringDataRightTruncation),
+        TypeModifierError::WrongType(_, _) => code(PgErrorCodes::DatatypeMismatch),
+    }
+}
+
+fn value_encoding_error(e: &ValueEncodingError) -> (PgErrorCodes, ErrorFields) {
+    match e {
+        ValueEncodingError::BinaryNotSupported(_)
+        | ValueEncodingError::BinaryParameterNotSupported(_) => {
+            code(PgErrorCodes::FeatureNotSupported)
+        }
+        ValueEncodingError::NotUtf8(_) => code(PgErrorCodes::CharacterNotInRepertoire),
+        ValueEncodingError::UnknownJsonbVersion() | ValueEncodingError::WrongLength(_, _) => {
+            code(PgErrorCodes::InvalidBinaryRepresentation)
+        }
+        ValueEncodingError::OutOfRange(_) => code(PgErrorCodes::NumericValueOutOfRange),
+        ValueEncodingError::UnknownTypeOid(_) => code(PgErrorCodes::UndefinedObject),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::SqlParser;
+
+    #[test]
+    fn test_describe_error() {
+        let (code, fields) = describe_error(&ClientProcessorError::EngineError(
+            EngineError::ExecutorError(ExecutorError::ConstraintManagerError(
+                ConstraintManagerError::PrimaryKeyViolation(
+                    "foo".to_string(),
+                    "(bar)=(1)".to_string(),
+                ),
+            )),
+        ));
+        assert_eq!(code, PgErrorCodes::UniqueViolation);
+        assert_eq!(
+            fields.detail,
+            Some("Key (bar)=(1) already exists.".to_string())
+        );
+        assert_eq!(fields.table, Some("foo".to_string()));
+
+        let (code, fields) = describe_error(&ClientProcessorError::EngineError(
+            EngineError::AnalyzerError(AnalyzerError::UnknownColumn(
+                "f.baz".to_string(),
+                "baz".to_string(),
+            )),
+        ));
+        assert_eq!(code, PgErrorCodes::UndefinedColumn);
+        assert_eq!(fields.column, Some("baz".to_string()));
+
+        let (_, fields) = describe_error(&ClientProcessorError::EngineError(
+            EngineError::AnalyzerError(AnalyzerError::AmbiguousColumn(
+                "f.a.b".to_string(),
+                "a.b".to_string(),
+            )),
+        ));
+        assert_eq!(fields.column, Some("a.b".to_string()));
+
+        let parse_error = SqlParser::parse("select bar\nfrm foo").unwrap_err();
+        let (code, fields) = describe_error(&ClientProcessorError::EngineError(parse_error.into()));
+        assert_eq!(code, PgErrorCodes::SyntaxError);
+        assert_eq!(fields.position, Some(12));
+
+        let (code, _) = describe_error(&ClientProcessorError::EngineError(
+            EngineError::AnalyzerError(AnalyzerError::BaseSqlTypesError(
+                BaseSqlTypesError::InvalidInt("99999999999".parse::<i32>().unwrap_err()),
+            )),
+        ));
+        assert_eq!(code, PgErrorCodes::NumericValueOutOfRange);
+    }
+}
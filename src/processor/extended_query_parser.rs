@@ -0,0 +1,183 @@
+//! Parsers for the payloads of the extended query messages.
+//! Formats found here: https://www.postgresql.org/docs/current/protocol-message-formats.html
+
+use nom::{
+    bytes::complete::{tag, take, take_till},
+    combinator::{all_consuming, map, map_res},
+    multi::count,
+    number::complete::{be_i16, be_i32, be_u32, be_u8},
+    sequence::{terminated, tuple},
+    IResult,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseMessage {
+    pub statement: String,
+    pub query: String,
+    pub parameter_types: Vec<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BindMessage {
+    pub portal: String,
+    pub statement: String,
+    pub parameter_formats: Vec<i16>,
+    pub parameters: Vec<Option<Vec<u8>>>,
+    pub result_formats: Vec<i16>,
+}
+
+/// Describe and Close both target either a statement or a portal
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    Statement(String),
+    Portal(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecuteMessage {
+    pub portal: String,
+    pub max_rows: i32,
+}
+
+pub fn parse_parse_message(
+    input: &[u8],
+) -> Result<ParseMessage, nom::Err<nom::error::Error<&[u8]>>> {
+    let (_, (statement, query, parameter_types)) =
+        all_consuming(tuple((till_null, till_null, counted(be_u32))))(input)?;
+    Ok(ParseMessage {
+        statement,
+        query,
+        parameter_types,
+    })
+}
+
+pub fn parse_bind_message(input: &[u8]) -> Result<BindMessage, nom::Err<nom::error::Error<&[u8]>>> {
+    let (_, (portal, statement, parameter_formats, parameters, result_formats)) =
+        all_consuming(tuple((
+            till_null,
+            till_null,
+            counted(be_i16),
+            counted(parse_parameter_value),
+            counted(be_i16),
+        )))(input)?;
+    Ok(BindMessage {
+        portal,
+        statement,
+        parameter_formats,
+        parameters,
+        result_formats,
+    })
+}
+
+/// Used for both Describe and Close
+pub fn parse_target_message(input: &[u8]) -> Result<Target, nom::Err<nom::error::Error<&[u8]>>> {
+    let (_, (kind, name)) = all_consuming(tuple((be_u8, till_null)))(input)?;
+    match kind {
+        b'S' => Ok(Target::Statement(name)),
+        b'P' => Ok(Target::Portal(name)),
+        _ => Err(nom::Err::Failure(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Char,
+        ))),
+    }
+}
+
+pub fn parse_execute_message(
+    input: &[u8],
+) -> Result<ExecuteMessage, nom::Err<nom::error::Error<&[u8]>>> {
+    let (_, (portal, max_rows)) = all_consuming(tuple((till_null, be_i32)))(input)?;
+    Ok(ExecuteMessage { portal, max_rows })
+}
+
+/// Unlike the startup message, names here are allowed to be empty
+fn till_null(input: &[u8]) -> IResult<&[u8], String> {
+    map_res(
+        terminated(take_till(|c| c == 0), tag(b"\0")),
+        |s: &[u8]| String::from_utf8(s.to_vec()),
+    )(input)
+}
+
+/// A 16 bit count followed by that many items
+fn counted<'a, O, F>(mut item: F) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vec<O>>
+where
+    F: FnMut(&'a [u8]) -> IResult<&'a [u8], O>,
+{
+    move |input: &'a [u8]| {
+        let (input, length) = be_i16(input)?;
+        count(&mut item, usize::from(length.max(0) as u16))(input)
+    }
+}
+
+/// A length of -1 is a NULL
+fn parse_parameter_value(input: &[u8]) -> IResult<&[u8], Option<Vec<u8>>> {
+    let (input, length) = be_i32(input)?;
+    if length < 0 {
+        return Ok((input, None));
+    }
+    map(take(length as usize), |v: &[u8]| Some(v.to_vec()))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_message() -> Result<(), Box<dyn std::error::Error>> {
+        let input = b"s0\0select $1\0\0\x01\0\0\0\x17";
+        let parsed = parse_parse_message(input).unwrap();
+        assert_eq!(
+            parsed,
+            ParseMessage {
+                statement: "s0".to_string(),
+                query: "select $1".to_string(),
+                parameter_types: vec![23],
+            }
+        );
+
+        let empty_name = b"\0select 1\0\0\0";
+        assert_eq!(parse_parse_message(empty_name).unwrap().statement, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_bind_message() -> Result<(), Box<dyn std::error::Error>> {
+        let input = b"\0s0\0\0\x01\0\x01\0\x02\0\0\0\x02hi\xff\xff\xff\xff\0\x01\0\x01";
+        let parsed = parse_bind_message(input).unwrap();
+        assert_eq!(
+            parsed,
+            BindMessage {
+                portal: "".to_string(),
+                statement: "s0".to_string(),
+                parameter_formats: vec![1],
+                parameters: vec![Some(b"hi".to_vec()), None],
+                result_formats: vec![1],
+            }
+        );
+
+        //Truncated parameter
+        assert!(parse_bind_message(b"\0s0\0\0\0\0\x01\0\0\0\x05hi\0\0").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_target_and_execute() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(
+            parse_target_message(b"Ss1\0").unwrap(),
+            Target::Statement("s1".to_string())
+        );
+        assert_eq!(
+            parse_target_message(b"P\0").unwrap(),
+            Target::Portal("".to_string())
+        );
+        assert!(parse_target_message(b"X\0").is_err());
+
+        assert_eq!(
+            parse_execute_message(b"\0\0\0\0\x05").unwrap(),
+            ExecuteMessage {
+                portal: "".to_string(),
+                max_rows: 5
+            }
+        );
+        Ok(())
+    }
+}
@@ -1,16 +1,26 @@
 use feophantlib::feophant::FeOphant;
 use log::LevelFilter;
 use simplelog::{ColorChoice, CombinedLogger, Config, TermLogger, TerminalMode};
+use std::ffi::OsString;
 use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
 use tokio::sync::oneshot;
 use tokio::sync::{
     mpsc::{self, UnboundedReceiver, UnboundedSender},
     oneshot::Sender,
 };
-use tokio_postgres::{Client, NoTls};
+use tokio_postgres::{Client, NoTls, SimpleQueryMessage};
 
 pub async fn _create_server_and_client(
 ) -> Result<(UnboundedSender<Sender<()>>, Client), Box<dyn std::error::Error>> {
+    let (request_shutdown, port) = _create_server().await?;
+    Ok((request_shutdown, _connect(port).await?))
+}
+
+/// For tests that need to talk the protocol themselves, returns the port to connect to
+pub async fn _create_server(
+) -> Result<(UnboundedSender<Sender<()>>, u16), Box<dyn std::error::Error>> {
     CombinedLogger::init(vec![TermLogger::new(
         LevelFilter::Debug,
         Config::default(),
@@ -20,18 +30,36 @@ pub async fn _create_server_and_client(
 
     let tmp = TempDir::new()?;
 
+    _start_server(tmp.into_path().into_os_string()).await
+}
+
+/// Starts a server on an existing data directory, the logger must already be set up
+pub async fn _start_server_and_client(
+    data_dir: OsString,
+) -> Result<(UnboundedSender<Sender<()>>, Client), Box<dyn std::error::Error>> {
+    let (request_shutdown, port) = _start_server(data_dir).await?;
+    Ok((request_shutdown, _connect(port).await?))
+}
+
+pub async fn _start_server(
+    data_dir: OsString,
+) -> Result<(UnboundedSender<Sender<()>>, u16), Box<dyn std::error::Error>> {
     let (request_shutdown, receive_shutdown): (
         UnboundedSender<Sender<()>>,
         UnboundedReceiver<Sender<()>>,
     ) = mpsc::unbounded_channel();
 
-    let feo = FeOphant::new(tmp.into_path().into_os_string(), 0).await?;
+    let feo = FeOphant::new(data_dir, 0).await?;
     let port = feo.port;
 
     tokio::spawn(async move {
         feo.start(receive_shutdown).await;
     });
 
+    Ok((request_shutdown, port))
+}
+
+pub async fn _connect(port: u16) -> Result<Client, Box<dyn std::error::Error>> {
     let connect_str = format!("host=127.0.0.1 user=postgres port={0}", port);
     let (client, connection) = tokio_postgres::connect(&connect_str, NoTls).await?;
     tokio::spawn(async move {
@@ -40,7 +68,50 @@ pub async fn _create_server_and_client(
         }
     });
 
-    Ok((request_shutdown, client))
+    Ok(client)
+}
+
+/// The type oid of each column a simple query's RowDescription reports, read off a connection of its own.
+/// tokio_postgres can't be used for types it would have to look up in pg_catalog before decoding.
+pub async fn _column_type_oids(
+    port: u16,
+    query: &str,
+) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await?;
+    let mut startup = 196608i32.to_be_bytes().to_vec();
+    startup.extend_from_slice(b"user\0postgres\0\0");
+    stream
+        .write_all(&(startup.len() as i32 + 4).to_be_bytes())
+        .await?;
+    stream.write_all(&startup).await?;
+
+    let mut message = vec![b'Q'];
+    message.extend_from_slice(&(query.len() as i32 + 5).to_be_bytes());
+    message.extend_from_slice(query.as_bytes());
+    message.push(0);
+    stream.write_all(&message).await?;
+
+    loop {
+        let message_type = stream.read_u8().await?;
+        let length = stream.read_i32().await?;
+        let mut payload = vec![0; length as usize - 4];
+        stream.read_exact(&mut payload).await?;
+        match message_type {
+            b'T' => {}
+            b'E' => return Err(format!("{} failed", query).into()),
+            _ => continue,
+        }
+
+        //Each field is its name then 18 bytes, the type oid is after the table oid and column number
+        let mut fields = &payload[2..];
+        let mut oids = vec![];
+        while let Some(name_end) = fields.iter().position(|b| *b == 0) {
+            let oid = &fields[name_end + 7..name_end + 11];
+            oids.push(u32::from_be_bytes([oid[0], oid[1], oid[2], oid[3]]));
+            fields = &fields[name_end + 19..];
+        }
+        return Ok(oids);
+    }
 }
 
 pub async fn _request_shutdown(
@@ -51,3 +122,44 @@ pub async fn _request_shutdown(
 
     Ok(rev_shutdown.await?)
 }
+
+/// Pulls the text of every data row out of a simple query's results
+pub fn _get_rows(messages: &[SimpleQueryMessage]) -> Vec<Vec<Option<String>>> {
+    messages
+        .iter()
+        .filter_map(|m| match m {
+            SimpleQueryMessage::Row(r) => Some(
+                (0..r.len())
+                    .map(|i| r.get(i).map(|s| s.to_string()))
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+/// For queries without an ORDER BY, where the row order isn't defined
+pub fn _get_sorted_rows(messages: &[SimpleQueryMessage]) -> Vec<Vec<Option<String>>> {
+    let mut rows = _get_rows(messages);
+    rows.sort();
+    rows
+}
+
+pub fn _row(values: &[&str]) -> Vec<Option<String>> {
+    values.iter().map(|v| Some(v.to_string())).collect()
+}
+
+pub fn _nullable_row(values: &[Option<&str>]) -> Vec<Option<String>> {
+    values.iter().map(|v| v.map(|s| s.to_string())).collect()
+}
+
+/// The row count each statement's CommandComplete reported, in order
+pub fn _get_counts(messages: &[SimpleQueryMessage]) -> Vec<u64> {
+    messages
+        .iter()
+        .filter_map(|m| match m {
+            SimpleQueryMessage::CommandComplete(c) => Some(*c),
+            _ => None,
+        })
+        .collect()
+}
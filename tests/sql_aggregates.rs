@@ -0,0 +1,102 @@
+mod common;
+
+#[tokio::test]
+async fn aggregates() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute("create table sales (region text not null, amount integer null)")
+        .await?;
+
+    //Nothing to aggregate still gives a row without a GROUP BY
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select count(*), sum(amount), max(region) from sales")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_nullable_row(&[Some("0"), None, None])]);
+
+    for (region, amount) in [
+        ("east", "10"),
+        ("west", "5"),
+        ("east", "20"),
+        ("north", "null"),
+        ("west", "6"),
+    ] {
+        client
+            .batch_execute(&format!(
+                "insert into sales (region, amount) values('{}', {})",
+                region, amount
+            ))
+            .await?;
+    }
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select count(*), count(amount), sum(amount), min(amount), max(amount), avg(amount) from sales",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![common::_nullable_row(&[
+            Some("5"),
+            Some("4"),
+            Some("41"),
+            Some("5"),
+            Some("20"),
+            Some("10.250000000000000")
+        ])]
+    );
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select region, count(*), sum(amount) from sales group by region order by region",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_nullable_row(&[Some("east"), Some("2"), Some("30")]),
+            common::_nullable_row(&[Some("north"), Some("1"), None]),
+            common::_nullable_row(&[Some("west"), Some("2"), Some("11")]),
+        ]
+    );
+
+    //Having and order by can use aggregates that aren't selected
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select region from sales group by region having count(amount) > 1 order by sum(amount) desc",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_nullable_row(&[Some("east")]),
+            common::_nullable_row(&[Some("west")])
+        ]
+    );
+
+    //The output columns are named after the aggregate
+    let stmt = client.prepare("select count(*) from sales").await?;
+    assert_eq!(stmt.columns()[0].name(), "count");
+
+    assert!(client
+        .simple_query("select region, count(*) from sales")
+        .await
+        .is_err());
+    assert!(client
+        .simple_query("select region from sales where count(*) > 1 group by region")
+        .await
+        .is_err());
+    assert!(client
+        .simple_query("select sum(region) from sales")
+        .await
+        .is_err());
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,166 @@
+use tokio_postgres::error::SqlState;
+
+mod common;
+
+#[tokio::test]
+async fn arrays() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute(
+            "create table lists (id integer not null primary key, nums integer[] null, tags varchar(3)[] null)",
+        )
+        .await?;
+
+    //Both literal forms, multiple dimensions and NULL elements
+    client
+        .batch_execute(
+            r#"insert into lists (id, nums, tags) values(1, '{1, 2, 3}', '{"a b", NULL, c}')"#,
+        )
+        .await?;
+    client
+        .batch_execute(
+            "insert into lists (id, nums, tags) values(2, ARRAY[[4, 5], [6, NULL]], ARRAY['x'])",
+        )
+        .await?;
+    client
+        .batch_execute("insert into lists (id, nums, tags) values(3, '{}', null)")
+        .await?;
+    assert!(client
+        .batch_execute("insert into lists (id, nums) values(4, '{1, {2}}')")
+        .await
+        .is_err());
+    assert!(client
+        .batch_execute("insert into lists (id, nums) values(4, ARRAY[[1], [2, 3]])")
+        .await
+        .is_err());
+    assert!(client
+        .batch_execute("insert into lists (id, tags) values(4, '{abcd}')")
+        .await
+        .is_err());
+    //Same as postgres no more than 6 dimensions, deeper nesting is an error and not a crash
+    let err = client
+        .batch_execute("insert into lists (id, nums) values(4, '{{{{{{{1}}}}}}}')")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::INVALID_TEXT_REPRESENTATION));
+    assert!(client
+        .batch_execute(&format!(
+            "insert into lists (id, nums) values(4, '{}1{}')",
+            "{".repeat(100_000),
+            "}".repeat(100_000)
+        ))
+        .await
+        .is_err());
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select nums, tags from lists order by id")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["{1,2,3}", r#"{"a b",NULL,c}"#]),
+            common::_row(&["{{4,5},{6,NULL}}", "{x}"]),
+            vec![Some("{}".to_string()), None],
+        ]
+    );
+
+    //Subscripts start at one and anything outside the array is NULL
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select nums[1], nums[2][1], nums[9] from lists order by id")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![Some("1".to_string()), None, None],
+            vec![None, Some("6".to_string()), None],
+            vec![None, None, None],
+        ]
+    );
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select id from lists where 2 = any(nums) or 5 = some(nums) order by id")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["1"]), common::_row(&["2"])]);
+
+    //ALL of an empty array is true, the NULL in row 2 makes it unknown
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select id from lists where 0 < all(nums) order by id")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["1"]), common::_row(&["3"])]);
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select id, nums @> '{3, 1}', nums && ARRAY[6, 7], array_length(nums, 1), array_length(nums, 2) from lists order by id",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                Some("1".to_string()),
+                Some("true".to_string()),
+                Some("false".to_string()),
+                Some("3".to_string()),
+                None
+            ],
+            vec![
+                Some("2".to_string()),
+                Some("false".to_string()),
+                Some("true".to_string()),
+                Some("2".to_string()),
+                Some("2".to_string())
+            ],
+            vec![
+                Some("3".to_string()),
+                Some("false".to_string()),
+                Some("false".to_string()),
+                None,
+                None
+            ],
+        ]
+    );
+
+    //unnest makes a row per element, the shorter one is padded with NULLs
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select id, unnest(nums), unnest(tags) from lists where id < 3 order by id",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["1", "1", "a b"]),
+            vec![Some("1".to_string()), Some("2".to_string()), None],
+            common::_row(&["1", "3", "c"]),
+            common::_row(&["2", "4", "x"]),
+            vec![Some("2".to_string()), Some("5".to_string()), None],
+            vec![Some("2".to_string()), Some("6".to_string()), None],
+            vec![Some("2".to_string()), None, None],
+        ]
+    );
+    assert!(client
+        .simple_query("select id from lists where unnest(nums) = 1")
+        .await
+        .is_err());
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select ARRAY[1, 2.5], ARRAY['a', 'b'][2] from lists where id = 1")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["{1,2.5}", "b"])]);
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,63 @@
+mod common;
+
+#[tokio::test]
+async fn bytea() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute("create table blob (hash bytea not null primary key, data bytea null)")
+        .await?;
+
+    //Hex and escape input can be mixed
+    client
+        .batch_execute("insert into blob (hash, data) values('\\xDEADBEEF', 'abc\\000\\\\')")
+        .await?;
+    client
+        .batch_execute("insert into blob (hash, data) values('\\x00ff', '\\x')")
+        .await?;
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select hash, data from blob order by hash")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["\\x00ff", "\\x"]),
+            common::_row(&["\\xdeadbeef", "\\x616263005c"])
+        ]
+    );
+
+    //Values are index keys so duplicates are caught
+    assert!(client
+        .batch_execute("insert into blob (hash) values('\\xdeadbeef')")
+        .await
+        .is_err());
+    assert!(client
+        .batch_execute("insert into blob (hash) values('\\xabc')")
+        .await
+        .is_err());
+
+    //Binary results are the raw bytes and parameters can be bound directly
+    let hash: &[u8] = &[0xde, 0xad, 0xbe, 0xef];
+    let found = client
+        .query_one("select data from blob where hash = $1", &[&hash])
+        .await?;
+    assert_eq!(found.get::<_, Vec<u8>>(0), b"abc\0\\".to_vec());
+
+    let data: &[u8] = &[1, 2, 3];
+    client
+        .execute(
+            "insert into blob (hash, data) values($1, $2)",
+            &[&data, &data],
+        )
+        .await?;
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select data from blob where hash = '\\x010203'")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["\\x010203"])]);
+
+    common::_request_shutdown(request_shutdown).await
+}
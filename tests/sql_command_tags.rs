@@ -0,0 +1,63 @@
+mod common;
+
+#[tokio::test]
+async fn command_tags() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    assert_eq!(
+        client
+            .execute("create table foo (bar integer not null primary key)", &[])
+            .await?,
+        0
+    );
+    assert_eq!(
+        client
+            .execute("create type mood as enum ('sad', 'happy')", &[])
+            .await?,
+        0
+    );
+
+    for i in 0..3 {
+        let statement = client.prepare("insert into foo (bar) values($1)").await?;
+        assert_eq!(client.execute(&statement, &[&i]).await?, 1);
+    }
+    assert_eq!(
+        common::_get_counts(
+            &client
+                .simple_query("insert into foo (bar) values(3)")
+                .await?
+        ),
+        vec![1]
+    );
+
+    assert_eq!(
+        common::_get_counts(&client.simple_query("select bar from foo").await?),
+        vec![4]
+    );
+    assert_eq!(
+        client
+            .execute("update foo set bar = bar + 10 where bar > 1", &[])
+            .await?,
+        2
+    );
+    assert_eq!(
+        client
+            .execute("delete from foo where bar < 10", &[])
+            .await?,
+        2
+    );
+
+    assert_eq!(
+        common::_get_counts(&client.simple_query("begin").await?),
+        vec![0]
+    );
+    assert_eq!(
+        common::_get_counts(&client.simple_query("drop type mood").await?),
+        vec![0]
+    );
+    assert_eq!(
+        common::_get_counts(&client.simple_query("commit").await?),
+        vec![0]
+    );
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,155 @@
+mod common;
+
+#[tokio::test]
+async fn date_time_types() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute(
+            "create table event (id int not null primary key, day date null, at time without time zone null, \
+            started timestamp null, logged timestamp with time zone null, length interval null)",
+        )
+        .await?;
+
+    client
+        .batch_execute(
+            "insert into event (id, day, at, started, logged, length) values(1, '2021-03-01', '09:30', \
+            '2021-03-01 09:30:00', '2021-03-01 12:00:00+02', '1 day 2 hours')",
+        )
+        .await?;
+    client
+        .batch_execute(
+            "insert into event (id, day, at, started, logged, length) values(2, '2020-02-29', '23:59:59.5', \
+            '2020-02-29 23:59:59.5', '2020-02-29T23:59:59.5Z', '-1 mons')",
+        )
+        .await?;
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select id, day, at, started, logged, length from event order by started")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&[
+                "2",
+                "2020-02-29",
+                "23:59:59.5",
+                "2020-02-29 23:59:59.5",
+                "2020-02-29 23:59:59.5+00",
+                "-1 mons"
+            ]),
+            common::_row(&[
+                "1",
+                "2021-03-01",
+                "09:30:00",
+                "2021-03-01 09:30:00",
+                "2021-03-01 10:00:00+00",
+                "1 day 02:00:00"
+            ]),
+        ]
+    );
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select day + 1, day - '2021-01-01', started + length, started - '1 year', \
+                logged - '2021-03-01 09:30:00+00', at + '1 hour' from event where id = 1",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![common::_row(&[
+            "2021-03-02",
+            "59",
+            "2021-03-02 11:30:00",
+            "2020-03-01 09:30:00",
+            "00:30:00",
+            "10:30:00"
+        ])]
+    );
+
+    //Subtracting a negative interval adds it
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select started - length, day + at from event where id = 2")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![common::_row(&[
+            "2020-03-29 23:59:59.5",
+            "2020-02-29 23:59:59.5"
+        ])]
+    );
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select id from event where logged > '2021-01-01' and length < '2 days'")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["1"])]);
+
+    //Literals can be written with their type in front
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select started + interval '1 hour', date '2021-03-05' - day, time '08:00' < at, \
+                interval '1 day' + timestamp with time zone '2021-03-01 10:00:00+00' from event \
+                where started >= timestamp '2021-03-01 09:30' and logged = timestamptz '2021-03-01 10:00Z'",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![common::_row(&[
+            "2021-03-01 10:30:00",
+            "4",
+            "true",
+            "2021-03-02 10:00:00+00"
+        ])]
+    );
+    assert!(client
+        .simple_query("select date '2021-02-30' from event")
+        .await
+        .is_err());
+
+    //now() is the start of the transaction so it doesn't move within one
+    client.batch_execute("begin").await?;
+    client
+        .batch_execute("insert into event (id, logged) values(3, now())")
+        .await?;
+    let first = common::_get_rows(
+        &client
+            .simple_query("select now(), current_timestamp, logged from event where id = 3")
+            .await?,
+    );
+    let second = common::_get_rows(
+        &client
+            .simple_query("select logged, logged, now() from event where id = 3")
+            .await?,
+    );
+    assert_eq!(first, second);
+    assert_eq!(first[0][0], first[0][2]);
+    client.batch_execute("commit").await?;
+
+    //Assignment converts between the timestamp types but not to unrelated ones
+    client
+        .batch_execute("update event set started = now() where id = 3")
+        .await?;
+    assert!(client
+        .batch_execute("update event set at = started where id = 3")
+        .await
+        .is_err());
+    assert!(client
+        .batch_execute("insert into event (id, day) values(4, '2021-02-30')")
+        .await
+        .is_err());
+    assert!(client
+        .simple_query("select day + day from event")
+        .await
+        .is_err());
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,88 @@
+mod common;
+
+#[tokio::test]
+async fn enums() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute("create type mood as enum ('sad', 'ok', 'happy')")
+        .await?;
+    assert!(client
+        .batch_execute("create type mood as enum ('other')")
+        .await
+        .is_err());
+    assert!(client
+        .batch_execute("create type text as enum ('other')")
+        .await
+        .is_err());
+    assert!(client
+        .batch_execute("create type twice as enum ('a', 'a')")
+        .await
+        .is_err());
+
+    client
+        .batch_execute(
+            "create table person (name text not null primary key, current mood null, history mood[] null)",
+        )
+        .await?;
+    client
+        .batch_execute("insert into person values('a', 'happy', '{sad, ok}')")
+        .await?;
+    client
+        .batch_execute("insert into person values('b', 'sad', null)")
+        .await?;
+    client
+        .batch_execute("insert into person values('c', 'ok', '{}')")
+        .await?;
+    assert!(client
+        .batch_execute("insert into person values('d', 'angry', null)")
+        .await
+        .is_err());
+    assert!(client
+        .batch_execute("insert into person values('d', 'ok', '{angry}')")
+        .await
+        .is_err());
+
+    //Declaration order, not alphabetical
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select name, current, history from person order by current")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![Some("b".to_string()), Some("sad".to_string()), None],
+            common::_row(&["c", "ok", "{}"]),
+            common::_row(&["a", "happy", "{sad,ok}"]),
+        ]
+    );
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select name from person where current > 'sad' order by name")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["a"]), common::_row(&["c"])]);
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select name from person where current = 'ok'")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["c"])]);
+
+    //The type can't go away while a column uses it
+    assert!(client.batch_execute("drop type mood").await.is_err());
+    assert!(client.batch_execute("drop type missing").await.is_err());
+    client.batch_execute("drop type if exists missing").await?;
+
+    client
+        .batch_execute("create type unused as enum ('x')")
+        .await?;
+    client.batch_execute("drop type unused").await?;
+    client
+        .batch_execute("create type unused as enum ('y')")
+        .await?;
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,69 @@
+use tokio_postgres::error::SqlState;
+
+mod common;
+
+#[tokio::test]
+async fn error_codes() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute("create table foo (id integer not null primary key, name text not null)")
+        .await?;
+    client
+        .batch_execute("insert into foo (id, name) values(1, 'one')")
+        .await?;
+
+    let e = client
+        .batch_execute("insert into foo (id, name) values(1, 'uno')")
+        .await
+        .unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::UNIQUE_VIOLATION));
+    let db_error = e.as_db_error().unwrap();
+    assert_eq!(db_error.table(), Some("foo"));
+    assert_eq!(db_error.detail(), Some("Key (id)=(1) already exists."));
+
+    let e = client
+        .batch_execute("insert into foo (id, name) values(2, null)")
+        .await
+        .unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::NOT_NULL_VIOLATION));
+    let db_error = e.as_db_error().unwrap();
+    assert_eq!(db_error.table(), Some("foo"));
+    assert_eq!(db_error.column(), Some("name"));
+
+    let e = client
+        .batch_execute("select missing from foo")
+        .await
+        .unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::UNDEFINED_COLUMN));
+    assert_eq!(e.as_db_error().unwrap().column(), Some("missing"));
+
+    let e = client
+        .batch_execute("select foo.\"a.b\" from foo")
+        .await
+        .unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::UNDEFINED_COLUMN));
+    assert_eq!(e.as_db_error().unwrap().column(), Some("a.b"));
+
+    let e = client
+        .batch_execute("select id from bar")
+        .await
+        .unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::UNDEFINED_TABLE));
+    assert_eq!(e.as_db_error().unwrap().table(), Some("bar"));
+
+    let e = client.batch_execute("selec id from foo").await.unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::SYNTAX_ERROR));
+
+    let e = client
+        .batch_execute("select id / 0 from foo")
+        .await
+        .unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::DIVISION_BY_ZERO));
+
+    //The connection is still usable after all of those
+    client
+        .batch_execute("insert into foo (id, name) values(2, 'two')")
+        .await?;
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,141 @@
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+mod common;
+
+/// Sends one frontend message, the length covers itself and the payload
+async fn send(
+    stream: &mut TcpStream,
+    message_type: u8,
+    payload: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut message = vec![message_type];
+    message.extend_from_slice(&(payload.len() as i32 + 4).to_be_bytes());
+    message.extend_from_slice(payload);
+    stream.write_all(&message).await?;
+    Ok(())
+}
+
+/// Parse, Bind and Execute of a query without parameters, using the unnamed statement and portal
+async fn send_query(stream: &mut TcpStream, query: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut parse = vec![0];
+    parse.extend_from_slice(query.as_bytes());
+    parse.extend_from_slice(&[0, 0, 0]);
+    send(stream, b'P', &parse).await?;
+    send(stream, b'B', &[0, 0, 0, 0, 0, 0, 0, 0]).await?;
+    send(stream, b'E', &[0, 0, 0, 0, 0]).await
+}
+
+/// Reads the type of each backend message until there are `count` of them
+async fn receive(
+    stream: &mut TcpStream,
+    count: usize,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut types = vec![];
+    while types.len() < count {
+        let message_type = stream.read_u8().await?;
+        let length = stream.read_i32().await?;
+        let mut payload = vec![0; length as usize - 4];
+        stream.read_exact(&mut payload).await?;
+
+        types.push(message_type);
+        //The transaction status is the interesting part of ReadyForQuery
+        if message_type == b'Z' {
+            types.push(payload[0]);
+        }
+    }
+    Ok(types)
+}
+
+#[tokio::test]
+async fn extended_query() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, port) = common::_create_server().await?;
+    let client = common::_connect(port).await?;
+    client
+        .batch_execute("create table foo (bar text, baz integer not null)")
+        .await?;
+
+    //Parameter types are worked out from the columns they are used with
+    let insert = client
+        .prepare("insert into foo (bar, baz) values($1, $2)")
+        .await?;
+    for (bar, baz) in [("one", 1), ("two", 2), ("three", 3)] {
+        client.execute(&insert, &[&bar, &baz]).await?;
+    }
+
+    let rows = client
+        .query("select bar, baz from foo where baz >= $1", &[&2])
+        .await?;
+    let mut values: Vec<(String, i32)> = rows
+        .iter()
+        .map(|r| (r.get::<_, &str>(0).to_string(), r.get::<_, i32>(1)))
+        .collect();
+    values.sort();
+    assert_eq!(
+        values,
+        vec![("three".to_string(), 3), ("two".to_string(), 2)]
+    );
+
+    let count = client
+        .execute("update foo set bar = $1 where baz = $2", &[&"changed", &1])
+        .await?;
+    assert_eq!(count, 1);
+
+    let row = client
+        .query_one("select bar from foo where baz = $1", &[&1])
+        .await?;
+    assert_eq!(row.get::<_, &str>(0), "changed");
+
+    //An error discards the rest of the request but the connection carries on
+    assert!(client.query("select nothing from foo", &[]).await.is_err());
+    let rows = client.query("select bar from foo", &[]).await?;
+    assert_eq!(rows.len(), 3);
+
+    let count = client
+        .execute("delete from foo where bar = $1", &[&"two"])
+        .await?;
+    assert_eq!(count, 1);
+
+    //Everything up to Sync runs in one transaction, drivers pipeline several statements this way
+    client
+        .batch_execute("create table pipelined (id integer not null primary key)")
+        .await?;
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await?;
+    let mut startup = 196608i32.to_be_bytes().to_vec();
+    startup.extend_from_slice(b"user\0postgres\0\0");
+    stream
+        .write_all(&(startup.len() as i32 + 4).to_be_bytes())
+        .await?;
+    stream.write_all(&startup).await?;
+    assert_eq!(receive(&mut stream, 2).await?, vec![b'R', b'Z', b'I']);
+
+    send_query(&mut stream, "insert into pipelined (id) values(1)").await?;
+    send_query(&mut stream, "insert into pipelined (id) values(2)").await?;
+    send(&mut stream, b'H', &[]).await?;
+    assert_eq!(
+        receive(&mut stream, 6).await?,
+        vec![b'1', b'2', b'C', b'1', b'2', b'C']
+    );
+    let rows = client.query("select id from pipelined", &[]).await?;
+    assert!(rows.is_empty());
+
+    send(&mut stream, b'S', &[]).await?;
+    assert_eq!(receive(&mut stream, 1).await?, vec![b'Z', b'I']);
+    let rows = client.query("select id from pipelined", &[]).await?;
+    assert_eq!(rows.len(), 2);
+
+    //An error rolls back the statements before it and skips the rest until Sync
+    send_query(&mut stream, "insert into pipelined (id) values(3)").await?;
+    send_query(&mut stream, "insert into pipelined (id) values(1)").await?;
+    send_query(&mut stream, "insert into pipelined (id) values(4)").await?;
+    send(&mut stream, b'S', &[]).await?;
+    assert_eq!(
+        receive(&mut stream, 7).await?,
+        vec![b'1', b'2', b'C', b'1', b'2', b'E', b'Z', b'I']
+    );
+    let rows = client.query("select id from pipelined", &[]).await?;
+    assert_eq!(rows.len(), 2);
+
+    common::_request_shutdown(request_shutdown).await?;
+    Ok(())
+}
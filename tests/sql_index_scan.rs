@@ -0,0 +1,61 @@
+mod common;
+
+#[tokio::test]
+async fn index_scan() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute(
+            "create table foo (
+            bar integer not null primary key,
+            baz text not null)",
+        )
+        .await?;
+
+    //Enough rows to split the index across several leaves
+    let insert = client
+        .prepare("insert into foo (bar, baz) values($1, $2)")
+        .await?;
+    for i in 0..400 {
+        client
+            .execute(&insert, &[&i, &format!("row {}", i)])
+            .await?;
+    }
+
+    let row = client
+        .query_one("select baz from foo where bar = 123", &[])
+        .await?;
+    assert_eq!(row.get::<_, &str>(0), "row 123");
+
+    let rows = client
+        .query("select bar from foo where bar >= 100 and bar < 110", &[])
+        .await?;
+    let mut values: Vec<i32> = rows.iter().map(|r| r.get(0)).collect();
+    values.sort_unstable();
+    assert_eq!(values, (100..110).collect::<Vec<i32>>());
+
+    //Constant on the left plus a condition the index can't answer
+    let rows = client
+        .query("select bar from foo where 10 > bar and baz = 'row 3'", &[])
+        .await?;
+    assert_eq!(rows.len(), 1);
+
+    //Only the current version of an updated row comes back
+    client
+        .batch_execute("update foo set baz = 'changed' where bar = 42")
+        .await?;
+    let rows = client
+        .query("select baz from foo where bar = 42", &[])
+        .await?;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, &str>(0), "changed");
+
+    client
+        .batch_execute("delete from foo where bar = 7")
+        .await?;
+    let rows = client
+        .query("select baz from foo where bar = 7", &[])
+        .await?;
+    assert!(rows.is_empty());
+
+    common::_request_shutdown(request_shutdown).await
+}
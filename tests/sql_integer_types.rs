@@ -0,0 +1,125 @@
+mod common;
+
+#[tokio::test]
+async fn integer_types() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute(
+            "create table account (id bigint not null primary key, branch smallint not null, balance int null)",
+        )
+        .await?;
+
+    client
+        .batch_execute("insert into account (id, branch, balance) values(9000000000, -2, -150)")
+        .await?;
+    client
+        .batch_execute("insert into account (id, branch, balance) values(-9000000000, 7, 300)")
+        .await?;
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select id, branch, balance from account where balance < 0")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["9000000000", "-2", "-150"])]);
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select id + 1, balance * 2 - 10 from account where id < -1 order by id")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["-8999999999", "590"])]);
+
+    client
+        .batch_execute("update account set balance = balance - 50 where branch = -2")
+        .await?;
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select sum(balance), count(*) from account")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["100", "2"])]);
+
+    //Different widths meet at the wider one, the same as postgres
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select branch + balance, id * branch from account where branch < balance and id < branch",
+            )
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["307", "-63000000000"])]);
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select balance from account where id = balance + 9000000200")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["-200"])]);
+
+    //Literals are integers, or bigints if they need to be, so the narrow column is widened
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select branch * 10000 from account where branch = 7")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["70000"])]);
+
+    client
+        .batch_execute(
+            "create table transfer (account_id integer not null, amount smallint not null)",
+        )
+        .await?;
+    client
+        .batch_execute("insert into transfer (account_id, amount) values(-2, 5)")
+        .await?;
+    client
+        .batch_execute("insert into transfer (account_id, amount) values(7, 8)")
+        .await?;
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select a.id, t.amount from transfer t join account a on t.account_id = a.branch order by amount",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["9000000000", "5"]),
+            common::_row(&["-9000000000", "8"])
+        ]
+    );
+
+    //An integer foreign key joined to a bigint primary key
+    client
+        .batch_execute("insert into account (id, branch, balance) values(12, 1, 0)")
+        .await?;
+    client
+        .batch_execute("insert into transfer (account_id, amount) values(12, 3)")
+        .await?;
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select t.account_id, a.balance from transfer t join account a on t.account_id = a.id",
+            )
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["12", "0"])]);
+
+    //Each width is range checked, both for literals and for arithmetic
+    assert!(client
+        .batch_execute("insert into account (id, branch, balance) values(1, 32768, 0)")
+        .await
+        .is_err());
+    assert!(client
+        .simple_query("select balance * 2147483647 from account")
+        .await
+        .is_err());
+    assert!(client
+        .simple_query("select id / 0 from account")
+        .await
+        .is_err());
+
+    common::_request_shutdown(request_shutdown).await
+}
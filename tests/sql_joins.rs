@@ -0,0 +1,123 @@
+mod common;
+
+#[tokio::test]
+async fn joins() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute("create table author (id integer not null, name text not null)")
+        .await?;
+    client
+        .batch_execute("create table book (authorid integer null, title text not null)")
+        .await?;
+
+    client
+        .batch_execute("insert into author (id, name) values(1, 'ann')")
+        .await?;
+    client
+        .batch_execute("insert into author (id, name) values(2, 'bob')")
+        .await?;
+    client
+        .batch_execute("insert into book (authorid, title) values(1, 'first')")
+        .await?;
+    client
+        .batch_execute("insert into book (authorid, title) values(1, 'second')")
+        .await?;
+    client
+        .batch_execute("insert into book (authorid, title) values(null, 'orphan')")
+        .await?;
+
+    let expected_inner = vec![
+        common::_nullable_row(&[Some("ann"), Some("first")]),
+        common::_nullable_row(&[Some("ann"), Some("second")]),
+    ];
+
+    //Hash join on the equality
+    let rows = common::_get_sorted_rows(
+        &client
+            .simple_query(
+                "select a.name, b.title from author a join book as b on a.id = b.authorid",
+            )
+            .await?,
+    );
+    assert_eq!(rows, expected_inner);
+
+    //Comma join has no condition of its own, the where clause filters the nested loop output
+    let rows = common::_get_sorted_rows(
+        &client
+            .simple_query(
+                "select name, title from author, book where id = authorid and title <> 'nothing'",
+            )
+            .await?,
+    );
+    assert_eq!(rows, expected_inner);
+
+    let rows = common::_get_sorted_rows(
+        &client
+            .simple_query(
+                "select a.name, b.title from author a left join book b on a.id = b.authorid",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_nullable_row(&[Some("ann"), Some("first")]),
+            common::_nullable_row(&[Some("ann"), Some("second")]),
+            common::_nullable_row(&[Some("bob"), None]),
+        ]
+    );
+
+    let rows = common::_get_sorted_rows(
+        &client
+            .simple_query(
+                "select a.name, b.title from author a right outer join book b on a.id = b.authorid",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_nullable_row(&[None, Some("orphan")]),
+            common::_nullable_row(&[Some("ann"), Some("first")]),
+            common::_nullable_row(&[Some("ann"), Some("second")]),
+        ]
+    );
+
+    //Non equality condition runs as a nested loop
+    let rows = common::_get_sorted_rows(
+        &client
+            .simple_query(
+                "select a.name, b.title from author a full join book b on a.id > 1 and b.authorid is null",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_nullable_row(&[None, Some("first")]),
+            common::_nullable_row(&[None, Some("second")]),
+            common::_nullable_row(&[Some("ann"), None]),
+            common::_nullable_row(&[Some("bob"), Some("orphan")]),
+        ]
+    );
+
+    //Both tables have a column called id in a self join, so it has to be qualified
+    assert!(client
+        .simple_query("select id from author a join author b on a.id = b.id")
+        .await
+        .is_err());
+    let rows = common::_get_sorted_rows(
+        &client
+            .simple_query("select a.name, b.id from author a join author b on a.id = b.id")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_nullable_row(&[Some("ann"), Some("1")]),
+            common::_nullable_row(&[Some("bob"), Some("2")])
+        ]
+    );
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,128 @@
+use tokio_postgres::error::SqlState;
+
+mod common;
+
+#[tokio::test]
+async fn jsonb() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute("create table docs (id integer not null primary key, doc jsonb null)")
+        .await?;
+
+    //Input is validated and stored normalized
+    client
+        .batch_execute(
+            r#"insert into docs (id, doc) values(1, '{"name":"a", "tags":["x","y"], "n":{"v":1.50}, "name":"b"}')"#,
+        )
+        .await?;
+    client
+        .batch_execute(r#"insert into docs (id, doc) values(2, '[1, "x", null]')"#)
+        .await?;
+    client
+        .batch_execute("insert into docs (id, doc) values(3, null)")
+        .await?;
+    assert!(client
+        .batch_execute("insert into docs (id, doc) values(4, '{\"a\": }')")
+        .await
+        .is_err());
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select doc from docs where id = 1")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![common::_row(&[
+            r#"{"n": {"v": 1.50}, "name": "b", "tags": ["x", "y"]}"#
+        ])]
+    );
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select doc -> 'name', doc ->> 'name', doc -> 'tags' -> -1, doc #> '{n,v}' from docs where id = 1",
+            )
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["\"b\"", "b", "\"y\"", "1.50"])]);
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select doc -> 1, doc ->> 2, doc -> 'name' from docs where id = 2")
+            .await?,
+    );
+    assert_eq!(rows, vec![vec![Some("\"x\"".to_string()), None, None]]);
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                r#"select id from docs where doc @> '{"tags": ["y"]}' or doc ? 'x' order by id"#,
+            )
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["1"]), common::_row(&["2"])]);
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select id from docs where doc ->> 'name' = 'b'")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["1"])]);
+
+    //Operators need a jsonb on the left
+    assert!(client
+        .simple_query("select id ->> 'a' from docs")
+        .await
+        .is_err());
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select jsonb_build_object('id', id, 'ok', true, 'name', doc ->> 'name') from docs where id = 1",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![common::_row(&[r#"{"id": 1, "ok": true, "name": "b"}"#])]
+    );
+    assert!(client
+        .simple_query("select jsonb_build_object('id') from docs")
+        .await
+        .is_err());
+
+    //jsonb_agg keeps the NULLs as json nulls
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select jsonb_agg(doc -> 0) from docs")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["[null, 1, null]"])]);
+
+    //Nesting is limited so a hostile literal is an error instead of overflowing the stack
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    let err = client
+        .batch_execute(&format!(
+            "insert into docs (id, doc) values(5, '{}')",
+            nested(100_000)
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::INVALID_TEXT_REPRESENTATION));
+
+    client
+        .batch_execute(&format!(
+            "insert into docs (id, doc) values(5, '{}')",
+            nested(512)
+        ))
+        .await?;
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select doc from docs where id = 5")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&[&nested(512)])]);
+
+    common::_request_shutdown(request_shutdown).await
+}
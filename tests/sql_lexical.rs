@@ -0,0 +1,109 @@
+use tokio_postgres::error::SqlState;
+
+mod common;
+
+#[tokio::test]
+async fn lexical() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+
+    //The sort of thing a migration tool writes out
+    client
+        .batch_execute(
+            r#"-- Migration 0001
+            /* Creates the tables
+               /* nested comments are fine too */ */
+            CREATE TABLE Accounts (
+                ID integer NOT NULL PRIMARY KEY, -- the key
+                "Display Name" text NULL
+            );
+            CREATE TABLE "Notes" (body text null);
+            INSERT INTO accounts (id, "Display Name") VALUES(1, 'O''Brien');
+            INSERT INTO ACCOUNTS (Id, "Display Name") VALUES(2, E'tab\there\nnew line \'quoted\'');
+            INSERT INTO "Notes" (BODY) VALUES($$it's $1 \n raw$$);
+            insert into "Notes" (body) values($note$has $$ inside$note$);"#,
+        )
+        .await?;
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                r#"SELECT Id, /* inline */ "Display Name" FROM ACCOUNTS -- trailing
+                ORDER BY id"#,
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["1", "O'Brien"]),
+            common::_row(&["2", "tab\there\nnew line 'quoted'"]),
+        ]
+    );
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query(r#"select "Notes".body from "Notes" order by body"#)
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["has $$ inside"]),
+            common::_row(&[r"it's $1 \n raw"])
+        ]
+    );
+
+    //Unquoted names are folded to lower case, quoted ones are not
+    let statement = client.prepare("select ID from Accounts").await?;
+    assert_eq!(statement.columns()[0].name(), "id");
+    let statement = client
+        .prepare(r#"select "Display Name" from accounts"#)
+        .await?;
+    assert_eq!(statement.columns()[0].name(), "Display Name");
+
+    let e = client
+        .batch_execute("select body from notes")
+        .await
+        .unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::UNDEFINED_TABLE));
+    let e = client
+        .batch_execute(r#"select "ID" from accounts"#)
+        .await
+        .unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::UNDEFINED_COLUMN));
+
+    //A dot inside quotes is part of the name and not a qualifier
+    client
+        .batch_execute(
+            r#"create table ledger ("a.b" integer null, b integer null);
+            insert into ledger ("a.b", b) values(1, 2);"#,
+        )
+        .await?;
+    let rows = common::_get_rows(
+        &client
+            .simple_query(r#"select "a.b", ledger."a.b", ledger.b from ledger"#)
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["1", "1", "2"])]);
+    let statement = client.prepare(r#"select ledger."a.b" from ledger"#).await?;
+    assert_eq!(statement.columns()[0].name(), "a.b");
+    let e = client
+        .batch_execute("select a.b from ledger")
+        .await
+        .unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::UNDEFINED_COLUMN));
+
+    //A bad escape or an unfinished comment is a syntax error
+    let e = client
+        .batch_execute(r"insert into accounts (id) values(E'\u12')")
+        .await
+        .unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::SYNTAX_ERROR));
+    let e = client
+        .batch_execute("select id from accounts /* forever")
+        .await
+        .unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::SYNTAX_ERROR));
+
+    common::_request_shutdown(request_shutdown).await
+}
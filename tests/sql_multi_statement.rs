@@ -0,0 +1,79 @@
+mod common;
+
+#[tokio::test]
+async fn multi_statement() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+
+    //Each statement gets its own results, later ones see what earlier ones did
+    let messages = client
+        .simple_query(
+            "create table foo (id integer not null primary key, name text null);
+            insert into foo (id, name) values(1, 'one');
+            insert into foo (id, name) values(2, 'two');
+            insert into foo (id, name) values(3, 'three');
+            select id, name from foo order by id;
+            update foo set name = 'dos' where id = 2;;
+            select name from foo where id = 2",
+        )
+        .await?;
+    assert_eq!(common::_get_counts(&messages), vec![0, 1, 1, 1, 3, 1, 1]);
+    assert_eq!(
+        common::_get_rows(&messages),
+        vec![
+            common::_row(&["1", "one"]),
+            common::_row(&["2", "two"]),
+            common::_row(&["3", "three"]),
+            common::_row(&["dos"]),
+        ]
+    );
+
+    //A failure rolls back the whole query, including the statements before it
+    assert!(client
+        .simple_query(
+            "insert into foo (id, name) values(4, 'four'); insert into foo (id, name) values(1, 'uno')"
+        )
+        .await
+        .is_err());
+    let messages = client
+        .simple_query("select id from foo where id = 4")
+        .await?;
+    assert!(common::_get_rows(&messages).is_empty());
+
+    //Nothing runs if any statement doesn't parse
+    assert!(client
+        .simple_query("insert into foo (id, name) values(4, 'four'); garbage")
+        .await
+        .is_err());
+    let messages = client
+        .simple_query("select id from foo where id = 4")
+        .await?;
+    assert!(common::_get_rows(&messages).is_empty());
+
+    //Explicit blocks are honoured, a COMMIT in the middle ends the implicit transaction
+    assert!(client
+        .simple_query(
+            "begin; insert into foo (id, name) values(4, 'four'); commit; insert into foo (id, name) values(4, 'again')"
+        )
+        .await
+        .is_err());
+    let messages = client
+        .simple_query("select name from foo where id = 4")
+        .await?;
+    assert_eq!(common::_get_rows(&messages), vec![common::_row(&["four"])]);
+
+    //A block left open carries on into the next query, BEGIN pulls in the statements before it
+    client
+        .batch_execute("insert into foo (id, name) values(5, 'five'); begin; insert into foo (id, name) values(6, 'six')")
+        .await?;
+    client.batch_execute("rollback").await?;
+    let messages = client
+        .simple_query("select name from foo where id > 4")
+        .await?;
+    assert!(common::_get_rows(&messages).is_empty());
+
+    //Empty queries are answered with EmptyQueryResponse instead of an error
+    let messages = client.simple_query(" ; ").await?;
+    assert!(common::_get_rows(&messages).is_empty());
+
+    common::_request_shutdown(request_shutdown).await
+}
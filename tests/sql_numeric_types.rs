@@ -0,0 +1,83 @@
+mod common;
+
+#[tokio::test]
+async fn numeric_types() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute(
+            "create table reading (id int not null primary key, weight real null, ratio double precision null, price numeric null)",
+        )
+        .await?;
+
+    client
+        .batch_execute("insert into reading (id, weight, ratio, price) values(1, 1.5, 0.1, 19.99)")
+        .await?;
+    client
+        .batch_execute(
+            "insert into reading (id, weight, ratio, price) values(2, -2.25, 2.5e-3, 0.01)",
+        )
+        .await?;
+    client
+        .batch_execute(
+            "insert into reading (id, weight, ratio, price) values(3, 'NaN', 'Infinity', 100)",
+        )
+        .await?;
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select id, weight, ratio, price from reading order by weight")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["2", "-2.25", "0.0025", "0.01"]),
+            common::_row(&["1", "1.5", "0.1", "19.99"]),
+            common::_row(&["3", "NaN", "Infinity", "100"]),
+        ]
+    );
+
+    //Exact numerics keep every digit, doubles round the same way postgres does
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select price + 0.01, ratio * 2 from reading where id = 1")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["20.00", "0.2"])]);
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select sum(price), avg(weight) from reading where id < 3")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["20.00", "-0.375"])]);
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select id from reading where price > 19.5 order by price desc")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["3"]), common::_row(&["1"])]);
+
+    assert!(client
+        .batch_execute("insert into reading (id, weight) values(4, 1e39)")
+        .await
+        .is_err());
+    assert!(client
+        .simple_query("select price / 0 from reading")
+        .await
+        .is_err());
+
+    //Large and small floats come back in exponent form, same as postgres
+    client
+        .batch_execute("insert into reading (id, weight, ratio) values(5, 1e-5, 1e308)")
+        .await?;
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select weight, ratio from reading where id = 5")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["1e-05", "1e+308"])]);
+
+    common::_request_shutdown(request_shutdown).await
+}
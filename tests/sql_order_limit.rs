@@ -0,0 +1,116 @@
+mod common;
+
+#[tokio::test]
+async fn order_limit() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute("create table foo (bar integer null, baz text not null)")
+        .await?;
+
+    for (bar, baz) in [
+        ("3", "c"),
+        ("1", "a"),
+        ("null", "n"),
+        ("2", "b"),
+        ("2", "bb"),
+    ] {
+        client
+            .batch_execute(&format!(
+                "insert into foo (bar, baz) values({}, '{}')",
+                bar, baz
+            ))
+            .await?;
+    }
+
+    //NULLs sort last ascending and first descending, ties keep the insert order
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select baz from foo order by bar")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["a"]),
+            common::_row(&["b"]),
+            common::_row(&["bb"]),
+            common::_row(&["c"]),
+            common::_row(&["n"])
+        ]
+    );
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select baz from foo order by bar desc, baz desc")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["n"]),
+            common::_row(&["c"]),
+            common::_row(&["bb"]),
+            common::_row(&["b"]),
+            common::_row(&["a"])
+        ]
+    );
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select bar from foo order by bar asc nulls first limit 2")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![common::_nullable_row(&[None]), common::_row(&["1"])]
+    );
+
+    //Sorting by output position, offset before the limit
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select baz, bar from foo where bar is not null order by 1 desc offset 1 limit 2",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![common::_row(&["bb", "2"]), common::_row(&["b", "2"])]
+    );
+
+    //A quoted number is just a value, every row sorts the same on it
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select baz, bar from foo where bar is not null order by '2', baz desc")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["c", "3"]),
+            common::_row(&["bb", "2"]),
+            common::_row(&["b", "2"]),
+            common::_row(&["a", "1"])
+        ]
+    );
+
+    let rows = common::_get_rows(&client.simple_query("select baz from foo limit 0").await?);
+    assert!(rows.is_empty());
+
+    //Parameters work for the limit and offset
+    let rows = client
+        .query(
+            "select baz from foo order by baz limit $1 offset $2",
+            &[&1, &4],
+        )
+        .await?;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, &str>(0), "n");
+
+    assert!(client
+        .simple_query("select baz from foo order by 3")
+        .await
+        .is_err());
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,98 @@
+use tokio_postgres::types::Type;
+
+mod common;
+
+#[tokio::test]
+async fn pg_type() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, port) = common::_create_server().await?;
+    let client = common::_connect(port).await?;
+
+    //Only real types make it into pg_attribute
+    assert!(client
+        .batch_execute("create table bad (a nosuchtype)")
+        .await
+        .is_err());
+    assert!(client
+        .batch_execute("create table bad (a integer, b nosuchtype[])")
+        .await
+        .is_err());
+    assert!(client.simple_query("select a from bad").await.is_err());
+
+    client
+        .batch_execute("create type mood as enum ('sad', 'happy')")
+        .await?;
+    client
+        .batch_execute("create type color as enum ('red')")
+        .await?;
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select typname, oid, typarray, typtype from pg_type where oid >= 16384 order by oid",
+            )
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["mood", "16384", "16385", "e"]),
+            common::_row(&["color", "16386", "16387", "e"]),
+        ]
+    );
+
+    //The built in types are rows too, under their postgres names
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select typname, typarray, typlen, typtype from pg_type where oid = 23")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["int4", "1007", "4", "b"])]);
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select typname, oid, typlen from pg_type where typname = 'text'")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["text", "25", "-1"])]);
+    assert!(client
+        .batch_execute("create type integer as enum ('one')")
+        .await
+        .is_err());
+    assert!(client.batch_execute("drop type int4").await.is_err());
+
+    client
+        .batch_execute(
+            "create table foo (id int4 not null primary key, name varchar(5) null, feeling mood null, \
+            scores integer[] null, feelings mood[] null, active boolean null)",
+        )
+        .await?;
+    client
+        .batch_execute("insert into foo values(1, 'abc', 'happy', '{1, 2}', '{sad}', true)")
+        .await?;
+
+    //The driver picks how to decode each column from the oids in RowDescription
+    let rows = client
+        .query("select id, name, active from foo", &[])
+        .await?;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].columns()[0].type_(), &Type::INT4);
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+    assert_eq!(rows[0].get::<_, String>(1), "abc");
+    assert_eq!(rows[0].columns()[2].type_(), &Type::BOOL);
+    assert!(rows[0].get::<_, bool>(2));
+
+    //Enums report their own oids, same as postgres
+    assert_eq!(
+        common::_column_type_oids(port, "select id, feeling, feelings from foo").await?,
+        vec![23, 16384, 16385]
+    );
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select feeling, feelings from foo")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["happy", "{sad}"])]);
+
+    let statement = client.prepare("select scores from foo").await?;
+    assert_eq!(statement.columns()[0].type_(), &Type::INT4_ARRAY);
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,53 @@
+use log::LevelFilter;
+use simplelog::{ColorChoice, CombinedLogger, Config, TermLogger, TerminalMode};
+use tempfile::TempDir;
+
+mod common;
+
+#[tokio::test]
+async fn restart_keeps_transaction_status() -> Result<(), Box<dyn std::error::Error>> {
+    CombinedLogger::init(vec![TermLogger::new(
+        LevelFilter::Warn,
+        Config::default(),
+        TerminalMode::Mixed,
+        ColorChoice::Auto,
+    )])?;
+
+    let tmp = TempDir::new()?;
+    let data_dir = tmp.path().as_os_str().to_os_string();
+
+    let (request_shutdown, mut client) = common::_start_server_and_client(data_dir.clone()).await?;
+    client.batch_execute("create table foo (bar text)").await?;
+    client
+        .batch_execute("insert into foo (bar) values('committed')")
+        .await?;
+
+    let tran = client.transaction().await?;
+    tran.batch_execute("insert into foo (bar) values('rolled back')")
+        .await?;
+    tran.rollback().await?;
+
+    client
+        .batch_execute("delete from foo where bar = 'nothing'")
+        .await?;
+    drop(client);
+    common::_request_shutdown(request_shutdown).await?;
+
+    //Everything written before the restart must keep its status
+    let (request_shutdown, client) = common::_start_server_and_client(data_dir).await?;
+    let rows = common::_get_rows(&client.simple_query("select bar from foo").await?);
+    assert_eq!(rows, vec![common::_row(&["committed"])]);
+
+    //New transactions must not reuse an id from before the restart
+    client
+        .batch_execute("insert into foo (bar) values('after')")
+        .await?;
+    client
+        .batch_execute("delete from foo where bar = 'committed'")
+        .await?;
+    let rows = common::_get_rows(&client.simple_query("select bar from foo").await?);
+    assert_eq!(rows, vec![common::_row(&["after"])]);
+
+    common::_request_shutdown(request_shutdown).await?;
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use tokio_postgres::types::Type;
+use uuid::Uuid;
+
+mod common;
+
+#[tokio::test]
+async fn row_description() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, port) = common::_create_server().await?;
+    let client = common::_connect(port).await?;
+    client
+        .batch_execute(
+            "create table foo (id integer not null primary key, uid uuid null, name varchar(10) null, code char(2) null, big bigint null)",
+        )
+        .await?;
+    client
+        .batch_execute("create table bar (other integer)")
+        .await?;
+    client
+        .batch_execute(
+            "insert into foo (id, uid, name, code, big) values(1, 'c4b7e8a3-2f5d-4b8e-9a1c-3d6f7e8a9b0c', 'abc', 'x', 5000000000)",
+        )
+        .await?;
+
+    //Every table gets its own oid
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select name, oid from pg_class order by oid")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["foo", "16384"]),
+            common::_row(&["bar", "16385"])
+        ]
+    );
+
+    //Transactions creating tables at the same time still get different oids
+    let second = common::_connect(port).await?;
+    client.batch_execute("begin").await?;
+    second.batch_execute("begin").await?;
+    client
+        .batch_execute("create table baz (baz_id integer)")
+        .await?;
+    second
+        .batch_execute("create table qux (qux_id integer)")
+        .await?;
+    client.batch_execute("commit").await?;
+    second.batch_execute("commit").await?;
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select name, oid from pg_class where oid > 16385 order by oid")
+            .await?,
+    );
+    assert_eq!(
+        rows,
+        vec![
+            common::_row(&["baz", "16386"]),
+            common::_row(&["qux", "16387"])
+        ]
+    );
+
+    //Binary results only work if the types are right
+    let rows = client
+        .query("select id, uid, name, code, big, id + 1 from foo", &[])
+        .await?;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+    assert_eq!(
+        rows[0].get::<_, Uuid>(1),
+        Uuid::parse_str("c4b7e8a3-2f5d-4b8e-9a1c-3d6f7e8a9b0c")?
+    );
+    assert_eq!(rows[0].get::<_, String>(2), "abc");
+    assert_eq!(rows[0].get::<_, String>(3), "x ");
+    assert_eq!(rows[0].get::<_, i64>(4), 5_000_000_000);
+    assert_eq!(rows[0].get::<_, i32>(5), 2);
+
+    let statement = client
+        .prepare("select id, uid, name, code, big, id + 1 from foo")
+        .await?;
+    let types: Vec<&Type> = statement.columns().iter().map(|c| c.type_()).collect();
+    assert_eq!(
+        types,
+        vec![
+            &Type::INT4,
+            &Type::UUID,
+            &Type::VARCHAR,
+            &Type::BPCHAR,
+            &Type::INT8,
+            &Type::INT4
+        ]
+    );
+
+    //Table columns say where they came from and computed ones are zeros
+    let origins: Vec<(Option<u32>, Option<i16>)> = statement
+        .columns()
+        .iter()
+        .map(|c| (c.table_oid(), c.column_id()))
+        .collect();
+    assert_eq!(
+        origins,
+        vec![
+            (Some(16384), Some(1)),
+            (Some(16384), Some(2)),
+            (Some(16384), Some(3)),
+            (Some(16384), Some(4)),
+            (Some(16384), Some(5)),
+            (None, None)
+        ]
+    );
+    let modifiers: Vec<i32> = statement
+        .columns()
+        .iter()
+        .map(|c| c.type_modifier())
+        .collect();
+    assert_eq!(modifiers, vec![-1, -1, 14, 6, -1, -1]);
+
+    common::_request_shutdown(request_shutdown).await
+}
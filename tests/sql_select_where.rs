@@ -0,0 +1,60 @@
+mod common;
+
+#[tokio::test]
+async fn select_where() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute("create table foo (bar text, baz integer not null, another text null)")
+        .await?;
+
+    client
+        .batch_execute("insert into foo (bar, baz, another) values('one', 1, 'first')")
+        .await?;
+    client
+        .batch_execute("insert into foo (bar, baz, another) values('two', 2, null)")
+        .await?;
+    client
+        .batch_execute("insert into foo (bar, baz, another) values('three', 3, 'third')")
+        .await?;
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select bar from foo where baz = 2;")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["two"])]);
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select bar, baz from foo where baz > 1 and another is not null")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["three", "3"])]);
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select bar from foo where not (bar = 'one' or another is null)")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["three"])]);
+
+    //Comparisons against null are never true
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select bar from foo where another <> 'first'")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["three"])]);
+
+    let result = client
+        .simple_query("select bar from foo where nonexistent = 1")
+        .await;
+    assert!(result.is_err());
+
+    let result = client
+        .simple_query("select bar from foo where baz = 'abc'")
+        .await;
+    assert!(result.is_err());
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,55 @@
+use tokio_postgres::error::{ErrorPosition, SqlState};
+
+mod common;
+
+#[tokio::test]
+async fn syntax_errors() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute("create table foo (id integer not null primary key)")
+        .await?;
+
+    //The position counts characters from one, so psql can draw its caret under the mistake
+    let e = client
+        .batch_execute("select id\n  frm foo")
+        .await
+        .unwrap_err();
+    assert_eq!(e.code(), Some(&SqlState::SYNTAX_ERROR));
+    let db_error = e.as_db_error().unwrap();
+    assert_eq!(db_error.position(), Some(&ErrorPosition::Original(13)));
+    assert_eq!(
+        db_error.message(),
+        r#"syntax error at or near "frm", expected "from""#
+    );
+
+    //Positions are for the whole query, not just the statement that failed
+    let e = client
+        .batch_execute("select id from foo; create table bar (baz text ]")
+        .await
+        .unwrap_err();
+    let db_error = e.as_db_error().unwrap();
+    assert_eq!(db_error.position(), Some(&ErrorPosition::Original(48)));
+    assert_eq!(
+        db_error.message(),
+        r#"syntax error at or near "]", expected ")""#
+    );
+
+    let e = client
+        .batch_execute("insert into foo (id) values(1")
+        .await
+        .unwrap_err();
+    let db_error = e.as_db_error().unwrap();
+    assert_eq!(db_error.position(), Some(&ErrorPosition::Original(30)));
+    assert!(db_error
+        .message()
+        .starts_with("syntax error at end of input"));
+
+    //Prepared statements report them too
+    let e = client.prepare("selec id from foo").await.unwrap_err();
+    assert_eq!(
+        e.as_db_error().unwrap().position(),
+        Some(&ErrorPosition::Original(1))
+    );
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,91 @@
+use tokio_postgres::SimpleQueryMessage;
+
+mod common;
+
+#[tokio::test]
+async fn transactions() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, mut client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute("create table foo (bar text, baz integer)")
+        .await?;
+
+    //Rolled back inserts disappear
+    let tran = client.transaction().await?;
+    tran.batch_execute("insert into foo (bar, baz) values('one', 1)")
+        .await?;
+    tran.batch_execute("insert into foo (bar, baz) values('two', 2)")
+        .await?;
+    let rows = common::_get_rows(&tran.simple_query("select bar from foo").await?);
+    assert_eq!(rows.len(), 2);
+    tran.rollback().await?;
+
+    let rows = common::_get_rows(&client.simple_query("select bar from foo").await?);
+    assert!(rows.is_empty());
+
+    //Committed ones stay, including their own updates and deletes
+    client.batch_execute("start transaction").await?;
+    client
+        .batch_execute("insert into foo (bar, baz) values('one', 1)")
+        .await?;
+    client
+        .batch_execute("insert into foo (bar, baz) values('two', 2)")
+        .await?;
+    client
+        .batch_execute("update foo set bar = 'uno' where baz = 1")
+        .await?;
+    client
+        .batch_execute("delete from foo where baz = 2")
+        .await?;
+    let rows = common::_get_rows(&client.simple_query("select bar from foo").await?);
+    assert_eq!(rows, vec![common::_row(&["uno"])]);
+    client.batch_execute("commit").await?;
+
+    let rows = common::_get_rows(&client.simple_query("select bar from foo").await?);
+    assert_eq!(rows, vec![common::_row(&["uno"])]);
+
+    //An error aborts the transaction until it is ended
+    client.batch_execute("begin").await?;
+    client
+        .batch_execute("insert into foo (bar, baz) values('three', 3)")
+        .await?;
+    assert!(client.batch_execute("select nope from foo").await.is_err());
+
+    let err = client
+        .batch_execute("select bar from foo")
+        .await
+        .expect_err("Transaction should be aborted");
+    assert_eq!(err.code().map(|c| c.code()), Some("25P02"));
+
+    //Commit on an aborted transaction rolls back
+    let messages = client.simple_query("commit").await?;
+    assert!(matches!(
+        messages.as_slice(),
+        [SimpleQueryMessage::CommandComplete(_)]
+    ));
+
+    let rows = common::_get_rows(&client.simple_query("select bar from foo").await?);
+    assert_eq!(rows, vec![common::_row(&["uno"])]);
+
+    //A rolled back update leaves the row live for later updates and deletes
+    client.batch_execute("begin").await?;
+    client
+        .batch_execute("update foo set bar = 'x' where baz = 1")
+        .await?;
+    client.batch_execute("rollback").await?;
+
+    let counts = common::_get_counts(
+        &client
+            .simple_query("update foo set bar = 'y' where baz = 1")
+            .await?,
+    );
+    assert_eq!(counts, vec![1]);
+    let rows = common::_get_rows(&client.simple_query("select bar from foo").await?);
+    assert_eq!(rows, vec![common::_row(&["y"])]);
+
+    let counts = common::_get_counts(&client.simple_query("delete from foo where baz = 1").await?);
+    assert_eq!(counts, vec![1]);
+    let rows = common::_get_rows(&client.simple_query("select bar from foo").await?);
+    assert!(rows.is_empty());
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,99 @@
+use tokio_postgres::error::SqlState;
+
+mod common;
+
+#[tokio::test]
+async fn type_modifiers() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute(
+            "create table item (id int not null primary key, code char(3) null, \
+            name character varying(5) null, price numeric(5, 2) null)",
+        )
+        .await?;
+
+    //Trailing spaces are trimmed to fit and char pads back out
+    client
+        .batch_execute(
+            "insert into item (id, code, name, price) values(1, 'ab', 'apple   ', 12.345)",
+        )
+        .await?;
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select code, name, price from item where id = 1")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["ab ", "apple", "12.35"])]);
+
+    let err = client
+        .batch_execute("insert into item (id, name) values(2, 'banana')")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::STRING_DATA_RIGHT_TRUNCATION));
+
+    let err = client
+        .batch_execute("insert into item (id, code) values(2, 'abcd')")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::STRING_DATA_RIGHT_TRUNCATION));
+
+    let err = client
+        .batch_execute("insert into item (id, price) values(2, 1000)")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::NUMERIC_VALUE_OUT_OF_RANGE));
+
+    //Updates are held to the same limits
+    let err = client
+        .batch_execute("update item set name = 'cherry' where id = 1")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::STRING_DATA_RIGHT_TRUNCATION));
+
+    //Trailing spaces don't count when comparing char values
+    for filter in &["code = 'ab'", "code = 'ab '", "'ab    ' = code"] {
+        let rows = common::_get_rows(
+            &client
+                .simple_query(&format!("select id, code from item where {}", filter))
+                .await?,
+        );
+        assert_eq!(rows, vec![common::_row(&["1", "ab "])]);
+    }
+
+    client
+        .batch_execute(
+            "create table tag (label char(5) not null primary key, color varchar(10) null); \
+            insert into tag (label, color) values('ab', 'red');",
+        )
+        .await?;
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query("select label, color from tag where label = 'ab '")
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["ab   ", "red"])]);
+
+    let err = client
+        .batch_execute("insert into tag (label, color) values('ab  ', 'blue')")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::UNIQUE_VIOLATION));
+
+    let rows = common::_get_rows(
+        &client
+            .simple_query(
+                "select i.code, t.label, t.color from item i join tag t on i.code = t.label",
+            )
+            .await?,
+    );
+    assert_eq!(rows, vec![common::_row(&["ab ", "ab   ", "red"])]);
+
+    assert!(client
+        .batch_execute("create table bad (id int not null, code int(3) null)")
+        .await
+        .is_err());
+
+    common::_request_shutdown(request_shutdown).await
+}
@@ -0,0 +1,93 @@
+mod common;
+
+#[tokio::test]
+async fn update_delete() -> Result<(), Box<dyn std::error::Error>> {
+    let (request_shutdown, client) = common::_create_server_and_client().await?;
+    client
+        .batch_execute("create table foo (bar text, baz integer not null, another text null)")
+        .await?;
+
+    client
+        .batch_execute("insert into foo (bar, baz, another) values('one', 1, 'first')")
+        .await?;
+    client
+        .batch_execute("insert into foo (bar, baz, another) values('two', 2, null)")
+        .await?;
+    client
+        .batch_execute("insert into foo (bar, baz, another) values('three', 3, 'third')")
+        .await?;
+
+    let count = common::_get_counts(
+        &client
+            .simple_query("update foo set another = 'changed', bar = 'big' where baz >= 2")
+            .await?,
+    );
+    assert_eq!(count, vec![2]);
+
+    let rows = common::_get_rows(&client.simple_query("select bar, another from foo").await?);
+    assert_eq!(rows.len(), 3);
+    assert!(rows.contains(&common::_row(&["one", "first"])));
+    assert_eq!(
+        rows.iter()
+            .filter(|r| **r == common::_row(&["big", "changed"]))
+            .count(),
+        2
+    );
+
+    //Not null is still enforced on the new version
+    assert!(client
+        .simple_query("update foo set baz = null where bar = 'one'")
+        .await
+        .is_err());
+
+    let count = common::_get_counts(&client.simple_query("delete from foo where baz = 2").await?);
+    assert_eq!(count, vec![1]);
+
+    let rows = common::_get_rows(&client.simple_query("select baz from foo").await?);
+    assert_eq!(rows.len(), 2);
+    assert!(!rows.contains(&common::_row(&["2"])));
+
+    let count = common::_get_counts(&client.simple_query("delete from foo").await?);
+    assert_eq!(count, vec![2]);
+
+    let rows = common::_get_rows(&client.simple_query("select baz from foo").await?);
+    assert!(rows.is_empty());
+
+    client
+        .batch_execute("create table pk (id integer primary key, name text)")
+        .await?;
+    client
+        .batch_execute("insert into pk (id, name) values(1, 'one')")
+        .await?;
+    client
+        .batch_execute("insert into pk (id, name) values(2, 'two')")
+        .await?;
+
+    //Updating other columns does not collide with the row's own key
+    let count = common::_get_counts(
+        &client
+            .simple_query("update pk set name = 'uno' where id = 1")
+            .await?,
+    );
+    assert_eq!(count, vec![1]);
+
+    //Moving onto an existing key is rejected
+    assert!(client
+        .simple_query("update pk set id = 2 where id = 1")
+        .await
+        .is_err());
+
+    //The key freed by a delete can be reused
+    client.batch_execute("delete from pk where id = 2").await?;
+    let count = common::_get_counts(
+        &client
+            .simple_query("update pk set id = 2 where id = 1")
+            .await?,
+    );
+    assert_eq!(count, vec![1]);
+
+    let rows = common::_get_rows(&client.simple_query("select id, name from pk").await?);
+    assert_eq!(rows, vec![common::_row(&["2", "uno"])]);
+
+    common::_request_shutdown(request_shutdown).await
+}
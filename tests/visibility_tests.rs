@@ -30,7 +30,7 @@ async fn test_row_manager_visibility() -> Result<(), Box<dyn std::error::Error>>
     let fm = Arc::new(FileManager2::new(tmp_dir.clone())?);
     let fsm = FreeSpaceManager::new(fm.clone());
     let rm = RowManager::new(fm.clone(), fsm);
-    let mut tm = TransactionManager::new();
+    let mut tm = TransactionManager::new(fm.clone()).await?;
     let vm = VisibleRowManager::new(rm.clone(), tm.clone());
     let row = get_row("test".to_string());
 
@@ -62,7 +62,7 @@ async fn test_row_manager_visibility() -> Result<(), Box<dyn std::error::Error>>
     let tran_id_3 = tm.start_trans().await?;
     debug!("On transaction {:?}, viewing as {:?}", tran_id_3, tran_id);
     rm.clone()
-        .delete_row(tran_id_3, &table, row_pointer)
+        .delete_row(tran_id_3, &table, row_pointer, None)
         .await?;
     tm.commit_trans(tran_id_3).await?;
     let res: Vec<RowData> = vm
@@ -82,5 +82,49 @@ async fn test_row_manager_visibility() -> Result<(), Box<dyn std::error::Error>>
         .await;
     assert!(res.is_empty());
 
+    info!("A row deleted by an uncommitted transaction is gone for it but not for others");
+    let tran_id_4 = tm.start_trans().await?;
+    let row_pointer_2 = rm
+        .clone()
+        .insert_row(tran_id_4, &table, row.clone())
+        .await?;
+    tm.commit_trans(tran_id_4).await?;
+
+    let tran_id_5 = tm.start_trans().await?;
+    let tran_id_6 = tm.start_trans().await?;
+    rm.clone()
+        .delete_row(tran_id_5, &table, row_pointer_2, None)
+        .await?;
+    let res: Vec<RowData> = vm
+        .clone()
+        .get_stream(tran_id_5, &table)
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert!(res.is_empty());
+
+    let res: Vec<RowData> = vm
+        .clone()
+        .get_stream(tran_id_6, &table)
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(res[0].user_data, row);
+
+    info!("Once that transaction aborts the row can be deleted again");
+    assert!(vm
+        .delete_row(tran_id_6, &table, row_pointer_2)
+        .await
+        .is_err());
+    tm.abort_trans(tran_id_5).await?;
+    vm.delete_row(tran_id_6, &table, row_pointer_2).await?;
+    let res: Vec<RowData> = vm
+        .clone()
+        .get_stream(tran_id_6, &table)
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert!(res.is_empty());
+
     Ok(())
 }